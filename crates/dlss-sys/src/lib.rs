@@ -1,2 +1,3 @@
 mod dlss;
 pub mod extensions;
+pub mod super_sampling;
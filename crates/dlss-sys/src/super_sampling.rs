@@ -0,0 +1,286 @@
+use std::{ptr, ffi::{c_void, c_char}};
+use anyhow::{anyhow, Result};
+use crate::dlss;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PerfQuality {
+    UltraPerformance,
+    MaxPerformance,
+    Balanced,
+    MaxQuality,
+    UltraQuality,
+    Dlaa
+}
+
+impl PerfQuality {
+    fn to_ngx(&self) -> dlss::NVSDK_NGX_PerfQuality_Value {
+        match self {
+            PerfQuality::UltraPerformance => dlss::NVSDK_NGX_PerfQuality_Value_NVSDK_NGX_PerfQuality_Value_UltraPerformance,
+            PerfQuality::MaxPerformance => dlss::NVSDK_NGX_PerfQuality_Value_NVSDK_NGX_PerfQuality_Value_MaxPerf,
+            PerfQuality::Balanced => dlss::NVSDK_NGX_PerfQuality_Value_NVSDK_NGX_PerfQuality_Value_Balanced,
+            PerfQuality::MaxQuality => dlss::NVSDK_NGX_PerfQuality_Value_NVSDK_NGX_PerfQuality_Value_MaxQuality,
+            PerfQuality::UltraQuality => dlss::NVSDK_NGX_PerfQuality_Value_NVSDK_NGX_PerfQuality_Value_UltraQuality,
+            PerfQuality::Dlaa => dlss::NVSDK_NGX_PerfQuality_Value_NVSDK_NGX_PerfQuality_Value_DLAA
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct OptimalSettings {
+    pub render_width: u32,
+    pub render_height: u32,
+    pub sharpness: f32
+}
+
+//Raw Vulkan handles and values, so this crate doesn't need to depend on a particular set of Vulkan bindings
+#[derive(Debug, Copy, Clone, Default)]
+pub struct ImageResource {
+    pub image: u64,
+    pub image_view: u64,
+    pub format: u32,
+    pub aspect_mask: u32,
+    pub width: u32,
+    pub height: u32
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct FeatureCreateFlags {
+    pub is_hdr: bool,
+    pub motion_vectors_low_res: bool,
+    pub motion_vectors_jittered: bool,
+    pub depth_inverted: bool,
+    pub auto_exposure: bool
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct EvaluateParameters {
+    pub color: ImageResource,
+    pub output: ImageResource,
+    pub depth: ImageResource,
+    pub motion_vectors: ImageResource,
+    pub jitter_offset: (f32, f32),
+    pub motion_vector_scale: (f32, f32),
+    pub render_width: u32,
+    pub render_height: u32,
+    pub sharpness: f32,
+    pub reset: bool
+}
+
+fn check_result(result: dlss::NVSDK_NGX_Result, action: &str) -> Result<()> {
+    if result != dlss::NVSDK_NGX_Result_NVSDK_NGX_Result_Success {
+        return Err(anyhow!("Unexpected error result from NGX while trying to {}: {:#x}", action, result));
+    }
+
+    Ok(())
+}
+
+fn param_name(name: &[u8]) -> *const c_char {
+    name.as_ptr() as *const c_char
+}
+
+#[cfg(windows)]
+fn to_wide_string(value: &str) -> Vec<dlss::wchar_t> {
+    value.encode_utf16().map(|c| c as dlss::wchar_t).chain(Some(0)).collect()
+}
+#[cfg(not(windows))]
+fn to_wide_string(value: &str) -> Vec<dlss::wchar_t> {
+    value.chars().map(|c| c as dlss::wchar_t).chain(Some(0)).collect()
+}
+
+fn create_resource(image: &ImageResource, read_write: bool) -> dlss::NVSDK_NGX_Resource_VK {
+    dlss::NVSDK_NGX_Resource_VK {
+        Resource: dlss::NVSDK_NGX_Resource_VK__bindgen_ty_1 {
+            ImageViewInfo: dlss::NVSDK_NGX_ImageViewInfo_VK {
+                ImageView: image.image_view as usize as dlss::VkImageView,
+                Image: image.image as usize as dlss::VkImage,
+                SubresourceRange: dlss::VkImageSubresourceRange {
+                    aspectMask: image.aspect_mask,
+                    baseMipLevel: 0,
+                    levelCount: 1,
+                    baseArrayLayer: 0,
+                    layerCount: 1
+                },
+                Format: image.format,
+                Width: image.width,
+                Height: image.height
+            }
+        },
+        Type: dlss::NVSDK_NGX_Resource_VK_Type_NVSDK_NGX_RESOURCE_VK_TYPE_VK_IMAGEVIEW,
+        ReadWrite: read_write
+    }
+}
+
+//Has to be called after the logical device is created, and before any other function in this module
+pub unsafe fn init(application_id: u64, application_data_path: &str, instance: usize, physical_device: usize, device: usize) -> Result<()> {
+    let data_path = to_wide_string(application_data_path);
+    let result = dlss::NVSDK_NGX_VULKAN_Init(
+        application_id as _,
+        data_path.as_ptr(),
+        instance as dlss::VkInstance,
+        physical_device as dlss::VkPhysicalDevice,
+        device as dlss::VkDevice,
+        None,
+        None,
+        ptr::null(),
+        dlss::NVSDK_NGX_Version_NVSDK_NGX_Version_API
+    );
+
+    check_result(result, "initialize")
+}
+
+pub unsafe fn shutdown(device: usize) -> Result<()> {
+    let result = dlss::NVSDK_NGX_VULKAN_Shutdown1(device as dlss::VkDevice);
+    check_result(result, "shut down")
+}
+
+#[derive(Debug)]
+pub struct CapabilityParameters {
+    parameters: *mut dlss::NVSDK_NGX_Parameter
+}
+
+impl CapabilityParameters {
+    pub unsafe fn get() -> Result<Self> {
+        let mut parameters: *mut dlss::NVSDK_NGX_Parameter = ptr::null_mut();
+        check_result(dlss::NVSDK_NGX_VULKAN_GetCapabilityParameters(&mut parameters), "get capability parameters")?;
+
+        Ok(Self { parameters })
+    }
+
+    pub unsafe fn is_super_sampling_available(&self) -> bool {
+        let mut available: ::core::ffi::c_int = 0;
+        let result = dlss::NVSDK_NGX_Parameter_GetI(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_SuperSampling_Available), &mut available);
+
+        result == dlss::NVSDK_NGX_Result_NVSDK_NGX_Result_Success && available != 0
+    }
+
+    //Reimplements NGX_DLSS_GET_OPTIMAL_SETTINGS, since static inline helpers don't get bindings
+    pub unsafe fn get_optimal_settings(&self, target_width: u32, target_height: u32, quality: PerfQuality) -> Result<OptimalSettings> {
+        let mut callback: *mut c_void = ptr::null_mut();
+        check_result(dlss::NVSDK_NGX_Parameter_GetVoidPointer(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_DLSSOptimalSettingsCallback), &mut callback), "get the optimal settings callback")?;
+        if callback.is_null() {
+            return Err(anyhow!("NGX did not provide a DLSS optimal settings callback"));
+        }
+        let callback: unsafe extern "C" fn(*mut dlss::NVSDK_NGX_Parameter) -> dlss::NVSDK_NGX_Result = std::mem::transmute(callback);
+
+        dlss::NVSDK_NGX_Parameter_SetUI(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_Width), target_width);
+        dlss::NVSDK_NGX_Parameter_SetUI(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_Height), target_height);
+        dlss::NVSDK_NGX_Parameter_SetI(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_PerfQualityValue), quality.to_ngx() as _);
+        dlss::NVSDK_NGX_Parameter_SetI(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_RTXValue), 0);
+        check_result(callback(self.parameters), "get optimal DLSS settings")?;
+
+        let mut settings = OptimalSettings::default();
+        check_result(dlss::NVSDK_NGX_Parameter_GetUI(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_OutWidth), &mut settings.render_width), "read the optimal render width")?;
+        check_result(dlss::NVSDK_NGX_Parameter_GetUI(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_OutHeight), &mut settings.render_height), "read the optimal render height")?;
+        //Sharpness is deprecated in newer DLSS versions, so it's fine if it's missing
+        dlss::NVSDK_NGX_Parameter_GetF(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_Sharpness), &mut settings.sharpness);
+
+        if settings.render_width == 0 || settings.render_height == 0 {
+            return Err(anyhow!("DLSS does not support {:?} at {}x{}", quality, target_width, target_height));
+        }
+
+        Ok(settings)
+    }
+}
+
+#[derive(Debug)]
+pub struct SuperSamplingFeature {
+    handle: *mut dlss::NVSDK_NGX_Handle,
+    parameters: *mut dlss::NVSDK_NGX_Parameter
+}
+
+impl SuperSamplingFeature {
+    //Reimplements NGX_VULKAN_CREATE_DLSS_EXT. The command buffer has to be submitted before the feature is evaluated
+    pub unsafe fn create(device: usize, command_buffer: usize, render_width: u32, render_height: u32, output_width: u32, output_height: u32, quality: PerfQuality, flags: FeatureCreateFlags) -> Result<Self> {
+        let mut parameters: *mut dlss::NVSDK_NGX_Parameter = ptr::null_mut();
+        check_result(dlss::NVSDK_NGX_VULKAN_AllocateParameters(&mut parameters), "allocate parameters")?;
+
+        let mut create_flags = 0;
+        if flags.is_hdr {
+            create_flags |= dlss::NVSDK_NGX_DLSS_Feature_Flags_NVSDK_NGX_DLSS_Feature_Flags_IsHDR;
+        }
+        if flags.motion_vectors_low_res {
+            create_flags |= dlss::NVSDK_NGX_DLSS_Feature_Flags_NVSDK_NGX_DLSS_Feature_Flags_MVLowRes;
+        }
+        if flags.motion_vectors_jittered {
+            create_flags |= dlss::NVSDK_NGX_DLSS_Feature_Flags_NVSDK_NGX_DLSS_Feature_Flags_MVJittered;
+        }
+        if flags.depth_inverted {
+            create_flags |= dlss::NVSDK_NGX_DLSS_Feature_Flags_NVSDK_NGX_DLSS_Feature_Flags_DepthInverted;
+        }
+        if flags.auto_exposure {
+            create_flags |= dlss::NVSDK_NGX_DLSS_Feature_Flags_NVSDK_NGX_DLSS_Feature_Flags_AutoExposure;
+        }
+
+        dlss::NVSDK_NGX_Parameter_SetUI(parameters, param_name(dlss::NVSDK_NGX_Parameter_CreationNodeMask), 1);
+        dlss::NVSDK_NGX_Parameter_SetUI(parameters, param_name(dlss::NVSDK_NGX_Parameter_VisibilityNodeMask), 1);
+        dlss::NVSDK_NGX_Parameter_SetUI(parameters, param_name(dlss::NVSDK_NGX_Parameter_Width), render_width);
+        dlss::NVSDK_NGX_Parameter_SetUI(parameters, param_name(dlss::NVSDK_NGX_Parameter_Height), render_height);
+        dlss::NVSDK_NGX_Parameter_SetUI(parameters, param_name(dlss::NVSDK_NGX_Parameter_OutWidth), output_width);
+        dlss::NVSDK_NGX_Parameter_SetUI(parameters, param_name(dlss::NVSDK_NGX_Parameter_OutHeight), output_height);
+        dlss::NVSDK_NGX_Parameter_SetI(parameters, param_name(dlss::NVSDK_NGX_Parameter_PerfQualityValue), quality.to_ngx() as _);
+        dlss::NVSDK_NGX_Parameter_SetI(parameters, param_name(dlss::NVSDK_NGX_Parameter_DLSS_Feature_Create_Flags), create_flags as _);
+
+        let mut handle: *mut dlss::NVSDK_NGX_Handle = ptr::null_mut();
+        let result = dlss::NVSDK_NGX_VULKAN_CreateFeature1(
+            device as dlss::VkDevice,
+            command_buffer as dlss::VkCommandBuffer,
+            dlss::NVSDK_NGX_Feature_NVSDK_NGX_Feature_SuperSampling,
+            parameters,
+            &mut handle
+        );
+        if let Err(e) = check_result(result, "create the DLSS feature") {
+            dlss::NVSDK_NGX_VULKAN_DestroyParameters(parameters);
+            return Err(e);
+        }
+
+        Ok(Self {
+            handle,
+            parameters
+        })
+    }
+
+    //Reimplements NGX_VULKAN_EVALUATE_DLSS_EXT. Inputs have to be in SHADER_READ_ONLY_OPTIMAL and the output in GENERAL
+    pub unsafe fn evaluate(&self, command_buffer: usize, params: &EvaluateParameters) -> Result<()> {
+        let mut color = create_resource(&params.color, false);
+        let mut output = create_resource(&params.output, true);
+        let mut depth = create_resource(&params.depth, false);
+        let mut motion_vectors = create_resource(&params.motion_vectors, false);
+
+        dlss::NVSDK_NGX_Parameter_SetVoidPointer(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_Color), &mut color as *mut _ as *mut c_void);
+        dlss::NVSDK_NGX_Parameter_SetVoidPointer(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_Output), &mut output as *mut _ as *mut c_void);
+        dlss::NVSDK_NGX_Parameter_SetVoidPointer(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_Depth), &mut depth as *mut _ as *mut c_void);
+        dlss::NVSDK_NGX_Parameter_SetVoidPointer(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_MotionVectors), &mut motion_vectors as *mut _ as *mut c_void);
+        dlss::NVSDK_NGX_Parameter_SetF(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_Jitter_Offset_X), params.jitter_offset.0);
+        dlss::NVSDK_NGX_Parameter_SetF(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_Jitter_Offset_Y), params.jitter_offset.1);
+        dlss::NVSDK_NGX_Parameter_SetF(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_MV_Scale_X), params.motion_vector_scale.0);
+        dlss::NVSDK_NGX_Parameter_SetF(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_MV_Scale_Y), params.motion_vector_scale.1);
+        dlss::NVSDK_NGX_Parameter_SetF(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_Sharpness), params.sharpness);
+        dlss::NVSDK_NGX_Parameter_SetI(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_Reset), if params.reset { 1 } else { 0 });
+        dlss::NVSDK_NGX_Parameter_SetUI(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_DLSS_Render_Subrect_Dimensions_Width), params.render_width);
+        dlss::NVSDK_NGX_Parameter_SetUI(self.parameters, param_name(dlss::NVSDK_NGX_Parameter_DLSS_Render_Subrect_Dimensions_Height), params.render_height);
+
+        let result = dlss::NVSDK_NGX_VULKAN_EvaluateFeature_C(command_buffer as dlss::VkCommandBuffer, self.handle, self.parameters, None);
+
+        //The resources only live for this call, so don't leave dangling pointers behind in the parameters
+        for name in [dlss::NVSDK_NGX_Parameter_Color, dlss::NVSDK_NGX_Parameter_Output, dlss::NVSDK_NGX_Parameter_Depth, dlss::NVSDK_NGX_Parameter_MotionVectors] {
+            dlss::NVSDK_NGX_Parameter_SetVoidPointer(self.parameters, param_name(name), ptr::null_mut());
+        }
+
+        check_result(result, "evaluate the DLSS feature")
+    }
+
+    //The feature must not be in use by any in-flight command buffer
+    pub unsafe fn release(&mut self) -> Result<()> {
+        let mut result = Ok(());
+        if !self.handle.is_null() {
+            result = check_result(dlss::NVSDK_NGX_VULKAN_ReleaseFeature(self.handle), "release the DLSS feature");
+            self.handle = ptr::null_mut();
+        }
+        if !self.parameters.is_null() {
+            dlss::NVSDK_NGX_VULKAN_DestroyParameters(self.parameters);
+            self.parameters = ptr::null_mut();
+        }
+
+        result
+    }
+}
@@ -0,0 +1,28 @@
+#version 450
+
+layout(binding = 1) uniform sampler2D texInput;
+
+layout(push_constant) uniform PushConstants {
+    vec4 params[2];
+    vec2 input_texel_size;
+    vec2 output_texel_size;
+} pcs;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+const float WEIGHTS[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main() {
+    float radius = pcs.params[0].z;
+    vec2 step_size = vec2(pcs.input_texel_size.x, 0.0) * radius;
+
+    vec3 color = texture(texInput, fragUv).rgb * WEIGHTS[0];
+    for (int q = 1; q < 5; q++) {
+        color += texture(texInput, clamp(fragUv + step_size * q, vec2(0.0), vec2(1.0))).rgb * WEIGHTS[q];
+        color += texture(texInput, clamp(fragUv - step_size * q, vec2(0.0), vec2(1.0))).rgb * WEIGHTS[q];
+    }
+
+    outColor = vec4(color, 1.0);
+}
@@ -0,0 +1,23 @@
+#version 450
+
+layout(binding = 1) uniform sampler2D texInput;
+layout(binding = 3) uniform sampler2D texEffectInput;
+
+layout(push_constant) uniform PushConstants {
+    vec4 params[2];
+    vec2 input_texel_size;
+    vec2 output_texel_size;
+} pcs;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    float intensity = pcs.params[0].y;
+
+    vec3 scene = texture(texEffectInput, fragUv).rgb;
+    vec3 bloom = texture(texInput, fragUv).rgb;
+
+    outColor = vec4(scene + bloom * intensity, 1.0);
+}
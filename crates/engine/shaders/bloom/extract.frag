@@ -0,0 +1,35 @@
+#version 450
+
+layout(binding = 1) uniform sampler2D texInput;
+
+layout(push_constant) uniform PushConstants {
+    vec4 params[2];
+    vec2 input_texel_size;
+    vec2 output_texel_size;
+} pcs;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    float threshold = pcs.params[0].x;
+
+    //The output is a quarter of the input's size, so average a 4x4 block of texels using 4 bilinear taps
+    vec2 offset = pcs.input_texel_size;
+    vec3 color = (
+        texture(texInput, clamp(fragUv + vec2(-offset.x, -offset.y), vec2(0.0), vec2(1.0))).rgb +
+        texture(texInput, clamp(fragUv + vec2(offset.x, -offset.y), vec2(0.0), vec2(1.0))).rgb +
+        texture(texInput, clamp(fragUv + vec2(-offset.x, offset.y), vec2(0.0), vec2(1.0))).rgb +
+        texture(texInput, clamp(fragUv + vec2(offset.x, offset.y), vec2(0.0), vec2(1.0))).rgb
+    ) * 0.25;
+
+    //Soft knee, so pixels don't pop in and out of the bloom as they cross the threshold
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    float knee = threshold * 0.5;
+    float soft = clamp(luminance - threshold + knee, 0.0, 2.0 * knee);
+    soft = (soft * soft) / (4.0 * knee + 0.0001);
+    float contribution = max(soft, luminance - threshold) / max(luminance, 0.0001);
+
+    outColor = vec4(color * contribution, 1.0);
+}
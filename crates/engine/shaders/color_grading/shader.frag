@@ -0,0 +1,29 @@
+#version 450
+
+layout(binding = 1) uniform sampler2D texInput;
+
+layout(push_constant) uniform PushConstants {
+    vec4 params[2];
+    vec2 input_texel_size;
+    vec2 output_texel_size;
+} pcs;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    float saturation = pcs.params[0].x;
+    float contrast = pcs.params[0].y;
+    vec3 tint = vec3(pcs.params[0].zw, pcs.params[1].x);
+
+    vec3 color = texture(texInput, fragUv).rgb * tint;
+
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    color = mix(vec3(luminance), color, saturation);
+
+    //Contrast pivots around middle grey so that it doesn't shift overall brightness
+    color = max((color - 0.18) * contrast + 0.18, vec3(0.0));
+
+    outColor = vec4(color, 1.0);
+}
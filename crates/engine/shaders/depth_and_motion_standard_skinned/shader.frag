@@ -0,0 +1,57 @@
+#version 450
+
+#define MAX_LIGHTS 32
+#define LIGHT_KIND_POINT 0u
+#define LIGHT_KIND_SPOT 1u
+#define MAX_SHADOW_CASCADES 4
+
+struct Light {
+    vec3 position;
+    float range;
+    vec3 direction;
+    uint kind;
+    vec3 color;
+    float inner_cone_cos;
+    vec3 attenuation;
+    float outer_cone_cos;
+};
+
+layout(binding = 0) uniform UniformBufferObject {
+    mat4 proj;
+    mat4 previous_proj;
+    vec3 ambient_light;
+    vec3 directional_light_direction;
+    vec3 directional_light_color;
+    vec2 resolution;
+    vec2 jitter;
+    float jitter_scale;
+    uint frame_index;
+    float time_in_seconds;
+    uint light_count;
+    Light lights[MAX_LIGHTS];
+    mat4 shadow_matrices[MAX_SHADOW_CASCADES];
+    vec4 shadow_cascade_splits;
+    uint shadow_cascade_count;
+    float shadow_bias;
+    vec2 shadow_texel_size;
+    //Each cascade's tile in the atlas as min xy, max xy, shrunk by a texel
+    vec4 shadow_cascade_rects[MAX_SHADOW_CASCADES];
+} ubo;
+
+layout(set = 1, binding = 0) uniform sampler2D tex[3];
+
+layout(push_constant) uniform PushConstants {
+    mat4 viewmodel;
+    mat4 previous_viewmodel;
+} pcs;
+
+layout(location = 0) in vec4 currentFragPositionClipSpace;
+layout(location = 1) in vec4 previousFragPositionClipSpace;
+
+layout(location = 0) out vec2 outMotionVector;
+
+void main() {
+    vec3 current_ndc = (currentFragPositionClipSpace / currentFragPositionClipSpace.w).xyz;
+    vec3 prev_ndc = (previousFragPositionClipSpace / previousFragPositionClipSpace.w).xyz;
+    outMotionVector = (current_ndc - prev_ndc).xy;
+}
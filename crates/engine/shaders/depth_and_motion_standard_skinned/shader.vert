@@ -0,0 +1,90 @@
+#version 450
+
+#define MAX_LIGHTS 32
+#define LIGHT_KIND_POINT 0u
+#define LIGHT_KIND_SPOT 1u
+#define MAX_SHADOW_CASCADES 4
+#define MAX_JOINTS 256
+
+struct Light {
+    vec3 position;
+    float range;
+    vec3 direction;
+    uint kind;
+    vec3 color;
+    float inner_cone_cos;
+    vec3 attenuation;
+    float outer_cone_cos;
+};
+
+layout(binding = 0) uniform UniformBufferObject {
+    mat4 proj;
+    mat4 previous_proj;
+    vec3 ambient_light;
+    vec3 directional_light_direction;
+    vec3 directional_light_color;
+    vec2 resolution;
+    vec2 jitter;
+    float jitter_scale;
+    uint frame_index;
+    float time_in_seconds;
+    uint light_count;
+    Light lights[MAX_LIGHTS];
+    mat4 shadow_matrices[MAX_SHADOW_CASCADES];
+    vec4 shadow_cascade_splits;
+    uint shadow_cascade_count;
+    float shadow_bias;
+    vec2 shadow_texel_size;
+    //Each cascade's tile in the atlas as min xy, max xy, shrunk by a texel
+    vec4 shadow_cascade_rects[MAX_SHADOW_CASCADES];
+} ubo;
+
+layout(push_constant) uniform PushConstants {
+    mat4 viewmodel;
+    mat4 previous_viewmodel;
+} pcs;
+
+layout(std430, set = 2, binding = 0) readonly buffer JointPalette {
+    mat4 joints[MAX_JOINTS];
+} joint_palette;
+
+//Last frame's pose, so animated vertices get motion vectors
+layout(std430, set = 2, binding = 1) readonly buffer PreviousJointPalette {
+    mat4 joints[MAX_JOINTS];
+} previous_joint_palette;
+
+layout(location = 0) in vec3 inPosition;
+layout(location = 1) in vec3 inNormal;
+layout(location = 2) in vec4 inTangent;
+layout(location = 3) in vec3 inColor;
+layout(location = 4) in vec2 inUv;
+layout(location = 5) in uvec4 inJoints;
+layout(location = 6) in vec4 inWeights;
+
+layout(location = 0) out vec4 currentFragPositionClipSpace;
+layout(location = 1) out vec4 previousFragPositionClipSpace;
+
+void main() {
+    mat4 jitter = mat4(1.0);
+    if (ubo.jitter_scale > 0) {
+        float deltaWidth = 1.0 / ubo.resolution.x;
+        float deltaHeight = 1.0 / ubo.resolution.y;
+        jitter[3][0] += ubo.jitter.x * deltaWidth * ubo.jitter_scale;
+        jitter[3][1] += ubo.jitter.y * deltaHeight * ubo.jitter_scale;
+    }
+
+    mat4 skin =
+        inWeights.x * joint_palette.joints[inJoints.x] +
+        inWeights.y * joint_palette.joints[inJoints.y] +
+        inWeights.z * joint_palette.joints[inJoints.z] +
+        inWeights.w * joint_palette.joints[inJoints.w];
+    mat4 previous_skin =
+        inWeights.x * previous_joint_palette.joints[inJoints.x] +
+        inWeights.y * previous_joint_palette.joints[inJoints.y] +
+        inWeights.z * previous_joint_palette.joints[inJoints.z] +
+        inWeights.w * previous_joint_palette.joints[inJoints.w];
+
+    gl_Position = jitter * ubo.proj * pcs.viewmodel * skin * vec4(inPosition, 1.0);
+    currentFragPositionClipSpace = gl_Position;
+    previousFragPositionClipSpace = (ubo.previous_proj * pcs.previous_viewmodel * previous_skin * vec4(inPosition, 1.0));
+}
@@ -1,36 +1,36 @@
 #version 450
 
-layout(binding = 0) uniform UniformBufferObject {
-    uint frame_index;
-    float time_in_seconds;
-    float exposure;
-} ubo;
-
-layout(binding = 1) uniform sampler2D texColor;
+layout(binding = 1) uniform sampler2D texInput;
 layout(binding = 2) uniform sampler2D texMotion;
 
+layout(push_constant) uniform PushConstants {
+    vec4 params[2];
+    vec2 input_texel_size;
+    vec2 output_texel_size;
+} pcs;
+
 layout(location = 0) in vec2 fragUv;
 
 layout(location = 0) out vec4 outColor;
 
+const int SAMPLE_COUNT = 9;
+
 void main() {
-    vec2 motion = texture(texMotion, fragUv).rg * -0.5;
-
-    vec3 samples = (texture(texColor, fragUv).rgb * 1.0) +
-        (texture(texColor, fragUv - motion * 0.125).rgb * 0.8) +
-        (texture(texColor, fragUv - motion * 0.25).rgb * 0.6) +
-        (texture(texColor, fragUv - motion * 0.375).rgb * 0.5) +
-        (texture(texColor, fragUv - motion * 0.5).rgb * 0.4) +
-        (texture(texColor, fragUv - motion * 0.625).rgb * 0.3) +
-        (texture(texColor, fragUv - motion * 0.75).rgb * 0.225) +
-        (texture(texColor, fragUv - motion * 0.875).rgb * 0.15) +
-        (texture(texColor, fragUv - motion * 1.0).rgb * 0.075);
-    samples /= 4.0;
-
-    //No motion blur
-    //vec3 samples = texture(texColor, fragUv).rgb;
-
-    samples /= 8.0; //TODO: no magic numbers like this
-    samples = vec3(1.0) - exp(-samples * ubo.exposure);
-    outColor = vec4(clamp(samples, 0.0, 1.0), 1.0);
+    float shutter = pcs.params[0].x;
+
+    //Motion vectors are current minus previous in NDC, which covers twice the range of UVs
+    vec2 motion = texture(texMotion, fragUv).rg * 0.5 * shutter;
+
+    //Samples further back along the motion contribute less, and the weights are normalized so brightness is preserved
+    vec3 color = vec3(0.0);
+    float total_weight = 0.0;
+    for (int q = 0; q < SAMPLE_COUNT; q++) {
+        float t = float(q) / float(SAMPLE_COUNT - 1);
+        float weight = 1.0 - t * 0.9;
+        vec2 sample_uv = clamp(fragUv - motion * t, vec2(0.0), vec2(1.0));
+        color += texture(texInput, sample_uv).rgb * weight;
+        total_weight += weight;
+    }
+
+    outColor = vec4(color / total_weight, 1.0);
 }
@@ -0,0 +1,77 @@
+#version 450
+
+//Must match OVERLAY_COLUMNS and OVERLAY_ROWS in uniform_buffer_object.rs
+#define OVERLAY_COLUMNS 40
+#define OVERLAY_ROWS 16
+
+#define GLYPH_WIDTH 3
+#define GLYPH_HEIGHT 5
+//One font pixel of spacing between glyphs and between rows
+#define CELL_WIDTH 4
+#define CELL_HEIGHT 6
+
+layout(binding = 0) uniform OverlayUniformBufferObject {
+    vec2 origin;
+    float scale;
+    uint row_count;
+    uint encode_pq;
+    float paper_white_nits;
+    uvec4 glyphs[16];
+    uvec4 text[OVERLAY_COLUMNS * OVERLAY_ROWS / 16];
+} ubo;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+//SMPTE ST 2084 inverse EOTF. Takes absolute luminance in nits
+float pq_encode(float nits) {
+    const float m1 = 2610.0 / 16384.0;
+    const float m2 = 2523.0 / 4096.0 * 128.0;
+    const float c1 = 3424.0 / 4096.0;
+    const float c2 = 2413.0 / 4096.0 * 32.0;
+    const float c3 = 2392.0 / 4096.0 * 32.0;
+
+    float y = pow(clamp(nits / 10000.0, 0.0, 1.0), m1);
+    return pow((c1 + c2 * y) / (1.0 + c3 * y), m2);
+}
+
+bool is_lit(ivec2 font_pixel) {
+    if (any(lessThan(font_pixel, ivec2(0)))) {
+        return false;
+    }
+
+    ivec2 cell = font_pixel / ivec2(CELL_WIDTH, CELL_HEIGHT);
+    ivec2 glyph_pixel = font_pixel - cell * ivec2(CELL_WIDTH, CELL_HEIGHT);
+    if (cell.x >= OVERLAY_COLUMNS || cell.y >= int(ubo.row_count) || glyph_pixel.x >= GLYPH_WIDTH || glyph_pixel.y >= GLYPH_HEIGHT) {
+        return false;
+    }
+
+    uint char_index = uint(cell.y * OVERLAY_COLUMNS + cell.x);
+    uint packed = ubo.text[char_index / 16u][(char_index / 4u) % 4u];
+    uint glyph_index = (packed >> ((char_index % 4u) * 8u)) & 0xFFu;
+    uint glyph = ubo.glyphs[glyph_index / 4u][glyph_index % 4u];
+    uint bit = uint(glyph_pixel.y * GLYPH_WIDTH + glyph_pixel.x);
+
+    return ((glyph >> bit) & 1u) != 0u;
+}
+
+void main() {
+    //Measured in font pixels from the top left of the text, with a font pixel of padding around it for the background
+    vec2 local = (gl_FragCoord.xy - ubo.origin) / ubo.scale;
+    vec2 box_size = vec2(OVERLAY_COLUMNS * CELL_WIDTH, float(ubo.row_count) * CELL_HEIGHT);
+    if (ubo.row_count == 0u || any(lessThan(local, vec2(-1.0))) || any(greaterThanEqual(local, box_size))) {
+        discard;
+    }
+
+    bool lit = is_lit(ivec2(floor(local)));
+    float value = lit ? 1.0 : 0.0;
+    float alpha = lit ? 1.0 : 0.6;
+
+    if (ubo.encode_pq != 0u) {
+        //The swapchain is HDR10, so white text has to be PQ encoded at paper white to not be blinding
+        value = pq_encode(value * ubo.paper_white_nits);
+    }
+
+    outColor = vec4(vec3(value), alpha);
+}
@@ -0,0 +1,53 @@
+#version 450
+
+layout(binding = 0) uniform UniformBufferObject {
+    uint frame_index;
+    float time_in_seconds;
+    uint tone_mapping_operator;
+    uint auto_exposure;
+    float manual_exposure;
+    float exposure_compensation;
+    float output_headroom;
+    uint encode_pq;
+    float paper_white_nits;
+    float max_nits;
+} ubo;
+
+layout(binding = 1) uniform sampler2D texColor;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+//Converts linear Rec. 709 primaries to Rec. 2020, which is what HDR10 expects
+const mat3 REC709_TO_REC2020 = mat3(
+    0.6274040, 0.0690970, 0.0163916,
+    0.3292820, 0.9195400, 0.0880132,
+    0.0433136, 0.0113612, 0.8955950
+);
+
+//SMPTE ST 2084 inverse EOTF. Takes absolute luminance in nits
+vec3 pq_encode(vec3 nits) {
+    const float m1 = 2610.0 / 16384.0;
+    const float m2 = 2523.0 / 4096.0 * 128.0;
+    const float c1 = 3424.0 / 4096.0;
+    const float c2 = 2413.0 / 4096.0 * 32.0;
+    const float c3 = 2392.0 / 4096.0 * 32.0;
+
+    vec3 y = pow(clamp(nits / 10000.0, 0.0, 1.0), vec3(m1));
+    return pow((c1 + c2 * y) / (1.0 + c3 * y), vec3(m2));
+}
+
+void main() {
+    //Whatever the postprocess effects produced is written out as-is. Tone mapping is one of the effects, which the builder adds if it's missing
+    vec3 color = max(texture(texColor, fragUv).rgb, vec3(0.0));
+
+    if (ubo.encode_pq != 0u) {
+        //1.0 is paper white. The swapchain format is UNORM, so the encoding has to happen here
+        vec3 nits = min(REC709_TO_REC2020 * color * ubo.paper_white_nits, vec3(ubo.max_nits));
+        outColor = vec4(pq_encode(nits), 1.0);
+    } else {
+        //The sRGB swapchain format applies the transfer function when the value is stored
+        outColor = vec4(clamp(color, 0.0, 1.0), 1.0);
+    }
+}
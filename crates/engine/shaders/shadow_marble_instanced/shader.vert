@@ -0,0 +1,12 @@
+#version 450
+
+layout(push_constant) uniform PushConstants {
+    mat4 shadow_viewmodel;
+} pcs;
+
+layout(location = 0) in vec3 inPosition;
+layout(location = 3) in mat4 inTransform;
+
+void main() {
+    gl_Position = pcs.shadow_viewmodel * inTransform * vec4(inPosition, 1.0);
+}
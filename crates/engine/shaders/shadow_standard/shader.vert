@@ -0,0 +1,11 @@
+#version 450
+
+layout(push_constant) uniform PushConstants {
+    mat4 shadow_viewmodel;
+} pcs;
+
+layout(location = 0) in vec3 inPosition;
+
+void main() {
+    gl_Position = pcs.shadow_viewmodel * vec4(inPosition, 1.0);
+}
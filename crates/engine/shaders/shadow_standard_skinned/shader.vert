@@ -0,0 +1,26 @@
+#version 450
+
+#define MAX_JOINTS 256
+
+layout(push_constant) uniform PushConstants {
+    mat4 shadow_viewmodel;
+} pcs;
+
+//The shadow pipeline layout has no other sets
+layout(std430, set = 0, binding = 0) readonly buffer JointPalette {
+    mat4 joints[MAX_JOINTS];
+} joint_palette;
+
+layout(location = 0) in vec3 inPosition;
+layout(location = 5) in uvec4 inJoints;
+layout(location = 6) in vec4 inWeights;
+
+void main() {
+    mat4 skin =
+        inWeights.x * joint_palette.joints[inJoints.x] +
+        inWeights.y * joint_palette.joints[inJoints.y] +
+        inWeights.z * joint_palette.joints[inJoints.z] +
+        inWeights.w * joint_palette.joints[inJoints.w];
+
+    gl_Position = pcs.shadow_viewmodel * skin * vec4(inPosition, 1.0);
+}
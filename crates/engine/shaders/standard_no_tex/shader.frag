@@ -1,5 +1,21 @@
 #version 450
 
+#define MAX_LIGHTS 32
+#define LIGHT_KIND_POINT 0u
+#define LIGHT_KIND_SPOT 1u
+#define MAX_SHADOW_CASCADES 4
+
+struct Light {
+    vec3 position;
+    float range;
+    vec3 direction;
+    uint kind;
+    vec3 color;
+    float inner_cone_cos;
+    vec3 attenuation;
+    float outer_cone_cos;
+};
+
 layout(binding = 0) uniform UniformBufferObject {
     mat4 proj;
     mat4 previous_proj;
@@ -11,8 +27,19 @@ layout(binding = 0) uniform UniformBufferObject {
     float jitter_scale;
     uint frame_index;
     float time_in_seconds;
+    uint light_count;
+    Light lights[MAX_LIGHTS];
+    mat4 shadow_matrices[MAX_SHADOW_CASCADES];
+    vec4 shadow_cascade_splits;
+    uint shadow_cascade_count;
+    float shadow_bias;
+    vec2 shadow_texel_size;
+    //Each cascade's tile in the atlas as min xy, max xy, shrunk by a texel
+    vec4 shadow_cascade_rects[MAX_SHADOW_CASCADES];
 } ubo;
 
+layout(binding = 2) uniform sampler2DShadow shadow_map;
+
 layout(push_constant) uniform PushConstants {
     mat4 viewmodel;
     mat4 normal_viewmodel;
@@ -24,22 +51,90 @@ layout(location = 2) in vec3 fragColor;
 
 layout(location = 0) out vec4 outColor;
 
+float calculate_shadow(vec3 position) {
+    uint cascade_count = min(ubo.shadow_cascade_count, uint(MAX_SHADOW_CASCADES));
+
+    uint cascade = cascade_count;
+    for (uint i = 0u; i < cascade_count; i++) {
+        if (position.z <= ubo.shadow_cascade_splits[i]) {
+            cascade = i;
+            break;
+        }
+    }
+    if (cascade >= cascade_count) {
+        return 1.0;
+    }
+
+    vec4 shadow_coord = ubo.shadow_matrices[cascade] * vec4(position, 1.0);
+    shadow_coord.xyz /= shadow_coord.w;
+
+    //3x3 PCF on top of the hardware's bilinear comparison, kept inside the cascade's tile so the edges don't sample the neighbouring cascade
+    vec4 rect = ubo.shadow_cascade_rects[cascade];
+    float shadow = 0.0;
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            vec2 offset = vec2(x, y) * ubo.shadow_texel_size;
+            vec2 uv = clamp(shadow_coord.xy + offset, rect.xy, rect.zw);
+            shadow += texture(shadow_map, vec3(uv, shadow_coord.z - ubo.shadow_bias));
+        }
+    }
+
+    return shadow / 9.0;
+}
+
+vec3 calculate_lights(vec3 position, vec3 normal, vec3 view_direction, vec3 mat_specular_color, float mat_shininess) {
+    vec3 light_color = vec3(0.0);
+
+    for (uint i = 0u; i < min(ubo.light_count, uint(MAX_LIGHTS)); i++) {
+        Light light = ubo.lights[i];
+
+        vec3 to_light = light.position - position;
+        float light_distance = length(to_light);
+        if (light_distance >= light.range) {
+            continue;
+        }
+        to_light /= light_distance;
+
+        float attenuation = 1.0 / max(light.attenuation.x + light.attenuation.y * light_distance + light.attenuation.z * light_distance * light_distance, 0.0001);
+        //Fade out smoothly at the range so lights don't pop
+        float range_falloff = clamp(1.0 - pow(light_distance / light.range, 4.0), 0.0, 1.0);
+        attenuation *= range_falloff * range_falloff;
+
+        if (light.kind == LIGHT_KIND_SPOT) {
+            float cone_cos = dot(-to_light, light.direction);
+            attenuation *= smoothstep(light.outer_cone_cos, light.inner_cone_cos, cone_cos);
+        }
+
+        float diffuse_amt = max(dot(normal, to_light), 0.0);
+        light_color += light.color * diffuse_amt * attenuation;
+
+        float specular_factor = max(0.0, dot(reflect(to_light, normal), view_direction));
+        light_color += mat_specular_color * pow(specular_factor, mat_shininess) * attenuation;
+    }
+
+    return light_color;
+}
+
 void main() {
     vec3 light_color = ubo.ambient_light;
 
     vec3 normal = normalize(fragNormal);
 
+    float shadow = calculate_shadow(currentFragPositionCameraSpace.xyz);
+
     float directional_amt = max(dot(normal, -ubo.directional_light_direction), 0.0);
-    light_color += ubo.directional_light_color * directional_amt;
+    light_color += ubo.directional_light_color * directional_amt * shadow;
 
     vec3 mat_specular_color = vec3(7.5, 7.5, 7.5);
     float mat_shininess = 20.0;
 
     vec3 view_direction = normalize(currentFragPositionCameraSpace.xyz);
     float specular_factor = max(0.0, dot(reflect(-ubo.directional_light_direction, normal), view_direction));
-    vec3 specular_color = mat_specular_color * pow(specular_factor, mat_shininess);
+    vec3 specular_color = mat_specular_color * pow(specular_factor, mat_shininess) * shadow;
     light_color += specular_color;
 
+    light_color += calculate_lights(currentFragPositionCameraSpace.xyz, normal, view_direction, mat_specular_color, mat_shininess);
+
     //Diagnose lights
     // outColor = vec4(light_color, 1.0);
 
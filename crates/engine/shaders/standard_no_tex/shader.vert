@@ -1,5 +1,21 @@
 #version 450
 
+#define MAX_LIGHTS 32
+#define LIGHT_KIND_POINT 0u
+#define LIGHT_KIND_SPOT 1u
+#define MAX_SHADOW_CASCADES 4
+
+struct Light {
+    vec3 position;
+    float range;
+    vec3 direction;
+    uint kind;
+    vec3 color;
+    float inner_cone_cos;
+    vec3 attenuation;
+    float outer_cone_cos;
+};
+
 layout(binding = 0) uniform UniformBufferObject {
     mat4 proj;
     mat4 previous_proj;
@@ -11,6 +27,15 @@ layout(binding = 0) uniform UniformBufferObject {
     float jitter_scale;
     uint frame_index;
     float time_in_seconds;
+    uint light_count;
+    Light lights[MAX_LIGHTS];
+    mat4 shadow_matrices[MAX_SHADOW_CASCADES];
+    vec4 shadow_cascade_splits;
+    uint shadow_cascade_count;
+    float shadow_bias;
+    vec2 shadow_texel_size;
+    //Each cascade's tile in the atlas as min xy, max xy, shrunk by a texel
+    vec4 shadow_cascade_rects[MAX_SHADOW_CASCADES];
 } ubo;
 
 layout(push_constant) uniform PushConstants {
@@ -38,6 +63,6 @@ void main() {
     currentFragPositionCameraSpace = pcs.viewmodel * vec4(inPosition, 1.0);
     gl_Position = jitter * ubo.proj * currentFragPositionCameraSpace;
 
-    fragNormal = normalize((pcs.normal_viewmodel * vec4(inNormal, 1.0)).rgb);
+    fragNormal = normalize((pcs.normal_viewmodel * vec4(inNormal, 0.0)).rgb);
     fragColor = inColor;
 }
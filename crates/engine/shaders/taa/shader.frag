@@ -0,0 +1,77 @@
+#version 450
+
+layout(binding = 0) uniform sampler2D texColor;
+layout(binding = 1) uniform sampler2D texMotion;
+layout(binding = 2) uniform sampler2D texHistory;
+
+layout(push_constant) uniform PushConstants {
+    vec2 jitter;
+    vec2 render_texel_size;
+    float history_weight;
+    uint has_history;
+} pcs;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+vec3 rgb_to_ycocg(vec3 c) {
+    return vec3(
+        0.25 * c.r + 0.5 * c.g + 0.25 * c.b,
+        0.5 * c.r - 0.5 * c.b,
+        -0.25 * c.r + 0.5 * c.g - 0.25 * c.b
+    );
+}
+
+vec3 ycocg_to_rgb(vec3 c) {
+    return vec3(
+        c.x + c.y - c.z,
+        c.x + c.z,
+        c.x - c.y - c.z
+    );
+}
+
+void main() {
+    //The base render was jittered, so this pixel's unjittered position ended up offset by the jitter
+    vec2 uv = fragUv + pcs.jitter;
+    vec3 current = texture(texColor, uv).rgb;
+
+    if (pcs.has_history == 0u) {
+        outColor = vec4(current, 1.0);
+        return;
+    }
+
+    //Motion vectors are current minus previous in NDC, and include this frame's jitter
+    vec2 motion = texture(texMotion, uv).rg;
+    vec2 history_uv = fragUv - (motion * 0.5) + pcs.jitter;
+    if (any(lessThan(history_uv, vec2(0.0))) || any(greaterThan(history_uv, vec2(1.0)))) {
+        outColor = vec4(current, 1.0);
+        return;
+    }
+
+    //Clamp the history to the current frame's neighborhood so that disoccluded or changed pixels don't ghost
+    vec3 moment1 = vec3(0.0);
+    vec3 moment2 = vec3(0.0);
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            vec2 sample_uv = clamp(uv + vec2(x, y) * pcs.render_texel_size, vec2(0.0), vec2(1.0));
+            vec3 neighbor = rgb_to_ycocg(texture(texColor, sample_uv).rgb);
+            moment1 += neighbor;
+            moment2 += neighbor * neighbor;
+        }
+    }
+    vec3 mean = moment1 / 9.0;
+    vec3 deviation = sqrt(max(moment2 / 9.0 - mean * mean, vec3(0.0)));
+    vec3 neighborhood_min = mean - deviation * 1.25;
+    vec3 neighborhood_max = mean + deviation * 1.25;
+
+    vec3 history = rgb_to_ycocg(texture(texHistory, history_uv).rgb);
+    history = ycocg_to_rgb(clamp(history, neighborhood_min, neighborhood_max));
+
+    //Weighting by inverse luminance keeps bright, HDR samples from dominating the blend and flickering
+    float current_weight = (1.0 - pcs.history_weight) / (1.0 + dot(current, vec3(0.2126, 0.7152, 0.0722)));
+    float history_weight = pcs.history_weight / (1.0 + dot(history, vec3(0.2126, 0.7152, 0.0722)));
+    vec3 resolved = (current * current_weight + history * history_weight) / max(current_weight + history_weight, 0.0001);
+
+    outColor = vec4(max(resolved, vec3(0.0)), 1.0);
+}
@@ -0,0 +1,122 @@
+#version 450
+
+layout(binding = 0) uniform UniformBufferObject {
+    uint frame_index;
+    float time_in_seconds;
+    uint tone_mapping_operator;
+    uint auto_exposure;
+    float manual_exposure;
+    float exposure_compensation;
+    float output_headroom;
+    uint encode_pq;
+    float paper_white_nits;
+    float max_nits;
+} ubo;
+
+layout(binding = 1) uniform sampler2D texInput;
+
+layout(std430, binding = 4) readonly buffer ExposureState {
+    float adapted_luminance;
+} exposure_state;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+const uint OPERATOR_REINHARD = 0;
+const uint OPERATOR_ACES = 1;
+const uint OPERATOR_AGX = 2;
+const uint OPERATOR_FILMIC = 3;
+
+//Auto exposure maps the average luminance to middle grey
+const float MIDDLE_GREY = 0.18;
+
+vec3 reinhard(vec3 v) {
+    return v / (1.0 + v);
+}
+
+vec3 aces_approx(vec3 v) {
+    v *= 0.6f;
+    float a = 2.51f;
+    float b = 0.03f;
+    float c = 2.43f;
+    float d = 0.59f;
+    float e = 0.14f;
+    return clamp((v*(a*v+b))/(v*(c*v+d)+e), 0.0f, 1.0f);
+}
+
+//Polynomial fit of the AgX base contrast curve, from Benjamin Wrensch's minimal AgX
+vec3 agx_default_contrast(vec3 x) {
+    vec3 x2 = x * x;
+    vec3 x4 = x2 * x2;
+    return 15.5 * x4 * x2
+        - 40.14 * x4 * x
+        + 31.96 * x4
+        - 6.868 * x2 * x
+        + 0.4298 * x2
+        + 0.1191 * x
+        - 0.00232;
+}
+
+vec3 agx(vec3 v) {
+    const mat3 agx_inset = mat3(
+        0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+        0.0784335999999992, 0.878468636469772, 0.0784336,
+        0.0792237451477643, 0.0791661274605434, 0.879142973793104
+    );
+    const mat3 agx_outset = mat3(
+        1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+        -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+        -0.0990297440797205, -0.0989611768448433, 1.15107367264116
+    );
+    const float min_ev = -12.47393;
+    const float max_ev = 4.026069;
+
+    v = agx_inset * v;
+    v = clamp(log2(max(v, vec3(1e-10))), min_ev, max_ev);
+    v = (v - min_ev) / (max_ev - min_ev);
+    v = agx_default_contrast(v);
+    v = agx_outset * v;
+
+    //The curve produces display-encoded values, but the swapchain expects linear ones
+    return pow(max(v, vec3(0.0)), vec3(2.2));
+}
+
+vec3 hable_partial(vec3 x) {
+    float a = 0.15;
+    float b = 0.50;
+    float c = 0.10;
+    float d = 0.20;
+    float e = 0.02;
+    float f = 0.30;
+    return ((x*(a*x+c*b)+d*e)/(x*(a*x+b)+d*f))-e/f;
+}
+
+vec3 filmic(vec3 v) {
+    const float exposure_bias = 2.0;
+    const vec3 white_point = vec3(11.2);
+    return clamp(hable_partial(v * exposure_bias) / hable_partial(white_point), 0.0, 1.0);
+}
+
+void main() {
+    float exposure = ubo.manual_exposure;
+    if (ubo.auto_exposure != 0u) {
+        exposure = MIDDLE_GREY / max(exposure_state.adapted_luminance, 0.0001);
+    }
+    exposure *= exp2(ubo.exposure_compensation);
+
+    //On an HDR display the curves are stretched over the headroom above paper white, rather than stopping at 1.0
+    vec3 color = max(texture(texInput, fragUv).rgb * exposure, vec3(0.0)) / ubo.output_headroom;
+
+    if (ubo.tone_mapping_operator == OPERATOR_REINHARD) {
+        color = reinhard(color);
+    } else if (ubo.tone_mapping_operator == OPERATOR_AGX) {
+        color = agx(color);
+    } else if (ubo.tone_mapping_operator == OPERATOR_FILMIC) {
+        color = filmic(color);
+    } else {
+        color = aces_approx(color);
+    }
+
+    outColor = vec4(color * ubo.output_headroom, 1.0);
+}
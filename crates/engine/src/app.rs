@@ -1,7 +1,11 @@
 use std::{
     collections::{HashSet},
     ffi::{CStr},
-    time::{Instant},
+    fs,
+    mem::{size_of},
+    path::{Path},
+    thread,
+    time::{Instant, Duration},
     sync::{
         Arc,
         atomic::{AtomicBool, Ordering}
@@ -13,7 +17,7 @@ use winit::{
     dpi::{LogicalSize},
     window::{Window, WindowBuilder},
     event_loop::{EventLoop, ControlFlow},
-    event::{Event, WindowEvent, VirtualKeyCode}
+    event::{Event, WindowEvent, VirtualKeyCode, ElementState}
 };
 use vulkanalia::{
     loader::{LibloadingLoader, LIBRARY},
@@ -22,14 +26,25 @@ use vulkanalia::{
     vk::{KhrSurfaceExtension, StringArray, KhrSwapchainExtension}
 };
 use nalgebra_glm as glm;
+use dlss_sys::super_sampling::{EvaluateParameters};
 
 use crate::{
     app_data::{AppData, VulkanQueueInfo},
-    bootstrap::{BootstrapLoader, QueueFamilyIndices},
-    shader_input::uniform_buffer_object::{UniformBufferObject, PostprocessingUniformBufferObject},
-    game::scene::{Scene},
+    bootstrap::{BootstrapLoader, QueueFamilyIndices, DlssInfo, TaaInfo, HdrSettings, PresentMode},
+    shader_input::{
+        uniform_buffer_object::{UniformBufferObject, PostprocessingUniformBufferObject, OverlayUniformBufferObject, LightUniform, MAX_LIGHTS, MAX_SHADOW_CASCADES},
+        push_constants::{TaaPushConstants, PostprocessPushConstants, AutoExposurePushConstants}
+    },
+    postprocessing::{PostprocessEffect, PostprocessImage},
+    game::{
+        scene::{Scene},
+        post_settings::{ExposureMode, AutoExposureSettings},
+        raycast::{Ray, RaycastHit}
+    },
+    input::{InputState},
     frame_info::{FrameInfo},
-    resources::{SingleFrameRenderInfo, ResourceLoader, SingleModelRenderInfo, Material},
+    frame_stats::{FrameStatsCollector, FrameStats, CpuPhase, GpuPass},
+    resources::{SingleFrameRenderInfo, ResourceLoader, SingleModelRenderInfo, ModelRenderPass, ShadowRenderInfo, Material, Image2D, Buffer, FrameReadback, ScreenshotSource, ScreenshotSettings, ScreenshotRequest, ScreenshotCapture, write_screenshot},
     util::{
         jitter_generator::{JitterGenerator},
         {vec_from_hash_set}
@@ -43,7 +58,8 @@ pub struct GraphicsCardSuitabilityError(pub &'static str);
 #[derive(Debug)]
 pub struct App {
     pub event_loop: Option<EventLoop<()>>,
-    pub window: Window,
+    //None when headless
+    pub window: Option<Window>,
     pub app_data: AppData,
     //entry can't be disposed of before inst or it causes a segmentation fault when you attempt to use inst
     #[allow(unused)]
@@ -56,34 +72,63 @@ pub struct App {
     pub resource_loader: ResourceLoader,
     pub frame_info: FrameInfo,
     pub jitter_info: JitterGenerator,
+    pub input: InputState,
 
     destroying: bool,
     needs_new_swapchain: bool,
-    shutdown_requested: Arc<AtomicBool>
+    shutdown_requested: Arc<AtomicBool>,
+    //Frames per second. None renders as fast as the present mode allows
+    frame_rate_limit: Option<f32>,
+
+    //Readback is only supported when headless
+    readback_every_frame: bool,
+    readback_requested: bool,
+    last_readback: Option<FrameReadback>,
+
+    screenshot_settings: ScreenshotSettings,
+    pending_screenshot: Option<ScreenshotRequest>,
+    screenshot_capture: Option<ScreenshotCapture>,
+
+    frame_stats: FrameStatsCollector,
+    stats_overlay_visible: bool
 }
 
 impl App {
-    pub fn create(initial_title: &'static str, default_size: LogicalSize<i32>, bootstrap_loaders: Vec<Box<dyn BootstrapLoader>>) -> Result<Self> {
-        debug!("Creating window and window event loop...");
-        let event_loop = EventLoop::new();
-        let window = WindowBuilder::new()
-            .with_title(initial_title)
-            .with_inner_size(default_size)
-            .build(&event_loop)?;
+    pub fn create(initial_title: &'static str, default_size: LogicalSize<i32>, bootstrap_loaders: Vec<Box<dyn BootstrapLoader>>, postprocess_effects: Vec<PostprocessEffect>, hdr_settings: HdrSettings, headless_extent: Option<vk::Extent2D>, screenshot_settings: ScreenshotSettings, present_mode: PresentMode, frame_rate_limit: Option<f32>, show_stats_overlay: bool) -> Result<Self> {
+        //Headless apps never touch the windowing system, so they can run on machines without a display
+        let (event_loop, window) = if headless_extent.is_none() {
+            debug!("Creating window and window event loop...");
+            let event_loop = EventLoop::new();
+            let window = WindowBuilder::new()
+                .with_title(initial_title)
+                .with_inner_size(default_size)
+                .build(&event_loop)?;
+
+            (Some(event_loop), Some(window))
+        } else {
+            debug!("Running headless. No window will be created.");
+            (None, None)
+        };
 
         let mut app_data = AppData::default();
+        app_data.postprocess_effects = postprocess_effects;
+        app_data.hdr_settings = hdr_settings;
+        app_data.headless_extent = headless_extent;
+        app_data.present_mode = present_mode;
 
         let inst: Instance;
         let entry: Entry;
         unsafe {
             let loader = LibloadingLoader::new(LIBRARY)?;
             entry = Entry::new(loader).map_err(|b| anyhow!("{}", b))?;
-            inst = Self::create_instance(initial_title, &bootstrap_loaders, &window, &mut app_data, &entry)?;
+            inst = Self::create_instance(initial_title, &bootstrap_loaders, window.as_ref(), &mut app_data, &entry)?;
         }
 
-        unsafe {
-            debug!("Creating Vulkan surface KHR...");
-            app_data.surface = Some(vk_window::create_surface(&inst, &window)?);
+        if let Some(window) = window.as_ref() {
+            unsafe {
+                debug!("Creating Vulkan surface KHR...");
+                app_data.surface = Some(vk_window::create_surface(&inst, window)?);
+            }
         }
 
         let device: Device;
@@ -102,14 +147,14 @@ impl App {
         }
 
         for loader in bootstrap_loaders.iter() {
-            loader.after_create_logical_device(&inst, &device, &window, &mut app_data)?;
+            loader.after_create_logical_device(&inst, &device, window.as_ref(), &mut app_data)?;
         }
 
         let scene = Scene::new();
-        let resource_loader = ResourceLoader::new(device.clone(), app_data.memory_properties);
+        let resource_loader = ResourceLoader::new(device.clone(), app_data.memory_properties, app_data.max_frames_in_flight())?;
 
         let app = Self {
-            event_loop: Some(event_loop),
+            event_loop,
             window,
             app_data,
             entry,
@@ -121,16 +166,29 @@ impl App {
             resource_loader,
             frame_info: FrameInfo::default(),
             jitter_info: Default::default(),
+            input: InputState::new(),
 
             destroying: false,
             needs_new_swapchain: false,
-            shutdown_requested: Arc::new(AtomicBool::new(false))
+            shutdown_requested: Arc::new(AtomicBool::new(false)),
+            frame_rate_limit,
+
+            readback_every_frame: false,
+            readback_requested: false,
+            last_readback: None,
+
+            screenshot_settings,
+            pending_screenshot: None,
+            screenshot_capture: None,
+
+            frame_stats: Default::default(),
+            stats_overlay_visible: show_stats_overlay
         };
 
         Ok(app)
     }
 
-    unsafe fn create_instance(initial_title: &str, bootstrap_loaders: &Vec<Box<dyn BootstrapLoader>>, window: &Window, app_data: &mut AppData, entry: &Entry) -> Result<Instance> {
+    unsafe fn create_instance(initial_title: &str, bootstrap_loaders: &Vec<Box<dyn BootstrapLoader>>, window: Option<&Window>, app_data: &mut AppData, entry: &Entry) -> Result<Instance> {
         debug!("Selecting instance extensions and layers, and creating instance...");
         let mut zero_terminated: String = "".to_owned();
         zero_terminated.push_str(initial_title);
@@ -146,10 +204,15 @@ impl App {
 
         let mut request_layers_ptrs = HashSet::new();
 
-        let mut request_extensions_ptrs = vk_window::get_required_instance_extensions(window)
-            .iter()
-            .map(|n| n.as_ptr())
-            .collect::<HashSet<_>>();
+        let mut request_extensions_ptrs = HashSet::new();
+        if let Some(window) = window {
+            request_extensions_ptrs.extend(vk_window::get_required_instance_extensions(window)
+                .iter()
+                .map(|n| n.as_ptr()));
+
+            //Surfaces only report the HDR10 color space when this is enabled
+            request_extensions_ptrs.insert(vk::EXT_SWAPCHAIN_COLORSPACE_EXTENSION.name.as_ptr());
+        }
 
         for loader in bootstrap_loaders.iter() {
             loader.add_required_instance_layers(&mut request_layers_ptrs)?;
@@ -267,7 +330,7 @@ impl App {
         if let None = queue_family_indices.graphics {
             return Err(anyhow!(GraphicsCardSuitabilityError("No queue family on this physical device supports graphics operations.")));
         }
-        if let None = queue_family_indices.present {
+        if app_data.surface.is_some() && queue_family_indices.present.is_none() {
             return Err(anyhow!(GraphicsCardSuitabilityError("No queue family on this physical device supports KHR present operations.")));
         }
 
@@ -280,7 +343,8 @@ impl App {
 
         let mut unique_queue_families = HashSet::new();
         let graphics_queue_family = indices.graphics.unwrap();
-        let present_queue_family = indices.present.unwrap();
+        //Headless apps never present, so the graphics queue stands in for the present queue
+        let present_queue_family = indices.present.unwrap_or(graphics_queue_family);
         unique_queue_families.insert(graphics_queue_family);
         unique_queue_families.insert(present_queue_family);
 
@@ -381,17 +445,17 @@ impl App {
 
         debug!("Recreating swapchain and related resources (possibly due to window resize)...");
 
-        let last_callback = move |_inst: &Instance, _device: &Device, _window: &Window, _app_data: &mut AppData| -> Result<()> {
+        let last_callback = move |_inst: &Instance, _device: &Device, _window: Option<&Window>, _app_data: &mut AppData| -> Result<()> {
             Ok(())
         };
 
-        fn create_and_invoke_callback(index: usize, bootstrap_loaders: &Vec<Box<dyn BootstrapLoader>>, last_callback: &dyn Fn(&Instance, &Device, &Window, &mut AppData) -> Result<()>, inst: &Instance, device: &Device, window: &Window, app_data: &mut AppData) -> Result<()> {
+        fn create_and_invoke_callback(index: usize, bootstrap_loaders: &Vec<Box<dyn BootstrapLoader>>, last_callback: &dyn Fn(&Instance, &Device, Option<&Window>, &mut AppData) -> Result<()>, inst: &Instance, device: &Device, window: Option<&Window>, app_data: &mut AppData) -> Result<()> {
             trace!("Invoking callback for index {} to recreate swapchain and related resources...", index);
             let loader_count = bootstrap_loaders.len();
             let loader_res = if index == loader_count { None } else { bootstrap_loaders.get(loader_count - index - 1) };
             match loader_res {
                 Some(loader) => {
-                    let next_callback = |inst: &Instance, device: &Device, window: &Window, app_data: &mut AppData| create_and_invoke_callback(index + 1, bootstrap_loaders, last_callback, inst, device, window, app_data);
+                    let next_callback = |inst: &Instance, device: &Device, window: Option<&Window>, app_data: &mut AppData| create_and_invoke_callback(index + 1, bootstrap_loaders, last_callback, inst, device, window, app_data);
                     loader.recreate_swapchain(inst, device, window, app_data, &next_callback)
                 },
                 None => {
@@ -400,7 +464,7 @@ impl App {
             }
         }
 
-        create_and_invoke_callback(0, &self.bootstrap_loaders, &last_callback, &self.inst, &self.device, &self.window, &mut self.app_data)?;
+        create_and_invoke_callback(0, &self.bootstrap_loaders, &last_callback, &self.inst, &self.device, self.window.as_ref(), &mut self.app_data)?;
 
         self.needs_new_swapchain = false;
 
@@ -416,6 +480,10 @@ impl App {
             self.set_ctrlc_handler(self.shutdown_requested.clone());
         }
 
+        if self.is_headless() {
+            self.run_headless();
+        }
+
         //TODO: Don't abuse Option<> in the struct in order to call run on the event loop without causing an ownership error
         let event_loop = self.event_loop.take().unwrap();
         event_loop.run(move |event, _, control_flow| {
@@ -445,15 +513,26 @@ impl App {
                     info!("Window close requested. Shutting down application...");
                     self.shutdown();
                 }
-                Event::WindowEvent { event: WindowEvent::KeyboardInput { input, .. }, .. } => {
-                    //TODO: pass keyboard input to game state
-
-                    if let Some(keycode) = input.virtual_keycode {
-                        if keycode == VirtualKeyCode::Escape {
-                            info!("Escape key pressed. Shutting down application...");
-                            self.shutdown();
+                Event::WindowEvent { event, .. } => {
+                    if let WindowEvent::KeyboardInput { input, .. } = &event {
+                        if let Some(keycode) = input.virtual_keycode {
+                            if keycode == VirtualKeyCode::Escape {
+                                info!("Escape key pressed. Shutting down application...");
+                                self.shutdown();
+                            } else if input.state == ElementState::Pressed {
+                                if let Some(source) = self.screenshot_settings.key_source(keycode) {
+                                    if let Err(e) = self.capture_screenshot_hotkey(source) {
+                                        error!("Failed to take screenshot: {:?}", e);
+                                    }
+                                }
+                            }
                         }
                     }
+
+                    self.input.handle_window_event(&event);
+                }
+                Event::DeviceEvent { event, .. } => {
+                    self.input.handle_device_event(&event);
                 }
                 _ => { }
             }
@@ -464,6 +543,33 @@ impl App {
         });
     }
 
+    //Without a window there are no events to wait for, so frames are rendered back to back until shutdown is requested
+    fn run_headless(mut self) -> ! {
+        info!("Starting headless render loop.");
+        while !self.shutdown_requested.load(Ordering::Relaxed) {
+            self.game_loop().unwrap();
+        }
+
+        info!("Shutdown requested via Ctrl+C or other asynchronous method. Shutting down application...");
+        self.shutdown();
+        std::process::exit(0);
+    }
+
+    //Renders a fixed number of frames and returns, so headless apps can drive the loop themselves
+    pub fn render_frames(&mut self, frame_count: u32) -> Result<()> {
+        for _ in 0..frame_count {
+            self.game_loop()?;
+        }
+
+        Ok(())
+    }
+
+    //Counterpart to render_frames for apps that never call run
+    pub fn close(mut self) -> () {
+        info!("Closing application...");
+        self.shutdown();
+    }
+
     fn set_ctrlc_handler(&self, shutdown_requested: Arc<AtomicBool>) -> Result<()> {
         ctrlc::set_handler(move || {
             warn!("Ctrl+C handled. Application will shut down asynchronously before rendering the next frame.");
@@ -473,27 +579,73 @@ impl App {
         Ok(())
     }
 
+    //Sleeps most of the remaining frame time, then spins for the last bit because sleeps routinely overshoot by a millisecond or more
+    fn wait_for_frame_rate_limit(&self) -> () {
+        const SPIN_DURATION: Duration = Duration::from_millis(1);
+
+        let frame_rate_limit = match self.frame_rate_limit {
+            Some(frame_rate_limit) => frame_rate_limit,
+            None => return
+        };
+
+        let next_frame_start_time = self.frame_info.last_frame_start_time + Duration::from_secs_f32(1.0 / frame_rate_limit);
+        let now = Instant::now();
+        if now >= next_frame_start_time {
+            return;
+        }
+
+        let remaining = next_frame_start_time - now;
+        if remaining > SPIN_DURATION {
+            thread::sleep(remaining - SPIN_DURATION);
+        }
+        while Instant::now() < next_frame_start_time {
+            std::hint::spin_loop();
+        }
+    }
+
     fn game_loop(&mut self) -> Result<()> {
         if !self.needs_new_swapchain {
+            self.wait_for_frame_rate_limit();
             self.frame_info.current_frame_time = Instant::now();
             self.frame_info.current_frame_delta_time = self.frame_info.current_frame_time - self.frame_info.last_frame_start_time;
-            self.scene.tick(&self.frame_info)?;
+            #[cfg(feature = "gamepad")]
+            self.input.poll_gamepads();
+
+            //Fixed updates run before the variable rate tick, so tick always sees the latest simulation state
+            let tick_start_time = Instant::now();
+            let fixed_update_count = self.frame_info.accumulate_fixed_time();
+            for _ in 0..fixed_update_count {
+                self.scene.fixed_tick(&self.frame_info, &self.input)?;
+                self.frame_info.fixed_update_index += 1;
+            }
+
+            self.scene.tick(&self.frame_info, &self.input)?;
+            self.frame_stats.add_cpu_time(CpuPhase::Tick, tick_start_time.elapsed());
+            self.input.end_frame();
             self.frame_info.last_frame_start_time = self.frame_info.current_frame_time;
             self.jitter_info.next();
 
             {
+                let load_start_time = Instant::now();
                 self.scene.load_and_unload(&mut self.resource_loader)?;
+                self.frame_stats.add_cpu_time(CpuPhase::LoadAndUnload, load_start_time.elapsed());
+
+                let resource_tick_start_time = Instant::now();
                 self.resource_loader.tick(&self.app_data)?;
+                self.frame_stats.add_cpu_time(CpuPhase::ResourceTick, resource_tick_start_time.elapsed());
             }
 
             let bounds = self.app_data.render_images.as_ref().unwrap().base_render_extent;
-            let frame_render_info = self.create_frame_render_info(bounds)?;
+            let mut frame_render_info = self.create_frame_render_info(bounds)?;
+            self.check_shadow_map_extent(&mut frame_render_info);
+            self.frame_stats.set_culling_stats(frame_render_info.culling_stats);
             let frame_render_info = frame_render_info.as_ref();
 
             self.scene.end_frame(bounds)?;
             self.frame_info.current_frame_index += 1;
 
             self.render(frame_render_info)?;
+            self.frame_stats.end_frame(self.frame_info.current_frame_delta_time);
         }
 
         if self.needs_new_swapchain {
@@ -508,25 +660,207 @@ impl App {
         self.shutdown_requested.clone()
     }
 
+    //Effects can be toggled or have their parameters changed at any time. Their passes and images are fixed when the app is built
+    pub fn postprocess_effect_mut(&mut self, name: &str) -> Option<&mut PostprocessEffect> {
+        self.app_data.postprocess_effects.iter_mut()
+            .find(|effect| effect.name == name)
+    }
+
+    //Whether the surface can present HDR10 at all. Only known once the swapchain has been created
+    pub fn hdr_supported(&self) -> bool {
+        self.app_data.swapchain.as_ref().map_or(false, |swapchain_info| swapchain_info.hdr_supported)
+    }
+
+    //Whether frames are currently being PQ encoded for an HDR10 swapchain
+    pub fn hdr_active(&self) -> bool {
+        self.app_data.swapchain.as_ref().map_or(false, |swapchain_info| swapchain_info.use_hdr)
+    }
+
+    pub fn hdr_settings(&self) -> &HdrSettings {
+        &self.app_data.hdr_settings
+    }
+
+    //Toggling HDR recreates the swapchain before the next frame. The brightness settings apply immediately
+    pub fn set_hdr_settings(&mut self, settings: HdrSettings) -> () {
+        if settings.enabled != self.app_data.hdr_settings.enabled {
+            self.needs_new_swapchain = true;
+        }
+        self.app_data.hdr_settings = settings;
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.window.is_none()
+    }
+
+    //Reads back the next rendered frame. Only supported when headless
+    pub fn request_readback(&mut self) -> Result<()> {
+        if !self.is_headless() {
+            return Err(anyhow!("Frame readback is only supported in headless mode"));
+        }
+        self.readback_requested = true;
+
+        Ok(())
+    }
+
+    //Reads back every rendered frame. This stalls the CPU until each frame finishes rendering
+    pub fn set_readback_every_frame(&mut self, readback_every_frame: bool) -> Result<()> {
+        if readback_every_frame && !self.is_headless() {
+            return Err(anyhow!("Frame readback is only supported in headless mode"));
+        }
+        self.readback_every_frame = readback_every_frame;
+
+        Ok(())
+    }
+
+    pub fn last_readback(&self) -> Option<&FrameReadback> {
+        self.last_readback.as_ref()
+    }
+
+    //Saves the next rendered frame as a PNG. The file is written once that frame finishes rendering
+    pub fn capture_screenshot(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        self.capture_screenshot_from(path, ScreenshotSource::Presented)
+    }
+
+    //HDR screenshots are written as EXR regardless of the path's extension
+    pub fn capture_screenshot_from(&mut self, path: impl AsRef<Path>, source: ScreenshotSource) -> Result<()> {
+        if source == ScreenshotSource::Presented && !self.app_data.swapchain.as_ref().map_or(false, |swapchain_info| swapchain_info.capture_supported) {
+            return Err(anyhow!("The surface doesn't allow copying from swapchain images, so the presented image can't be captured"));
+        }
+        if self.pending_screenshot.is_some() {
+            return Err(anyhow!("A screenshot is already waiting for the next frame"));
+        }
+
+        self.pending_screenshot = Some(ScreenshotRequest { path: path.as_ref().to_path_buf(), source });
+
+        Ok(())
+    }
+
+    pub fn screenshot_settings_mut(&mut self) -> &mut ScreenshotSettings {
+        &mut self.screenshot_settings
+    }
+
+    fn capture_screenshot_hotkey(&mut self, source: ScreenshotSource) -> Result<()> {
+        fs::create_dir_all(&self.screenshot_settings.directory)?;
+        let path = self.screenshot_settings.next_path(source);
+
+        self.capture_screenshot_from(path, source)
+    }
+
+    fn readback_pending(&self) -> bool {
+        self.readback_every_frame || self.readback_requested
+    }
+
+    //The mode that was asked for. The swapchain may be using a fallback if the surface doesn't support it
+    pub fn present_mode(&self) -> PresentMode {
+        self.app_data.present_mode
+    }
+
+    pub fn active_present_mode(&self) -> Option<vk::PresentModeKHR> {
+        self.app_data.swapchain.as_ref().map(|swapchain_info| swapchain_info.present_mode)
+    }
+
+    //Recreates the swapchain before the next frame
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) -> () {
+        if present_mode != self.app_data.present_mode {
+            self.app_data.present_mode = present_mode;
+            self.needs_new_swapchain = true;
+        }
+    }
+
+    pub fn frame_rate_limit(&self) -> Option<f32> {
+        self.frame_rate_limit
+    }
+
+    //Takes effect on the next frame. Limits at or below zero are treated as no limit
+    pub fn set_frame_rate_limit(&mut self, frame_rate_limit: Option<f32>) -> () {
+        self.frame_rate_limit = frame_rate_limit.filter(|limit| *limit > 0.0);
+    }
+
+    //Covers the last few seconds of frames. GPU pass timings lag a couple of frames behind the CPU timings
+    pub fn frame_stats(&self) -> FrameStats {
+        self.frame_stats.stats()
+    }
+
+    pub fn stats_overlay_visible(&self) -> bool {
+        self.stats_overlay_visible
+    }
+
+    pub fn set_stats_overlay_visible(&mut self, visible: bool) -> () {
+        self.stats_overlay_visible = visible;
+    }
+
+    //The point is in window pixels, like InputState::cursor_position. It's scaled to the render extent, which is smaller when upscaling
+    pub fn screen_point_to_ray(&self, point: glm::DVec2) -> Result<Ray> {
+        let output_extent = self.app_data.swapchain.as_ref().unwrap().extent;
+        let render_extent = self.app_data.render_images.as_ref().unwrap().base_render_extent;
+
+        let render_point = glm::vec2(
+            point.x * render_extent.width as f64 / output_extent.width as f64,
+            point.y * render_extent.height as f64 / output_extent.height as f64
+        );
+        self.scene.render_camera.screen_point_to_ray(render_point, render_extent)
+    }
+
+    //What's under a point in the window, such as the cursor
+    pub fn pick(&self, point: glm::DVec2) -> Result<Option<RaycastHit>> {
+        let ray = self.screen_point_to_ray(point)?;
+        self.scene.raycast(ray.origin, ray.direction, &self.resource_loader)
+    }
+
+    //The image's last command buffer has finished by now, so its timestamps are ready
+    fn collect_gpu_timings(&mut self, image_index: usize) -> Result<()> {
+        let gpu_timing_info = match self.app_data.gpu_timing.as_mut() {
+            Some(gpu_timing_info) => gpu_timing_info,
+            None => return Ok(())
+        };
+
+        if let Some(pass_durations) = gpu_timing_info.read_pass_durations(&self.device, image_index)? {
+            self.frame_stats.add_gpu_sample(pass_durations);
+        }
+
+        Ok(())
+    }
+
     fn create_frame_render_info(&self, bounds: vk::Extent2D) -> Result<Box<SingleFrameRenderInfo>> {
         let mut frame_info = SingleFrameRenderInfo {
             frame_index: self.frame_info.current_frame_index,
-            time_in_seconds: self.frame_info.current_frame_delta_time.as_secs_f32(),
+            time_in_seconds: self.frame_info.elapsed_time().as_secs_f32(),
+            delta_time_in_seconds: self.frame_info.current_frame_delta_time.as_secs_f32(),
+            fixed_update_alpha: self.frame_info.fixed_update_alpha,
 
             ..Default::default()
         };
 
         self.scene.create_frame_render_info(&mut frame_info, bounds)?;
+        frame_info.cull_models(&self.resource_loader);
 
         Ok(Box::new(frame_info))
     }
 
+    fn check_shadow_map_extent(&mut self, frame_info: &mut SingleFrameRenderInfo) -> () {
+        let shadow_map_extent = self.app_data.render_images.as_ref().unwrap().shadow_map_extent;
+
+        if let Some(shadows) = frame_info.shadows.as_ref() {
+            if shadows.atlas_extent != shadow_map_extent {
+                debug!("Shadow settings require a {:?} shadow map but the current one is {:?}. Recreating render images.", shadows.atlas_extent, shadow_map_extent);
+                self.app_data.requested_shadow_map_extent = Some(shadows.atlas_extent);
+                self.needs_new_swapchain = true;
+
+                //The current shadow map doesn't match the cascade layout, so skip shadows for this frame
+                frame_info.shadows = None;
+            }
+        }
+    }
+
     fn render(&mut self, frame_info: &SingleFrameRenderInfo) -> Result<()> {
+        if self.is_headless() {
+            return self.render_headless(frame_info);
+        }
+
         let swapchain = self.app_data.swapchain.as_ref().unwrap().swapchain;
 
         let sync_frame = (self.frame_info.current_frame_index % self.app_data.max_frames_in_flight()) as usize;
-        let sync_objects_info = self.app_data.sync_objects.as_mut().unwrap();
-        let frame_sync = sync_objects_info.get_sync_objects(sync_frame)?;
+        let frame_sync = self.app_data.sync_objects.as_ref().unwrap().get_sync_objects(sync_frame)?;
 
         let image_index: usize;
         unsafe {
@@ -542,32 +876,10 @@ impl App {
                 },
                 Err(e) => return Err(anyhow!(e))
             }
-
-            let image_in_flight = sync_objects_info.images_in_flight[image_index];
-            if !image_in_flight.is_null() {
-                self.device.wait_for_fences(&[image_in_flight], true, u64::MAX)?;
-            }
         }
 
-        sync_objects_info.images_in_flight[image_index] = frame_sync.in_flight_fence;
-
-        self.update_uniform_buffer(image_index, frame_info)?;
-
-        let command_pools_info = self.app_data.command_pools.as_ref();
-        let command_buffer = command_pools_info.unwrap().command_buffers[image_index];
-        let wait_semaphores = &[frame_sync.image_available];
         let signal_semaphores = &[frame_sync.render_finished];
-
-        command_pools_info.unwrap().submit_command_async(
-            &self.device,
-            &command_buffer,
-            wait_semaphores,
-            &[vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT],
-            signal_semaphores,
-            &frame_sync.in_flight_fence,
-            |cb| {
-                self.update_command_buffer(image_index, cb, frame_info)
-            })?;
+        self.record_and_submit_frame(image_index, frame_info, &[frame_sync.image_available], &[vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT], signal_semaphores, frame_sync.in_flight_fence)?;
 
         let swapchains = &[swapchain];
         let image_indices = &[image_index as u32];
@@ -594,10 +906,122 @@ impl App {
         Ok(())
     }
 
+    //Same as render, but the offscreen images are used round robin and nothing is presented
+    fn render_headless(&mut self, frame_info: &SingleFrameRenderInfo) -> Result<()> {
+        let image_count = self.app_data.swapchain.as_ref().unwrap().images.len() as u32;
+        let image_index = (self.frame_info.current_frame_index % image_count) as usize;
+
+        let sync_frame = (self.frame_info.current_frame_index % self.app_data.max_frames_in_flight()) as usize;
+        let frame_sync = self.app_data.sync_objects.as_ref().unwrap().get_sync_objects(sync_frame)?;
+
+        unsafe {
+            self.device.wait_for_fences(&[frame_sync.in_flight_fence], true, u64::MAX)?;
+        }
+
+        let readback = self.readback_pending();
+        self.record_and_submit_frame(image_index, frame_info, &[], &[], &[], frame_sync.in_flight_fence)?;
+
+        if readback {
+            unsafe {
+                self.device.wait_for_fences(&[frame_sync.in_flight_fence], true, u64::MAX)?;
+            }
+
+            let swapchain_info = self.app_data.swapchain.as_ref().unwrap();
+            self.last_readback = Some(FrameReadback {
+                frame_index: frame_info.frame_index,
+                extent: swapchain_info.extent,
+                pixels: swapchain_info.readback_buffers[image_index].get_data(&self.device)?
+            });
+            self.readback_requested = false;
+        }
+
+        Ok(())
+    }
+
+    //Everything between getting an image to render to and presenting or reading it back, which is the same with or without a window
+    fn record_and_submit_frame(&mut self, image_index: usize, frame_info: &SingleFrameRenderInfo, wait_semaphores: &[vk::Semaphore], wait_dst_stage_mask: &[vk::PipelineStageFlags], signal_semaphores: &[vk::Semaphore], in_flight_fence: vk::Fence) -> Result<()> {
+        let sync_objects_info = self.app_data.sync_objects.as_mut().unwrap();
+        unsafe {
+            let image_in_flight = sync_objects_info.images_in_flight[image_index];
+            if !image_in_flight.is_null() {
+                self.device.wait_for_fences(&[image_in_flight], true, u64::MAX)?;
+            }
+        }
+
+        sync_objects_info.images_in_flight[image_index] = in_flight_fence;
+
+        self.collect_gpu_timings(image_index)?;
+
+        let record_start_time = Instant::now();
+        self.update_uniform_buffer(image_index, frame_info)?;
+        self.update_joint_palette_buffer(image_index, frame_info)?;
+        self.update_overlay_uniform_buffer(image_index)?;
+        self.update_taa_history(image_index);
+        self.begin_screenshot_capture(image_index)?;
+
+        let command_pools_info = self.app_data.command_pools.as_ref();
+        let command_buffer = command_pools_info.unwrap().command_buffers[image_index];
+
+        command_pools_info.unwrap().submit_command_async(
+            &self.device,
+            &command_buffer,
+            wait_semaphores,
+            wait_dst_stage_mask,
+            signal_semaphores,
+            &in_flight_fence,
+            |cb| {
+                self.update_command_buffer(image_index, cb, frame_info)
+            })?;
+        self.frame_stats.add_cpu_time(CpuPhase::RecordAndSubmit, record_start_time.elapsed());
+
+        if let Some(gpu_timing_info) = self.app_data.gpu_timing.as_mut() {
+            gpu_timing_info.queries_written[image_index] = true;
+        }
+
+        if let Some(taa_info) = self.app_data.taa.as_mut() {
+            taa_info.history_image_index = Some(image_index);
+        }
+
+        self.finish_screenshot_capture(in_flight_fence)
+    }
+
     fn update_uniform_buffer(&mut self, image_index: usize, frame_info: &SingleFrameRenderInfo) -> Result<()> {
         let render_resolution = self.app_data.render_images.as_ref().unwrap().base_render_extent;
+        let jitter_scale = self.jitter_scale();
         let buffer = &mut self.app_data.uniforms.as_mut().unwrap().uniform_buffers[image_index];
 
+        if frame_info.lights.len() > MAX_LIGHTS {
+            trace!("Scene has {} lights but only {} are supported. Extra lights will be ignored.", frame_info.lights.len(), MAX_LIGHTS);
+        }
+
+        let light_count = usize::min(frame_info.lights.len(), MAX_LIGHTS);
+        let mut lights = [LightUniform::default(); MAX_LIGHTS];
+        lights[..light_count].copy_from_slice(&frame_info.lights[..light_count]);
+
+        let mut shadow_matrices = [glm::Mat4::identity(); MAX_SHADOW_CASCADES];
+        let mut shadow_cascade_splits = glm::Vec4::zeros();
+        let mut shadow_cascade_count = 0;
+        let mut shadow_bias = 0.0;
+        let mut shadow_texel_size = glm::Vec2::zeros();
+        let mut shadow_cascade_rects = [glm::Vec4::zeros(); MAX_SHADOW_CASCADES];
+        if let Some(shadows) = frame_info.shadows.as_ref() {
+            shadow_cascade_count = usize::min(shadows.cascades.len(), MAX_SHADOW_CASCADES);
+            shadow_bias = shadows.sample_bias;
+            shadow_texel_size = glm::vec2(1.0 / shadows.atlas_extent.width as f32, 1.0 / shadows.atlas_extent.height as f32);
+            for (q, cascade) in shadows.cascades.iter().take(shadow_cascade_count).enumerate() {
+                shadow_matrices[q] = cascade.sampling_matrix;
+                shadow_cascade_splits[q] = cascade.split_depth;
+
+                let viewport = &cascade.viewport;
+                shadow_cascade_rects[q] = glm::vec4(
+                    (viewport.offset.x as f32 + 1.0) * shadow_texel_size.x,
+                    (viewport.offset.y as f32 + 1.0) * shadow_texel_size.y,
+                    (viewport.offset.x as f32 + viewport.extent.width as f32 - 1.0) * shadow_texel_size.x,
+                    (viewport.offset.y as f32 + viewport.extent.height as f32 - 1.0) * shadow_texel_size.y
+                );
+            }
+        }
+
         let ubo = UniformBufferObject {
             proj: frame_info.proj,
             previous_proj: frame_info.previous_proj,
@@ -606,8 +1030,16 @@ impl App {
             directional_light_direction: frame_info.directional_light_direction,
             frame_index: frame_info.frame_index,
             time_in_seconds: frame_info.time_in_seconds,
-
-            jitter_scale: 1.0,
+            light_count: light_count as u32,
+            lights,
+            shadow_matrices,
+            shadow_cascade_splits,
+            shadow_cascade_count: shadow_cascade_count as u32,
+            shadow_bias,
+            shadow_texel_size,
+            shadow_cascade_rects,
+
+            jitter_scale,
             jitter: self.jitter_info.current_jitter,
             resolution: glm::vec2(render_resolution.width as f32, render_resolution.height as f32),
 
@@ -615,39 +1047,308 @@ impl App {
         };
         buffer.set_data(&self.device, &ubo)?;
 
+        let use_hdr = self.app_data.swapchain.as_ref().unwrap().use_hdr;
+        let hdr_settings = self.app_data.hdr_settings;
         let postprocessing_buffer = &mut self.app_data.uniforms.as_mut().unwrap().postprocessing_uniform_buffers[image_index];
 
+        let post_settings = &frame_info.post_settings;
+        let (auto_exposure, manual_exposure) = match post_settings.exposure {
+            ExposureMode::Manual(exposure) => (0, exposure),
+            ExposureMode::Auto(_) => (1, 1.0)
+        };
         let postprocessing_ubo = PostprocessingUniformBufferObject {
             frame_index: frame_info.frame_index,
             time_in_seconds: frame_info.time_in_seconds,
-            exposure: 0.13,
-            // exposure: 1.0,
+            tone_mapping_operator: post_settings.tone_mapping.shader_index(),
+            auto_exposure,
+            manual_exposure,
+            exposure_compensation: post_settings.exposure_compensation,
+            output_headroom: hdr_settings.headroom(use_hdr),
+            encode_pq: use_hdr as u32,
+            paper_white_nits: hdr_settings.paper_white_nits,
+            max_nits: hdr_settings.max_nits
+        };
+        postprocessing_buffer.set_data(&self.device, &postprocessing_ubo)?;
 
+        Ok(())
+    }
+
+    fn update_joint_palette_buffer(&mut self, image_index: usize, frame_info: &SingleFrameRenderInfo) -> Result<()> {
+        let joint_palette_buffer = &mut self.app_data.uniforms.as_mut().unwrap().joint_palette_buffers[image_index];
+        joint_palette_buffer.set_data(&self.device, &frame_info.joint_palettes)?;
+
+        Ok(())
+    }
+
+    fn update_overlay_uniform_buffer(&mut self, image_index: usize) -> Result<()> {
+        if !self.stats_overlay_visible {
+            return Ok(());
+        }
+
+        let use_hdr = self.app_data.swapchain.as_ref().unwrap().use_hdr;
+        let hdr_settings = self.app_data.hdr_settings;
+        //Font pixels are doubled up, and scaled again on high DPI displays so the text stays readable
+        let scale = (2.0 * self.window.as_ref().map_or(1.0, |window| window.scale_factor() as f32)).round();
+
+        let mut overlay_ubo = OverlayUniformBufferObject {
+            origin: glm::vec2(4.0 * scale, 4.0 * scale),
+            scale,
+            encode_pq: use_hdr as u32,
+            paper_white_nits: hdr_settings.paper_white_nits,
             ..Default::default()
         };
-        postprocessing_buffer.set_data(&self.device, &postprocessing_ubo)?;
+        overlay_ubo.set_text(&self.frame_stats.stats().overlay_lines());
+
+        let overlay_buffer = &mut self.app_data.uniforms.as_mut().unwrap().overlay_uniform_buffers[image_index];
+        overlay_buffer.set_data(&self.device, &overlay_ubo)?;
 
         Ok(())
     }
 
+    //The history binding has to point at whichever upscaled image was resolved last. The command buffer for this image has already finished, so its descriptor set can be rewritten
+    fn update_taa_history(&self, image_index: usize) -> () {
+        let history_image_index = match self.app_data.taa.as_ref().and_then(|taa_info| taa_info.history_image_index) {
+            Some(history_image_index) if history_image_index != image_index => history_image_index,
+            _ => return
+        };
+
+        let render_images_info = self.app_data.render_images.as_ref().unwrap();
+        let descriptor_set_info = self.app_data.descriptor_sets.as_ref().unwrap();
+        descriptor_set_info.update_taa_history(&self.device, image_index, &render_images_info.upscaled_render_images[history_image_index]);
+    }
+
+    //Jitter is scaled so that it covers a full pixel when DLSS or TAA is resolving it, and half a pixel otherwise
+    fn jitter_scale(&self) -> f32 {
+        if self.app_data.dlss.is_some() || self.app_data.taa.is_some() { 2.0 } else { 1.0 }
+    }
+
     fn update_command_buffer(&self, image_index: usize, command_buffer: &vk::CommandBuffer, frame_info: &SingleFrameRenderInfo) -> Result<()> {
         let descriptor_set_info = &self.app_data.descriptor_sets.as_ref().unwrap();
 
         let framebuffer_info = &self.app_data.framebuffer.as_ref().unwrap();
+        let gpu_timing_info = self.app_data.gpu_timing.as_ref();
+
+        if let Some(gpu_timing_info) = gpu_timing_info {
+            gpu_timing_info.record_frame_start(&self.device, *command_buffer, image_index);
+        }
 
-        self.render_base(command_buffer, &framebuffer_info.base_render_framebuffers[image_index], &[descriptor_set_info.base_descriptor_sets[image_index]], frame_info)?;
+        if let Some(shadows) = frame_info.shadows.as_ref() {
+            self.render_shadows(command_buffer, &framebuffer_info.shadow_framebuffers[image_index], descriptor_set_info.skinning_descriptor_sets[image_index], shadows)?;
+        }
+        if let Some(gpu_timing_info) = gpu_timing_info {
+            gpu_timing_info.record_pass_end(&self.device, *command_buffer, image_index, GpuPass::Shadows);
+        }
 
-        self.render_postprocessing(command_buffer, &framebuffer_info.postprocessing_framebuffers[image_index], &[descriptor_set_info.postprocessing_descriptor_sets[image_index]])?;
+        self.render_base(command_buffer, image_index, &framebuffer_info.base_render_framebuffers[image_index], &[descriptor_set_info.base_descriptor_sets[image_index]], descriptor_set_info.skinning_descriptor_sets[image_index], frame_info)?;
+        if let Some(gpu_timing_info) = gpu_timing_info {
+            gpu_timing_info.record_pass_end(&self.device, *command_buffer, image_index, GpuPass::BaseRender);
+        }
+
+        if let Some(dlss_info) = self.app_data.dlss.as_ref() {
+            self.render_dlss(command_buffer, image_index, dlss_info)?;
+        } else if let Some(taa_info) = self.app_data.taa.as_ref() {
+            self.render_taa(command_buffer, image_index, &framebuffer_info.taa_framebuffers[image_index], &[descriptor_set_info.taa_descriptor_sets[image_index]], taa_info)?;
+        }
+
+        if let ExposureMode::Auto(settings) = frame_info.post_settings.exposure {
+            if !self.app_data.postprocess_effects.is_empty() {
+                self.render_auto_exposure(command_buffer, image_index, &settings, frame_info)?;
+            }
+        }
+        if let Some(gpu_timing_info) = gpu_timing_info {
+            gpu_timing_info.record_pass_end(&self.device, *command_buffer, image_index, GpuPass::Upscale);
+        }
+
+        let postprocessing_source = self.render_postprocess_effects(command_buffer, image_index)?;
+        descriptor_set_info.update_postprocessing_source(&self.device, image_index, postprocessing_source);
+        let overlay_descriptor_sets = &[descriptor_set_info.overlay_descriptor_sets[image_index]][..];
+        self.render_postprocessing(command_buffer, &framebuffer_info.postprocessing_framebuffers[image_index], &[descriptor_set_info.postprocessing_descriptor_sets[image_index]], Some(overlay_descriptor_sets).filter(|_| self.stats_overlay_visible))?;
+        if let Some(gpu_timing_info) = gpu_timing_info {
+            gpu_timing_info.record_pass_end(&self.device, *command_buffer, image_index, GpuPass::Postprocessing);
+        }
+
+        if self.is_headless() && self.readback_pending() {
+            let swapchain_info = self.app_data.swapchain.as_ref().unwrap();
+            self.record_image_readback(command_buffer, &swapchain_info.images[image_index], vk::ImageLayout::TRANSFER_SRC_OPTIMAL, vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, vk::AccessFlags::COLOR_ATTACHMENT_WRITE, &swapchain_info.readback_buffers[image_index])?;
+        }
+
+        if let Some(capture) = self.screenshot_capture.as_ref() {
+            let (image, layout, src_stage, src_access) = self.screenshot_image(image_index, capture.request.source);
+            self.record_image_readback(command_buffer, image, layout, src_stage, src_access, &capture.buffer)?;
+        }
 
         Ok(())
     }
-    fn render_base(&self, command_buffer: &vk::CommandBuffer, framebuffer: &vk::Framebuffer, descriptor_sets: &[vk::DescriptorSet], frame_info: &SingleFrameRenderInfo) -> Result<()> {
+    fn render_shadows(&self, command_buffer: &vk::CommandBuffer, framebuffer: &vk::Framebuffer, skinning_descriptor_set: vk::DescriptorSet, shadows: &ShadowRenderInfo) -> Result<()> {
+        let pipeline_info = &self.app_data.pipeline.as_ref().unwrap();
+
+        let shadow_area = vk::Rect2D::builder()
+            .offset(vk::Offset2D::default())
+            .extent(shadows.atlas_extent)
+            .build();
+
+        let depth_clear_value = vk::ClearValue {
+            depth_stencil: vk::ClearDepthStencilValue {
+                depth: 1.0,
+                stencil: 0
+            }
+        };
+
+        let shadow_clear_values = &[depth_clear_value];
+        let shadow_pass_info = vk::RenderPassBeginInfo::builder()
+            .render_pass(pipeline_info.shadow_render_pass)
+            .framebuffer(*framebuffer)
+            .render_area(shadow_area)
+            .clear_values(shadow_clear_values);
+
+        unsafe {
+            self.device.cmd_begin_render_pass(*command_buffer, &shadow_pass_info, vk::SubpassContents::INLINE);
+
+            self.device.cmd_set_depth_bias(*command_buffer, shadows.depth_bias_constant, 0.0, shadows.depth_bias_slope);
+
+            for (cascade, casters) in shadows.cascades.iter().zip(shadows.cascade_casters.iter()) {
+                let mut shadow_casters = casters.iter().collect::<Vec<_>>();
+                shadow_casters.sort_by(|a, b| a.material.get_id().cmp(&b.material.get_id()));
+
+                let render_pass = ModelRenderPass::Shadow { shadow_matrix: cascade.shadow_matrix };
+                self.render_models(command_buffer, &shadow_casters, pipeline_info.shadow_layout, &[], skinning_descriptor_set, render_pass, cascade.viewport, |mat| mat.shadow)?;
+            }
+
+            self.device.cmd_end_render_pass(*command_buffer);
+        }
+
+        Ok(())
+    }
+    fn render_dlss(&self, command_buffer: &vk::CommandBuffer, image_index: usize, dlss_info: &DlssInfo) -> Result<()> {
+        let render_images_info = self.app_data.render_images.as_ref().unwrap();
+        let render_extent = render_images_info.base_render_extent;
+        let output_image = &render_images_info.upscaled_render_images[image_index];
+
+        let subresource = vk::ImageSubresourceRange::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .base_mip_level(0)
+            .level_count(1)
+            .base_array_layer(0)
+            .layer_count(1)
+            .build();
+
+        //The base render pass leaves its attachments in SHADER_READ_ONLY_OPTIMAL, but DLSS writes its output in GENERAL
+        let input_barrier = vk::MemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ);
+        let output_barrier = vk::ImageMemoryBarrier::builder()
+            .old_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .new_layout(vk::ImageLayout::GENERAL)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(unsafe { output_image.raw_image().unwrap() })
+            .subresource_range(subresource)
+            .src_access_mask(vk::AccessFlags::SHADER_READ)
+            .dst_access_mask(vk::AccessFlags::SHADER_WRITE);
+
+        //The projection's jitter moves the geometry, so the sample moved the other way relative to it
+        let jitter = -self.jitter_info.current_jitter * self.jitter_scale() / 2.0;
+        let params = EvaluateParameters {
+            color: DlssInfo::image_resource(&render_images_info.base_render_images[image_index], unsafe { render_images_info.base_render_images[image_index].raw_image_view().unwrap() }, vk::ImageAspectFlags::COLOR),
+            output: DlssInfo::image_resource(output_image, unsafe { output_image.raw_image_view().unwrap() }, vk::ImageAspectFlags::COLOR),
+            depth: DlssInfo::image_resource(&render_images_info.depth_stencil_buffers[image_index], render_images_info.depth_sample_views[image_index], vk::ImageAspectFlags::DEPTH),
+            motion_vectors: DlssInfo::image_resource(&render_images_info.motion_vector_buffers[image_index], unsafe { render_images_info.motion_vector_buffers[image_index].raw_image_view().unwrap() }, vk::ImageAspectFlags::COLOR),
+            jitter_offset: (jitter.x, jitter.y),
+            //Motion vectors are stored as current minus previous in NDC, but DLSS wants previous minus current in pixels
+            motion_vector_scale: (-(render_extent.width as f32) / 2.0, -(render_extent.height as f32) / 2.0),
+            render_width: render_extent.width,
+            render_height: render_extent.height,
+            sharpness: dlss_info.sharpness,
+            reset: false
+        };
+
+        unsafe {
+            self.device.cmd_pipeline_barrier(
+                *command_buffer,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS | vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::DependencyFlags::empty(),
+                &[input_barrier],
+                &[] as &[vk::BufferMemoryBarrier],
+                &[output_barrier]
+            );
+
+            dlss_info.evaluate(command_buffer, &params)?;
+
+            let output_barrier = vk::ImageMemoryBarrier::builder()
+                .old_layout(vk::ImageLayout::GENERAL)
+                .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .image(output_image.raw_image().unwrap())
+                .subresource_range(subresource)
+                .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+                .dst_access_mask(vk::AccessFlags::SHADER_READ);
+            self.device.cmd_pipeline_barrier(
+                *command_buffer,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::empty(),
+                &[] as &[vk::MemoryBarrier],
+                &[] as &[vk::BufferMemoryBarrier],
+                &[output_barrier]
+            );
+        }
+
+        Ok(())
+    }
+    fn render_taa(&self, command_buffer: &vk::CommandBuffer, image_index: usize, framebuffer: &vk::Framebuffer, descriptor_sets: &[vk::DescriptorSet], taa_info: &TaaInfo) -> Result<()> {
+        let pipeline_info = &self.app_data.pipeline.as_ref().unwrap();
+        let render_extent = self.app_data.render_images.as_ref().unwrap().base_render_extent;
+
+        let taa_area = vk::Rect2D::builder()
+            .offset(vk::Offset2D::default())
+            .extent(taa_info.output_extent);
+
+        let taa_pass_info = vk::RenderPassBeginInfo::builder()
+            .render_pass(pipeline_info.taa_render_pass)
+            .framebuffer(*framebuffer)
+            .render_area(taa_area);
+
+        //The projection's jitter is in NDC, which covers twice the range of UVs
+        let jitter = self.jitter_info.current_jitter * self.jitter_scale();
+        let has_history = taa_info.history_image_index.map_or(false, |history_image_index| history_image_index != image_index);
+        let push_constants = TaaPushConstants {
+            jitter: glm::vec2(jitter.x / (2.0 * render_extent.width as f32), jitter.y / (2.0 * render_extent.height as f32)),
+            render_texel_size: glm::vec2(1.0 / render_extent.width as f32, 1.0 / render_extent.height as f32),
+            history_weight: taa_info.settings.history_weight,
+            has_history: has_history as u32
+        };
+
+        let descriptor_set_info = &self.app_data.descriptor_sets.as_ref().unwrap();
+
+        unsafe {
+            self.device.cmd_begin_render_pass(*command_buffer, &taa_pass_info, vk::SubpassContents::INLINE);
+
+            {
+                self.device.cmd_bind_pipeline(*command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline_info.taa_pipeline);
+                self.device.cmd_bind_descriptor_sets(*command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline_info.taa_layout, 0, descriptor_sets, &[]);
+                self.device.cmd_push_constants(*command_buffer, pipeline_info.taa_layout, vk::ShaderStageFlags::FRAGMENT, 0, push_constants.as_bytes());
+
+                let buffer = &descriptor_set_info.postprocessing_vertex_buffer;
+                let raw_buffer = buffer.raw_buffer().unwrap();
+                self.device.cmd_bind_vertex_buffers(*command_buffer, 0, &[raw_buffer], &[0]);
+                self.device.cmd_draw(*command_buffer, buffer.used_element_count() as u32, 1, 0, 0);
+            }
+
+            self.device.cmd_end_render_pass(*command_buffer);
+        }
+
+        Ok(())
+    }
+    fn render_base(&self, command_buffer: &vk::CommandBuffer, image_index: usize, framebuffer: &vk::Framebuffer, descriptor_sets: &[vk::DescriptorSet], skinning_descriptor_set: vk::DescriptorSet, frame_info: &SingleFrameRenderInfo) -> Result<()> {
         let pipeline_info = &self.app_data.pipeline.as_ref().unwrap();
         let render_extent = self.app_data.render_images.as_ref().unwrap().base_render_extent;
 
         let base_render_area = vk::Rect2D::builder()
             .offset(vk::Offset2D::default())
-            .extent(render_extent);
+            .extent(render_extent)
+            .build();
 
         let clear_color = frame_info.clear_color;
         let color_clear_value = vk::ClearValue {
@@ -675,40 +1376,47 @@ impl App {
             .clear_values(base_render_clear_values);
 
         //TODO: sort models to render to ensure the least fragment redraws
-        //TODO: filter models that are obviously outside of the view frustum
         //TODO: filter models that are occluded completely
-        //TODO: render translucent models
-
-        let mut opaque_models = vec![];
-        let mut translucent_models = vec![];
-        for model in &frame_info.models_to_render {
-            if model.is_opaque {
-                opaque_models.push(model);
-            } else {
-                translucent_models.push(model);
-            }
-        }
 
+        let mut opaque_models = frame_info.models_to_render.iter().filter(|model| model.is_opaque).collect::<Vec<_>>();
         opaque_models.sort_by(|a, b| a.material.get_id().cmp(&b.material.get_id()));
 
+        let translucent_draws = frame_info.sorted_translucent_draws();
+        let translucent_models = translucent_draws.iter().collect::<Vec<_>>();
+
         unsafe {
             self.device.cmd_begin_render_pass(*command_buffer, &base_render_pass_info, vk::SubpassContents::INLINE);
 
-            self.render_models(command_buffer, &opaque_models, pipeline_info.depth_motion_layout, descriptor_sets, true, render_extent, |mat| mat.depth_motion)?;
+            self.render_models(command_buffer, &opaque_models, pipeline_info.depth_motion_layout, descriptor_sets, skinning_descriptor_set, ModelRenderPass::DepthMotion, base_render_area, |mat| mat.depth_motion)?;
+
+            if let Some(gpu_timing_info) = self.app_data.gpu_timing.as_ref() {
+                gpu_timing_info.record_pass_end(&self.device, *command_buffer, image_index, GpuPass::DepthMotion);
+            }
+
+            self.device.cmd_next_subpass(*command_buffer, vk::SubpassContents::INLINE);
+
+            self.render_models(command_buffer, &opaque_models, pipeline_info.base_render_layout, descriptor_sets, skinning_descriptor_set, ModelRenderPass::BaseRender, base_render_area, |mat| mat.base_render)?;
 
             self.device.cmd_next_subpass(*command_buffer, vk::SubpassContents::INLINE);
 
-            self.render_models(command_buffer, &opaque_models, pipeline_info.base_render_layout, descriptor_sets, false, render_extent, |mat| mat.base_render)?;
+            self.render_models(command_buffer, &translucent_models, pipeline_info.base_render_layout, descriptor_sets, skinning_descriptor_set, ModelRenderPass::BaseRender, base_render_area, |mat| mat.translucent)?;
 
             self.device.cmd_end_render_pass(*command_buffer);
         }
 
         Ok(())
     }
-    unsafe fn render_models(&self, command_buffer: &vk::CommandBuffer, models: &Vec<&SingleModelRenderInfo>, pipeline_layout: vk::PipelineLayout, descriptor_sets: &[vk::DescriptorSet], is_depth_motion_pass: bool, extent: vk::Extent2D, pipeline_selector: impl Fn(&Material) -> Option<vk::Pipeline>) -> Result<()> {
+    unsafe fn render_models(&self, command_buffer: &vk::CommandBuffer, models: &Vec<&SingleModelRenderInfo>, pipeline_layout: vk::PipelineLayout, descriptor_sets: &[vk::DescriptorSet], skinning_descriptor_set: vk::DescriptorSet, render_pass: ModelRenderPass, render_area: vk::Rect2D, pipeline_selector: impl Fn(&Material) -> Option<vk::Pipeline>) -> Result<()> {
         let mut current_mat_id = 0u32;
 
-        self.device.cmd_bind_descriptor_sets(*command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline_layout, 0, descriptor_sets, &[]);
+        if !descriptor_sets.is_empty() {
+            self.device.cmd_bind_descriptor_sets(*command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline_layout, 0, descriptor_sets, &[]);
+        }
+
+        //The skinning set comes after the material set, or first in passes without descriptor sets (shadows)
+        let skinning_set_index = if descriptor_sets.is_empty() { 0 } else { descriptor_sets.len() as u32 + 1 };
+        let mut current_joint_palette_offsets = (0, 0);
+        self.device.cmd_bind_descriptor_sets(*command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline_layout, skinning_set_index, &[skinning_descriptor_set], &[0, 0]);
 
         let mut is_viewport_scissor_set = false;
 
@@ -720,6 +1428,11 @@ impl App {
                     if let Some(pipeline) = pipeline_selector(&render_mat) {
                         current_mat_id = mat.get_id();
                         self.device.cmd_bind_pipeline(*command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline);
+
+                        //The material set follows the frame's sets, so passes without descriptor sets (shadows) don't use it either
+                        if let Some(material_descriptor_set) = render_mat.descriptor_set.filter(|_| !descriptor_sets.is_empty()) {
+                            self.device.cmd_bind_descriptor_sets(*command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline_layout, descriptor_sets.len() as u32, &[material_descriptor_set], &[]);
+                        }
                     } else {
                         continue;
                     }
@@ -730,29 +1443,207 @@ impl App {
 
             if !is_viewport_scissor_set {
                 let viewport = vk::Viewport::builder()
-                    .x(0.0)
-                    .y(0.0)
-                    .width(extent.width as f32)
-                    .height(extent.height as f32)
+                    .x(render_area.offset.x as f32)
+                    .y(render_area.offset.y as f32)
+                    .width(render_area.extent.width as f32)
+                    .height(render_area.extent.height as f32)
                     .min_depth(0.0)
                     .max_depth(1.0);
 
-                let scissor = vk::Rect2D::builder()
-                    .offset(vk::Offset2D { x: 0, y: 0 })
-                    .extent(extent);
-
                 self.device.cmd_set_viewport(*command_buffer, 0, &[viewport]);
-                self.device.cmd_set_scissor(*command_buffer, 0, &[scissor]);
+                self.device.cmd_set_scissor(*command_buffer, 0, &[render_area]);
 
                 is_viewport_scissor_set = true;
             }
 
-            model.render(&self.device, command_buffer, &pipeline_layout, is_depth_motion_pass, &self.resource_loader)?;
+            if let Some(joint_palette_offsets) = model.joint_palette_offsets.filter(|offsets| *offsets != current_joint_palette_offsets) {
+                let (offset, previous_offset) = joint_palette_offsets;
+                let dynamic_offsets = &[(offset * size_of::<glm::Mat4>()) as u32, (previous_offset * size_of::<glm::Mat4>()) as u32];
+                self.device.cmd_bind_descriptor_sets(*command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline_layout, skinning_set_index, &[skinning_descriptor_set], dynamic_offsets);
+                current_joint_palette_offsets = joint_palette_offsets;
+            }
+
+            model.render(&self.device, command_buffer, &pipeline_layout, render_pass, &self.resource_loader)?;
+        }
+
+        Ok(())
+    }
+    //Builds a histogram of the base render's luminance, then moves the adapted luminance towards its average
+    fn render_auto_exposure(&self, command_buffer: &vk::CommandBuffer, image_index: usize, settings: &AutoExposureSettings, frame_info: &SingleFrameRenderInfo) -> Result<()> {
+        let pipeline_info = self.app_data.pipeline.as_ref().unwrap();
+        let descriptor_set_info = self.app_data.descriptor_sets.as_ref().unwrap();
+        let render_extent = self.app_data.render_images.as_ref().unwrap().base_render_extent;
+
+        let min_log_luminance = settings.min_luminance.max(f32::EPSILON).log2();
+        let max_log_luminance = settings.max_luminance.max(settings.min_luminance * 2.0).log2();
+        let push_constants = AutoExposurePushConstants {
+            min_log_luminance,
+            log_luminance_range: max_log_luminance - min_log_luminance,
+            adaptation_speed_up: settings.adaptation_speed_up,
+            adaptation_speed_down: settings.adaptation_speed_down,
+            delta_time: frame_info.delta_time_in_seconds,
+            pixel_count: render_extent.width * render_extent.height
+        };
+
+        //The previous frame's adaptation has to finish with the shared buffers before this frame touches them
+        let input_barrier = vk::MemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE | vk::AccessFlags::SHADER_WRITE)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE);
+        let histogram_barrier = vk::MemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE);
+        let output_barrier = vk::MemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ);
+
+        let descriptor_sets = &[descriptor_set_info.auto_exposure_descriptor_sets[image_index]];
+
+        unsafe {
+            self.device.cmd_pipeline_barrier(
+                *command_buffer,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::DependencyFlags::empty(),
+                &[input_barrier],
+                &[] as &[vk::BufferMemoryBarrier],
+                &[] as &[vk::ImageMemoryBarrier]
+            );
+
+            self.device.cmd_bind_descriptor_sets(*command_buffer, vk::PipelineBindPoint::COMPUTE, pipeline_info.auto_exposure_layout, 0, descriptor_sets, &[]);
+            self.device.cmd_push_constants(*command_buffer, pipeline_info.auto_exposure_layout, vk::ShaderStageFlags::COMPUTE, 0, push_constants.as_bytes());
+
+            //Must match the workgroup size in the histogram shader
+            self.device.cmd_bind_pipeline(*command_buffer, vk::PipelineBindPoint::COMPUTE, pipeline_info.luminance_histogram_pipeline);
+            self.device.cmd_dispatch(*command_buffer, (render_extent.width + 15) / 16, (render_extent.height + 15) / 16, 1);
+
+            self.device.cmd_pipeline_barrier(
+                *command_buffer,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::DependencyFlags::empty(),
+                &[histogram_barrier],
+                &[] as &[vk::BufferMemoryBarrier],
+                &[] as &[vk::ImageMemoryBarrier]
+            );
+
+            self.device.cmd_bind_pipeline(*command_buffer, vk::PipelineBindPoint::COMPUTE, pipeline_info.exposure_adaptation_pipeline);
+            self.device.cmd_dispatch(*command_buffer, 1, 1, 1);
+
+            self.device.cmd_pipeline_barrier(
+                *command_buffer,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::empty(),
+                &[output_barrier],
+                &[] as &[vk::BufferMemoryBarrier],
+                &[] as &[vk::ImageMemoryBarrier]
+            );
+        }
+
+        Ok(())
+    }
+    //Runs each enabled effect in order, and returns the image the last one wrote to
+    fn render_postprocess_effects(&self, command_buffer: &vk::CommandBuffer, image_index: usize) -> Result<&Image2D> {
+        let render_images_info = self.app_data.render_images.as_ref().unwrap();
+        let framebuffer_info = self.app_data.framebuffer.as_ref().unwrap();
+        let pipeline_info = self.app_data.pipeline.as_ref().unwrap();
+        let descriptor_set_info = self.app_data.descriptor_sets.as_ref().unwrap();
+
+        let mut effect_input = &render_images_info.postprocessing_source_images()[image_index];
+        let mut chain_index = 0;
+        for (effect_index, effect) in self.app_data.postprocess_effects.iter().enumerate() {
+            if !effect.enabled {
+                continue;
+            }
+
+            let resolve_image = |image: PostprocessImage| match image {
+                PostprocessImage::EffectInput => (effect_input, None),
+                PostprocessImage::EffectOutput => (
+                    &render_images_info.postprocess_chain_images[image_index][chain_index],
+                    Some(framebuffer_info.postprocess_chain_framebuffers[image_index][chain_index])
+                ),
+                PostprocessImage::Intermediate(q) => (
+                    &render_images_info.postprocess_intermediate_images[image_index][effect_index][q],
+                    Some(framebuffer_info.postprocess_intermediate_framebuffers[image_index][effect_index][q])
+                )
+            };
+
+            let parameters = effect.parameters();
+            for (pass_index, pass) in effect.passes.iter().enumerate() {
+                let (input_image, _) = resolve_image(pass.input);
+                let (output_image, output_framebuffer) = resolve_image(pass.output);
+                let output_framebuffer = output_framebuffer.ok_or_else(|| anyhow!("Postprocess effect {} writes to its own input", effect.name))?;
+
+                descriptor_set_info.update_postprocess_effect_inputs(&self.device, image_index, effect_index, pass_index, input_image, effect_input);
+
+                let input_extent = input_image.size().unwrap();
+                let output_extent = output_image.size().unwrap();
+                let push_constants = PostprocessPushConstants {
+                    parameters: [
+                        glm::vec4(parameters[0], parameters[1], parameters[2], parameters[3]),
+                        glm::vec4(parameters[4], parameters[5], parameters[6], parameters[7])
+                    ],
+                    input_texel_size: glm::vec2(1.0 / input_extent.width as f32, 1.0 / input_extent.height as f32),
+                    output_texel_size: glm::vec2(1.0 / output_extent.width as f32, 1.0 / output_extent.height as f32)
+                };
+
+                let pipeline = pipeline_info.postprocess_effect_pipelines[effect_index][pass_index];
+                let descriptor_sets = &[descriptor_set_info.postprocess_effect_descriptor_sets[image_index][effect_index][pass_index]];
+                self.render_postprocess_pass(command_buffer, output_framebuffer, output_extent, pipeline, descriptor_sets, &push_constants)?;
+            }
+
+            effect_input = &render_images_info.postprocess_chain_images[image_index][chain_index];
+            chain_index = 1 - chain_index;
+        }
+
+        Ok(effect_input)
+    }
+    fn render_postprocess_pass(&self, command_buffer: &vk::CommandBuffer, framebuffer: vk::Framebuffer, extent: vk::Extent2D, pipeline: vk::Pipeline, descriptor_sets: &[vk::DescriptorSet], push_constants: &PostprocessPushConstants) -> Result<()> {
+        let pipeline_info = &self.app_data.pipeline.as_ref().unwrap();
+        let pipeline_layout = pipeline_info.postprocess_effect_layout;
+
+        let pass_area = vk::Rect2D::builder()
+            .offset(vk::Offset2D::default())
+            .extent(extent);
+
+        let pass_info = vk::RenderPassBeginInfo::builder()
+            .render_pass(pipeline_info.postprocess_effect_render_pass)
+            .framebuffer(framebuffer)
+            .render_area(pass_area);
+
+        let viewport = vk::Viewport::builder()
+            .x(0.0)
+            .y(0.0)
+            .width(extent.width as f32)
+            .height(extent.height as f32)
+            .min_depth(0.0)
+            .max_depth(1.0);
+
+        let descriptor_set_info = &self.app_data.descriptor_sets.as_ref().unwrap();
+
+        unsafe {
+            self.device.cmd_begin_render_pass(*command_buffer, &pass_info, vk::SubpassContents::INLINE);
+
+            {
+                self.device.cmd_bind_pipeline(*command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline);
+                self.device.cmd_set_viewport(*command_buffer, 0, &[viewport]);
+                self.device.cmd_set_scissor(*command_buffer, 0, &[pass_area]);
+                self.device.cmd_bind_descriptor_sets(*command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline_layout, 0, descriptor_sets, &[]);
+                self.device.cmd_push_constants(*command_buffer, pipeline_layout, vk::ShaderStageFlags::FRAGMENT, 0, push_constants.as_bytes());
+
+                let buffer = &descriptor_set_info.postprocessing_vertex_buffer;
+                let raw_buffer = buffer.raw_buffer().unwrap();
+                self.device.cmd_bind_vertex_buffers(*command_buffer, 0, &[raw_buffer], &[0]);
+                self.device.cmd_draw(*command_buffer, buffer.used_element_count() as u32, 1, 0, 0);
+            }
+
+            self.device.cmd_end_render_pass(*command_buffer);
         }
 
         Ok(())
     }
-    fn render_postprocessing(&self, command_buffer: &vk::CommandBuffer, framebuffer: &vk::Framebuffer, descriptor_sets: &[vk::DescriptorSet]) -> Result<()> {
+    //The stats overlay is drawn over the final image when overlay descriptor sets are given
+    fn render_postprocessing(&self, command_buffer: &vk::CommandBuffer, framebuffer: &vk::Framebuffer, descriptor_sets: &[vk::DescriptorSet], overlay_descriptor_sets: Option<&[vk::DescriptorSet]>) -> Result<()> {
         let pipeline_info = &self.app_data.pipeline.as_ref().unwrap();
         let swapchain_extent = self.app_data.swapchain.as_ref().unwrap().extent;
 
@@ -791,12 +1682,162 @@ impl App {
                 self.device.cmd_draw(*command_buffer, buffer.used_element_count() as u32, 1, 0, 0);
             }
 
+            if let Some(overlay_descriptor_sets) = overlay_descriptor_sets {
+                self.device.cmd_bind_pipeline(*command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline_info.overlay_pipeline);
+                self.device.cmd_bind_descriptor_sets(*command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline_info.overlay_layout, 0, overlay_descriptor_sets, &[]);
+
+                //Same fullscreen quad. The overlay shader discards everything outside of its text box
+                let buffer = &descriptor_set_info.postprocessing_vertex_buffer;
+                let raw_buffer = buffer.raw_buffer().unwrap();
+                self.device.cmd_bind_vertex_buffers(*command_buffer, 0, &[raw_buffer], &[0]);
+                self.device.cmd_draw(*command_buffer, buffer.used_element_count() as u32, 1, 0, 0);
+            }
+
             self.device.cmd_end_render_pass(*command_buffer);
         }
 
         Ok(())
     }
 
+    //Copies a color image into a host visible buffer. The image is transitioned for the copy and put back into the layout it was in
+    fn record_image_readback(&self, command_buffer: &vk::CommandBuffer, image: &Image2D, layout: vk::ImageLayout, src_stage: vk::PipelineStageFlags, src_access: vk::AccessFlags, buffer: &Buffer<u8>) -> Result<()> {
+        let raw_image = unsafe { image.raw_image().unwrap() };
+        let extent = image.size().unwrap();
+
+        let subresource_range = vk::ImageSubresourceRange::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .base_mip_level(0)
+            .level_count(1)
+            .base_array_layer(0)
+            .layer_count(1);
+
+        let to_transfer_barrier = vk::ImageMemoryBarrier::builder()
+            .old_layout(layout)
+            .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(raw_image)
+            .subresource_range(subresource_range)
+            .src_access_mask(src_access)
+            .dst_access_mask(vk::AccessFlags::TRANSFER_READ);
+
+        let region = vk::BufferImageCopy::builder()
+            .buffer_offset(0)
+            .buffer_row_length(0)
+            .buffer_image_height(0)
+            .image_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1
+            })
+            .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+            .image_extent(vk::Extent3D { width: extent.width, height: extent.height, depth: 1 });
+
+        let from_transfer_barrier = vk::ImageMemoryBarrier::builder()
+            .old_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            .new_layout(layout)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(raw_image)
+            .subresource_range(subresource_range)
+            .src_access_mask(vk::AccessFlags::TRANSFER_READ)
+            .dst_access_mask(vk::AccessFlags::empty());
+
+        let buffer_barrier = vk::BufferMemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(vk::AccessFlags::HOST_READ)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .buffer(unsafe { buffer.raw_buffer().unwrap() })
+            .offset(0)
+            .size(vk::WHOLE_SIZE as u64);
+
+        unsafe {
+            self.device.cmd_pipeline_barrier(
+                *command_buffer,
+                src_stage,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[] as &[vk::MemoryBarrier],
+                &[] as &[vk::BufferMemoryBarrier],
+                &[to_transfer_barrier]
+            );
+
+            self.device.cmd_copy_image_to_buffer(*command_buffer, raw_image, vk::ImageLayout::TRANSFER_SRC_OPTIMAL, buffer.raw_buffer().unwrap(), &[region]);
+
+            self.device.cmd_pipeline_barrier(
+                *command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::HOST | vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                vk::DependencyFlags::empty(),
+                &[] as &[vk::MemoryBarrier],
+                &[buffer_barrier],
+                &[from_transfer_barrier]
+            );
+        }
+
+        Ok(())
+    }
+
+    //Where the current frame's copy of each screenshot source lives, and how it's left at the end of the frame
+    fn screenshot_image(&self, image_index: usize, source: ScreenshotSource) -> (&Image2D, vk::ImageLayout, vk::PipelineStageFlags, vk::AccessFlags) {
+        match source {
+            ScreenshotSource::Presented => {
+                let swapchain_info = self.app_data.swapchain.as_ref().unwrap();
+                let layout = if swapchain_info.headless { vk::ImageLayout::TRANSFER_SRC_OPTIMAL } else { vk::ImageLayout::PRESENT_SRC_KHR };
+                (&swapchain_info.images[image_index], layout, vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+            },
+            ScreenshotSource::Hdr => {
+                let render_images_info = self.app_data.render_images.as_ref().unwrap();
+                (&render_images_info.postprocessing_source_images()[image_index], vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, vk::PipelineStageFlags::FRAGMENT_SHADER, vk::AccessFlags::SHADER_READ)
+            }
+        }
+    }
+
+    fn begin_screenshot_capture(&mut self, image_index: usize) -> Result<()> {
+        let request = match self.pending_screenshot.take() {
+            Some(request) => request,
+            None => return Ok(())
+        };
+
+        let (image, _, _, _) = self.screenshot_image(image_index, request.source);
+        let format = image.format().unwrap();
+        let extent = image.size().unwrap();
+        let bytes_per_pixel = if format == vk::Format::R16G16B16A16_SFLOAT { 8 } else { 4 };
+
+        let mut buffer = Buffer::<u8>::new(vk::BufferUsageFlags::TRANSFER_DST, (extent.width * extent.height) as usize * bytes_per_pixel, false);
+        buffer.create(&self.device, &self.app_data.memory_properties)?;
+
+        self.screenshot_capture = Some(ScreenshotCapture { request, format, extent, buffer });
+
+        Ok(())
+    }
+
+    //Failing to write a screenshot shouldn't take the app down with it, so errors are only logged
+    fn finish_screenshot_capture(&mut self, fence: vk::Fence) -> Result<()> {
+        let mut capture = match self.screenshot_capture.take() {
+            Some(capture) => capture,
+            None => return Ok(())
+        };
+
+        unsafe {
+            self.device.wait_for_fences(&[fence], true, u64::MAX)?;
+        }
+
+        let path = &capture.request.path;
+        let result = capture.buffer.get_data(&self.device)
+            .and_then(|data| write_screenshot(path, capture.format, capture.extent, &data));
+        match result {
+            Ok(_) => info!("Saved screenshot to {}", path.display()),
+            Err(e) => error!("Failed to save screenshot to {}: {:?}", path.display(), e)
+        }
+
+        capture.buffer.destroy(&self.device);
+
+        Ok(())
+    }
+
     fn shutdown(&mut self) {
         if self.destroying {
             warn!("App::shutdown invoked more than once. Ignoring repeat.");
@@ -816,6 +1857,9 @@ impl App {
         unsafe {
             self.device.device_wait_idle().unwrap();
 
+            if let Err(e) = self.resource_loader.finish_pending_uploads(&self.app_data) {
+                error!("Failed to finish pending resource uploads: {:?}", e);
+            }
             self.scene.unload(&mut self.resource_loader);
             self.resource_loader.force_unload_all();
 
@@ -843,19 +1887,13 @@ impl App {
 
 //TODO: learn to use (and actually use) HDR color space
 //TODO: render at a lower resolution than the swapchain-created images
-//TODO: add asynchronous loading of assets; move asset loading onto other threads (placeholder models/textures if things don't load fast enough)
+//TODO: placeholder models/textures while assets load in the background
 //TODO: only create one sampler resource, not one per image
 //TODO: use bindless rendering to support multiple textures
-//TODO: add support for DLSS2
 
 //TODO: single location for GPU memory management (allocation/freeing)
 //TODO: improve game object abstraction
-//TODO: add support for keyboard/mouse input
 //TODO: add support for FSR2
 //TODO: add support for fullscreen
-//TODO: make texture loading asynchronous - remove usages of queue_wait_idle in favor of proper synchronization
 //TODO: figure out how to use screen refresh rate
-//TODO: find and integrate 3D physics engine
-//TODO: support rendering text
 //TODO: look into ray tracing
-//TODO: add support for in-scene light sources in shaders
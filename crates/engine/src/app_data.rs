@@ -7,14 +7,20 @@ use crate::{
     bootstrap::{
         ValidationInfo,
         SwapchainInfo,
+        HdrSettings,
+        PresentMode,
         UniformsInfo,
         RenderImagesInfo,
         PipelineInfo,
         FramebufferInfo,
         CommandPoolsInfo,
         SyncObjectsInfo,
-        DescriptorSetInfo
-    }
+        DescriptorSetInfo,
+        DlssInfo,
+        TaaInfo,
+        GpuTimingInfo
+    },
+    postprocessing::{PostprocessEffect}
 };
 
 #[derive(Debug, Copy, Clone, Default)]
@@ -31,6 +37,15 @@ pub struct AppData {
     pub memory_properties: vk::PhysicalDeviceMemoryProperties,
     pub queue_info: Option<Arc<VulkanQueueInfo>>,
     pub surface: Option<vk::SurfaceKHR>,
+    pub requested_shadow_map_extent: Option<vk::Extent2D>,
+    //Set when the app renders to offscreen images instead of a window
+    pub headless_extent: Option<vk::Extent2D>,
+    //Changing whether HDR is enabled only takes effect once the swapchain is recreated
+    pub hdr_settings: HdrSettings,
+    //Also only takes effect once the swapchain is recreated
+    pub present_mode: PresentMode,
+    //Run in order between the base render (or its upscaled version) and the final output pass
+    pub postprocess_effects: Vec<PostprocessEffect>,
 
     pub validation: Option<ValidationInfo>,
     pub swapchain: Option<SwapchainInfo>,
@@ -40,7 +55,11 @@ pub struct AppData {
     pub framebuffer: Option<FramebufferInfo>,
     pub command_pools: Option<CommandPoolsInfo>,
     pub sync_objects: Option<SyncObjectsInfo>,
-    pub descriptor_sets: Option<DescriptorSetInfo>
+    pub descriptor_sets: Option<DescriptorSetInfo>,
+    pub dlss: Option<DlssInfo>,
+    pub taa: Option<TaaInfo>,
+    //Only present when the graphics queue supports timestamps
+    pub gpu_timing: Option<GpuTimingInfo>
 }
 
 impl AppData {
@@ -75,6 +75,51 @@ impl CommandPoolsInfo {
         self.submit_command_transient(device, &self.transient_command_pool, &self.queue_info.graphics_queue, command)
     }
 
+    //Like submit_command_transient_sync, but doesn't wait for the queue. Poll the returned fence and call free_command_transient once it's signaled
+    pub fn submit_command_transient_fenced(&self, device: &Device, command: impl FnOnce(&vk::CommandBuffer) -> Result<()>) -> Result<(vk::CommandBuffer, vk::Fence)> {
+        let cmd_buff_info = vk::CommandBufferAllocateInfo::builder()
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_pool(self.transient_command_pool)
+            .command_buffer_count(1);
+
+        let command_buffer: vk::CommandBuffer;
+        unsafe {
+            command_buffer = device.allocate_command_buffers(&cmd_buff_info)?[0];
+        }
+
+        let begin_info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+        unsafe {
+            device.begin_command_buffer(command_buffer, &begin_info)?;
+
+            command(&command_buffer)?;
+
+            device.end_command_buffer(command_buffer)?;
+        }
+
+        let command_buffers = &[command_buffer];
+        let submit_info = vk::SubmitInfo::builder()
+            .command_buffers(command_buffers);
+
+        let fence_info = vk::FenceCreateInfo::builder();
+
+        let fence: vk::Fence;
+        unsafe {
+            fence = device.create_fence(&fence_info, None)?;
+            device.queue_submit(self.queue_info.graphics_queue, &[submit_info], fence)?;
+        }
+
+        Ok((command_buffer, fence))
+    }
+
+    pub fn free_command_transient(&self, device: &Device, command_buffer: vk::CommandBuffer, fence: vk::Fence) -> () {
+        unsafe {
+            device.destroy_fence(fence, None);
+            device.free_command_buffers(self.transient_command_pool, &[command_buffer]);
+        }
+    }
+
     fn submit_command_graphics(&self, device: &Device, command_buffer: &vk::CommandBuffer, submit_queue: &vk::Queue, wait_semaphores: &[vk::Semaphore], wait_dst_stage_mask: &[vk::PipelineStageFlags], signal_semaphores: &[vk::Semaphore], fence: &vk::Fence, command: impl FnOnce(&vk::CommandBuffer) -> Result<()>) -> Result<()> {
         unsafe {
             device.reset_command_buffer(*command_buffer, vk::CommandBufferResetFlags::empty())?;
@@ -195,7 +240,7 @@ impl BootstrapCommandBufferLoader {
 }
 
 impl BootstrapLoader for BootstrapCommandBufferLoader {
-    fn after_create_logical_device(&self, _inst: &Instance, device: &Device, _window: &Window, app_data: &mut AppData) -> Result<()> {
+    fn after_create_logical_device(&self, _inst: &Instance, device: &Device, _window: Option<&Window>, app_data: &mut AppData) -> Result<()> {
         let mut command_pools_info = CommandPoolsInfo::new(app_data.queue_info.as_ref().unwrap().clone());
         self.create_command_pools(device, &mut command_pools_info, app_data)?;
         self.create_command_buffers(device, &mut command_pools_info, app_data)?;
@@ -211,7 +256,7 @@ impl BootstrapLoader for BootstrapCommandBufferLoader {
         }
     }
 
-    fn recreate_swapchain(&self, inst: &Instance, device: &Device, window: &Window, app_data: &mut AppData, next: &dyn Fn(&Instance, &Device, &Window, &mut AppData) -> Result<()>) -> Result<()> {
+    fn recreate_swapchain(&self, inst: &Instance, device: &Device, window: Option<&Window>, app_data: &mut AppData, next: &dyn Fn(&Instance, &Device, Option<&Window>, &mut AppData) -> Result<()>) -> Result<()> {
         trace!("Recreating command buffers (but not command pool) in recreate_swapchain");
 
         let mut command_pools_info = app_data.command_pools.take().unwrap();
@@ -1,11 +1,11 @@
-use super::{BootstrapLoader, BootstrapUniformLoader, BootstrapCommandBufferLoader, CommandPoolsInfo};
+use super::{BootstrapLoader, BootstrapUniformLoader, BootstrapCommandBufferLoader};
 
 use std::{
-    mem::{size_of},
-    fs::{File}
+    mem::{size_of}
 };
-use anyhow::{anyhow, Result};
+use anyhow::{Result};
 use winit::window::{Window};
+use nalgebra_glm as glm;
 use vulkanalia::{
     prelude::v1_0::*
 };
@@ -14,7 +14,7 @@ use crate::{
     app_data::{AppData},
     shader_input::{
         {motion_blur},
-        uniform_buffer_object::{UniformBufferObject, PostprocessingUniformBufferObject}
+        uniform_buffer_object::{UniformBufferObject, PostprocessingUniformBufferObject, OverlayUniformBufferObject, ExposureState, MAX_JOINTS}
     },
     resources::{Image2D, Buffer},
     bootstrap_loader
@@ -22,62 +22,93 @@ use crate::{
 
 #[derive(Debug, Default)]
 pub struct DescriptorSetInfo {
-    pub diffuse: Image2D,
-    pub normal: Image2D,
-    pub occlusion_roughness_metallic: Image2D,
-
     pub base_descriptor_sets: Vec<vk::DescriptorSet>,
     pub postprocessing_descriptor_sets: Vec<vk::DescriptorSet>,
+    //Only allocated when TAA is enabled. The history binding is rewritten every frame
+    pub taa_descriptor_sets: Vec<vk::DescriptorSet>,
+    //Only allocated when there are postprocess effects. One per pass of each effect, for each swapchain image. The input bindings are rewritten every frame
+    pub postprocess_effect_descriptor_sets: Vec<Vec<Vec<vk::DescriptorSet>>>,
+    //Allocated along with the postprocess effect descriptor sets. One per swapchain image
+    pub auto_exposure_descriptor_sets: Vec<vk::DescriptorSet>,
+    pub overlay_descriptor_sets: Vec<vk::DescriptorSet>,
+    //Bound with each skinned model's dynamic offsets into the image's joint palette buffer
+    pub skinning_descriptor_sets: Vec<vk::DescriptorSet>,
 
     pub postprocessing_vertex_buffer: Buffer<motion_blur::Vertex>
 }
 
-bootstrap_loader! {
-    pub struct BootstrapDescriptorSetLoader {
-        depends_on(BootstrapUniformLoader, BootstrapCommandBufferLoader);
-    }
-}
-
-impl BootstrapDescriptorSetLoader {
-    fn load_image(&self, path: &'static str, device: &Device, memory_properties: &vk::PhysicalDeviceMemoryProperties, command_pools_info: &CommandPoolsInfo, is_srgb: bool) -> Result<Image2D> {
-        let image_file = File::open(path).map_err(|_| anyhow!("Problem loading PNG file at {:?}", path))?;
+impl DescriptorSetInfo {
+    //Only safe once the command buffer last recorded for this image has finished executing
+    pub fn update_taa_history(&self, device: &Device, image_index: usize, history_image: &Image2D) -> () {
+        let image_info = &[
+            history_image.get_descriptor_image_info()
+        ];
+        let sampler_write = vk::WriteDescriptorSet::builder()
+            .dst_set(self.taa_descriptor_sets[image_index])
+            .dst_binding(2)
+            .dst_array_element(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(image_info);
 
-        let mut decoder = png::Decoder::new(image_file);
-        decoder.set_ignore_text_chunk(true);
-        let mut reader = decoder.read_info()?;
-
-        let mut image = Image2D::new();
-        image.create_from_png(&mut reader, device, memory_properties, command_pools_info, is_srgb)?;
-
-        Ok(image)
+        unsafe {
+            device.update_descriptor_sets(&[sampler_write], &[] as &[vk::CopyDescriptorSet]);
+        }
     }
 
-    fn load_images(&self, device: &Device, descriptor_sets_info: &mut DescriptorSetInfo, app_data: &AppData) -> Result<()> {
-        let memory_properties = &app_data.memory_properties;
-        let command_pools_info = app_data.command_pools.as_ref().unwrap();
+    //Which images a pass reads depends on which effects are enabled, so these are set while recording. Same safety requirements as update_taa_history
+    pub fn update_postprocess_effect_inputs(&self, device: &Device, image_index: usize, effect_index: usize, pass_index: usize, pass_input: &Image2D, effect_input: &Image2D) -> () {
+        let desc_set = self.postprocess_effect_descriptor_sets[image_index][effect_index][pass_index];
+
+        let pass_input_info = &[
+            pass_input.get_descriptor_image_info()
+        ];
+        let pass_input_write = vk::WriteDescriptorSet::builder()
+            .dst_set(desc_set)
+            .dst_binding(1)
+            .dst_array_element(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(pass_input_info);
+
+        let effect_input_info = &[
+            effect_input.get_descriptor_image_info()
+        ];
+        let effect_input_write = vk::WriteDescriptorSet::builder()
+            .dst_set(desc_set)
+            .dst_binding(3)
+            .dst_array_element(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(effect_input_info);
 
-        // descriptor_sets_info.diffuse = self.load_image("resources/models/die/die_DefaultMaterial_BaseColor.png", device, memory_properties, command_pools_info, true)?;
-        // descriptor_sets_info.occlusion_roughness_metallic = self.load_image("resources/models/die/die_DefaultMaterial_OcclusionRoughnessMetallic.png", device, memory_properties, command_pools_info, false)?;
-        // descriptor_sets_info.diffuse = self.load_image("resources/models/viking-room/viking-room.png", device, memory_properties, command_pools_info, true)?;
-        // descriptor_sets_info.diffuse = self.load_image("resources/models/sphere/sphere_DefaultMaterial_BaseColor.png", device, memory_properties, command_pools_info, true)?;
-
-        descriptor_sets_info.diffuse = self.load_image("resources/models/marbles/bowl_DefaultMaterial_BaseColor.png", device, memory_properties, command_pools_info, true)?;
-        descriptor_sets_info.normal = self.load_image("resources/models/marbles/bowl_DefaultMaterial_Normal.png", device, memory_properties, command_pools_info, false)?;
-        descriptor_sets_info.occlusion_roughness_metallic = self.load_image("resources/models/marbles/bowl_DefaultMaterial_OcclusionRoughnessMetallic.png", device, memory_properties, command_pools_info, false)?;
+        unsafe {
+            device.update_descriptor_sets(&[pass_input_write, effect_input_write], &[] as &[vk::CopyDescriptorSet]);
+        }
+    }
 
-        // descriptor_sets_info.diffuse = self.load_image("resources/models/marbles/flat_plane_DefaultMaterial_BaseColor.png", device, memory_properties, command_pools_info, true)?;
-        // descriptor_sets_info.normal = self.load_image("resources/models/marbles/flat_plane_DefaultMaterial_Normal.png", device, memory_properties, command_pools_info, false)?;
-        // descriptor_sets_info.occlusion_roughness_metallic = self.load_image("resources/models/marbles/flat_plane_DefaultMaterial_OcclusionRoughnessMetallic.png", device, memory_properties, command_pools_info, false)?;
+    //Points the final output pass at the result of the postprocess effects. Same safety requirements as update_taa_history
+    pub fn update_postprocessing_source(&self, device: &Device, image_index: usize, source_image: &Image2D) -> () {
+        let image_info = &[
+            source_image.get_descriptor_image_info()
+        ];
+        let sampler_write = vk::WriteDescriptorSet::builder()
+            .dst_set(self.postprocessing_descriptor_sets[image_index])
+            .dst_binding(1)
+            .dst_array_element(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(image_info);
 
-        Ok(())
+        unsafe {
+            device.update_descriptor_sets(&[sampler_write], &[] as &[vk::CopyDescriptorSet]);
+        }
     }
+}
 
-    fn destroy_images(&self, device: &Device, descriptor_sets_info: &mut DescriptorSetInfo) -> () {
-        descriptor_sets_info.diffuse.destroy(device);
-        descriptor_sets_info.normal.destroy(device);
-        descriptor_sets_info.occlusion_roughness_metallic.destroy(device);
+bootstrap_loader! {
+    pub struct BootstrapDescriptorSetLoader {
+        depends_on(BootstrapUniformLoader, BootstrapCommandBufferLoader);
     }
+}
 
+impl BootstrapDescriptorSetLoader {
     fn allocate_descriptor_sets(&self, device: &Device, count: u32, layout: vk::DescriptorSetLayout, desc_pool: vk::DescriptorPool) -> Result<Vec<vk::DescriptorSet>> {
         let layouts = vec![layout; count as usize];
         let desc_set_info = vk::DescriptorSetAllocateInfo::builder()
@@ -88,7 +119,7 @@ impl BootstrapDescriptorSetLoader {
             Ok(device.allocate_descriptor_sets(&desc_set_info)?)
         }
     }
-    fn create_base_descriptor_sets(&self, device: &Device, count: u32, layout: vk::DescriptorSetLayout, desc_pool: vk::DescriptorPool, uniform_buffers: &Vec<Buffer<UniformBufferObject>>, descriptor_sets_info: &DescriptorSetInfo) -> Result<Vec<vk::DescriptorSet>> {
+    fn create_base_descriptor_sets(&self, device: &Device, count: u32, layout: vk::DescriptorSetLayout, desc_pool: vk::DescriptorPool, uniform_buffers: &Vec<Buffer<UniformBufferObject>>, shadow_maps: &Vec<Image2D>) -> Result<Vec<vk::DescriptorSet>> {
         let desc_sets = self.allocate_descriptor_sets(device, count, layout, desc_pool)?;
 
         for (q, desc_set) in desc_sets.iter().enumerate() {
@@ -106,26 +137,24 @@ impl BootstrapDescriptorSetLoader {
                 .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
                 .buffer_info(buffer_info);
 
-            let image_info = &[
-                descriptor_sets_info.diffuse.get_descriptor_image_info(),
-                descriptor_sets_info.normal.get_descriptor_image_info(),
-                descriptor_sets_info.occlusion_roughness_metallic.get_descriptor_image_info()
+            let shadow_map_info = &[
+                shadow_maps[q].get_descriptor_image_info()
             ];
-            let sampler_write = vk::WriteDescriptorSet::builder()
+            let shadow_map_write = vk::WriteDescriptorSet::builder()
                 .dst_set(*desc_set)
-                .dst_binding(1)
+                .dst_binding(2)
                 .dst_array_element(0)
                 .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-                .image_info(image_info);
+                .image_info(shadow_map_info);
 
             unsafe {
-                device.update_descriptor_sets(&[ubo_write, sampler_write], &[] as &[vk::CopyDescriptorSet]);
+                device.update_descriptor_sets(&[ubo_write, shadow_map_write], &[] as &[vk::CopyDescriptorSet]);
             }
         }
 
         Ok(desc_sets)
     }
-    fn create_postprocessing_descriptor_sets(&self, device: &Device, count: u32, layout: vk::DescriptorSetLayout, desc_pool: vk::DescriptorPool, uniform_buffers: &Vec<Buffer<PostprocessingUniformBufferObject>>, base_render_images: &Vec<Image2D>, motion_vector_images: &Vec<Image2D>) -> Result<Vec<vk::DescriptorSet>> {
+    fn create_postprocessing_descriptor_sets(&self, device: &Device, count: u32, layout: vk::DescriptorSetLayout, desc_pool: vk::DescriptorPool, uniform_buffers: &Vec<Buffer<PostprocessingUniformBufferObject>>, color_images: &Vec<Image2D>, motion_vector_images: &Vec<Image2D>) -> Result<Vec<vk::DescriptorSet>> {
         let desc_sets = self.allocate_descriptor_sets(device, count, layout, desc_pool)?;
 
         for (q, desc_set) in desc_sets.iter().enumerate() {
@@ -144,7 +173,7 @@ impl BootstrapDescriptorSetLoader {
                 .buffer_info(buffer_info);
 
             let image_info = &[
-                base_render_images[q].get_descriptor_image_info(),
+                color_images[q].get_descriptor_image_info(),
                 motion_vector_images[q].get_descriptor_image_info()
             ];
             let sampler_write = vk::WriteDescriptorSet::builder()
@@ -161,14 +190,204 @@ impl BootstrapDescriptorSetLoader {
 
         Ok(desc_sets)
     }
+    fn create_taa_descriptor_sets(&self, device: &Device, count: u32, layout: vk::DescriptorSetLayout, desc_pool: vk::DescriptorPool, color_images: &Vec<Image2D>, motion_vector_images: &Vec<Image2D>) -> Result<Vec<vk::DescriptorSet>> {
+        let desc_sets = self.allocate_descriptor_sets(device, count, layout, desc_pool)?;
+
+        for (q, desc_set) in desc_sets.iter().enumerate() {
+            //There's no history until the first frame has been resolved, so the current color stands in for it
+            let image_info = &[
+                color_images[q].get_descriptor_image_info(),
+                motion_vector_images[q].get_descriptor_image_info(),
+                color_images[q].get_descriptor_image_info()
+            ];
+            let sampler_write = vk::WriteDescriptorSet::builder()
+                .dst_set(*desc_set)
+                .dst_binding(0)
+                .dst_array_element(0)
+                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .image_info(image_info);
+
+            unsafe {
+                device.update_descriptor_sets(&[sampler_write], &[] as &[vk::CopyDescriptorSet]);
+            }
+        }
+
+        Ok(desc_sets)
+    }
+    fn create_postprocess_effect_descriptor_sets(&self, device: &Device, count: u32, layout: vk::DescriptorSetLayout, desc_pool: vk::DescriptorPool, uniform_buffers: &Vec<Buffer<PostprocessingUniformBufferObject>>, motion_vector_images: &Vec<Image2D>, exposure_buffer: &Buffer<ExposureState>, app_data: &AppData) -> Result<Vec<Vec<Vec<vk::DescriptorSet>>>> {
+        let mut image_desc_sets = vec![];
+        for q in 0..(count as usize) {
+            let mut effect_desc_sets = vec![];
+            for effect in app_data.postprocess_effects.iter() {
+                let desc_sets = self.allocate_descriptor_sets(device, effect.passes.len() as u32, layout, desc_pool)?;
+
+                for desc_set in desc_sets.iter() {
+                    let buffer = unsafe { uniform_buffers[q].raw_buffer().unwrap() };
+                    let buff_info = vk::DescriptorBufferInfo::builder()
+                        .buffer(buffer)
+                        .offset(0)
+                        .range(size_of::<PostprocessingUniformBufferObject>() as u64);
+
+                    let buffer_info = &[buff_info];
+                    let ubo_write = vk::WriteDescriptorSet::builder()
+                        .dst_set(*desc_set)
+                        .dst_binding(0)
+                        .dst_array_element(0)
+                        .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+                        .buffer_info(buffer_info);
+
+                    let motion_vector_info = &[
+                        motion_vector_images[q].get_descriptor_image_info()
+                    ];
+                    let motion_vector_write = vk::WriteDescriptorSet::builder()
+                        .dst_set(*desc_set)
+                        .dst_binding(2)
+                        .dst_array_element(0)
+                        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                        .image_info(motion_vector_info);
+
+                    let exposure_buffer_info = &[
+                        vk::DescriptorBufferInfo::builder()
+                            .buffer(unsafe { exposure_buffer.raw_buffer().unwrap() })
+                            .offset(0)
+                            .range(size_of::<ExposureState>() as u64)
+                    ];
+                    let exposure_write = vk::WriteDescriptorSet::builder()
+                        .dst_set(*desc_set)
+                        .dst_binding(4)
+                        .dst_array_element(0)
+                        .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                        .buffer_info(exposure_buffer_info);
+
+                    unsafe {
+                        device.update_descriptor_sets(&[ubo_write, motion_vector_write, exposure_write], &[] as &[vk::CopyDescriptorSet]);
+                    }
+                }
+
+                effect_desc_sets.push(desc_sets);
+            }
+            image_desc_sets.push(effect_desc_sets);
+        }
+
+        Ok(image_desc_sets)
+    }
+    fn create_auto_exposure_descriptor_sets(&self, device: &Device, count: u32, layout: vk::DescriptorSetLayout, desc_pool: vk::DescriptorPool, color_images: &Vec<Image2D>, histogram_buffer: &Buffer<u32>, exposure_buffer: &Buffer<ExposureState>) -> Result<Vec<vk::DescriptorSet>> {
+        let desc_sets = self.allocate_descriptor_sets(device, count, layout, desc_pool)?;
+
+        for (q, desc_set) in desc_sets.iter().enumerate() {
+            let color_info = &[
+                color_images[q].get_descriptor_image_info()
+            ];
+            let color_write = vk::WriteDescriptorSet::builder()
+                .dst_set(*desc_set)
+                .dst_binding(0)
+                .dst_array_element(0)
+                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .image_info(color_info);
+
+            let histogram_buffer_info = &[
+                vk::DescriptorBufferInfo::builder()
+                    .buffer(unsafe { histogram_buffer.raw_buffer().unwrap() })
+                    .offset(0)
+                    .range(histogram_buffer.allocated_buffer_size())
+            ];
+            let histogram_write = vk::WriteDescriptorSet::builder()
+                .dst_set(*desc_set)
+                .dst_binding(1)
+                .dst_array_element(0)
+                .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                .buffer_info(histogram_buffer_info);
+
+            let exposure_buffer_info = &[
+                vk::DescriptorBufferInfo::builder()
+                    .buffer(unsafe { exposure_buffer.raw_buffer().unwrap() })
+                    .offset(0)
+                    .range(size_of::<ExposureState>() as u64)
+            ];
+            let exposure_write = vk::WriteDescriptorSet::builder()
+                .dst_set(*desc_set)
+                .dst_binding(2)
+                .dst_array_element(0)
+                .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                .buffer_info(exposure_buffer_info);
+
+            unsafe {
+                device.update_descriptor_sets(&[color_write, histogram_write, exposure_write], &[] as &[vk::CopyDescriptorSet]);
+            }
+        }
+
+        Ok(desc_sets)
+    }
+    fn create_overlay_descriptor_sets(&self, device: &Device, count: u32, layout: vk::DescriptorSetLayout, desc_pool: vk::DescriptorPool, uniform_buffers: &Vec<Buffer<OverlayUniformBufferObject>>) -> Result<Vec<vk::DescriptorSet>> {
+        let desc_sets = self.allocate_descriptor_sets(device, count, layout, desc_pool)?;
+
+        for (q, desc_set) in desc_sets.iter().enumerate() {
+            let buffer = unsafe { uniform_buffers[q].raw_buffer().unwrap() };
+            let buff_info = vk::DescriptorBufferInfo::builder()
+                .buffer(buffer)
+                .offset(0)
+                .range(size_of::<OverlayUniformBufferObject>() as u64);
+
+            let buffer_info = &[buff_info];
+            let ubo_write = vk::WriteDescriptorSet::builder()
+                .dst_set(*desc_set)
+                .dst_binding(0)
+                .dst_array_element(0)
+                .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+                .buffer_info(buffer_info);
+
+            unsafe {
+                device.update_descriptor_sets(&[ubo_write], &[] as &[vk::CopyDescriptorSet]);
+            }
+        }
+
+        Ok(desc_sets)
+    }
+    fn create_skinning_descriptor_sets(&self, device: &Device, count: u32, layout: vk::DescriptorSetLayout, desc_pool: vk::DescriptorPool, joint_palette_buffers: &Vec<Buffer<glm::Mat4>>) -> Result<Vec<vk::DescriptorSet>> {
+        let desc_sets = self.allocate_descriptor_sets(device, count, layout, desc_pool)?;
+
+        for (q, desc_set) in desc_sets.iter().enumerate() {
+            let buffer = unsafe { joint_palette_buffers[q].raw_buffer().unwrap() };
+            let buff_info = vk::DescriptorBufferInfo::builder()
+                .buffer(buffer)
+                .offset(0)
+                .range((MAX_JOINTS * size_of::<glm::Mat4>()) as u64);
+
+            //Current and previous palettes, which only differ in their dynamic offsets
+            let buffer_info = &[buff_info, buff_info];
+            let palette_write = vk::WriteDescriptorSet::builder()
+                .dst_set(*desc_set)
+                .dst_binding(0)
+                .dst_array_element(0)
+                .descriptor_type(vk::DescriptorType::STORAGE_BUFFER_DYNAMIC)
+                .buffer_info(buffer_info);
+
+            unsafe {
+                device.update_descriptor_sets(&[palette_write], &[] as &[vk::CopyDescriptorSet]);
+            }
+        }
+
+        Ok(desc_sets)
+    }
     fn create_descriptor_sets(&self, device: &Device, descriptor_sets_info: &mut DescriptorSetInfo, app_data: &AppData) -> Result<()> {
         let image_count = app_data.swapchain.as_ref().unwrap().image_count;
         let uniforms_info = app_data.uniforms.as_ref().unwrap();
         let render_images_info = app_data.render_images.as_ref().unwrap();
 
         debug!("Allocating descriptor sets...");
-        descriptor_sets_info.base_descriptor_sets = self.create_base_descriptor_sets(device, image_count, uniforms_info.base_descriptor_set_layout, uniforms_info.base_descriptor_pool, &uniforms_info.uniform_buffers, descriptor_sets_info)?;
-        descriptor_sets_info.postprocessing_descriptor_sets = self.create_postprocessing_descriptor_sets(device, image_count, uniforms_info.postprocessing_descriptor_set_layout, uniforms_info.postprocessing_descriptor_pool, &uniforms_info.postprocessing_uniform_buffers, &render_images_info.base_render_images, &render_images_info.motion_vector_buffers)?;
+        descriptor_sets_info.base_descriptor_sets = self.create_base_descriptor_sets(device, image_count, uniforms_info.base_descriptor_set_layout, uniforms_info.base_descriptor_pool, &uniforms_info.uniform_buffers, &render_images_info.shadow_maps)?;
+        descriptor_sets_info.postprocessing_descriptor_sets = self.create_postprocessing_descriptor_sets(device, image_count, uniforms_info.postprocessing_descriptor_set_layout, uniforms_info.postprocessing_descriptor_pool, &uniforms_info.postprocessing_uniform_buffers, render_images_info.postprocessing_source_images(), &render_images_info.motion_vector_buffers)?;
+        if app_data.taa.is_some() {
+            descriptor_sets_info.taa_descriptor_sets = self.create_taa_descriptor_sets(device, image_count, uniforms_info.taa_descriptor_set_layout, uniforms_info.taa_descriptor_pool, &render_images_info.base_render_images, &render_images_info.motion_vector_buffers)?;
+        }
+        if !app_data.postprocess_effects.is_empty() {
+            let histogram_buffer = uniforms_info.luminance_histogram_buffer.as_ref().unwrap();
+            let exposure_buffer = uniforms_info.exposure_buffer.as_ref().unwrap();
+            descriptor_sets_info.postprocess_effect_descriptor_sets = self.create_postprocess_effect_descriptor_sets(device, image_count, uniforms_info.postprocess_effect_descriptor_set_layout, uniforms_info.postprocess_effect_descriptor_pool, &uniforms_info.postprocessing_uniform_buffers, &render_images_info.motion_vector_buffers, exposure_buffer, app_data)?;
+            descriptor_sets_info.auto_exposure_descriptor_sets = self.create_auto_exposure_descriptor_sets(device, image_count, uniforms_info.auto_exposure_descriptor_set_layout, uniforms_info.auto_exposure_descriptor_pool, &render_images_info.base_render_images, histogram_buffer, exposure_buffer)?;
+        }
+        descriptor_sets_info.overlay_descriptor_sets = self.create_overlay_descriptor_sets(device, image_count, uniforms_info.overlay_descriptor_set_layout, uniforms_info.overlay_descriptor_pool, &uniforms_info.overlay_uniform_buffers)?;
+        descriptor_sets_info.skinning_descriptor_sets = self.create_skinning_descriptor_sets(device, image_count, uniforms_info.skinning_descriptor_set_layout, uniforms_info.skinning_descriptor_pool, &uniforms_info.joint_palette_buffers)?;
         debug!("Descriptor sets allocated: {:?}", descriptor_sets_info.base_descriptor_sets);
 
         Ok(())
@@ -194,9 +413,8 @@ impl BootstrapDescriptorSetLoader {
 }
 
 impl BootstrapLoader for BootstrapDescriptorSetLoader {
-    fn after_create_logical_device(&self, _inst: &Instance, device: &Device, _window: &Window, app_data: &mut AppData) -> Result<()> {
+    fn after_create_logical_device(&self, _inst: &Instance, device: &Device, _window: Option<&Window>, app_data: &mut AppData) -> Result<()> {
         let mut descriptor_sets_info = DescriptorSetInfo::default();
-        self.load_images(device, &mut descriptor_sets_info, app_data)?;
         self.create_postprocessing_vertex_buffer(device, &mut descriptor_sets_info, app_data)?;
         self.create_descriptor_sets(device, &mut descriptor_sets_info, app_data)?;
         app_data.descriptor_sets = Some(descriptor_sets_info);
@@ -208,18 +426,27 @@ impl BootstrapLoader for BootstrapDescriptorSetLoader {
         if let Some(mut descriptor_sets_info) = app_data.descriptor_sets.take() {
             descriptor_sets_info.base_descriptor_sets.clear(); //No need to clean these up, apparently
             descriptor_sets_info.postprocessing_descriptor_sets.clear(); //No need to clean these up, apparently
+            descriptor_sets_info.taa_descriptor_sets.clear(); //No need to clean these up, apparently
+            descriptor_sets_info.postprocess_effect_descriptor_sets.clear(); //No need to clean these up, apparently
+            descriptor_sets_info.auto_exposure_descriptor_sets.clear(); //No need to clean these up, apparently
+            descriptor_sets_info.overlay_descriptor_sets.clear(); //No need to clean these up, apparently
+            descriptor_sets_info.skinning_descriptor_sets.clear(); //No need to clean these up, apparently
             self.destroy_postprocessing_vertex_buffer(device, &mut descriptor_sets_info);
-            self.destroy_images(device, &mut descriptor_sets_info);
         }
     }
 
-    fn recreate_swapchain(&self, inst: &Instance, device: &Device, window: &Window, app_data: &mut AppData, next: &dyn Fn(&Instance, &Device, &Window, &mut AppData) -> Result<()>) -> Result<()> {
-        trace!("Recreating descriptor sets (but not images or postprocessing model) in recreate_swapchain");
+    fn recreate_swapchain(&self, inst: &Instance, device: &Device, window: Option<&Window>, app_data: &mut AppData, next: &dyn Fn(&Instance, &Device, Option<&Window>, &mut AppData) -> Result<()>) -> Result<()> {
+        trace!("Recreating descriptor sets (but not postprocessing model) in recreate_swapchain");
 
         let mut descriptor_sets_info = app_data.descriptor_sets.take().unwrap();
 
         descriptor_sets_info.base_descriptor_sets.clear(); //No need to clean these up, apparently
         descriptor_sets_info.postprocessing_descriptor_sets.clear(); //No need to clean these up, apparently
+        descriptor_sets_info.taa_descriptor_sets.clear(); //No need to clean these up, apparently
+        descriptor_sets_info.postprocess_effect_descriptor_sets.clear(); //No need to clean these up, apparently
+        descriptor_sets_info.auto_exposure_descriptor_sets.clear(); //No need to clean these up, apparently
+        descriptor_sets_info.overlay_descriptor_sets.clear(); //No need to clean these up, apparently
+        descriptor_sets_info.skinning_descriptor_sets.clear(); //No need to clean these up, apparently
         next(inst, device, window, app_data)?;
         self.create_descriptor_sets(device, &mut descriptor_sets_info, app_data)?;
 
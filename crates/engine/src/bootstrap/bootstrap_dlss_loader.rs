@@ -1,24 +1,173 @@
-use super::{BootstrapLoader};
+use super::{BootstrapLoader, BootstrapSwapchainLoader, BootstrapCommandBufferLoader};
 
 use std::collections::{HashSet};
-use anyhow::{Result};
-use dlss_sys::extensions::{get_vulkan_required_extensions};
+use anyhow::{anyhow, Result};
+use dlss_sys::{
+    extensions::{get_vulkan_required_extensions},
+    super_sampling::{self, PerfQuality, CapabilityParameters, SuperSamplingFeature, FeatureCreateFlags, ImageResource, EvaluateParameters}
+};
+use winit::window::{Window};
 use vulkanalia::{
-    prelude::v1_0::*
+    prelude::v1_0::*,
+    vk::{Handle}
 };
 
 use crate::{
+    app_data::{AppData},
+    resources::{Image2D},
     bootstrap_loader
 };
 
+//NVIDIA issues application IDs for shipping titles. Zero is accepted during development
+const NGX_APPLICATION_ID: u64 = 0;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum DlssQualityMode {
+    UltraPerformance,
+    Performance,
+    Balanced,
+    #[default]
+    Quality,
+    UltraQuality,
+    //Runs at native resolution, only using DLSS for anti-aliasing
+    Dlaa
+}
+
+impl DlssQualityMode {
+    fn perf_quality(&self) -> PerfQuality {
+        match self {
+            DlssQualityMode::UltraPerformance => PerfQuality::UltraPerformance,
+            DlssQualityMode::Performance => PerfQuality::MaxPerformance,
+            DlssQualityMode::Balanced => PerfQuality::Balanced,
+            DlssQualityMode::Quality => PerfQuality::MaxQuality,
+            DlssQualityMode::UltraQuality => PerfQuality::UltraQuality,
+            DlssQualityMode::Dlaa => PerfQuality::Dlaa
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DlssInfo {
+    pub quality: DlssQualityMode,
+    pub render_extent: vk::Extent2D,
+    pub output_extent: vk::Extent2D,
+    pub sharpness: f32,
+
+    capability_parameters: CapabilityParameters,
+    feature: Option<SuperSamplingFeature>
+}
+
+impl DlssInfo {
+    pub fn image_resource(image: &Image2D, image_view: vk::ImageView, aspect_mask: vk::ImageAspectFlags) -> ImageResource {
+        let size = image.size().unwrap();
+
+        ImageResource {
+            image: unsafe { image.raw_image().unwrap().as_raw() },
+            image_view: image_view.as_raw(),
+            format: image.format().unwrap().as_raw() as u32,
+            aspect_mask: aspect_mask.bits(),
+            width: size.width,
+            height: size.height
+        }
+    }
+
+    pub unsafe fn evaluate(&self, command_buffer: &vk::CommandBuffer, params: &EvaluateParameters) -> Result<()> {
+        let feature = self.feature.as_ref().ok_or_else(|| anyhow!("The DLSS feature has not been created"))?;
+        feature.evaluate(command_buffer.as_raw(), params)
+    }
+}
+
 bootstrap_loader! {
     pub struct BootstrapDlssLoader {
-        depends_on();
+        depends_on(BootstrapSwapchainLoader, BootstrapCommandBufferLoader);
+        quality: DlssQualityMode
     }
 }
 
 impl BootstrapDlssLoader {
+    pub fn new_with_quality(quality: DlssQualityMode) -> Self {
+        Self {
+            quality
+        }
+    }
+
+    fn init_ngx(&self, inst: &Instance, device: &Device, app_data: &AppData) -> Result<CapabilityParameters> {
+        debug!("Initializing NGX...");
+
+        let data_path = std::env::current_dir()?;
+        let physical_device = app_data.physical_device.unwrap();
+
+        unsafe {
+            super_sampling::init(NGX_APPLICATION_ID, &data_path.to_string_lossy(), inst.handle().as_raw(), physical_device.as_raw(), device.handle().as_raw())?;
+
+            let capability_parameters = match CapabilityParameters::get() {
+                Ok(capability_parameters) => capability_parameters,
+                Err(e) => {
+                    self.shutdown_ngx(device);
+                    return Err(e);
+                }
+            };
+            if !capability_parameters.is_super_sampling_available() {
+                self.shutdown_ngx(device);
+                return Err(anyhow!("DLSS super resolution is not available on this device"));
+            }
+
+            Ok(capability_parameters)
+        }
+    }
+
+    fn shutdown_ngx(&self, device: &Device) -> () {
+        debug!("Shutting down NGX...");
+
+        if let Err(e) = unsafe { super_sampling::shutdown(device.handle().as_raw()) } {
+            warn!("Failed to shut down NGX: {:?}", e);
+        }
+    }
+
+    fn create_feature(&self, device: &Device, dlss_info: &mut DlssInfo, app_data: &AppData) -> Result<()> {
+        let output_extent = app_data.swapchain.as_ref().unwrap().extent;
+        let settings = unsafe { dlss_info.capability_parameters.get_optimal_settings(output_extent.width, output_extent.height, dlss_info.quality.perf_quality())? };
+        let render_extent = vk::Extent2D {
+            width: settings.render_width,
+            height: settings.render_height
+        };
+
+        debug!("Creating DLSS feature ({:?}) upscaling {:?} to {:?}...", dlss_info.quality, render_extent, output_extent);
+
+        //The base render is HDR, its motion vectors are at render resolution and include the jitter, and its depth isn't inverted
+        let flags = FeatureCreateFlags {
+            is_hdr: true,
+            motion_vectors_low_res: true,
+            motion_vectors_jittered: true,
+            depth_inverted: false,
+            auto_exposure: true
+        };
 
+        let command_pools_info = app_data.command_pools.as_ref().unwrap();
+        let mut feature = None;
+        command_pools_info.submit_command_transient_sync(device, |command_buffer| {
+            feature = Some(unsafe { SuperSamplingFeature::create(device.handle().as_raw(), command_buffer.as_raw(), render_extent.width, render_extent.height, output_extent.width, output_extent.height, dlss_info.quality.perf_quality(), flags)? });
+
+            Ok(())
+        })?;
+
+        dlss_info.render_extent = render_extent;
+        dlss_info.output_extent = output_extent;
+        dlss_info.sharpness = settings.sharpness;
+        dlss_info.feature = feature;
+
+        Ok(())
+    }
+
+    fn destroy_feature(&self, dlss_info: &mut DlssInfo) -> () {
+        if let Some(mut feature) = dlss_info.feature.take() {
+            debug!("Releasing DLSS feature...");
+
+            if let Err(e) = unsafe { feature.release() } {
+                warn!("Failed to release the DLSS feature: {:?}", e);
+            }
+        }
+    }
 }
 
 impl BootstrapLoader for BootstrapDlssLoader {
@@ -42,4 +191,60 @@ impl BootstrapLoader for BootstrapDlssLoader {
 
         Ok(())
     }
+
+    fn after_create_logical_device(&self, inst: &Instance, device: &Device, _window: Option<&Window>, app_data: &mut AppData) -> Result<()> {
+        //Without DLSS the rest of the renderer falls back to its own upscaling, so this isn't fatal
+        let capability_parameters = match self.init_ngx(inst, device, app_data) {
+            Ok(capability_parameters) => capability_parameters,
+            Err(e) => {
+                warn!("DLSS could not be initialized and will be disabled: {:?}", e);
+                return Ok(());
+            }
+        };
+
+        let mut dlss_info = DlssInfo {
+            quality: self.quality,
+            render_extent: Default::default(),
+            output_extent: Default::default(),
+            sharpness: 0.0,
+            capability_parameters,
+            feature: None
+        };
+        if let Err(e) = self.create_feature(device, &mut dlss_info, app_data) {
+            warn!("The DLSS feature could not be created and DLSS will be disabled: {:?}", e);
+            self.shutdown_ngx(device);
+            return Ok(());
+        }
+        app_data.dlss = Some(dlss_info);
+
+        Ok(())
+    }
+
+    fn before_destroy_logical_device(&self, _inst: &Instance, device: &Device, app_data: &mut AppData) -> () {
+        if let Some(mut dlss_info) = app_data.dlss.take() {
+            self.destroy_feature(&mut dlss_info);
+            self.shutdown_ngx(device);
+        }
+    }
+
+    fn recreate_swapchain(&self, inst: &Instance, device: &Device, window: Option<&Window>, app_data: &mut AppData, next: &dyn Fn(&Instance, &Device, Option<&Window>, &mut AppData) -> Result<()>) -> Result<()> {
+        trace!("Recreating DLSS feature (but not NGX) in recreate_swapchain");
+
+        let mut dlss_info = match app_data.dlss.take() {
+            Some(dlss_info) => dlss_info,
+            None => return next(inst, device, window, app_data)
+        };
+
+        self.destroy_feature(&mut dlss_info);
+        next(inst, device, window, app_data)?;
+        //The render pass and render images were already set up for DLSS, so there's no falling back from here
+        if let Err(e) = self.create_feature(device, &mut dlss_info, app_data) {
+            self.shutdown_ngx(device);
+            return Err(e);
+        }
+
+        app_data.dlss = Some(dlss_info);
+
+        Ok(())
+    }
 }
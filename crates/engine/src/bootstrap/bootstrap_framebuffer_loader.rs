@@ -8,13 +8,20 @@ use vulkanalia::{
 
 use crate::{
     app_data::{AppData},
+    resources::{Image2D},
     bootstrap_loader
 };
 
 #[derive(Debug, Default)]
 pub struct FramebufferInfo {
     pub base_render_framebuffers: Vec<vk::Framebuffer>,
-    pub postprocessing_framebuffers: Vec<vk::Framebuffer>
+    pub postprocessing_framebuffers: Vec<vk::Framebuffer>,
+    pub shadow_framebuffers: Vec<vk::Framebuffer>,
+    //Only created when TAA is enabled
+    pub taa_framebuffers: Vec<vk::Framebuffer>,
+    //Only created when there are postprocess effects. Laid out the same way as the images in RenderImagesInfo
+    pub postprocess_chain_framebuffers: Vec<Vec<vk::Framebuffer>>,
+    pub postprocess_intermediate_framebuffers: Vec<Vec<Vec<vk::Framebuffer>>>
 }
 
 bootstrap_loader! {
@@ -24,6 +31,22 @@ bootstrap_loader! {
 }
 
 impl BootstrapFramebufferLoader {
+    fn create_postprocess_framebuffer(&self, device: &Device, render_pass: vk::RenderPass, image: &Image2D) -> Result<vk::Framebuffer> {
+        let image_view = unsafe { image.raw_image_view().unwrap() };
+        let attachments = &[image_view];
+        let extent = image.size().unwrap();
+        let framebuffer_info = vk::FramebufferCreateInfo::builder()
+            .render_pass(render_pass)
+            .attachments(attachments)
+            .width(extent.width)
+            .height(extent.height)
+            .layers(1);
+
+        unsafe {
+            Ok(device.create_framebuffer(&framebuffer_info, None)?)
+        }
+    }
+
     fn create_framebuffers(&self, device: &Device, framebuffer_info: &mut FramebufferInfo, app_data: &AppData) -> Result<()> {
         let render_images_info = &app_data.render_images.as_ref().unwrap();
         let render_extent = render_images_info.base_render_extent;
@@ -74,9 +97,73 @@ impl BootstrapFramebufferLoader {
             })
             .collect::<Result<Vec<_>, _>>()?;
 
-        debug!("Framebuffers created: {:?}; {:?}", base_render_framebuffers, postprocessing_framebuffers);
+        let shadow_map_extent = render_images_info.shadow_map_extent;
+        let shadow_framebuffers = (0..image_count)
+            .map(|q| {
+                let shadow_map_view = unsafe { render_images_info.shadow_maps[q as usize].raw_image_view().unwrap() };
+                let attachments = &[shadow_map_view];
+                let extent = shadow_map_extent;
+                let framebuffer_info = vk::FramebufferCreateInfo::builder()
+                    .render_pass(pipeline_info.shadow_render_pass)
+                    .attachments(attachments)
+                    .width(extent.width)
+                    .height(extent.height)
+                    .layers(1);
+
+                unsafe {
+                    device.create_framebuffer(&framebuffer_info, None)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let taa_framebuffers = match app_data.taa.as_ref() {
+            Some(taa_info) => (0..image_count)
+                .map(|q| {
+                    let upscaled_render_image_view = unsafe { render_images_info.upscaled_render_images[q as usize].raw_image_view().unwrap() };
+                    let attachments = &[upscaled_render_image_view];
+                    let extent = taa_info.output_extent;
+                    let framebuffer_info = vk::FramebufferCreateInfo::builder()
+                        .render_pass(pipeline_info.taa_render_pass)
+                        .attachments(attachments)
+                        .width(extent.width)
+                        .height(extent.height)
+                        .layers(1);
+
+                    unsafe {
+                        device.create_framebuffer(&framebuffer_info, None)
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => vec![]
+        };
+
+        let render_pass = pipeline_info.postprocess_effect_render_pass;
+        let postprocess_chain_framebuffers = render_images_info.postprocess_chain_images.iter()
+            .map(|chain_images| {
+                chain_images.iter()
+                    .map(|chain_image| self.create_postprocess_framebuffer(device, render_pass, chain_image))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let postprocess_intermediate_framebuffers = render_images_info.postprocess_intermediate_images.iter()
+            .map(|effect_intermediate_images| {
+                effect_intermediate_images.iter()
+                    .map(|intermediate_images| {
+                        intermediate_images.iter()
+                            .map(|intermediate_image| self.create_postprocess_framebuffer(device, render_pass, intermediate_image))
+                            .collect::<Result<Vec<_>>>()
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        debug!("Framebuffers created: {:?}; {:?}; {:?}; {:?}; {:?}; {:?}", base_render_framebuffers, postprocessing_framebuffers, shadow_framebuffers, taa_framebuffers, postprocess_chain_framebuffers, postprocess_intermediate_framebuffers);
         framebuffer_info.base_render_framebuffers = base_render_framebuffers;
         framebuffer_info.postprocessing_framebuffers = postprocessing_framebuffers;
+        framebuffer_info.shadow_framebuffers = shadow_framebuffers;
+        framebuffer_info.taa_framebuffers = taa_framebuffers;
+        framebuffer_info.postprocess_chain_framebuffers = postprocess_chain_framebuffers;
+        framebuffer_info.postprocess_intermediate_framebuffers = postprocess_intermediate_framebuffers;
 
         Ok(())
     }
@@ -97,11 +184,39 @@ impl BootstrapFramebufferLoader {
             }
         }
         framebuffer_info.postprocessing_framebuffers.clear();
+
+        unsafe {
+            for framebuffer in framebuffer_info.shadow_framebuffers.iter() {
+                device.destroy_framebuffer(*framebuffer, None);
+            }
+        }
+        framebuffer_info.shadow_framebuffers.clear();
+
+        unsafe {
+            for framebuffer in framebuffer_info.taa_framebuffers.iter() {
+                device.destroy_framebuffer(*framebuffer, None);
+            }
+        }
+        framebuffer_info.taa_framebuffers.clear();
+
+        unsafe {
+            for framebuffer in framebuffer_info.postprocess_chain_framebuffers.iter().flatten() {
+                device.destroy_framebuffer(*framebuffer, None);
+            }
+        }
+        framebuffer_info.postprocess_chain_framebuffers.clear();
+
+        unsafe {
+            for framebuffer in framebuffer_info.postprocess_intermediate_framebuffers.iter().flatten().flatten() {
+                device.destroy_framebuffer(*framebuffer, None);
+            }
+        }
+        framebuffer_info.postprocess_intermediate_framebuffers.clear();
     }
 }
 
 impl BootstrapLoader for BootstrapFramebufferLoader {
-    fn after_create_logical_device(&self, _inst: &Instance, device: &Device, _window: &Window, app_data: &mut AppData) -> Result<()> {
+    fn after_create_logical_device(&self, _inst: &Instance, device: &Device, _window: Option<&Window>, app_data: &mut AppData) -> Result<()> {
         let mut framebuffer_info = FramebufferInfo::default();
         self.create_framebuffers(device, &mut framebuffer_info, app_data)?;
         app_data.framebuffer = Some(framebuffer_info);
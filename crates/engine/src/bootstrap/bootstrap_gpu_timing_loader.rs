@@ -0,0 +1,163 @@
+use super::{BootstrapLoader, BootstrapSwapchainLoader};
+
+use std::{
+    mem::{size_of},
+    time::{Duration}
+};
+use anyhow::{Result};
+use winit::window::{Window};
+use vulkanalia::{
+    prelude::v1_0::*
+};
+
+use crate::{
+    app_data::{AppData},
+    frame_stats::{GpuPass},
+    bootstrap_loader
+};
+
+#[derive(Debug, Default)]
+pub struct GpuTimingInfo {
+    //One set of GpuPass::timestamp_count() queries per swapchain image
+    pub query_pool: vk::QueryPool,
+    //Nanoseconds per timestamp tick
+    pub timestamp_period: f32,
+    //Timestamps only have this many meaningful bits, and wrap around past them
+    pub timestamp_mask: u64,
+    //Whether the last command buffer submitted for each image wrote its timestamps. Set by the app once it's submitted
+    pub queries_written: Vec<bool>
+}
+
+impl GpuTimingInfo {
+    fn first_query(image_index: usize) -> u32 {
+        image_index as u32 * GpuPass::timestamp_count()
+    }
+
+    //Has to be recorded outside of a render pass, before any of the image's other timestamps
+    pub fn record_frame_start(&self, device: &Device, command_buffer: vk::CommandBuffer, image_index: usize) -> () {
+        unsafe {
+            device.cmd_reset_query_pool(command_buffer, self.query_pool, Self::first_query(image_index), GpuPass::timestamp_count());
+            device.cmd_write_timestamp(command_buffer, vk::PipelineStageFlags::TOP_OF_PIPE, self.query_pool, Self::first_query(image_index));
+        }
+    }
+
+    pub fn record_pass_end(&self, device: &Device, command_buffer: vk::CommandBuffer, image_index: usize, pass: GpuPass) -> () {
+        unsafe {
+            device.cmd_write_timestamp(command_buffer, vk::PipelineStageFlags::BOTTOM_OF_PIPE, self.query_pool, Self::first_query(image_index) + pass.end_timestamp());
+        }
+    }
+
+    //Only call once the image's last command buffer has finished executing. Returns None if it didn't write any timestamps
+    pub fn read_pass_durations(&mut self, device: &Device, image_index: usize) -> Result<Option<[Duration; GpuPass::ALL.len()]>> {
+        if !self.queries_written[image_index] {
+            return Ok(None);
+        }
+        self.queries_written[image_index] = false;
+
+        let mut data = vec![0u8; GpuPass::timestamp_count() as usize * size_of::<u64>()];
+        let success = unsafe {
+            device.get_query_pool_results(self.query_pool, Self::first_query(image_index), GpuPass::timestamp_count(), &mut data, size_of::<u64>() as u64, vk::QueryResultFlags::_64)?
+        };
+        if success == vk::SuccessCode::NOT_READY {
+            return Ok(None);
+        }
+
+        let timestamps = data.chunks_exact(size_of::<u64>())
+            .map(|bytes| u64::from_ne_bytes(bytes.try_into().unwrap()))
+            .collect::<Vec<_>>();
+
+        let mut durations = [Duration::ZERO; GpuPass::ALL.len()];
+        for (q, duration) in durations.iter_mut().enumerate() {
+            let ticks = timestamps[q + 1].wrapping_sub(timestamps[q]) & self.timestamp_mask;
+            *duration = Duration::from_nanos((ticks as f64 * self.timestamp_period as f64) as u64);
+        }
+
+        Ok(Some(durations))
+    }
+}
+
+bootstrap_loader! {
+    pub struct BootstrapGpuTimingLoader {
+        depends_on(BootstrapSwapchainLoader);
+    }
+}
+
+impl BootstrapGpuTimingLoader {
+    fn create_query_pool(&self, device: &Device, gpu_timing_info: &mut GpuTimingInfo, app_data: &AppData) -> Result<()> {
+        let image_count = app_data.swapchain.as_ref().unwrap().image_count;
+
+        debug!("Creating timestamp query pool...");
+        let query_pool_info = vk::QueryPoolCreateInfo::builder()
+            .query_type(vk::QueryType::TIMESTAMP)
+            .query_count(image_count * GpuPass::timestamp_count());
+
+        let query_pool = unsafe { device.create_query_pool(&query_pool_info, None)? };
+        debug!("Timestamp query pool created: {:?}", query_pool);
+
+        gpu_timing_info.query_pool = query_pool;
+        gpu_timing_info.queries_written = vec![false; image_count as usize];
+
+        Ok(())
+    }
+
+    fn destroy_query_pool(&self, device: &Device, gpu_timing_info: &mut GpuTimingInfo) -> () {
+        debug!("Destroying timestamp query pool...");
+
+        unsafe {
+            device.destroy_query_pool(gpu_timing_info.query_pool, None);
+        }
+        gpu_timing_info.query_pool = vk::QueryPool::null();
+        gpu_timing_info.queries_written.clear();
+    }
+}
+
+impl BootstrapLoader for BootstrapGpuTimingLoader {
+    fn after_create_logical_device(&self, inst: &Instance, device: &Device, _window: Option<&Window>, app_data: &mut AppData) -> Result<()> {
+        let physical_device = app_data.physical_device.unwrap();
+        let graphics_queue_family = app_data.queue_info.as_ref().unwrap().graphics_queue_family;
+
+        let properties = unsafe { inst.get_physical_device_properties(physical_device) };
+        let queue_family_properties = unsafe { inst.get_physical_device_queue_family_properties(physical_device) };
+        let timestamp_valid_bits = queue_family_properties[graphics_queue_family as usize].timestamp_valid_bits;
+
+        //GPU timings are optional, so frame stats just go without them
+        if timestamp_valid_bits == 0 {
+            debug!("The graphics queue doesn't support timestamps, so GPU pass timings will be disabled");
+            return Ok(());
+        }
+
+        let mut gpu_timing_info = GpuTimingInfo {
+            timestamp_period: properties.limits.timestamp_period,
+            timestamp_mask: if timestamp_valid_bits >= 64 { u64::MAX } else { (1u64 << timestamp_valid_bits) - 1 },
+            ..Default::default()
+        };
+        self.create_query_pool(device, &mut gpu_timing_info, app_data)?;
+        app_data.gpu_timing = Some(gpu_timing_info);
+
+        Ok(())
+    }
+
+    fn before_destroy_logical_device(&self, _inst: &Instance, device: &Device, app_data: &mut AppData) -> () {
+        if let Some(mut gpu_timing_info) = app_data.gpu_timing.take() {
+            self.destroy_query_pool(device, &mut gpu_timing_info);
+        }
+    }
+
+    fn recreate_swapchain(&self, inst: &Instance, device: &Device, window: Option<&Window>, app_data: &mut AppData, next: &dyn Fn(&Instance, &Device, Option<&Window>, &mut AppData) -> Result<()>) -> Result<()> {
+        trace!("Recreating timestamp query pool in recreate_swapchain");
+
+        let mut gpu_timing_info = match app_data.gpu_timing.take() {
+            Some(gpu_timing_info) => gpu_timing_info,
+            None => return next(inst, device, window, app_data)
+        };
+
+        //The image count might change
+        self.destroy_query_pool(device, &mut gpu_timing_info);
+        next(inst, device, window, app_data)?;
+        self.create_query_pool(device, &mut gpu_timing_info, app_data)?;
+
+        app_data.gpu_timing = Some(gpu_timing_info);
+
+        Ok(())
+    }
+}
@@ -46,12 +46,12 @@ pub trait BootstrapLoader : Debug + IndirectDependency {
         Ok(())
     }
 
-    fn after_create_logical_device(&self, _inst: &Instance, _device: &Device, _window: &Window, _app_data: &mut AppData) -> Result<()> {
+    fn after_create_logical_device(&self, _inst: &Instance, _device: &Device, _window: Option<&Window>, _app_data: &mut AppData) -> Result<()> {
         Ok(())
     }
     fn before_destroy_logical_device(&self, _inst: &Instance, _device: &Device, _app_data: &mut AppData) -> () { }
 
-    fn recreate_swapchain(&self, inst: &Instance, device: &Device, window: &Window, app_data: &mut AppData, next: &dyn Fn(&Instance, &Device, &Window, &mut AppData) -> Result<()>) -> Result<()> {
+    fn recreate_swapchain(&self, inst: &Instance, device: &Device, window: Option<&Window>, app_data: &mut AppData, next: &dyn Fn(&Instance, &Device, Option<&Window>, &mut AppData) -> Result<()>) -> Result<()> {
         trace!("Default BootstrapLoader::recreate_swapchain");
 
         self.before_destroy_logical_device(inst, device, app_data);
@@ -74,10 +74,13 @@ macro_rules! bootstrap_loader {
     (
         pub struct $name:ident {
             depends_on ( $( $dependty:ty ),* ) ;
+            $( $field:ident : $fieldty:ty ),* $(,)?
         }
     ) => {
         #[derive(Debug, Default)]
-        pub struct $name { }
+        pub struct $name {
+            $( $field: $fieldty ),*
+        }
 
         #[allow(non_upper_case_globals)]
         const __bootstrap_loader_depends_on: [&str; 0 $( + crate::replace_expr!($dependty, 1) )* ] = [ $( <$dependty>::dependency_name() ),* ];
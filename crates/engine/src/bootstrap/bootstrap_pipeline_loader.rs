@@ -14,22 +14,40 @@ use crate::{
     shader_input::{
         {motion_blur},
         vertex_attribute_builder::{HasVertexAttributeBindings},
-        push_constants::{DepthMotionPushConstants, BaseRenderPushConstants}
+        push_constants::{DepthMotionPushConstants, BaseRenderPushConstants, ShadowPushConstants, TaaPushConstants, PostprocessPushConstants, AutoExposurePushConstants}
     },
     bootstrap_loader,
-    resources::shader_source::{AttachmentDescriptor, SubpassDescriptor, SubpassAttachmentDescriptor, ShaderSource, BlendStateDescriptor, DepthBufferUsageMode, create_pipeline_layout, create_pipeline}
+    resources::shader_source::{AttachmentDescriptor, SubpassDescriptor, SubpassAttachmentDescriptor, ShaderSource, BlendStateDescriptor, DepthBufferUsageMode, create_pipeline_layout, create_pipeline, create_compute_pipeline}
 };
 
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct PipelineInfo {
     pub base_render_pass: vk::RenderPass,
     pub postprocessing_render_pass: vk::RenderPass,
+    pub shadow_render_pass: vk::RenderPass,
+    //The TAA render pass, pipeline, and layout are only created when TAA is enabled
+    pub taa_render_pass: vk::RenderPass,
+    //The postprocess effect render pass, pipelines, and layout are only created when there are postprocess effects
+    pub postprocess_effect_render_pass: vk::RenderPass,
 
     pub depth_motion_layout: vk::PipelineLayout,
     pub base_render_layout: vk::PipelineLayout,
     pub postprocessing_layout: vk::PipelineLayout,
-
-    pub postprocessing_pipeline: vk::Pipeline
+    pub shadow_layout: vk::PipelineLayout,
+    pub taa_layout: vk::PipelineLayout,
+    pub postprocess_effect_layout: vk::PipelineLayout,
+    //Shared by both auto exposure compute pipelines, which are created along with the postprocess effect pipelines
+    pub auto_exposure_layout: vk::PipelineLayout,
+    pub overlay_layout: vk::PipelineLayout,
+
+    pub postprocessing_pipeline: vk::Pipeline,
+    //Draws the stats overlay on top of the final image, in the same subpass
+    pub overlay_pipeline: vk::Pipeline,
+    pub taa_pipeline: vk::Pipeline,
+    //One per pass of each effect, in the same order as AppData::postprocess_effects
+    pub postprocess_effect_pipelines: Vec<Vec<vk::Pipeline>>,
+    pub luminance_histogram_pipeline: vk::Pipeline,
+    pub exposure_adaptation_pipeline: vk::Pipeline
 }
 
 bootstrap_loader! {
@@ -209,11 +227,20 @@ impl BootstrapPipelineLoader {
                 ..Default::default()
             }
         ][..];
-        let depth_attachment = AttachmentDescriptor {
-            format: depth_buffer_format,
-            store_op: vk::AttachmentStoreOp::DONT_CARE,
-            final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-            ..Default::default()
+        //DLSS reads the depth buffer after the base render, otherwise it can be thrown away
+        let depth_attachment = match app_data.dlss {
+            Some(_) => AttachmentDescriptor {
+                format: depth_buffer_format,
+                store_op: vk::AttachmentStoreOp::STORE,
+                final_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                ..Default::default()
+            },
+            None => AttachmentDescriptor {
+                format: depth_buffer_format,
+                store_op: vk::AttachmentStoreOp::DONT_CARE,
+                final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                ..Default::default()
+            }
         };
         let subpasses = &[
             SubpassDescriptor {
@@ -232,6 +259,23 @@ impl BootstrapPipelineLoader {
                     ref_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
                 }
             },
+            SubpassDescriptor {
+                color_attachments: vec![
+                    SubpassAttachmentDescriptor {
+                        attached: true,
+                        ref_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+                    },
+                    SubpassAttachmentDescriptor {
+                        attached: false,
+                        ..Default::default()
+                    }
+                ],
+                depth_attachment: SubpassAttachmentDescriptor {
+                    attached: true,
+                    ref_layout: vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL
+                }
+            },
+            //Translucent models are blended over the base render. They're depth tested against the opaque models, but don't write depth or motion vectors
             SubpassDescriptor {
                 color_attachments: vec![
                     SubpassAttachmentDescriptor {
@@ -257,6 +301,15 @@ impl BootstrapPipelineLoader {
                 .src_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
                 .dst_stage_mask(vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS)
                 .dst_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ)
+                .build(),
+            vk::SubpassDependency::builder()
+                .src_subpass(1)
+                .dst_subpass(2)
+                .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .dst_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .dependency_flags(vk::DependencyFlags::BY_REGION)
                 .build()
         ][..];
         let base_render_pass = self.create_render_pass(device, color_attachments, Some(&depth_attachment), subpasses, subpass_dependencies)?;
@@ -269,12 +322,15 @@ impl BootstrapPipelineLoader {
     fn create_postprocessing_render_pass(&self, device: &Device, pipeline_info: &mut PipelineInfo, app_data: &AppData) -> Result<()> {
         debug!("Creating postprocessing render pass...");
 
-        let swapchain_format = app_data.swapchain.as_ref().unwrap().surface_format.format;
+        let swapchain_info = app_data.swapchain.as_ref().unwrap();
+        let swapchain_format = swapchain_info.surface_format.format;
+        //Headless frames are copied out rather than presented
+        let final_layout = if swapchain_info.headless { vk::ImageLayout::TRANSFER_SRC_OPTIMAL } else { vk::ImageLayout::PRESENT_SRC_KHR };
 
         let color_attachments = &[
             AttachmentDescriptor {
                 format: swapchain_format,
-                final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+                final_layout,
                 ..Default::default()
             }
         ][..];
@@ -301,6 +357,174 @@ impl BootstrapPipelineLoader {
         Ok(())
     }
 
+    fn create_shadow_render_pass(&self, device: &Device, pipeline_info: &mut PipelineInfo, app_data: &AppData) -> Result<()> {
+        debug!("Creating shadow render pass...");
+
+        let render_images_info = &app_data.render_images.as_ref().unwrap();
+        let shadow_map_format = render_images_info.shadow_map_format();
+
+        let depth_attachment = AttachmentDescriptor {
+            format: shadow_map_format,
+            stencil_load_op: Some(vk::AttachmentLoadOp::DONT_CARE),
+            stencil_store_op: Some(vk::AttachmentStoreOp::DONT_CARE),
+            final_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            ..Default::default()
+        };
+        let subpasses = &[
+            SubpassDescriptor {
+                color_attachments: vec![],
+                depth_attachment: SubpassAttachmentDescriptor {
+                    attached: true,
+                    ref_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
+                }
+            }
+        ][..];
+        let subpass_dependencies = &[
+            vk::SubpassDependency::builder()
+                .src_subpass(vk::SUBPASS_EXTERNAL)
+                .dst_subpass(0)
+                .src_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+                .src_access_mask(vk::AccessFlags::SHADER_READ)
+                .dst_stage_mask(vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS)
+                .dst_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+                .build(),
+            vk::SubpassDependency::builder()
+                .src_subpass(0)
+                .dst_subpass(vk::SUBPASS_EXTERNAL)
+                .src_stage_mask(vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+                .src_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+                .dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+                .dst_access_mask(vk::AccessFlags::SHADER_READ)
+                .build()
+        ][..];
+        let shadow_render_pass = self.create_render_pass(device, &[], Some(&depth_attachment), subpasses, subpass_dependencies)?;
+
+        debug!("Shadow render pass created: {:?}", shadow_render_pass);
+        pipeline_info.shadow_render_pass = shadow_render_pass;
+
+        Ok(())
+    }
+
+    fn create_taa_render_pass(&self, device: &Device, pipeline_info: &mut PipelineInfo, app_data: &AppData) -> Result<()> {
+        if app_data.taa.is_none() {
+            return Ok(());
+        }
+
+        debug!("Creating TAA render pass...");
+
+        let render_images_info = &app_data.render_images.as_ref().unwrap();
+        let upscaled_render_format = render_images_info.upscaled_render_format();
+
+        //Every pixel is overwritten, so the previous contents don't matter
+        let color_attachments = &[
+            AttachmentDescriptor {
+                format: upscaled_render_format,
+                load_op: vk::AttachmentLoadOp::DONT_CARE,
+                final_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                ..Default::default()
+            }
+        ][..];
+        let subpasses = &[
+            SubpassDescriptor {
+                color_attachments: vec![
+                    SubpassAttachmentDescriptor {
+                        attached: true,
+                        ref_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+                    }
+                ],
+                depth_attachment: SubpassAttachmentDescriptor {
+                    attached: false,
+                    ..Default::default()
+                }
+            }
+        ][..];
+        //Reads the base render, and overwrites an image that the previous frame may still be reading as its history
+        let subpass_dependencies = &[
+            vk::SubpassDependency::builder()
+                .src_subpass(vk::SUBPASS_EXTERNAL)
+                .dst_subpass(0)
+                .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vk::PipelineStageFlags::FRAGMENT_SHADER)
+                .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .dst_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vk::PipelineStageFlags::FRAGMENT_SHADER)
+                .dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE | vk::AccessFlags::SHADER_READ)
+                .build(),
+            vk::SubpassDependency::builder()
+                .src_subpass(0)
+                .dst_subpass(vk::SUBPASS_EXTERNAL)
+                .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+                .dst_access_mask(vk::AccessFlags::SHADER_READ)
+                .build()
+        ][..];
+        let taa_render_pass = self.create_render_pass(device, color_attachments, None, subpasses, subpass_dependencies)?;
+
+        debug!("TAA render pass created: {:?}", taa_render_pass);
+        pipeline_info.taa_render_pass = taa_render_pass;
+
+        Ok(())
+    }
+
+    fn create_postprocess_effect_render_pass(&self, device: &Device, pipeline_info: &mut PipelineInfo, app_data: &AppData) -> Result<()> {
+        if app_data.postprocess_effects.is_empty() {
+            return Ok(());
+        }
+
+        debug!("Creating postprocess effect render pass...");
+
+        let render_images_info = &app_data.render_images.as_ref().unwrap();
+        let postprocess_format = render_images_info.postprocess_format();
+
+        //Every pixel is overwritten, so the previous contents don't matter
+        let color_attachments = &[
+            AttachmentDescriptor {
+                format: postprocess_format,
+                load_op: vk::AttachmentLoadOp::DONT_CARE,
+                final_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                ..Default::default()
+            }
+        ][..];
+        let subpasses = &[
+            SubpassDescriptor {
+                color_attachments: vec![
+                    SubpassAttachmentDescriptor {
+                        attached: true,
+                        ref_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+                    }
+                ],
+                depth_attachment: SubpassAttachmentDescriptor {
+                    attached: false,
+                    ..Default::default()
+                }
+            }
+        ][..];
+        //Each pass reads what earlier passes wrote, and may overwrite an image that an earlier pass read from
+        let subpass_dependencies = &[
+            vk::SubpassDependency::builder()
+                .src_subpass(vk::SUBPASS_EXTERNAL)
+                .dst_subpass(0)
+                .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vk::PipelineStageFlags::FRAGMENT_SHADER)
+                .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .dst_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vk::PipelineStageFlags::FRAGMENT_SHADER)
+                .dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE | vk::AccessFlags::SHADER_READ)
+                .build(),
+            vk::SubpassDependency::builder()
+                .src_subpass(0)
+                .dst_subpass(vk::SUBPASS_EXTERNAL)
+                .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+                .dst_access_mask(vk::AccessFlags::SHADER_READ)
+                .build()
+        ][..];
+        let postprocess_effect_render_pass = self.create_render_pass(device, color_attachments, None, subpasses, subpass_dependencies)?;
+
+        debug!("Postprocess effect render pass created: {:?}", postprocess_effect_render_pass);
+        pipeline_info.postprocess_effect_render_pass = postprocess_effect_render_pass;
+
+        Ok(())
+    }
+
     fn destroy_base_render_pass(&self, device: &Device, pipeline_info: &mut PipelineInfo) -> () {
         debug!("Destroying base render pass...");
 
@@ -318,8 +542,63 @@ impl BootstrapPipelineLoader {
         pipeline_info.postprocessing_render_pass = vk::RenderPass::null();
     }
 
-    fn create_depth_motion_pipeline_layout(&self, device: &Device, pipeline_info: &mut PipelineInfo, descriptor_set_layout: vk::DescriptorSetLayout) -> Result<()> {
-        let set_layouts = &[descriptor_set_layout][..];
+    fn destroy_shadow_render_pass(&self, device: &Device, pipeline_info: &mut PipelineInfo) -> () {
+        debug!("Destroying shadow render pass...");
+
+        unsafe {
+            device.destroy_render_pass(pipeline_info.shadow_render_pass, None);
+        }
+        pipeline_info.shadow_render_pass = vk::RenderPass::null();
+    }
+
+    fn destroy_taa_render_pass(&self, device: &Device, pipeline_info: &mut PipelineInfo) -> () {
+        debug!("Destroying TAA render pass...");
+
+        unsafe {
+            device.destroy_render_pass(pipeline_info.taa_render_pass, None);
+        }
+        pipeline_info.taa_render_pass = vk::RenderPass::null();
+    }
+
+    fn destroy_postprocess_effect_render_pass(&self, device: &Device, pipeline_info: &mut PipelineInfo) -> () {
+        debug!("Destroying postprocess effect render pass...");
+
+        unsafe {
+            device.destroy_render_pass(pipeline_info.postprocess_effect_render_pass, None);
+        }
+        pipeline_info.postprocess_effect_render_pass = vk::RenderPass::null();
+    }
+
+    fn create_shadow_pipeline_layout(&self, device: &Device, pipeline_info: &mut PipelineInfo, app_data: &AppData) -> Result<()> {
+        debug!("Creating shadow pipeline layout...");
+
+        //Only skinned shadow shaders use the joint palettes
+        let uniforms_info = app_data.uniforms.as_ref().unwrap();
+        let set_layouts = &[uniforms_info.skinning_descriptor_set_layout][..];
+
+        let vert_push_constant_range = vk::PushConstantRange::builder()
+            .stage_flags(vk::ShaderStageFlags::ALL_GRAPHICS)
+            .offset(0)
+            .size(size_of::<ShadowPushConstants>() as u32)
+            .build();
+        let push_constant_ranges = &[vert_push_constant_range][..];
+
+        let pipeline_layout = create_pipeline_layout(device, set_layouts, push_constant_ranges)?;
+        pipeline_info.shadow_layout = pipeline_layout;
+
+        Ok(())
+    }
+    fn destroy_shadow_pipeline_layout(&self, device: &Device, pipeline_info: &mut PipelineInfo) -> () {
+        debug!("Destroying shadow pipeline layout...");
+
+        unsafe {
+            device.destroy_pipeline_layout(pipeline_info.shadow_layout, None);
+        }
+        pipeline_info.shadow_layout = vk::PipelineLayout::null();
+    }
+
+    fn create_depth_motion_pipeline_layout(&self, device: &Device, pipeline_info: &mut PipelineInfo, descriptor_set_layout: vk::DescriptorSetLayout, material_descriptor_set_layout: vk::DescriptorSetLayout, skinning_descriptor_set_layout: vk::DescriptorSetLayout) -> Result<()> {
+        let set_layouts = &[descriptor_set_layout, material_descriptor_set_layout, skinning_descriptor_set_layout][..];
 
         let vert_push_constant_range = vk::PushConstantRange::builder()
             .stage_flags(vk::ShaderStageFlags::ALL_GRAPHICS)
@@ -333,8 +612,8 @@ impl BootstrapPipelineLoader {
 
         Ok(())
     }
-    fn create_base_render_pipeline_layout(&self, device: &Device, pipeline_info: &mut PipelineInfo, descriptor_set_layout: vk::DescriptorSetLayout) -> Result<()> {
-        let set_layouts = &[descriptor_set_layout][..];
+    fn create_base_render_pipeline_layout(&self, device: &Device, pipeline_info: &mut PipelineInfo, descriptor_set_layout: vk::DescriptorSetLayout, material_descriptor_set_layout: vk::DescriptorSetLayout, skinning_descriptor_set_layout: vk::DescriptorSetLayout) -> Result<()> {
+        let set_layouts = &[descriptor_set_layout, material_descriptor_set_layout, skinning_descriptor_set_layout][..];
 
         let vert_push_constant_range = vk::PushConstantRange::builder()
             .stage_flags(vk::ShaderStageFlags::ALL_GRAPHICS)
@@ -351,8 +630,8 @@ impl BootstrapPipelineLoader {
     fn create_base_render_pipeline_layouts(&self, device: &Device, pipeline_info: &mut PipelineInfo, app_data: &AppData) -> Result<()> {
         let uniforms_info = app_data.uniforms.as_ref().unwrap();
 
-        self.create_depth_motion_pipeline_layout(device, pipeline_info, uniforms_info.base_descriptor_set_layout)?;
-        self.create_base_render_pipeline_layout(device, pipeline_info, uniforms_info.base_descriptor_set_layout)?;
+        self.create_depth_motion_pipeline_layout(device, pipeline_info, uniforms_info.base_descriptor_set_layout, uniforms_info.material_descriptor_set_layout, uniforms_info.skinning_descriptor_set_layout)?;
+        self.create_base_render_pipeline_layout(device, pipeline_info, uniforms_info.base_descriptor_set_layout, uniforms_info.material_descriptor_set_layout, uniforms_info.skinning_descriptor_set_layout)?;
 
         Ok(())
     }
@@ -360,16 +639,13 @@ impl BootstrapPipelineLoader {
     fn create_postprocessing_pipeline_and_layout(&self, device: &Device, pipeline_info: &mut PipelineInfo, app_data: &AppData) -> Result<()> {
         debug!("Creating postprocessing pipeline layout and pipeline...");
 
-        let swapchain_info = app_data.swapchain.as_ref().unwrap();
-        let use_hdr = swapchain_info.use_hdr;
-
         let vert_source: ShaderSource;
         let frag_source: ShaderSource;
 
         let vert_bytes = include_bytes!("../../shaders/motion_blur/shader.vert.spv").to_vec();
         vert_source = ShaderSource::Source(vert_bytes.into_boxed_slice(), "main");
 
-        let frag_bytes = if use_hdr { include_bytes!("../../shaders/motion_blur/shader-hdr.frag.spv").to_vec() } else { include_bytes!("../../shaders/motion_blur/shader.frag.spv").to_vec() };
+        let frag_bytes = include_bytes!("../../shaders/postprocess_output/shader.frag.spv").to_vec();
         frag_source = ShaderSource::Source(frag_bytes.into_boxed_slice(), "main");
 
         let uniforms_info = app_data.uniforms.as_ref().unwrap();
@@ -396,6 +672,234 @@ impl BootstrapPipelineLoader {
         Ok(())
     }
 
+    fn create_overlay_pipeline_and_layout(&self, device: &Device, pipeline_info: &mut PipelineInfo, app_data: &AppData) -> Result<()> {
+        debug!("Creating overlay pipeline layout and pipeline...");
+
+        let vert_bytes = include_bytes!("../../shaders/motion_blur/shader.vert.spv").to_vec();
+        let vert_source = ShaderSource::Source(vert_bytes.into_boxed_slice(), "main");
+
+        let frag_bytes = include_bytes!("../../shaders/overlay/shader.frag.spv").to_vec();
+        let frag_source = ShaderSource::Source(frag_bytes.into_boxed_slice(), "main");
+
+        let uniforms_info = app_data.uniforms.as_ref().unwrap();
+        let set_layouts = &[uniforms_info.overlay_descriptor_set_layout][..];
+
+        let push_constant_ranges = &[][..] as &[vk::PushConstantRange];
+
+        let extent = app_data.swapchain.as_ref().unwrap().extent;
+
+        let pipeline_layout = create_pipeline_layout(device, set_layouts, push_constant_ranges)?;
+
+        //Blended over the final image, leaving its alpha alone
+        let blend_state = &[
+            BlendStateDescriptor {
+                enable_blend: true,
+                src_alpha_blend_factor: vk::BlendFactor::ZERO,
+                dst_alpha_blend_factor: vk::BlendFactor::ONE,
+                ..Default::default()
+            }
+        ][..];
+        let binding_descriptions = &[motion_blur::Vertex::binding_descriptions()].concat()[..];
+        let attribute_descriptions = &[motion_blur::Vertex::attribute_descriptions()].concat()[..];
+        let pipeline = create_pipeline(vert_source, frag_source, device, Some(extent), pipeline_layout, pipeline_info.postprocessing_render_pass, 0, blend_state, DepthBufferUsageMode::DontUse, binding_descriptions, attribute_descriptions)?;
+
+        debug!("Overlay pipeline layout ({:?}) and pipeline ({:?}) created.", pipeline_layout, pipeline);
+
+        pipeline_info.overlay_layout = pipeline_layout;
+        pipeline_info.overlay_pipeline = pipeline;
+
+        Ok(())
+    }
+
+    fn create_taa_pipeline_and_layout(&self, device: &Device, pipeline_info: &mut PipelineInfo, app_data: &AppData) -> Result<()> {
+        let taa_info = match app_data.taa.as_ref() {
+            Some(taa_info) => taa_info,
+            None => return Ok(())
+        };
+
+        debug!("Creating TAA pipeline layout and pipeline...");
+
+        let vert_bytes = include_bytes!("../../shaders/motion_blur/shader.vert.spv").to_vec();
+        let vert_source = ShaderSource::Source(vert_bytes.into_boxed_slice(), "main");
+
+        let frag_bytes = include_bytes!("../../shaders/taa/shader.frag.spv").to_vec();
+        let frag_source = ShaderSource::Source(frag_bytes.into_boxed_slice(), "main");
+
+        let uniforms_info = app_data.uniforms.as_ref().unwrap();
+        let set_layouts = &[uniforms_info.taa_descriptor_set_layout][..];
+
+        let frag_push_constant_range = vk::PushConstantRange::builder()
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+            .offset(0)
+            .size(size_of::<TaaPushConstants>() as u32)
+            .build();
+        let push_constant_ranges = &[frag_push_constant_range][..];
+
+        let pipeline_layout = create_pipeline_layout(device, set_layouts, push_constant_ranges)?;
+
+        let blend_state = &[
+            BlendStateDescriptor::default()
+        ][..];
+        let binding_descriptions = &[motion_blur::Vertex::binding_descriptions()].concat()[..];
+        let attribute_descriptions = &[motion_blur::Vertex::attribute_descriptions()].concat()[..];
+        let pipeline = create_pipeline(vert_source, frag_source, device, Some(taa_info.output_extent), pipeline_layout, pipeline_info.taa_render_pass, 0, blend_state, DepthBufferUsageMode::DontUse, binding_descriptions, attribute_descriptions)?;
+
+        debug!("TAA pipeline layout ({:?}) and pipeline ({:?}) created.", pipeline_layout, pipeline);
+
+        pipeline_info.taa_layout = pipeline_layout;
+        pipeline_info.taa_pipeline = pipeline;
+
+        Ok(())
+    }
+
+    fn create_postprocess_effect_pipelines_and_layout(&self, device: &Device, pipeline_info: &mut PipelineInfo, app_data: &AppData) -> Result<()> {
+        if app_data.postprocess_effects.is_empty() {
+            return Ok(());
+        }
+
+        debug!("Creating postprocess effect pipeline layout and pipelines...");
+
+        let uniforms_info = app_data.uniforms.as_ref().unwrap();
+        let set_layouts = &[uniforms_info.postprocess_effect_descriptor_set_layout][..];
+
+        let frag_push_constant_range = vk::PushConstantRange::builder()
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+            .offset(0)
+            .size(size_of::<PostprocessPushConstants>() as u32)
+            .build();
+        let push_constant_ranges = &[frag_push_constant_range][..];
+
+        let pipeline_layout = create_pipeline_layout(device, set_layouts, push_constant_ranges)?;
+
+        let blend_state = &[
+            BlendStateDescriptor::default()
+        ][..];
+        let binding_descriptions = &[motion_blur::Vertex::binding_descriptions()].concat()[..];
+        let attribute_descriptions = &[motion_blur::Vertex::attribute_descriptions()].concat()[..];
+
+        //Passes render to images of different sizes, so the viewport is set when each pass is recorded
+        let mut postprocess_effect_pipelines = vec![];
+        for effect in app_data.postprocess_effects.iter() {
+            let pipelines = effect.passes.iter()
+                .map(|pass| {
+                    let vert_bytes = include_bytes!("../../shaders/motion_blur/shader.vert.spv").to_vec();
+                    let vert_source = ShaderSource::Source(vert_bytes.into_boxed_slice(), "main");
+
+                    create_pipeline(vert_source, pass.fragment_shader.clone(), device, None, pipeline_layout, pipeline_info.postprocess_effect_render_pass, 0, blend_state, DepthBufferUsageMode::DontUse, binding_descriptions, attribute_descriptions)
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            debug!("Postprocess effect {} pipelines created: {:?}", effect.name, pipelines);
+            postprocess_effect_pipelines.push(pipelines);
+        }
+
+        debug!("Postprocess effect pipeline layout created: {:?}", pipeline_layout);
+
+        pipeline_info.postprocess_effect_layout = pipeline_layout;
+        pipeline_info.postprocess_effect_pipelines = postprocess_effect_pipelines;
+
+        Ok(())
+    }
+
+    fn create_auto_exposure_pipelines_and_layout(&self, device: &Device, pipeline_info: &mut PipelineInfo, app_data: &AppData) -> Result<()> {
+        if app_data.postprocess_effects.is_empty() {
+            return Ok(());
+        }
+
+        debug!("Creating auto exposure pipeline layout and pipelines...");
+
+        let uniforms_info = app_data.uniforms.as_ref().unwrap();
+        let set_layouts = &[uniforms_info.auto_exposure_descriptor_set_layout][..];
+
+        let comp_push_constant_range = vk::PushConstantRange::builder()
+            .stage_flags(vk::ShaderStageFlags::COMPUTE)
+            .offset(0)
+            .size(size_of::<AutoExposurePushConstants>() as u32)
+            .build();
+        let push_constant_ranges = &[comp_push_constant_range][..];
+
+        let pipeline_layout = create_pipeline_layout(device, set_layouts, push_constant_ranges)?;
+
+        let histogram_bytes = include_bytes!("../../shaders/auto_exposure/histogram.comp.spv").to_vec();
+        let histogram_source = ShaderSource::Source(histogram_bytes.into_boxed_slice(), "main");
+        let histogram_pipeline = create_compute_pipeline(histogram_source, device, pipeline_layout)?;
+
+        let average_bytes = include_bytes!("../../shaders/auto_exposure/average.comp.spv").to_vec();
+        let average_source = ShaderSource::Source(average_bytes.into_boxed_slice(), "main");
+        let adaptation_pipeline = create_compute_pipeline(average_source, device, pipeline_layout)?;
+
+        debug!("Auto exposure pipeline layout ({:?}) and pipelines ({:?}, {:?}) created.", pipeline_layout, histogram_pipeline, adaptation_pipeline);
+
+        pipeline_info.auto_exposure_layout = pipeline_layout;
+        pipeline_info.luminance_histogram_pipeline = histogram_pipeline;
+        pipeline_info.exposure_adaptation_pipeline = adaptation_pipeline;
+
+        Ok(())
+    }
+
+    fn destroy_auto_exposure_pipelines_and_layout(&self, device: &Device, pipeline_info: &mut PipelineInfo) -> () {
+        debug!("Destroying auto exposure pipelines and pipeline layout...");
+
+        unsafe {
+            device.destroy_pipeline(pipeline_info.exposure_adaptation_pipeline, None);
+        }
+        pipeline_info.exposure_adaptation_pipeline = vk::Pipeline::null();
+
+        unsafe {
+            device.destroy_pipeline(pipeline_info.luminance_histogram_pipeline, None);
+        }
+        pipeline_info.luminance_histogram_pipeline = vk::Pipeline::null();
+
+        unsafe {
+            device.destroy_pipeline_layout(pipeline_info.auto_exposure_layout, None);
+        }
+        pipeline_info.auto_exposure_layout = vk::PipelineLayout::null();
+    }
+
+    fn destroy_postprocess_effect_pipelines_and_layout(&self, device: &Device, pipeline_info: &mut PipelineInfo) -> () {
+        debug!("Destroying postprocess effect pipelines and pipeline layout...");
+
+        unsafe {
+            for pipeline in pipeline_info.postprocess_effect_pipelines.iter().flatten() {
+                device.destroy_pipeline(*pipeline, None);
+            }
+        }
+        pipeline_info.postprocess_effect_pipelines.clear();
+
+        unsafe {
+            device.destroy_pipeline_layout(pipeline_info.postprocess_effect_layout, None);
+        }
+        pipeline_info.postprocess_effect_layout = vk::PipelineLayout::null();
+    }
+
+    fn destroy_taa_pipeline_and_layout(&self, device: &Device, pipeline_info: &mut PipelineInfo) -> () {
+        debug!("Destroying TAA pipeline and pipeline layout...");
+
+        unsafe {
+            device.destroy_pipeline(pipeline_info.taa_pipeline, None);
+        }
+        pipeline_info.taa_pipeline = vk::Pipeline::null();
+
+        unsafe {
+            device.destroy_pipeline_layout(pipeline_info.taa_layout, None);
+        }
+        pipeline_info.taa_layout = vk::PipelineLayout::null();
+    }
+
+    fn destroy_overlay_pipeline_and_layout(&self, device: &Device, pipeline_info: &mut PipelineInfo) -> () {
+        debug!("Destroying overlay pipeline and pipeline layout...");
+
+        unsafe {
+            device.destroy_pipeline(pipeline_info.overlay_pipeline, None);
+        }
+        pipeline_info.overlay_pipeline = vk::Pipeline::null();
+
+        unsafe {
+            device.destroy_pipeline_layout(pipeline_info.overlay_layout, None);
+        }
+        pipeline_info.overlay_layout = vk::PipelineLayout::null();
+    }
+
     fn destroy_postprocessing_pipeline_and_layout(&self, device: &Device, pipeline_info: &mut PipelineInfo) -> () {
         debug!("Destroying postprocessing pipeline and pipeline layout...");
 
@@ -425,12 +929,20 @@ impl BootstrapPipelineLoader {
 }
 
 impl BootstrapLoader for BootstrapPipelineLoader {
-    fn after_create_logical_device(&self, _inst: &Instance, device: &Device, _window: &Window, app_data: &mut AppData) -> Result<()> {
+    fn after_create_logical_device(&self, _inst: &Instance, device: &Device, _window: Option<&Window>, app_data: &mut AppData) -> Result<()> {
         let mut pipeline_info = PipelineInfo::default();
         self.create_base_render_pass(device, &mut pipeline_info, app_data)?;
         self.create_base_render_pipeline_layouts(device, &mut pipeline_info, app_data)?;
+        self.create_shadow_render_pass(device, &mut pipeline_info, app_data)?;
+        self.create_shadow_pipeline_layout(device, &mut pipeline_info, app_data)?;
+        self.create_taa_render_pass(device, &mut pipeline_info, app_data)?;
+        self.create_taa_pipeline_and_layout(device, &mut pipeline_info, app_data)?;
+        self.create_postprocess_effect_render_pass(device, &mut pipeline_info, app_data)?;
+        self.create_postprocess_effect_pipelines_and_layout(device, &mut pipeline_info, app_data)?;
+        self.create_auto_exposure_pipelines_and_layout(device, &mut pipeline_info, app_data)?;
         self.create_postprocessing_render_pass(device, &mut pipeline_info, app_data)?;
         self.create_postprocessing_pipeline_and_layout(device, &mut pipeline_info, app_data)?;
+        self.create_overlay_pipeline_and_layout(device, &mut pipeline_info, app_data)?;
         app_data.pipeline = Some(pipeline_info);
 
         Ok(())
@@ -438,23 +950,37 @@ impl BootstrapLoader for BootstrapPipelineLoader {
 
     fn before_destroy_logical_device(&self, _inst: &Instance, device: &Device, app_data: &mut AppData) -> () {
         if let Some(mut pipeline_info) = app_data.pipeline.take() {
+            self.destroy_overlay_pipeline_and_layout(device, &mut pipeline_info);
             self.destroy_postprocessing_pipeline_and_layout(device, &mut pipeline_info);
             self.destroy_postprocessing_render_pass(device, &mut pipeline_info);
+            self.destroy_auto_exposure_pipelines_and_layout(device, &mut pipeline_info);
+            self.destroy_postprocess_effect_pipelines_and_layout(device, &mut pipeline_info);
+            self.destroy_postprocess_effect_render_pass(device, &mut pipeline_info);
+            self.destroy_taa_pipeline_and_layout(device, &mut pipeline_info);
+            self.destroy_taa_render_pass(device, &mut pipeline_info);
+            self.destroy_shadow_pipeline_layout(device, &mut pipeline_info);
+            self.destroy_shadow_render_pass(device, &mut pipeline_info);
             self.destroy_base_render_pipeline_layouts(device, &mut pipeline_info);
             self.destroy_base_render_pass(device, &mut pipeline_info);
         }
     }
 
-    fn recreate_swapchain(&self, inst: &Instance, device: &Device, window: &Window, app_data: &mut AppData, next: &dyn Fn(&Instance, &Device, &Window, &mut AppData) -> Result<()>) -> Result<()> {
-        trace!("Recreating postprocessing render pass (but not base render pass, pipelines, or pipeline layouts) in recreate_swapchain");
+    fn recreate_swapchain(&self, inst: &Instance, device: &Device, window: Option<&Window>, app_data: &mut AppData, next: &dyn Fn(&Instance, &Device, Option<&Window>, &mut AppData) -> Result<()>) -> Result<()> {
+        trace!("Recreating postprocessing and TAA render passes and pipelines, and the overlay pipeline, (but not base render pass, pipelines, or pipeline layouts) in recreate_swapchain");
 
         let mut pipeline_info = app_data.pipeline.take().unwrap();
 
+        self.destroy_overlay_pipeline_and_layout(device, &mut pipeline_info);
         self.destroy_postprocessing_pipeline_and_layout(device, &mut pipeline_info);
         self.destroy_postprocessing_render_pass(device, &mut pipeline_info);
+        self.destroy_taa_pipeline_and_layout(device, &mut pipeline_info);
+        self.destroy_taa_render_pass(device, &mut pipeline_info);
         next(inst, device, window, app_data)?;
+        self.create_taa_render_pass(device, &mut pipeline_info, app_data)?;
+        self.create_taa_pipeline_and_layout(device, &mut pipeline_info, app_data)?;
         self.create_postprocessing_render_pass(device, &mut pipeline_info, app_data)?;
         self.create_postprocessing_pipeline_and_layout(device, &mut pipeline_info, app_data)?;
+        self.create_overlay_pipeline_and_layout(device, &mut pipeline_info, app_data)?;
 
         app_data.pipeline = Some(pipeline_info);
 
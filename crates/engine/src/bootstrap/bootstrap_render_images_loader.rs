@@ -8,17 +8,32 @@ use vulkanalia::{
 
 use crate::{
     app_data::{AppData},
-    resources::{Image2D},
+    resources::{Image2D, AttachmentKind},
     bootstrap_loader
 };
 
+//Fits the default shadow settings of four 2048x2048 cascades
+const DEFAULT_SHADOW_MAP_EXTENT: vk::Extent2D = vk::Extent2D { width: 4096, height: 4096 };
+
 #[derive(Debug, Default)]
 pub struct RenderImagesInfo {
     pub base_render_extent: vk::Extent2D,
 
     pub base_render_images: Vec<Image2D>,
     pub depth_stencil_buffers: Vec<Image2D>,
-    pub motion_vector_buffers: Vec<Image2D>
+    pub motion_vector_buffers: Vec<Image2D>,
+
+    //Only created when DLSS or TAA is enabled, which upscale the base render into these
+    pub upscaled_render_images: Vec<Image2D>,
+    pub depth_sample_views: Vec<vk::ImageView>,
+
+    pub shadow_map_extent: vk::Extent2D,
+    pub shadow_maps: Vec<Image2D>,
+
+    //Only created when there are postprocess effects. For each swapchain image, two images that the effects take turns writing their results to
+    pub postprocess_chain_images: Vec<Vec<Image2D>>,
+    //For each swapchain image, each effect's own intermediate images
+    pub postprocess_intermediate_images: Vec<Vec<Vec<Image2D>>>
 }
 
 impl RenderImagesInfo {
@@ -33,6 +48,27 @@ impl RenderImagesInfo {
     pub fn motion_vector_format(&self) -> vk::Format {
         self.motion_vector_buffers[0].format().unwrap()
     }
+
+    pub fn upscaled_render_format(&self) -> vk::Format {
+        self.upscaled_render_images[0].format().unwrap()
+    }
+
+    pub fn shadow_map_format(&self) -> vk::Format {
+        self.shadow_maps[0].format().unwrap()
+    }
+
+    pub fn postprocess_format(&self) -> vk::Format {
+        self.postprocess_chain_images[0][0].format().unwrap()
+    }
+
+    //The images postprocessing reads from
+    pub fn postprocessing_source_images(&self) -> &Vec<Image2D> {
+        if self.upscaled_render_images.is_empty() {
+            &self.base_render_images
+        } else {
+            &self.upscaled_render_images
+        }
+    }
 }
 
 bootstrap_loader! {
@@ -46,9 +82,13 @@ impl BootstrapRenderImagesLoader {
         let swapchain_info = app_data.swapchain.as_ref().unwrap();
         let swapchain_extent = swapchain_info.extent;
 
-        let base_render_extent = vk::Extent2D {
-            width: swapchain_extent.width / 2,
-            height: swapchain_extent.height / 2
+        let base_render_extent = match (app_data.dlss.as_ref(), app_data.taa.as_ref()) {
+            (Some(dlss_info), _) => dlss_info.render_extent,
+            (None, Some(taa_info)) => taa_info.render_extent,
+            (None, None) => vk::Extent2D {
+                width: swapchain_extent.width / 2,
+                height: swapchain_extent.height / 2
+            }
         };
 
         render_images_info.base_render_extent = base_render_extent;
@@ -64,8 +104,15 @@ impl BootstrapRenderImagesLoader {
 
         let command_pool_info = &app_data.command_pools.as_ref().unwrap();
 
-        let depth_stencil_buffers = Image2D::new_and_create_depth_stencil_buffers(image_count, inst, device, app_data.physical_device.as_ref().unwrap(), &app_data.memory_properties, &render_images_info.base_render_extent, false, command_pool_info)?;
+        //DLSS reads the depth buffer
+        let sampled = app_data.dlss.is_some();
+        let depth_stencil_buffers = Image2D::new_and_create_depth_stencil_buffers(image_count, inst, device, app_data.physical_device.as_ref().unwrap(), &app_data.memory_properties, &render_images_info.base_render_extent, sampled, command_pool_info)?;
 
+        if sampled {
+            render_images_info.depth_sample_views = depth_stencil_buffers.iter()
+                .map(|depth_stencil_buffer| depth_stencil_buffer.create_depth_sample_view(device))
+                .collect::<Result<Vec<_>, _>>()?;
+        }
         render_images_info.depth_stencil_buffers = depth_stencil_buffers;
 
         Ok(())
@@ -74,6 +121,13 @@ impl BootstrapRenderImagesLoader {
     fn destroy_depth_objects(&self, device: &Device, render_images_info: &mut RenderImagesInfo) -> () {
         debug!("Destroying depth and stencil buffer images...");
 
+        for depth_sample_view in render_images_info.depth_sample_views.iter() {
+            unsafe {
+                device.destroy_image_view(*depth_sample_view, None);
+            }
+        }
+        render_images_info.depth_sample_views.clear();
+
         for depth_stencil_buffer in render_images_info.depth_stencil_buffers.iter_mut() {
             depth_stencil_buffer.destroy(device);
         }
@@ -105,6 +159,95 @@ impl BootstrapRenderImagesLoader {
         render_images_info.base_render_images.clear();
     }
 
+    fn create_upscaled_render_images(&self, inst: &Instance, device: &Device, render_images_info: &mut RenderImagesInfo, app_data: &AppData) -> Result<()> {
+        //DLSS writes its output from compute work, while TAA resolves in a render pass
+        let (output_extent, attachment_kind) = match (app_data.dlss.as_ref(), app_data.taa.as_ref()) {
+            (Some(dlss_info), _) => (dlss_info.output_extent, AttachmentKind::Storage),
+            (None, Some(taa_info)) => (taa_info.output_extent, AttachmentKind::Color),
+            (None, None) => return Ok(())
+        };
+
+        debug!("Creating upscaled render images...");
+
+        let swapchain_info = app_data.swapchain.as_ref().unwrap();
+        let image_count = swapchain_info.image_count;
+
+        let command_pool_info = &app_data.command_pools.as_ref().unwrap();
+
+        let upscaled_render_images = Image2D::new_and_create_upscaled_render_images(image_count, inst, device, app_data.physical_device.as_ref().unwrap(), &app_data.memory_properties, &output_extent, attachment_kind, command_pool_info)?;
+
+        debug!("Upscaled render images created: {:?}", upscaled_render_images);
+        render_images_info.upscaled_render_images = upscaled_render_images;
+
+        Ok(())
+    }
+
+    fn destroy_upscaled_render_images(&self, device: &Device, render_images_info: &mut RenderImagesInfo) -> () {
+        debug!("Destroying upscaled render images...");
+
+        for upscaled_render_image in render_images_info.upscaled_render_images.iter_mut() {
+            upscaled_render_image.destroy(device);
+        }
+        render_images_info.upscaled_render_images.clear();
+    }
+
+    fn create_postprocess_images(&self, inst: &Instance, device: &Device, render_images_info: &mut RenderImagesInfo, app_data: &AppData) -> Result<()> {
+        if app_data.postprocess_effects.is_empty() {
+            return Ok(());
+        }
+
+        debug!("Creating postprocess images...");
+
+        let swapchain_info = app_data.swapchain.as_ref().unwrap();
+        let image_count = swapchain_info.image_count;
+        //Effects run at the output resolution, whatever the base render resolution is
+        let output_extent = swapchain_info.extent;
+
+        let command_pool_info = &app_data.command_pools.as_ref().unwrap();
+        let physical_device = app_data.physical_device.as_ref().unwrap();
+
+        let mut postprocess_chain_images = vec![];
+        let mut postprocess_intermediate_images = vec![];
+        for _ in 0..image_count {
+            postprocess_chain_images.push(Image2D::new_and_create_render_images(2, inst, device, physical_device, &app_data.memory_properties, &output_extent, true, command_pool_info)?);
+
+            let mut effect_intermediate_images = vec![];
+            for effect in app_data.postprocess_effects.iter() {
+                let intermediate_images = effect.intermediate_scales.iter()
+                    .map(|scale| {
+                        let extent = vk::Extent2D {
+                            width: u32::max((output_extent.width as f32 * scale) as u32, 1),
+                            height: u32::max((output_extent.height as f32 * scale) as u32, 1)
+                        };
+                        Ok(Image2D::new_and_create_render_images(1, inst, device, physical_device, &app_data.memory_properties, &extent, true, command_pool_info)?.remove(0))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                effect_intermediate_images.push(intermediate_images);
+            }
+            postprocess_intermediate_images.push(effect_intermediate_images);
+        }
+
+        debug!("Postprocess images created: {:?}; {:?}", postprocess_chain_images, postprocess_intermediate_images);
+        render_images_info.postprocess_chain_images = postprocess_chain_images;
+        render_images_info.postprocess_intermediate_images = postprocess_intermediate_images;
+
+        Ok(())
+    }
+
+    fn destroy_postprocess_images(&self, device: &Device, render_images_info: &mut RenderImagesInfo) -> () {
+        debug!("Destroying postprocess images...");
+
+        for chain_image in render_images_info.postprocess_chain_images.iter_mut().flatten() {
+            chain_image.destroy(device);
+        }
+        render_images_info.postprocess_chain_images.clear();
+
+        for intermediate_image in render_images_info.postprocess_intermediate_images.iter_mut().flatten().flatten() {
+            intermediate_image.destroy(device);
+        }
+        render_images_info.postprocess_intermediate_images.clear();
+    }
+
     fn create_motion_vector_buffers(&self, inst: &Instance, device: &Device, render_images_info: &mut RenderImagesInfo, app_data: &AppData) -> Result<()> {
         debug!("Creating motion vector buffers...");
 
@@ -129,15 +272,45 @@ impl BootstrapRenderImagesLoader {
         }
         render_images_info.motion_vector_buffers.clear();
     }
+
+    fn create_shadow_maps(&self, inst: &Instance, device: &Device, render_images_info: &mut RenderImagesInfo, app_data: &AppData) -> Result<()> {
+        debug!("Creating shadow maps...");
+
+        let swapchain_info = app_data.swapchain.as_ref().unwrap();
+        let image_count = swapchain_info.image_count;
+
+        let command_pool_info = &app_data.command_pools.as_ref().unwrap();
+
+        let shadow_map_extent = app_data.requested_shadow_map_extent.unwrap_or(DEFAULT_SHADOW_MAP_EXTENT);
+        let shadow_maps = Image2D::new_and_create_shadow_maps(image_count, inst, device, app_data.physical_device.as_ref().unwrap(), &app_data.memory_properties, &shadow_map_extent, command_pool_info)?;
+
+        debug!("Shadow maps created: {:?}", shadow_maps);
+        render_images_info.shadow_map_extent = shadow_map_extent;
+        render_images_info.shadow_maps = shadow_maps;
+
+        Ok(())
+    }
+
+    fn destroy_shadow_maps(&self, device: &Device, render_images_info: &mut RenderImagesInfo) -> () {
+        debug!("Destroying shadow maps...");
+
+        for shadow_map in render_images_info.shadow_maps.iter_mut() {
+            shadow_map.destroy(device);
+        }
+        render_images_info.shadow_maps.clear();
+    }
 }
 
 impl BootstrapLoader for BootstrapRenderImagesLoader {
-    fn after_create_logical_device(&self, inst: &Instance, device: &Device, _window: &Window, app_data: &mut AppData) -> Result<()> {
+    fn after_create_logical_device(&self, inst: &Instance, device: &Device, _window: Option<&Window>, app_data: &mut AppData) -> Result<()> {
         let mut render_images_info = RenderImagesInfo::default();
         self.select_base_render_extent(&mut render_images_info, app_data)?;
         self.create_depth_objects(inst, device, &mut render_images_info, app_data)?;
         self.create_render_images(inst, device, &mut render_images_info, app_data)?;
+        self.create_upscaled_render_images(inst, device, &mut render_images_info, app_data)?;
         self.create_motion_vector_buffers(inst, device, &mut render_images_info, app_data)?;
+        self.create_shadow_maps(inst, device, &mut render_images_info, app_data)?;
+        self.create_postprocess_images(inst, device, &mut render_images_info, app_data)?;
         app_data.render_images = Some(render_images_info);
 
         Ok(())
@@ -145,7 +318,10 @@ impl BootstrapLoader for BootstrapRenderImagesLoader {
 
     fn before_destroy_logical_device(&self, _inst: &Instance, device: &Device, app_data: &mut AppData) -> () {
         if let Some(mut render_images_info) = app_data.render_images.take() {
+            self.destroy_postprocess_images(device, &mut render_images_info);
+            self.destroy_shadow_maps(device, &mut render_images_info);
             self.destroy_motion_vector_buffers(device, &mut render_images_info);
+            self.destroy_upscaled_render_images(device, &mut render_images_info);
             self.destroy_render_images(device, &mut render_images_info);
             self.destroy_depth_objects(device, &mut render_images_info);
         }
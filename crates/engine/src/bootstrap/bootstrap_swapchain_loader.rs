@@ -11,10 +11,68 @@ use vulkanalia::{
 use crate::{
     app::{GraphicsCardSuitabilityError},
     app_data::{AppData},
-    resources::{Image2D},
+    resources::{Image2D, Buffer},
     bootstrap_loader
 };
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HdrSettings {
+    //When false, or when the surface doesn't support HDR10, the swapchain falls back to sRGB
+    pub enabled: bool,
+    //How bright a tone mapped value of 1.0 is on an HDR display. 203 is the BT.2408 reference white
+    pub paper_white_nits: f32,
+    //The brightest the display should be asked for. Tone mapping compresses highlights into the headroom above paper white
+    pub max_nits: f32
+}
+
+impl Default for HdrSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            paper_white_nits: 203.0,
+            max_nits: 1000.0
+        }
+    }
+}
+
+impl HdrSettings {
+    //How many times brighter than paper white the display goes, or 1.0 when presenting SDR
+    pub fn headroom(&self, use_hdr: bool) -> f32 {
+        if use_hdr {
+            f32::max(self.max_nits / self.paper_white_nits, 1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PresentMode {
+    //Vsync. Always available
+    Fifo,
+    //Vsync without blocking on present, so frames render uncapped but never tear
+    Mailbox,
+    //No vsync. Tears, but has the lowest latency. Use this to profile uncapped performance
+    Immediate
+}
+
+impl Default for PresentMode {
+    fn default() -> Self {
+        PresentMode::Mailbox
+    }
+}
+
+impl PresentMode {
+    //Modes to try in order when the requested one isn't supported. Every surface supports FIFO, so every list ends with it
+    pub fn fallbacks(&self) -> &'static [vk::PresentModeKHR] {
+        match self {
+            PresentMode::Fifo => &[vk::PresentModeKHR::FIFO],
+            PresentMode::Mailbox => &[vk::PresentModeKHR::MAILBOX, vk::PresentModeKHR::FIFO],
+            PresentMode::Immediate => &[vk::PresentModeKHR::IMMEDIATE, vk::PresentModeKHR::MAILBOX, vk::PresentModeKHR::FIFO]
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct SwapchainInfo {
     pub surface_format: vk::SurfaceFormatKHR,
@@ -23,7 +81,16 @@ pub struct SwapchainInfo {
     pub image_count: u32,
     pub swapchain: vk::SwapchainKHR,
     pub images: Vec<Image2D>,
-    pub use_hdr: bool
+    //Whether the swapchain was created with the HDR10 (PQ encoded) surface format
+    pub use_hdr: bool,
+    //Whether the surface offers the HDR10 format at all, regardless of whether it's in use
+    pub hdr_supported: bool,
+    //When there's no surface, the images are offscreen targets and swapchain is null
+    pub headless: bool,
+    //Only created when headless. One per image, for copying finished frames back to the CPU
+    pub readback_buffers: Vec<Buffer<u8>>,
+    //Whether the images can be copied from, which screenshots of the presented image need
+    pub capture_supported: bool
 }
 
 #[derive(Debug)]
@@ -53,8 +120,18 @@ bootstrap_loader! {
     }
 }
 
+const HDR_SURFACE_FORMAT: (vk::Format, vk::ColorSpaceKHR) = (vk::Format::A2B10G10R10_UNORM_PACK32, vk::ColorSpaceKHR::HDR10_ST2084_EXT);
+
 const SURFACE_FORMAT_PREFERENCES: &[(vk::Format, vk::ColorSpaceKHR)] = &[
-    (vk::Format::A2B10G10R10_UNORM_PACK32, vk::ColorSpaceKHR::HDR10_ST2084_EXT),
+    HDR_SURFACE_FORMAT,
+    (vk::Format::B8G8R8A8_SRGB, vk::ColorSpaceKHR::SRGB_NONLINEAR)
+];
+
+//Tightly packed RGBA, so read back frames can be written straight to a PNG
+const HEADLESS_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
+const HEADLESS_IMAGE_COUNT: u32 = 3;
+
+const SDR_SURFACE_FORMAT_PREFERENCES: &[(vk::Format, vk::ColorSpaceKHR)] = &[
     (vk::Format::B8G8R8A8_SRGB, vk::ColorSpaceKHR::SRGB_NONLINEAR)
 ];
 
@@ -79,21 +156,18 @@ impl BootstrapSwapchainLoader {
         }
     }
 
-    fn choose_presentation_mode(&self, swapchain_support: &SwapchainSupport) -> Option<vk::PresentModeKHR> {
+    fn choose_presentation_mode(&self, swapchain_support: &SwapchainSupport, requested: PresentMode) -> Option<vk::PresentModeKHR> {
         let available_presentation_modes = &swapchain_support.present_modes[..];
 
-        let mut has_mailbox = false;
-        for mode in available_presentation_modes {
-            if *mode == vk::PresentModeKHR::MAILBOX {
-                has_mailbox = true;
-            }
-        }
+        let mode = requested.fallbacks().iter()
+            .find(|mode| available_presentation_modes.contains(mode))
+            .copied();
 
-        if has_mailbox {
-            Some(vk::PresentModeKHR::MAILBOX)
-        } else {
-            Some(vk::PresentModeKHR::FIFO)
+        if mode.is_some() && mode != requested.fallbacks().first().copied() {
+            debug!("Present mode {:?} isn't supported. Falling back to {:?}", requested, mode.unwrap());
         }
+
+        mode
     }
 
     #[allow(unused)]
@@ -112,6 +186,39 @@ impl BootstrapSwapchainLoader {
         }
     }
 
+    fn create_headless_targets(&self, device: &Device, app_data: &mut AppData) -> Result<()> {
+        let extent = app_data.headless_extent.ok_or_else(|| anyhow!("There's no surface to present to, and no headless resolution was set"))?;
+
+        debug!("Creating headless render targets...");
+        let images = Image2D::new_and_create_offscreen_targets(HEADLESS_IMAGE_COUNT, device, &app_data.memory_properties, HEADLESS_FORMAT, &extent)?;
+
+        let pixel_count = (extent.width * extent.height) as usize;
+        let mut readback_buffers = (0..HEADLESS_IMAGE_COUNT)
+            .map(|_| {
+                Buffer::<u8>::new(vk::BufferUsageFlags::TRANSFER_DST, pixel_count * 4, false)
+            })
+            .collect::<Vec<_>>();
+
+        for buffer in readback_buffers.iter_mut() {
+            buffer.create(device, &app_data.memory_properties)?;
+        }
+
+        let mut swapchain_info = SwapchainInfo::default();
+        swapchain_info.surface_format = vk::SurfaceFormatKHR { format: HEADLESS_FORMAT, color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR };
+        swapchain_info.present_mode = vk::PresentModeKHR::IMMEDIATE;
+        swapchain_info.extent = extent;
+        swapchain_info.image_count = HEADLESS_IMAGE_COUNT;
+        swapchain_info.swapchain = vk::SwapchainKHR::null();
+        swapchain_info.images = images;
+        swapchain_info.headless = true;
+        swapchain_info.readback_buffers = readback_buffers;
+        swapchain_info.capture_supported = true;
+        debug!("Headless render targets created: {:?}", swapchain_info.images);
+        app_data.swapchain = Some(swapchain_info);
+
+        Ok(())
+    }
+
     fn create_swapchain(&self, inst: &Instance, window: &Window, device: &Device, app_data: &mut AppData) -> Result<()> {
         let physical_device = app_data.physical_device.unwrap();
         let swapchain_support: SwapchainSupport;
@@ -119,8 +226,11 @@ impl BootstrapSwapchainLoader {
             swapchain_support = SwapchainSupport::get(inst, app_data, physical_device)?;
         }
 
-        let format = self.choose_surface_format(&swapchain_support, &SURFACE_FORMAT_PREFERENCES[..]).unwrap();
-        let mode = self.choose_presentation_mode(&swapchain_support).unwrap();
+        let preferences = if app_data.hdr_settings.enabled { SURFACE_FORMAT_PREFERENCES } else { SDR_SURFACE_FORMAT_PREFERENCES };
+        let format = self.choose_surface_format(&swapchain_support, preferences).unwrap();
+        let hdr_supported = swapchain_support.formats.iter()
+            .any(|format| format.format == HDR_SURFACE_FORMAT.0 && format.color_space == HDR_SURFACE_FORMAT.1);
+        let mode = self.choose_presentation_mode(&swapchain_support, app_data.present_mode).unwrap();
         let extent = self.choose_swapchain_extent(&swapchain_support, window);
 
         let mut image_count = swapchain_support.capabilities.min_image_count + 1;
@@ -142,6 +252,14 @@ impl BootstrapSwapchainLoader {
 
         let surface = app_data.surface.unwrap();
 
+        //Nearly every surface allows this, but it's only needed for screenshots so it isn't a hard requirement
+        let capture_supported = swapchain_support.capabilities.supported_usage_flags.contains(vk::ImageUsageFlags::TRANSFER_SRC);
+        let image_usage = if capture_supported {
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC
+        } else {
+            vk::ImageUsageFlags::COLOR_ATTACHMENT
+        };
+
         let swapchain_info = vk::SwapchainCreateInfoKHR::builder()
             .surface(surface)
             .min_image_count(image_count)
@@ -149,7 +267,7 @@ impl BootstrapSwapchainLoader {
             .image_color_space(format.color_space)
             .image_extent(extent)
             .image_array_layers(1)
-            .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
+            .image_usage(image_usage)
             .image_sharing_mode(image_sharing_mode)
             .queue_family_indices(&queue_family_indices)
             .pre_transform(swapchain_support.capabilities.current_transform)
@@ -179,6 +297,9 @@ impl BootstrapSwapchainLoader {
         swapchain_info.swapchain = swapchain;
         swapchain_info.images = images;
         swapchain_info.use_hdr = format.color_space == vk::ColorSpaceKHR::HDR10_ST2084_EXT;
+        swapchain_info.hdr_supported = hdr_supported;
+        swapchain_info.capture_supported = capture_supported;
+        debug!("Swapchain surface format: {:?}; HDR supported: {}, in use: {}", format, hdr_supported, swapchain_info.use_hdr);
         app_data.swapchain = Some(swapchain_info);
 
         Ok(())
@@ -192,8 +313,14 @@ impl BootstrapSwapchainLoader {
                 image.destroy(device);
             }
 
-            unsafe {
-                device.destroy_swapchain_khr(swapchain.swapchain, None);
+            for buffer in swapchain.readback_buffers.iter_mut() {
+                buffer.destroy(device);
+            }
+
+            if !swapchain.headless {
+                unsafe {
+                    device.destroy_swapchain_khr(swapchain.swapchain, None);
+                }
             }
         }
     }
@@ -216,6 +343,10 @@ impl BootstrapLoader for BootstrapSwapchainLoader {
     fn check_physical_device_compatibility(&self, inst: &Instance, app_data: &AppData, physical_device: vk::PhysicalDevice, properties: PhysicalDeviceProperties, _features: PhysicalDeviceFeatures) -> Result<()> {
         //Note: this method assumes the KHR_SWAPCHAIN_EXTENSION has already been checked and is present.
 
+        if app_data.surface.is_none() {
+            return Ok(());
+        }
+
         let swapchain_support: SwapchainSupport;
         unsafe {
             swapchain_support = SwapchainSupport::get(inst, app_data, physical_device)?;
@@ -226,7 +357,7 @@ impl BootstrapLoader for BootstrapSwapchainLoader {
             return Err(anyhow!(GraphicsCardSuitabilityError("Physical device does not support sufficient swapchain formats.")))
         }
 
-        let present_mode = self.choose_presentation_mode(&swapchain_support);
+        let present_mode = self.choose_presentation_mode(&swapchain_support, app_data.present_mode);
         if let None = present_mode {
             return Err(anyhow!(GraphicsCardSuitabilityError("Physical device does not support sufficient swapchain present modes.")))
         }
@@ -236,8 +367,11 @@ impl BootstrapLoader for BootstrapSwapchainLoader {
         Ok(())
     }
 
-    fn after_create_logical_device(&self, inst: &Instance, device: &Device, window: &Window, app_data: &mut AppData) -> Result<()> {
-        self.create_swapchain(inst, window, device, app_data)?;
+    fn after_create_logical_device(&self, inst: &Instance, device: &Device, window: Option<&Window>, app_data: &mut AppData) -> Result<()> {
+        match window {
+            Some(window) if app_data.surface.is_some() => self.create_swapchain(inst, window, device, app_data)?,
+            _ => self.create_headless_targets(device, app_data)?
+        }
 
         Ok(())
     }
@@ -123,7 +123,7 @@ impl BootstrapSyncObjectsLoader {
 }
 
 impl BootstrapLoader for BootstrapSyncObjectsLoader {
-    fn after_create_logical_device(&self, _inst: &Instance, device: &Device, _window: &Window, app_data: &mut AppData) -> Result<()> {
+    fn after_create_logical_device(&self, _inst: &Instance, device: &Device, _window: Option<&Window>, app_data: &mut AppData) -> Result<()> {
         let mut sync_objects_info = SyncObjectsInfo::default();
         self.create_sync_objects(device, &mut sync_objects_info, app_data)?;
         app_data.sync_objects = Some(sync_objects_info);
@@ -137,7 +137,7 @@ impl BootstrapLoader for BootstrapSyncObjectsLoader {
         }
     }
 
-    fn recreate_swapchain(&self, inst: &Instance, device: &Device, window: &Window, app_data: &mut AppData, next: &dyn Fn(&Instance, &Device, &Window, &mut AppData) -> Result<()>) -> Result<()> {
+    fn recreate_swapchain(&self, inst: &Instance, device: &Device, window: Option<&Window>, app_data: &mut AppData, next: &dyn Fn(&Instance, &Device, Option<&Window>, &mut AppData) -> Result<()>) -> Result<()> {
         trace!("Recreating nothing in recreate_swapchain");
 
         next(inst, device, window, app_data)?;
@@ -0,0 +1,111 @@
+use super::{BootstrapLoader, BootstrapSwapchainLoader, BootstrapCommandBufferLoader};
+
+use anyhow::{Result};
+use winit::window::{Window};
+use vulkanalia::{
+    prelude::v1_0::*
+};
+
+use crate::{
+    app_data::{AppData},
+    bootstrap_loader
+};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TaaSettings {
+    //Fraction of the output resolution the base render uses on each axis. 1.0 only anti-aliases, anything lower also upscales
+    pub render_scale: f32,
+    //How much of the reprojected history is kept each frame. Higher is smoother, but slower to react to changes
+    pub history_weight: f32
+}
+
+impl Default for TaaSettings {
+    fn default() -> Self {
+        Self {
+            render_scale: 2.0 / 3.0,
+            history_weight: 0.9
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TaaInfo {
+    pub settings: TaaSettings,
+    pub render_extent: vk::Extent2D,
+    pub output_extent: vk::Extent2D,
+
+    //The upscaled render image resolved by the last submitted frame, which is this frame's history
+    pub history_image_index: Option<usize>
+}
+
+bootstrap_loader! {
+    pub struct BootstrapTaaLoader {
+        depends_on(BootstrapSwapchainLoader, BootstrapCommandBufferLoader);
+        settings: TaaSettings
+    }
+}
+
+impl BootstrapTaaLoader {
+    pub fn new_with_settings(settings: TaaSettings) -> Self {
+        Self {
+            settings
+        }
+    }
+
+    fn select_extents(&self, taa_info: &mut TaaInfo, app_data: &AppData) -> () {
+        let output_extent = app_data.swapchain.as_ref().unwrap().extent;
+        let render_scale = taa_info.settings.render_scale.clamp(0.25, 1.0);
+        let render_extent = vk::Extent2D {
+            width: u32::max((output_extent.width as f32 * render_scale) as u32, 1),
+            height: u32::max((output_extent.height as f32 * render_scale) as u32, 1)
+        };
+
+        debug!("TAA resolving {:?} to {:?}...", render_extent, output_extent);
+
+        taa_info.render_extent = render_extent;
+        taa_info.output_extent = output_extent;
+        //Whatever was in the history doesn't match the new images
+        taa_info.history_image_index = None;
+    }
+}
+
+impl BootstrapLoader for BootstrapTaaLoader {
+    fn after_create_logical_device(&self, _inst: &Instance, _device: &Device, _window: Option<&Window>, app_data: &mut AppData) -> Result<()> {
+        //TAA is the fallback for when DLSS isn't available. DLSS already resolves the jitter when it is
+        if app_data.dlss.is_some() {
+            debug!("DLSS is enabled, so TAA will be disabled");
+            return Ok(());
+        }
+
+        let mut taa_info = TaaInfo {
+            settings: self.settings,
+            render_extent: Default::default(),
+            output_extent: Default::default(),
+            history_image_index: None
+        };
+        self.select_extents(&mut taa_info, app_data);
+        app_data.taa = Some(taa_info);
+
+        Ok(())
+    }
+
+    fn before_destroy_logical_device(&self, _inst: &Instance, _device: &Device, app_data: &mut AppData) -> () {
+        app_data.taa = None;
+    }
+
+    fn recreate_swapchain(&self, inst: &Instance, device: &Device, window: Option<&Window>, app_data: &mut AppData, next: &dyn Fn(&Instance, &Device, Option<&Window>, &mut AppData) -> Result<()>) -> Result<()> {
+        trace!("Reselecting TAA extents in recreate_swapchain");
+
+        let mut taa_info = match app_data.taa.take() {
+            Some(taa_info) => taa_info,
+            None => return next(inst, device, window, app_data)
+        };
+
+        next(inst, device, window, app_data)?;
+        self.select_extents(&mut taa_info, app_data);
+
+        app_data.taa = Some(taa_info);
+
+        Ok(())
+    }
+}
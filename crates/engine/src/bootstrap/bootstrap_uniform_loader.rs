@@ -2,14 +2,15 @@ use super::{BootstrapLoader, BootstrapSwapchainLoader};
 
 use anyhow::{Result};
 use winit::window::{Window};
+use nalgebra_glm as glm;
 use vulkanalia::{
     prelude::v1_0::*
 };
 
 use crate::{
     app_data::{AppData},
-    shader_input::uniform_buffer_object::{UniformBufferObject, PostprocessingUniformBufferObject},
-    resources::{Buffer},
+    shader_input::uniform_buffer_object::{UniformBufferObject, PostprocessingUniformBufferObject, OverlayUniformBufferObject, ExposureState, LUMINANCE_HISTOGRAM_BIN_COUNT, MAX_JOINTS, MAX_FRAME_JOINT_MATRICES},
+    resources::{Buffer, MATERIAL_TEXTURE_COUNT},
     bootstrap_loader
 };
 
@@ -17,12 +18,34 @@ use crate::{
 pub struct UniformsInfo {
     pub base_descriptor_set_layout: vk::DescriptorSetLayout,
     pub postprocessing_descriptor_set_layout: vk::DescriptorSetLayout,
+    pub material_descriptor_set_layout: vk::DescriptorSetLayout,
+    //Only created when TAA is enabled
+    pub taa_descriptor_set_layout: vk::DescriptorSetLayout,
+    pub postprocess_effect_descriptor_set_layout: vk::DescriptorSetLayout,
+    //Only created when there are postprocess effects, like the rest of the auto exposure resources
+    pub auto_exposure_descriptor_set_layout: vk::DescriptorSetLayout,
+    pub overlay_descriptor_set_layout: vk::DescriptorSetLayout,
+    //The current and previous joint palettes of skinned models, at dynamic offsets into joint_palette_buffers
+    pub skinning_descriptor_set_layout: vk::DescriptorSetLayout,
 
     pub uniform_buffers: Vec<Buffer::<UniformBufferObject>>,
     pub postprocessing_uniform_buffers: Vec<Buffer::<PostprocessingUniformBufferObject>>,
+    pub overlay_uniform_buffers: Vec<Buffer::<OverlayUniformBufferObject>>,
+    //Holds SingleFrameRenderInfo::joint_palettes
+    pub joint_palette_buffers: Vec<Buffer::<glm::Mat4>>,
+    //Shared by every swapchain image, because the adapted luminance carries over from one frame to the next
+    pub luminance_histogram_buffer: Option<Buffer::<u32>>,
+    pub exposure_buffer: Option<Buffer::<ExposureState>>,
 
     pub base_descriptor_pool: vk::DescriptorPool,
-    pub postprocessing_descriptor_pool: vk::DescriptorPool
+    pub postprocessing_descriptor_pool: vk::DescriptorPool,
+    pub taa_descriptor_pool: vk::DescriptorPool,
+    //Only created when there are postprocess effects. Holds a set for every pass of every effect, for each swapchain image
+    pub postprocess_effect_descriptor_pool: vk::DescriptorPool,
+    pub auto_exposure_descriptor_pool: vk::DescriptorPool,
+    //Always created, so the stats overlay can be shown at any time
+    pub overlay_descriptor_pool: vk::DescriptorPool,
+    pub skinning_descriptor_pool: vk::DescriptorPool
 }
 
 bootstrap_loader! {
@@ -39,13 +62,13 @@ impl BootstrapUniformLoader {
             .descriptor_count(1)
             .stage_flags(vk::ShaderStageFlags::ALL_GRAPHICS);
 
-        let sampler_binding = vk::DescriptorSetLayoutBinding::builder()
-            .binding(1)
+        let shadow_map_binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(2)
             .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-            .descriptor_count(3)
+            .descriptor_count(1)
             .stage_flags(vk::ShaderStageFlags::ALL_GRAPHICS);
 
-        let bindings = &[ubo_binding, sampler_binding];
+        let bindings = &[ubo_binding, shadow_map_binding];
         let dsl_info = vk::DescriptorSetLayoutCreateInfo::builder()
             .bindings(bindings);
 
@@ -80,11 +103,155 @@ impl BootstrapUniformLoader {
             Ok(device.create_descriptor_set_layout(&dsl_info, None)?)
         }
     }
-    fn create_descriptor_set_layouts(&self, device: &Device, uniforms_info: &mut UniformsInfo) -> Result<()> {
+    fn create_material_descriptor_set_layout(&self, device: &Device) -> Result<vk::DescriptorSetLayout> {
+        //Base color, normal, and occlusion/roughness/metallic
+        let sampler_binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(MATERIAL_TEXTURE_COUNT)
+            .stage_flags(vk::ShaderStageFlags::ALL_GRAPHICS);
+        //The factors multiplied with them
+        let factors_binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(1)
+            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::ALL_GRAPHICS);
+
+        let bindings = &[sampler_binding, factors_binding];
+        let dsl_info = vk::DescriptorSetLayoutCreateInfo::builder()
+            .bindings(bindings);
+
+        unsafe {
+            Ok(device.create_descriptor_set_layout(&dsl_info, None)?)
+        }
+    }
+    fn create_taa_descriptor_set_layout(&self, device: &Device) -> Result<vk::DescriptorSetLayout> {
+        //Current color, motion vectors, and history
+        let sampler_bindings = (0..3)
+            .map(|q| {
+                vk::DescriptorSetLayoutBinding::builder()
+                    .binding(q)
+                    .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                    .descriptor_count(1)
+                    .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+            })
+            .collect::<Vec<_>>();
+
+        let dsl_info = vk::DescriptorSetLayoutCreateInfo::builder()
+            .bindings(&sampler_bindings);
+
+        unsafe {
+            Ok(device.create_descriptor_set_layout(&dsl_info, None)?)
+        }
+    }
+    fn create_postprocess_effect_descriptor_set_layout(&self, device: &Device) -> Result<vk::DescriptorSetLayout> {
+        let ubo_binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT);
+
+        //The pass's input, motion vectors, and the effect's input
+        let mut bindings = vec![ubo_binding];
+        bindings.extend((1..4)
+            .map(|q| {
+                vk::DescriptorSetLayoutBinding::builder()
+                    .binding(q)
+                    .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                    .descriptor_count(1)
+                    .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+            }));
+
+        let exposure_binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(4)
+            .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT);
+        bindings.push(exposure_binding);
+
+        let dsl_info = vk::DescriptorSetLayoutCreateInfo::builder()
+            .bindings(&bindings);
+
+        unsafe {
+            Ok(device.create_descriptor_set_layout(&dsl_info, None)?)
+        }
+    }
+    fn create_auto_exposure_descriptor_set_layout(&self, device: &Device) -> Result<vk::DescriptorSetLayout> {
+        let color_binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::COMPUTE);
+
+        let histogram_binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(1)
+            .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::COMPUTE);
+
+        let exposure_binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(2)
+            .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::COMPUTE);
+
+        let bindings = &[color_binding, histogram_binding, exposure_binding];
+        let dsl_info = vk::DescriptorSetLayoutCreateInfo::builder()
+            .bindings(bindings);
+
+        unsafe {
+            Ok(device.create_descriptor_set_layout(&dsl_info, None)?)
+        }
+    }
+    fn create_overlay_descriptor_set_layout(&self, device: &Device) -> Result<vk::DescriptorSetLayout> {
+        let ubo_binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT);
+
+        let bindings = &[ubo_binding];
+        let dsl_info = vk::DescriptorSetLayoutCreateInfo::builder()
+            .bindings(bindings);
+
+        unsafe {
+            Ok(device.create_descriptor_set_layout(&dsl_info, None)?)
+        }
+    }
+    fn create_skinning_descriptor_set_layout(&self, device: &Device) -> Result<vk::DescriptorSetLayout> {
+        //Current and previous joint palettes
+        let palette_bindings = (0..2)
+            .map(|q| {
+                vk::DescriptorSetLayoutBinding::builder()
+                    .binding(q)
+                    .descriptor_type(vk::DescriptorType::STORAGE_BUFFER_DYNAMIC)
+                    .descriptor_count(1)
+                    .stage_flags(vk::ShaderStageFlags::VERTEX)
+            })
+            .collect::<Vec<_>>();
+
+        let dsl_info = vk::DescriptorSetLayoutCreateInfo::builder()
+            .bindings(&palette_bindings);
+
+        unsafe {
+            Ok(device.create_descriptor_set_layout(&dsl_info, None)?)
+        }
+    }
+    fn create_descriptor_set_layouts(&self, device: &Device, uniforms_info: &mut UniformsInfo, app_data: &AppData) -> Result<()> {
         debug!("Creating descriptor set layouts...");
         uniforms_info.base_descriptor_set_layout = self.create_base_descriptor_set_layout(device)?;
         uniforms_info.postprocessing_descriptor_set_layout = self.create_postprocessing_descriptor_set_layout(device)?;
-        debug!("Descriptor set layouts created: {:?}, {:?}", uniforms_info.base_descriptor_set_layout, uniforms_info.postprocessing_descriptor_set_layout);
+        uniforms_info.material_descriptor_set_layout = self.create_material_descriptor_set_layout(device)?;
+        if app_data.taa.is_some() {
+            uniforms_info.taa_descriptor_set_layout = self.create_taa_descriptor_set_layout(device)?;
+        }
+        if !app_data.postprocess_effects.is_empty() {
+            uniforms_info.postprocess_effect_descriptor_set_layout = self.create_postprocess_effect_descriptor_set_layout(device)?;
+            uniforms_info.auto_exposure_descriptor_set_layout = self.create_auto_exposure_descriptor_set_layout(device)?;
+        }
+        uniforms_info.overlay_descriptor_set_layout = self.create_overlay_descriptor_set_layout(device)?;
+        uniforms_info.skinning_descriptor_set_layout = self.create_skinning_descriptor_set_layout(device)?;
+        debug!("Descriptor set layouts created: {:?}, {:?}, {:?}", uniforms_info.base_descriptor_set_layout, uniforms_info.postprocessing_descriptor_set_layout, uniforms_info.material_descriptor_set_layout);
 
         Ok(())
     }
@@ -101,6 +268,36 @@ impl BootstrapUniformLoader {
             device.destroy_descriptor_set_layout(uniforms_info.postprocessing_descriptor_set_layout, None);
         }
         uniforms_info.postprocessing_descriptor_set_layout = vk::DescriptorSetLayout::null();
+
+        unsafe {
+            device.destroy_descriptor_set_layout(uniforms_info.material_descriptor_set_layout, None);
+        }
+        uniforms_info.material_descriptor_set_layout = vk::DescriptorSetLayout::null();
+
+        unsafe {
+            device.destroy_descriptor_set_layout(uniforms_info.taa_descriptor_set_layout, None);
+        }
+        uniforms_info.taa_descriptor_set_layout = vk::DescriptorSetLayout::null();
+
+        unsafe {
+            device.destroy_descriptor_set_layout(uniforms_info.postprocess_effect_descriptor_set_layout, None);
+        }
+        uniforms_info.postprocess_effect_descriptor_set_layout = vk::DescriptorSetLayout::null();
+
+        unsafe {
+            device.destroy_descriptor_set_layout(uniforms_info.auto_exposure_descriptor_set_layout, None);
+        }
+        uniforms_info.auto_exposure_descriptor_set_layout = vk::DescriptorSetLayout::null();
+
+        unsafe {
+            device.destroy_descriptor_set_layout(uniforms_info.overlay_descriptor_set_layout, None);
+        }
+        uniforms_info.overlay_descriptor_set_layout = vk::DescriptorSetLayout::null();
+
+        unsafe {
+            device.destroy_descriptor_set_layout(uniforms_info.skinning_descriptor_set_layout, None);
+        }
+        uniforms_info.skinning_descriptor_set_layout = vk::DescriptorSetLayout::null();
     }
 
     fn create_uniform_buffers(&self, device: &Device, uniforms_info: &mut UniformsInfo, app_data: &AppData) -> Result<()> {
@@ -127,9 +324,32 @@ impl BootstrapUniformLoader {
             buffer.create(device, &app_data.memory_properties)?;
         }
 
-        debug!("Uniform buffers created: {:?}; {:?}", uniform_buffers, postprocessing_uniform_buffers);
+        let mut overlay_uniform_buffers = (0..image_count)
+            .map(|_| {
+                Buffer::<OverlayUniformBufferObject>::new(vk::BufferUsageFlags::UNIFORM_BUFFER, 1, false)
+            })
+            .collect::<Vec<_>>();
+
+        for buffer in overlay_uniform_buffers.iter_mut() {
+            buffer.create(device, &app_data.memory_properties)?;
+        }
+
+        //The last palette can start as late as MAX_FRAME_JOINT_MATRICES, and the descriptors always cover MAX_JOINTS matrices from there
+        let mut joint_palette_buffers = (0..image_count)
+            .map(|_| {
+                Buffer::<glm::Mat4>::new(vk::BufferUsageFlags::STORAGE_BUFFER, MAX_FRAME_JOINT_MATRICES + MAX_JOINTS, false)
+            })
+            .collect::<Vec<_>>();
+
+        for buffer in joint_palette_buffers.iter_mut() {
+            buffer.create(device, &app_data.memory_properties)?;
+        }
+
+        debug!("Uniform buffers created: {:?}; {:?}; {:?}; {:?}", uniform_buffers, postprocessing_uniform_buffers, overlay_uniform_buffers, joint_palette_buffers);
         uniforms_info.uniform_buffers = uniform_buffers;
         uniforms_info.postprocessing_uniform_buffers = postprocessing_uniform_buffers;
+        uniforms_info.overlay_uniform_buffers = overlay_uniform_buffers;
+        uniforms_info.joint_palette_buffers = joint_palette_buffers;
 
         Ok(())
     }
@@ -137,6 +357,16 @@ impl BootstrapUniformLoader {
     fn destroy_uniform_buffers(&self, device: &Device, uniforms_info: &mut UniformsInfo) -> () {
         debug!("Destroying uniform buffers...");
 
+        for joint_palette_buffer in uniforms_info.joint_palette_buffers.iter_mut() {
+            joint_palette_buffer.destroy(device);
+        }
+        uniforms_info.joint_palette_buffers.clear();
+
+        for uniform_buffer in uniforms_info.overlay_uniform_buffers.iter_mut() {
+            uniform_buffer.destroy(device);
+        }
+        uniforms_info.overlay_uniform_buffers.clear();
+
         for uniform_buffer in uniforms_info.postprocessing_uniform_buffers.iter_mut() {
             uniform_buffer.destroy(device);
         }
@@ -148,16 +378,49 @@ impl BootstrapUniformLoader {
         uniforms_info.uniform_buffers.clear();
     }
 
+    fn create_auto_exposure_buffers(&self, device: &Device, uniforms_info: &mut UniformsInfo, app_data: &AppData) -> Result<()> {
+        if app_data.postprocess_effects.is_empty() {
+            return Ok(());
+        }
+
+        debug!("Creating auto exposure buffers...");
+        let mut luminance_histogram_buffer = Buffer::<u32>::new(vk::BufferUsageFlags::STORAGE_BUFFER, LUMINANCE_HISTOGRAM_BIN_COUNT, false);
+        luminance_histogram_buffer.create(device, &app_data.memory_properties)?;
+        luminance_histogram_buffer.set_data(device, &[0u32; LUMINANCE_HISTOGRAM_BIN_COUNT])?;
+
+        let mut exposure_buffer = Buffer::<ExposureState>::new(vk::BufferUsageFlags::STORAGE_BUFFER, 1, false);
+        exposure_buffer.create(device, &app_data.memory_properties)?;
+        exposure_buffer.set_data(device, &ExposureState::default())?;
+
+        debug!("Auto exposure buffers created: {:?}; {:?}", luminance_histogram_buffer, exposure_buffer);
+        uniforms_info.luminance_histogram_buffer = Some(luminance_histogram_buffer);
+        uniforms_info.exposure_buffer = Some(exposure_buffer);
+
+        Ok(())
+    }
+
+    fn destroy_auto_exposure_buffers(&self, device: &Device, uniforms_info: &mut UniformsInfo) -> () {
+        debug!("Destroying auto exposure buffers...");
+
+        if let Some(mut exposure_buffer) = uniforms_info.exposure_buffer.take() {
+            exposure_buffer.destroy(device);
+        }
+
+        if let Some(mut luminance_histogram_buffer) = uniforms_info.luminance_histogram_buffer.take() {
+            luminance_histogram_buffer.destroy(device);
+        }
+    }
+
     fn create_base_descriptor_pool(&self, device: &Device, image_count: u32) -> Result<vk::DescriptorPool> {
         let ubo_size = vk::DescriptorPoolSize::builder()
             .type_(vk::DescriptorType::UNIFORM_BUFFER)
             .descriptor_count(image_count);
 
-        let sampler_size = vk::DescriptorPoolSize::builder()
+        let shadow_map_size = vk::DescriptorPoolSize::builder()
             .type_(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
             .descriptor_count(image_count);
 
-        let pool_sizes = &[ubo_size, sampler_size, sampler_size, sampler_size];
+        let pool_sizes = &[ubo_size, shadow_map_size];
         let desc_pool_info = vk::DescriptorPoolCreateInfo::builder()
             .pool_sizes(pool_sizes)
             .max_sets(image_count);
@@ -188,12 +451,106 @@ impl BootstrapUniformLoader {
             Ok(device.create_descriptor_pool(&desc_pool_info, None)?)
         }
     }
+    fn create_taa_descriptor_pool(&self, device: &Device, image_count: u32) -> Result<vk::DescriptorPool> {
+        let sampler_size = vk::DescriptorPoolSize::builder()
+            .type_(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(image_count * 3);
+
+        let pool_sizes = &[sampler_size];
+        let desc_pool_info = vk::DescriptorPoolCreateInfo::builder()
+            .pool_sizes(pool_sizes)
+            .max_sets(image_count);
+
+        unsafe {
+            Ok(device.create_descriptor_pool(&desc_pool_info, None)?)
+        }
+    }
+    fn create_postprocess_effect_descriptor_pool(&self, device: &Device, set_count: u32) -> Result<vk::DescriptorPool> {
+        let ubo_size = vk::DescriptorPoolSize::builder()
+            .type_(vk::DescriptorType::UNIFORM_BUFFER)
+            .descriptor_count(set_count);
+
+        let sampler_size = vk::DescriptorPoolSize::builder()
+            .type_(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(set_count * 3);
+
+        let exposure_size = vk::DescriptorPoolSize::builder()
+            .type_(vk::DescriptorType::STORAGE_BUFFER)
+            .descriptor_count(set_count);
+
+        let pool_sizes = &[ubo_size, sampler_size, exposure_size];
+        let desc_pool_info = vk::DescriptorPoolCreateInfo::builder()
+            .pool_sizes(pool_sizes)
+            .max_sets(set_count);
+
+        unsafe {
+            Ok(device.create_descriptor_pool(&desc_pool_info, None)?)
+        }
+    }
+    fn create_auto_exposure_descriptor_pool(&self, device: &Device, image_count: u32) -> Result<vk::DescriptorPool> {
+        let sampler_size = vk::DescriptorPoolSize::builder()
+            .type_(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(image_count);
+
+        let storage_size = vk::DescriptorPoolSize::builder()
+            .type_(vk::DescriptorType::STORAGE_BUFFER)
+            .descriptor_count(image_count * 2);
+
+        let pool_sizes = &[sampler_size, storage_size];
+        let desc_pool_info = vk::DescriptorPoolCreateInfo::builder()
+            .pool_sizes(pool_sizes)
+            .max_sets(image_count);
+
+        unsafe {
+            Ok(device.create_descriptor_pool(&desc_pool_info, None)?)
+        }
+    }
+    fn create_overlay_descriptor_pool(&self, device: &Device, image_count: u32) -> Result<vk::DescriptorPool> {
+        let ubo_size = vk::DescriptorPoolSize::builder()
+            .type_(vk::DescriptorType::UNIFORM_BUFFER)
+            .descriptor_count(image_count);
+
+        let pool_sizes = &[ubo_size];
+        let desc_pool_info = vk::DescriptorPoolCreateInfo::builder()
+            .pool_sizes(pool_sizes)
+            .max_sets(image_count);
+
+        unsafe {
+            Ok(device.create_descriptor_pool(&desc_pool_info, None)?)
+        }
+    }
+    fn create_skinning_descriptor_pool(&self, device: &Device, image_count: u32) -> Result<vk::DescriptorPool> {
+        let palette_size = vk::DescriptorPoolSize::builder()
+            .type_(vk::DescriptorType::STORAGE_BUFFER_DYNAMIC)
+            .descriptor_count(image_count * 2);
+
+        let pool_sizes = &[palette_size];
+        let desc_pool_info = vk::DescriptorPoolCreateInfo::builder()
+            .pool_sizes(pool_sizes)
+            .max_sets(image_count);
+
+        unsafe {
+            Ok(device.create_descriptor_pool(&desc_pool_info, None)?)
+        }
+    }
     fn create_descriptor_pools(&self, device: &Device, uniforms_info: &mut UniformsInfo, app_data: &AppData) -> Result<()> {
         let image_count = app_data.swapchain.as_ref().unwrap().image_count;
 
         debug!("Creating descriptor pools...");
         uniforms_info.base_descriptor_pool = self.create_base_descriptor_pool(device, image_count)?;
         uniforms_info.postprocessing_descriptor_pool = self.create_postprocessing_descriptor_pool(device, image_count)?;
+        if app_data.taa.is_some() {
+            uniforms_info.taa_descriptor_pool = self.create_taa_descriptor_pool(device, image_count)?;
+        }
+        if !app_data.postprocess_effects.is_empty() {
+            let pass_count = app_data.postprocess_effects.iter()
+                .map(|effect| effect.passes.len() as u32)
+                .sum::<u32>();
+            uniforms_info.postprocess_effect_descriptor_pool = self.create_postprocess_effect_descriptor_pool(device, image_count * pass_count)?;
+            uniforms_info.auto_exposure_descriptor_pool = self.create_auto_exposure_descriptor_pool(device, image_count)?;
+        }
+        uniforms_info.overlay_descriptor_pool = self.create_overlay_descriptor_pool(device, image_count)?;
+        uniforms_info.skinning_descriptor_pool = self.create_skinning_descriptor_pool(device, image_count)?;
         debug!("Descriptor pool created: {:?}, {:?}", uniforms_info.base_descriptor_pool, uniforms_info.postprocessing_descriptor_pool);
 
         Ok(())
@@ -211,14 +568,40 @@ impl BootstrapUniformLoader {
             device.destroy_descriptor_pool(uniforms_info.postprocessing_descriptor_pool, None);
         }
         uniforms_info.postprocessing_descriptor_pool = vk::DescriptorPool::null();
+
+        unsafe {
+            device.destroy_descriptor_pool(uniforms_info.taa_descriptor_pool, None);
+        }
+        uniforms_info.taa_descriptor_pool = vk::DescriptorPool::null();
+
+        unsafe {
+            device.destroy_descriptor_pool(uniforms_info.postprocess_effect_descriptor_pool, None);
+        }
+        uniforms_info.postprocess_effect_descriptor_pool = vk::DescriptorPool::null();
+
+        unsafe {
+            device.destroy_descriptor_pool(uniforms_info.auto_exposure_descriptor_pool, None);
+        }
+        uniforms_info.auto_exposure_descriptor_pool = vk::DescriptorPool::null();
+
+        unsafe {
+            device.destroy_descriptor_pool(uniforms_info.overlay_descriptor_pool, None);
+        }
+        uniforms_info.overlay_descriptor_pool = vk::DescriptorPool::null();
+
+        unsafe {
+            device.destroy_descriptor_pool(uniforms_info.skinning_descriptor_pool, None);
+        }
+        uniforms_info.skinning_descriptor_pool = vk::DescriptorPool::null();
     }
 }
 
 impl BootstrapLoader for BootstrapUniformLoader {
-    fn after_create_logical_device(&self, _inst: &Instance, device: &Device, _window: &Window, app_data: &mut AppData) -> Result<()> {
+    fn after_create_logical_device(&self, _inst: &Instance, device: &Device, _window: Option<&Window>, app_data: &mut AppData) -> Result<()> {
         let mut uniforms_info = UniformsInfo::default();
-        self.create_descriptor_set_layouts(device, &mut uniforms_info)?;
+        self.create_descriptor_set_layouts(device, &mut uniforms_info, app_data)?;
         self.create_uniform_buffers(device, &mut uniforms_info, app_data)?;
+        self.create_auto_exposure_buffers(device, &mut uniforms_info, app_data)?;
         self.create_descriptor_pools(device, &mut uniforms_info, app_data)?;
         app_data.uniforms = Some(uniforms_info);
 
@@ -228,13 +611,14 @@ impl BootstrapLoader for BootstrapUniformLoader {
     fn before_destroy_logical_device(&self, _inst: &Instance, device: &Device, app_data: &mut AppData) -> () {
         if let Some(mut uniforms_info) = app_data.uniforms.take() {
             self.destroy_descriptor_pools(device, &mut uniforms_info);
+            self.destroy_auto_exposure_buffers(device, &mut uniforms_info);
             self.destroy_uniform_buffers(device, &mut uniforms_info);
             self.destroy_descriptor_set_layouts(device, &mut uniforms_info);
         }
     }
 
-    fn recreate_swapchain(&self, inst: &Instance, device: &Device, window: &Window, app_data: &mut AppData, next: &dyn Fn(&Instance, &Device, &Window, &mut AppData) -> Result<()>) -> Result<()> {
-        trace!("Recreating descriptor pool, and uniform buffers (but not descriptor set layout) in recreate_swapchain");
+    fn recreate_swapchain(&self, inst: &Instance, device: &Device, window: Option<&Window>, app_data: &mut AppData, next: &dyn Fn(&Instance, &Device, Option<&Window>, &mut AppData) -> Result<()>) -> Result<()> {
+        trace!("Recreating descriptor pool, and uniform buffers (but not descriptor set layout or auto exposure buffers) in recreate_swapchain");
 
         let mut uniforms_info = app_data.uniforms.take().unwrap();
 
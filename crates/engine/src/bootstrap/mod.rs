@@ -5,9 +5,11 @@ mod bootstrap_render_images_loader;
 mod bootstrap_descriptor_sets_loader;
 mod bootstrap_dlss_loader;
 mod bootstrap_framebuffer_loader;
+mod bootstrap_gpu_timing_loader;
 mod bootstrap_pipeline_loader;
 mod bootstrap_swapchain_loader;
 mod bootstrap_sync_objects_loader;
+mod bootstrap_taa_loader;
 mod bootstrap_texture_sampling_loader;
 mod bootstrap_uniform_loader;
 mod bootstrap_validation_loader;
@@ -21,9 +23,11 @@ pub use bootstrap_render_images_loader::*;
 pub use bootstrap_descriptor_sets_loader::*;
 pub use bootstrap_dlss_loader::*;
 pub use bootstrap_framebuffer_loader::*;
+pub use bootstrap_gpu_timing_loader::*;
 pub use bootstrap_pipeline_loader::*;
 pub use bootstrap_swapchain_loader::*;
 pub use bootstrap_sync_objects_loader::*;
+pub use bootstrap_taa_loader::*;
 pub use bootstrap_texture_sampling_loader::*;
 pub use bootstrap_uniform_loader::*;
 pub use bootstrap_validation_loader::*;
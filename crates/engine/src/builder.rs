@@ -1,6 +1,7 @@
 use std::collections::{HashSet};
-use winit::dpi::{LogicalSize};
+use winit::dpi::{LogicalSize, PhysicalSize};
 use anyhow::{anyhow, Result};
+use vulkanalia::prelude::v1_0::*;
 use crate::{
     app::App,
     bootstrap::{
@@ -9,14 +10,22 @@ use crate::{
         BootstrapRenderImagesLoader,
         BootstrapDescriptorSetLoader,
         BootstrapDlssLoader,
+        DlssQualityMode,
         BootstrapFramebufferLoader,
+        BootstrapGpuTimingLoader,
         BootstrapPipelineLoader,
         BootstrapSwapchainLoader,
+        HdrSettings,
+        PresentMode,
         BootstrapSyncObjectsLoader,
+        BootstrapTaaLoader,
+        TaaSettings,
         BootstrapTextureSamplingLoader,
         BootstrapUniformLoader,
         BootstrapValidationLoader
-    }
+    },
+    postprocessing::{PostprocessEffect, MotionBlurSettings, ToneMappingSettings, TONE_MAPPING_EFFECT_NAME},
+    resources::{ScreenshotSettings}
 };
 
 pub trait HasHeapBuilder {
@@ -35,16 +44,35 @@ impl HasHeapBuilder for App {
 #[derive(Debug)]
 pub struct AppBuilder {
     bootstrap_loaders: Vec<Box<dyn BootstrapLoader>>,
+    //DLSS and TAA pick the base render resolution, so they're kept apart from the other loaders to make sure they're resolved before the render images
+    dlss_loader: Option<BootstrapDlssLoader>,
+    taa_loader: Option<BootstrapTaaLoader>,
+    postprocess_effects: Vec<PostprocessEffect>,
+    hdr_settings: HdrSettings,
     initial_title: &'static str,
-    default_size: LogicalSize<i32>
+    default_size: LogicalSize<i32>,
+    headless_extent: Option<vk::Extent2D>,
+    screenshot_settings: ScreenshotSettings,
+    present_mode: PresentMode,
+    frame_rate_limit: Option<f32>,
+    show_stats_overlay: bool
 }
 
 impl<'a> Default for AppBuilder {
     fn default() -> Self {
         Self {
             bootstrap_loaders: vec![],
+            dlss_loader: None,
+            taa_loader: None,
+            postprocess_effects: vec![],
+            hdr_settings: Default::default(),
             initial_title: "",
-            default_size: LogicalSize::new(300, 300)
+            default_size: LogicalSize::new(300, 300),
+            headless_extent: None,
+            screenshot_settings: Default::default(),
+            present_mode: Default::default(),
+            frame_rate_limit: None,
+            show_stats_overlay: false
         }
     }
 }
@@ -66,10 +94,60 @@ impl AppBuilder {
             .add_bootstrap_loader(Box::new(BootstrapFramebufferLoader::new()))
             .add_bootstrap_loader(Box::new(BootstrapSyncObjectsLoader::new()))
             .add_bootstrap_loader(Box::new(BootstrapDescriptorSetLoader::new()))
+            .add_bootstrap_loader(Box::new(BootstrapGpuTimingLoader::new()))
+    }
+
+    pub fn add_dlss(mut self, quality: DlssQualityMode) -> Self {
+        self.dlss_loader = Some(BootstrapDlssLoader::new_with_quality(quality));
+
+        self
+    }
+
+    //Only used when DLSS wasn't added or isn't available
+    pub fn add_taa(mut self, settings: TaaSettings) -> Self {
+        self.taa_loader = Some(BootstrapTaaLoader::new_with_settings(settings));
+
+        self
+    }
+
+    //Effects run in the order they're added. Tone mapping is added at the end if it wasn't added explicitly
+    pub fn add_postprocess_effect(mut self, effect: impl Into<PostprocessEffect>) -> Self {
+        self.postprocess_effects.push(effect.into());
+
+        self
+    }
+
+    pub fn add_default_postprocess_effects(self) -> Self {
+        self.add_postprocess_effect(MotionBlurSettings::default())
+            .add_postprocess_effect(ToneMappingSettings::default())
+    }
+
+    //HDR is used by default whenever the display supports it
+    pub fn hdr_settings(mut self, settings: HdrSettings) -> Self {
+        self.hdr_settings = settings;
+
+        self
+    }
+
+    //Falls back towards FIFO if the surface doesn't support the requested mode. Can be changed later with App::set_present_mode
+    pub fn present_mode(mut self, present_mode: PresentMode) -> Self {
+        self.present_mode = present_mode;
+
+        self
     }
 
-    pub fn add_dlss(self) -> Self {
-        self.add_bootstrap_loader(Box::new(BootstrapDlssLoader::new()))
+    //Caps frames per second by sleeping between frames, which saves power. Can be changed later with App::set_frame_rate_limit
+    pub fn frame_rate_limit(mut self, frame_rate_limit: f32) -> Self {
+        self.frame_rate_limit = Some(frame_rate_limit).filter(|limit| *limit > 0.0);
+
+        self
+    }
+
+    //Frame stats are always collected. This only decides whether they're drawn on top of the final image. Can be toggled later with App::set_stats_overlay_visible
+    pub fn show_stats_overlay(mut self, visible: bool) -> Self {
+        self.show_stats_overlay = visible;
+
+        self
     }
 
     pub fn add_validation(self) -> Self {
@@ -88,8 +166,26 @@ impl AppBuilder {
         self
     }
 
+    //Renders to offscreen images of the given size instead of a window. Frames can be read back with App::request_readback
+    pub fn headless(mut self, size: PhysicalSize<u32>) -> Self {
+        self.headless_extent = Some(vk::Extent2D { width: size.width, height: size.height });
+
+        self
+    }
+
+    //Screenshot hotkeys are off by default
+    pub fn screenshot_settings(mut self, settings: ScreenshotSettings) -> Self {
+        self.screenshot_settings = settings;
+
+        self
+    }
+
     pub fn build(self) -> Result<App> {
-        let mut bootstrap_loaders = self.bootstrap_loaders;
+        //Loaders are resolved in order as soon as their dependencies are met, so DLSS then TAA go first to be set up before the render images are created
+        let mut bootstrap_loaders = self.dlss_loader.into_iter().map(|loader| Box::new(loader) as Box<dyn BootstrapLoader>)
+            .chain(self.taa_loader.into_iter().map(|loader| Box::new(loader) as Box<dyn BootstrapLoader>))
+            .chain(self.bootstrap_loaders)
+            .collect::<Vec<_>>();
         let mut ordered_bootstrap_loaders = vec![];
         let mut satisfied_dependencies = HashSet::<&str>::new();
 
@@ -127,6 +223,20 @@ impl AppBuilder {
             return Err(anyhow!("Could not resolve dependencies for the following bootstrap loaders: {:?}", bootstrap_loaders));
         }
 
-        App::create(self.initial_title, self.default_size, ordered_bootstrap_loaders)
+        //The base render is HDR, so without tone mapping anything brighter than 1.0 would just be clipped
+        let mut postprocess_effects = self.postprocess_effects;
+        if !postprocess_effects.iter().any(|effect| effect.name == TONE_MAPPING_EFFECT_NAME) {
+            postprocess_effects.push(ToneMappingSettings::default().into());
+        }
+
+        let mut effect_names = HashSet::<&str>::new();
+        for effect in postprocess_effects.iter() {
+            effect.validate()?;
+            if !effect_names.insert(effect.name) {
+                return Err(anyhow!("Multiple postprocess effects named {} detected. Effects are looked up by name, so they must be unique", effect.name));
+            }
+        }
+
+        App::create(self.initial_title, self.default_size, ordered_bootstrap_loaders, postprocess_effects, self.hdr_settings, self.headless_extent, self.screenshot_settings, self.present_mode, self.frame_rate_limit, self.show_stats_overlay)
     }
 }
@@ -3,6 +3,11 @@ use std::time::{
     Duration
 };
 
+//60 Hz
+pub const DEFAULT_FIXED_DELTA_TIME: Duration = Duration::from_nanos(16_666_667);
+//If a frame takes long enough to need more fixed updates than this, the extra time is dropped so the simulation can't fall further and further behind
+pub const DEFAULT_MAX_FIXED_UPDATES_PER_FRAME: u32 = 8;
+
 #[derive(Debug, Copy, Clone)]
 pub struct FrameInfo {
     pub current_frame_index: u32,
@@ -12,7 +17,16 @@ pub struct FrameInfo {
     //Used for frame pacing/delta time
     pub last_frame_start_time: Instant,
 
-    pub app_start_time: Instant
+    pub app_start_time: Instant,
+
+    //Fixed updates run zero or more times per frame, always with this delta. Change it before the first frame to pick a different rate
+    pub fixed_delta_time: Duration,
+    pub max_fixed_updates_per_frame: u32,
+    pub fixed_update_index: u64,
+    //Time not yet consumed by a fixed update
+    pub fixed_time_accumulator: Duration,
+    //How far the current frame is between the last fixed update and the next one, from 0 to 1. Used to interpolate fixed update state when rendering
+    pub fixed_update_alpha: f32
 }
 
 impl Default for FrameInfo {
@@ -23,6 +37,36 @@ impl Default for FrameInfo {
             current_frame_delta_time: Duration::from_millis(0),
             last_frame_start_time: Instant::now(),
             app_start_time: Instant::now(),
+            fixed_delta_time: DEFAULT_FIXED_DELTA_TIME,
+            max_fixed_updates_per_frame: DEFAULT_MAX_FIXED_UPDATES_PER_FRAME,
+            fixed_update_index: 0,
+            fixed_time_accumulator: Duration::from_millis(0),
+            fixed_update_alpha: 0.0
         }
     }
 }
+
+impl FrameInfo {
+    pub fn elapsed_time(&self) -> Duration {
+        self.current_frame_time - self.app_start_time
+    }
+
+    //Adds the frame's delta time to the accumulator and returns how many fixed updates are due
+    pub fn accumulate_fixed_time(&mut self) -> u32 {
+        let max_accumulated_time = self.fixed_delta_time * self.max_fixed_updates_per_frame;
+        self.fixed_time_accumulator += self.current_frame_delta_time;
+        if self.fixed_time_accumulator > max_accumulated_time {
+            trace!("Frame took {:?}, which is more than {} fixed updates. Dropping the extra time.", self.current_frame_delta_time, self.max_fixed_updates_per_frame);
+            self.fixed_time_accumulator = max_accumulated_time;
+        }
+
+        let mut update_count = 0;
+        while self.fixed_time_accumulator >= self.fixed_delta_time {
+            self.fixed_time_accumulator -= self.fixed_delta_time;
+            update_count += 1;
+        }
+        self.fixed_update_alpha = self.fixed_time_accumulator.as_secs_f32() / self.fixed_delta_time.as_secs_f32();
+
+        update_count
+    }
+}
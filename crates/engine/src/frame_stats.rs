@@ -0,0 +1,231 @@
+use std::{
+    collections::{VecDeque},
+    time::{Duration}
+};
+
+//Roughly four seconds at 60 FPS. Long enough for stable percentiles, short enough to follow changes
+pub const DEFAULT_FRAME_STATS_WINDOW: usize = 240;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CpuPhase {
+    Tick,
+    LoadAndUnload,
+    ResourceTick,
+    RecordAndSubmit
+}
+
+impl CpuPhase {
+    pub const ALL: [CpuPhase; 4] = [CpuPhase::Tick, CpuPhase::LoadAndUnload, CpuPhase::ResourceTick, CpuPhase::RecordAndSubmit];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CpuPhase::Tick => "Tick",
+            CpuPhase::LoadAndUnload => "Load/unload",
+            CpuPhase::ResourceTick => "Resource tick",
+            CpuPhase::RecordAndSubmit => "Record/submit"
+        }
+    }
+}
+
+//Each pass is timed from the end of the one before it, so together they add up to the whole GPU frame
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GpuPass {
+    Shadows,
+    DepthMotion,
+    BaseRender,
+    //DLSS or TAA, plus auto exposure
+    Upscale,
+    Postprocessing
+}
+
+impl GpuPass {
+    pub const ALL: [GpuPass; 5] = [GpuPass::Shadows, GpuPass::DepthMotion, GpuPass::BaseRender, GpuPass::Upscale, GpuPass::Postprocessing];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GpuPass::Shadows => "Shadows",
+            GpuPass::DepthMotion => "Depth/motion",
+            GpuPass::BaseRender => "Base render",
+            GpuPass::Upscale => "Upscale",
+            GpuPass::Postprocessing => "Postprocessing"
+        }
+    }
+
+    //The timestamp written when this pass ends. Timestamp 0 is written at the start of the frame
+    pub fn end_timestamp(&self) -> u32 {
+        *self as u32 + 1
+    }
+
+    pub fn timestamp_count() -> u32 {
+        Self::ALL.len() as u32 + 1
+    }
+}
+
+//Counts for a single frame. Instanced draws can be split into several draws around culled instances
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct CullingStats {
+    pub submitted_draws: u32,
+    pub submitted_instances: u32,
+    pub visible_draws: u32,
+    pub visible_instances: u32,
+    //Summed over all shadow cascades
+    pub shadow_caster_draws: u32,
+    pub shadow_caster_instances: u32
+}
+
+impl CullingStats {
+    pub fn culled_instances(&self) -> u32 {
+        self.submitted_instances.saturating_sub(self.visible_instances)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DurationStats {
+    pub average: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration
+}
+
+impl DurationStats {
+    fn from_samples(samples: impl Iterator<Item = Duration>) -> Self {
+        let mut sorted = samples.collect::<Vec<_>>();
+        if sorted.is_empty() {
+            return Self::default();
+        }
+        sorted.sort();
+
+        let percentile = |p: f32| sorted[((sorted.len() - 1) as f32 * p).round() as usize];
+        Self {
+            average: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+            max: *sorted.last().unwrap()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FrameStats {
+    pub frame_time: DurationStats,
+    pub frames_per_second: f32,
+    pub cpu_phases: Vec<(CpuPhase, DurationStats)>,
+    //Empty when the GPU doesn't support timestamp queries
+    pub gpu_passes: Vec<(GpuPass, DurationStats)>,
+    pub gpu_total: DurationStats,
+    //From the latest frame rather than averaged, since it only changes when the scene or camera does
+    pub culling: CullingStats
+}
+
+impl FrameStats {
+    //Short enough to fit the overlay's columns
+    pub fn overlay_lines(&self) -> Vec<String> {
+        let ms = |duration: Duration| duration.as_secs_f32() * 1000.0;
+
+        let mut lines = vec![
+            format!("{:.0} FPS  {:.2} MS", self.frames_per_second, ms(self.frame_time.average)),
+            format!("P50 {:.2}  P95 {:.2}  P99 {:.2}", ms(self.frame_time.p50), ms(self.frame_time.p95), ms(self.frame_time.p99)),
+            format!("DRAWS {}  INST {}/{}  SHADOW {}", self.culling.visible_draws, self.culling.visible_instances, self.culling.submitted_instances, self.culling.shadow_caster_draws)
+        ];
+
+        for (phase, stats) in self.cpu_phases.iter() {
+            lines.push(format!("CPU {:<15}{:>7.2} MS", phase.name(), ms(stats.average)));
+        }
+
+        if !self.gpu_passes.is_empty() {
+            for (pass, stats) in self.gpu_passes.iter() {
+                lines.push(format!("GPU {:<15}{:>7.2} MS", pass.name(), ms(stats.average)));
+            }
+            lines.push(format!("GPU {:<15}{:>7.2} MS", "Total", ms(self.gpu_total.average)));
+        }
+
+        lines
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+struct CpuSample {
+    frame_time: Duration,
+    phases: [Duration; CpuPhase::ALL.len()]
+}
+
+//GPU timings come in a few frames late, once the frame's queries are available, so they're kept separately
+#[derive(Debug, Clone)]
+pub struct FrameStatsCollector {
+    window: usize,
+    current: CpuSample,
+    cpu_samples: VecDeque<CpuSample>,
+    gpu_samples: VecDeque<[Duration; GpuPass::ALL.len()]>,
+    culling: CullingStats
+}
+
+impl Default for FrameStatsCollector {
+    fn default() -> Self {
+        Self::new(DEFAULT_FRAME_STATS_WINDOW)
+    }
+}
+
+impl FrameStatsCollector {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            current: Default::default(),
+            cpu_samples: VecDeque::with_capacity(window),
+            gpu_samples: VecDeque::with_capacity(window),
+            culling: Default::default()
+        }
+    }
+
+    pub fn add_cpu_time(&mut self, phase: CpuPhase, duration: Duration) -> () {
+        self.current.phases[phase as usize] += duration;
+    }
+
+    pub fn end_frame(&mut self, frame_time: Duration) -> () {
+        self.current.frame_time = frame_time;
+        if self.cpu_samples.len() == self.window {
+            self.cpu_samples.pop_front();
+        }
+        self.cpu_samples.push_back(self.current);
+        self.current = Default::default();
+    }
+
+    pub fn set_culling_stats(&mut self, culling: CullingStats) -> () {
+        self.culling = culling;
+    }
+
+    pub fn add_gpu_sample(&mut self, passes: [Duration; GpuPass::ALL.len()]) -> () {
+        if self.gpu_samples.len() == self.window {
+            self.gpu_samples.pop_front();
+        }
+        self.gpu_samples.push_back(passes);
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        let frame_time = DurationStats::from_samples(self.cpu_samples.iter().map(|sample| sample.frame_time));
+        let frames_per_second = if frame_time.average.is_zero() { 0.0 } else { 1.0 / frame_time.average.as_secs_f32() };
+
+        let cpu_phases = CpuPhase::ALL.iter()
+            .map(|phase| (*phase, DurationStats::from_samples(self.cpu_samples.iter().map(|sample| sample.phases[*phase as usize]))))
+            .collect();
+
+        let gpu_passes = if self.gpu_samples.is_empty() {
+            vec![]
+        } else {
+            GpuPass::ALL.iter()
+                .map(|pass| (*pass, DurationStats::from_samples(self.gpu_samples.iter().map(|sample| sample[*pass as usize]))))
+                .collect()
+        };
+        let gpu_total = DurationStats::from_samples(self.gpu_samples.iter().map(|sample| sample.iter().sum()));
+
+        FrameStats {
+            frame_time,
+            frames_per_second,
+            cpu_phases,
+            gpu_passes,
+            gpu_total,
+            culling: self.culling
+        }
+    }
+}
@@ -1,4 +1,10 @@
-use super::transform::{Transform};
+use super::{
+    transform::{Transform},
+    frustum::{Frustum},
+    raycast::{Ray},
+    game_object::{GameObjectId},
+    post_settings::{CameraPostSettings}
+};
 
 use nalgebra_glm as glm;
 use anyhow::{Result};
@@ -19,11 +25,15 @@ pub enum CameraKind {
 #[derive(Debug, Copy, Clone)]
 pub struct Camera {
     pub transform: Transform,
+    pub post_settings: CameraPostSettings,
     near: f32,
     far: f32,
     kind: CameraKind,
     fovy: f32,
 
+    parent: Option<GameObjectId>,
+    parent_matrix: glm::DMat4,
+
     previous_proj: Option<glm::Mat4>,
     previous_view: Option<glm::DMat4>
 }
@@ -32,11 +42,15 @@ impl Default for Camera {
     fn default() -> Self {
         Self {
             transform: Default::default(),
+            post_settings: Default::default(),
             near: 0.001,
             far: 1000.0,
             kind: Default::default(),
             fovy: 45.0,
 
+            parent: Default::default(),
+            parent_matrix: glm::DMat4::identity(),
+
             previous_proj: Default::default(),
             previous_view: Default::default()
         }
@@ -80,6 +94,21 @@ impl Camera {
         self.fovy = fovy;
     }
 
+    pub fn parent(&self) -> Option<GameObjectId> {
+        self.parent
+    }
+    pub fn attach_to(&mut self, parent: GameObjectId) -> () {
+        self.parent = Some(parent);
+    }
+    pub fn detach(&mut self) -> () {
+        self.parent = None;
+        self.parent_matrix = glm::DMat4::identity();
+    }
+
+    pub(crate) fn set_parent_matrix(&mut self, parent_matrix: glm::DMat4) -> () {
+        self.parent_matrix = parent_matrix;
+    }
+
     pub fn end_frame(&mut self, bounds: vk::Extent2D) -> Result<()> {
         self.previous_view = Some(self.get_view_matrix()?);
         self.previous_proj = Some(self.get_projection_matrix(bounds)?);
@@ -94,11 +123,39 @@ impl Camera {
     pub fn get_previous_view_matrix(&self) -> Option<&glm::DMat4> {
         self.previous_view.as_ref()
     }
+
+    //The point is in pixels from the top left of bounds, which should be the extent the camera renders at. Uses the projection without TAA or DLSS jitter
+    pub fn screen_point_to_ray(&self, point: glm::DVec2, bounds: vk::Extent2D) -> Result<Ray> {
+        let projection = glm::convert::<glm::Mat4, glm::DMat4>(self.get_projection_matrix(bounds)?);
+        let inverse_view_projection = glm::inverse(&(projection * self.get_view_matrix()?));
+
+        //The projection's Y is already flipped for Vulkan, so Y points down in clip space like it does on screen
+        let x = 2.0 * point.x / bounds.width as f64 - 1.0;
+        let y = 2.0 * point.y / bounds.height as f64 - 1.0;
+
+        let near = inverse_view_projection * glm::vec4(x, y, 0.0, 1.0);
+        let far = inverse_view_projection * glm::vec4(x, y, 1.0, 1.0);
+        let near = near.xyz() / near.w;
+        let far = far.xyz() / far.w;
+
+        Ok(Ray::new(near, glm::normalize(&(far - near))))
+    }
+
+    //In world space
+    pub fn get_frustum(&self, bounds: vk::Extent2D) -> Result<Frustum> {
+        let view = glm::convert::<glm::DMat4, glm::Mat4>(self.get_view_matrix()?);
+        Ok(Frustum::from_matrix(&(self.get_projection_matrix(bounds)? * view)))
+    }
 }
 
 impl HasCameraMatrix for Camera {
     fn get_view_matrix(&self) -> Result<glm::DMat4> {
-        self.transform.as_matrix_inverse()
+        let view = self.transform.as_matrix_inverse()?;
+        if self.parent.is_none() {
+            return Ok(view);
+        }
+
+        Ok(view * glm::inverse(&self.parent_matrix))
     }
 
     fn get_projection_matrix(&self, bounds: vk::Extent2D) -> Result<glm::Mat4> {
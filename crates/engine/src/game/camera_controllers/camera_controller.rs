@@ -0,0 +1,12 @@
+use std::fmt::{Debug};
+use anyhow::{Result};
+
+use crate::{
+    frame_info::{FrameInfo},
+    game::camera::{Camera},
+    input::{InputState}
+};
+
+pub trait CameraController : Debug {
+    fn update(&mut self, frame_info: &FrameInfo, input: &InputState, camera: &mut Camera) -> Result<()>;
+}
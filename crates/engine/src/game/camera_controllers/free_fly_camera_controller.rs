@@ -0,0 +1,160 @@
+use super::{CameraController};
+
+use nalgebra_glm as glm;
+use anyhow::{Result};
+
+use crate::{
+    frame_info::{FrameInfo},
+    game::{
+        camera::{Camera},
+        transform::{DEFAULT_UP}
+    },
+    input::{InputState, ActionMap, InputBinding, VirtualKeyCode, MouseButton}
+};
+#[cfg(feature = "gamepad")]
+use crate::input::{GamepadAxis};
+
+pub const MOVE_FORWARD: &str = "free_fly_move_forward";
+pub const MOVE_BACKWARD: &str = "free_fly_move_backward";
+pub const MOVE_LEFT: &str = "free_fly_move_left";
+pub const MOVE_RIGHT: &str = "free_fly_move_right";
+pub const MOVE_UP: &str = "free_fly_move_up";
+pub const MOVE_DOWN: &str = "free_fly_move_down";
+pub const MOVE_FAST: &str = "free_fly_move_fast";
+pub const LOOK_LEFT: &str = "free_fly_look_left";
+pub const LOOK_RIGHT: &str = "free_fly_look_right";
+pub const LOOK_UP: &str = "free_fly_look_up";
+pub const LOOK_DOWN: &str = "free_fly_look_down";
+
+const MAX_PITCH_DEGREES: f64 = 89.0;
+
+#[derive(Debug, Copy, Clone)]
+pub struct FreeFlyCameraController {
+    pub yaw: f64,
+    pub pitch: f64,
+
+    pub move_speed: f64,
+    pub fast_multiplier: f64,
+    pub mouse_sensitivity: f64,
+    pub gamepad_look_speed: f64,
+    pub zoom_speed: f32,
+    pub min_fovy: f32,
+    pub max_fovy: f32,
+    pub look_button: Option<MouseButton>
+}
+
+impl Default for FreeFlyCameraController {
+    fn default() -> Self {
+        Self {
+            yaw: 0.0,
+            pitch: 0.0,
+
+            move_speed: 2.0,
+            fast_multiplier: 4.0,
+            mouse_sensitivity: 0.003,
+            gamepad_look_speed: 2.0,
+            zoom_speed: 2.0,
+            min_fovy: 10.0,
+            max_fovy: 90.0,
+            look_button: Some(MouseButton::Right)
+        }
+    }
+}
+
+impl FreeFlyCameraController {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn looking_at(pos: glm::DVec3, target: glm::DVec3) -> Self {
+        let dir = target - pos;
+        let horizontal_len = glm::length(&glm::vec2(dir.x, dir.y));
+
+        Self {
+            yaw: f64::atan2(dir.y, dir.x),
+            pitch: f64::atan2(dir.z, horizontal_len),
+            ..Default::default()
+        }
+    }
+
+    pub fn bind_default_actions(actions: &mut ActionMap) -> () {
+        actions.bind(MOVE_FORWARD, InputBinding::Key(VirtualKeyCode::W));
+        actions.bind(MOVE_BACKWARD, InputBinding::Key(VirtualKeyCode::S));
+        actions.bind(MOVE_LEFT, InputBinding::Key(VirtualKeyCode::A));
+        actions.bind(MOVE_RIGHT, InputBinding::Key(VirtualKeyCode::D));
+        actions.bind(MOVE_UP, InputBinding::Key(VirtualKeyCode::E));
+        actions.bind(MOVE_DOWN, InputBinding::Key(VirtualKeyCode::Q));
+        actions.bind(MOVE_FAST, InputBinding::Key(VirtualKeyCode::LShift));
+
+        #[cfg(feature = "gamepad")]
+        Self::bind_default_gamepad_actions(actions);
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn bind_default_gamepad_actions(actions: &mut ActionMap) -> () {
+        actions.bind(MOVE_FORWARD, InputBinding::GamepadAxis { axis: GamepadAxis::LeftStickY, positive: true });
+        actions.bind(MOVE_BACKWARD, InputBinding::GamepadAxis { axis: GamepadAxis::LeftStickY, positive: false });
+        actions.bind(MOVE_LEFT, InputBinding::GamepadAxis { axis: GamepadAxis::LeftStickX, positive: false });
+        actions.bind(MOVE_RIGHT, InputBinding::GamepadAxis { axis: GamepadAxis::LeftStickX, positive: true });
+        actions.bind(LOOK_LEFT, InputBinding::GamepadAxis { axis: GamepadAxis::RightStickX, positive: false });
+        actions.bind(LOOK_RIGHT, InputBinding::GamepadAxis { axis: GamepadAxis::RightStickX, positive: true });
+        actions.bind(LOOK_UP, InputBinding::GamepadAxis { axis: GamepadAxis::RightStickY, positive: true });
+        actions.bind(LOOK_DOWN, InputBinding::GamepadAxis { axis: GamepadAxis::RightStickY, positive: false });
+    }
+
+    pub fn forward(&self) -> glm::DVec3 {
+        glm::vec3(
+            self.pitch.cos() * self.yaw.cos(),
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin()
+        )
+    }
+}
+
+impl CameraController for FreeFlyCameraController {
+    fn update(&mut self, frame_info: &FrameInfo, input: &InputState, camera: &mut Camera) -> Result<()> {
+        let delta_time = frame_info.current_frame_delta_time.as_secs_f64();
+
+        let mouse_look_active = self.look_button.map(|button| input.is_mouse_button_held(button)).unwrap_or(true);
+        if mouse_look_active {
+            let mouse_delta = input.mouse_delta();
+            self.yaw -= mouse_delta.x * self.mouse_sensitivity;
+            self.pitch -= mouse_delta.y * self.mouse_sensitivity;
+        }
+
+        self.yaw -= input.action_axis(LOOK_LEFT, LOOK_RIGHT) as f64 * self.gamepad_look_speed * delta_time;
+        self.pitch += input.action_axis(LOOK_DOWN, LOOK_UP) as f64 * self.gamepad_look_speed * delta_time;
+
+        let max_pitch = MAX_PITCH_DEGREES.to_radians();
+        self.pitch = self.pitch.clamp(-max_pitch, max_pitch);
+
+        let up = glm::convert::<glm::Vec3, glm::DVec3>(*DEFAULT_UP);
+        let forward = self.forward();
+        let right = glm::normalize(&glm::cross(&forward, &up));
+
+        let mut movement = forward * input.action_axis(MOVE_BACKWARD, MOVE_FORWARD) as f64
+            + right * input.action_axis(MOVE_LEFT, MOVE_RIGHT) as f64
+            + up * input.action_axis(MOVE_DOWN, MOVE_UP) as f64;
+        if glm::length(&movement) > 1.0 {
+            movement = glm::normalize(&movement);
+        }
+
+        let mut speed = self.move_speed;
+        if input.is_action_held(MOVE_FAST) {
+            speed *= self.fast_multiplier;
+        }
+
+        camera.transform.pos += movement * speed * delta_time;
+
+        let scroll = input.scroll_delta().y;
+        if scroll != 0.0 {
+            let fovy = (camera.fovy() - scroll * self.zoom_speed).clamp(self.min_fovy, self.max_fovy);
+            camera.set_fovy(fovy);
+        }
+
+        let pos = camera.transform.pos;
+        camera.look_at(pos + forward);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,7 @@
+mod camera_controller;
+mod free_fly_camera_controller;
+mod orbit_camera_controller;
+
+pub use camera_controller::{CameraController};
+pub use free_fly_camera_controller::{FreeFlyCameraController};
+pub use orbit_camera_controller::{OrbitCameraController};
@@ -0,0 +1,137 @@
+use super::{CameraController};
+
+use nalgebra_glm as glm;
+use anyhow::{Result};
+
+use crate::{
+    frame_info::{FrameInfo},
+    game::camera::{Camera},
+    input::{InputState, ActionMap, InputBinding, VirtualKeyCode, MouseButton}
+};
+#[cfg(feature = "gamepad")]
+use crate::input::{GamepadAxis};
+
+pub const ORBIT_LEFT: &str = "orbit_left";
+pub const ORBIT_RIGHT: &str = "orbit_right";
+pub const ORBIT_UP: &str = "orbit_up";
+pub const ORBIT_DOWN: &str = "orbit_down";
+pub const ZOOM_IN: &str = "orbit_zoom_in";
+pub const ZOOM_OUT: &str = "orbit_zoom_out";
+
+const MAX_PITCH_DEGREES: f64 = 89.0;
+
+#[derive(Debug, Copy, Clone)]
+pub struct OrbitCameraController {
+    pub target: glm::DVec3,
+    pub distance: f64,
+    pub yaw: f64,
+    pub pitch: f64,
+
+    pub min_distance: f64,
+    pub max_distance: f64,
+    pub orbit_speed: f64,
+    pub mouse_sensitivity: f64,
+    pub zoom_speed: f64,
+    pub orbit_button: Option<MouseButton>
+}
+
+impl Default for OrbitCameraController {
+    fn default() -> Self {
+        Self {
+            target: Default::default(),
+            distance: 5.0,
+            yaw: 0.0,
+            pitch: 0.0,
+
+            min_distance: 0.1,
+            max_distance: 500.0,
+            orbit_speed: 1.5,
+            mouse_sensitivity: 0.005,
+            zoom_speed: 0.1,
+            orbit_button: Some(MouseButton::Left)
+        }
+    }
+}
+
+impl OrbitCameraController {
+    pub fn new(target: glm::DVec3, distance: f64) -> Self {
+        Self {
+            target,
+            distance,
+            ..Default::default()
+        }
+    }
+
+    pub fn from_position(pos: glm::DVec3, target: glm::DVec3) -> Self {
+        let offset = pos - target;
+        let horizontal_len = glm::length(&glm::vec2(offset.x, offset.y));
+
+        Self {
+            target,
+            distance: glm::length(&offset),
+            yaw: f64::atan2(offset.y, offset.x),
+            pitch: f64::atan2(offset.z, horizontal_len),
+            ..Default::default()
+        }
+    }
+
+    pub fn bind_default_actions(actions: &mut ActionMap) -> () {
+        actions.bind(ORBIT_LEFT, InputBinding::Key(VirtualKeyCode::A));
+        actions.bind(ORBIT_RIGHT, InputBinding::Key(VirtualKeyCode::D));
+        actions.bind(ORBIT_UP, InputBinding::Key(VirtualKeyCode::W));
+        actions.bind(ORBIT_DOWN, InputBinding::Key(VirtualKeyCode::S));
+        actions.bind(ZOOM_IN, InputBinding::Key(VirtualKeyCode::E));
+        actions.bind(ZOOM_OUT, InputBinding::Key(VirtualKeyCode::Q));
+
+        #[cfg(feature = "gamepad")]
+        Self::bind_default_gamepad_actions(actions);
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn bind_default_gamepad_actions(actions: &mut ActionMap) -> () {
+        actions.bind(ORBIT_LEFT, InputBinding::GamepadAxis { axis: GamepadAxis::LeftStickX, positive: false });
+        actions.bind(ORBIT_RIGHT, InputBinding::GamepadAxis { axis: GamepadAxis::LeftStickX, positive: true });
+        actions.bind(ORBIT_UP, InputBinding::GamepadAxis { axis: GamepadAxis::LeftStickY, positive: true });
+        actions.bind(ORBIT_DOWN, InputBinding::GamepadAxis { axis: GamepadAxis::LeftStickY, positive: false });
+        actions.bind(ZOOM_IN, InputBinding::GamepadAxis { axis: GamepadAxis::RightStickY, positive: true });
+        actions.bind(ZOOM_OUT, InputBinding::GamepadAxis { axis: GamepadAxis::RightStickY, positive: false });
+    }
+
+    pub fn position(&self) -> glm::DVec3 {
+        let offset = glm::vec3(
+            self.pitch.cos() * self.yaw.cos(),
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin()
+        );
+
+        self.target + offset * self.distance
+    }
+}
+
+impl CameraController for OrbitCameraController {
+    fn update(&mut self, frame_info: &FrameInfo, input: &InputState, camera: &mut Camera) -> Result<()> {
+        let delta_time = frame_info.current_frame_delta_time.as_secs_f64();
+
+        let mouse_orbit_active = self.orbit_button.map(|button| input.is_mouse_button_held(button)).unwrap_or(true);
+        if mouse_orbit_active {
+            let mouse_delta = input.mouse_delta();
+            self.yaw -= mouse_delta.x * self.mouse_sensitivity;
+            self.pitch += mouse_delta.y * self.mouse_sensitivity;
+        }
+
+        self.yaw += input.action_axis(ORBIT_LEFT, ORBIT_RIGHT) as f64 * self.orbit_speed * delta_time;
+        self.pitch += input.action_axis(ORBIT_DOWN, ORBIT_UP) as f64 * self.orbit_speed * delta_time;
+
+        let max_pitch = MAX_PITCH_DEGREES.to_radians();
+        self.pitch = self.pitch.clamp(-max_pitch, max_pitch);
+
+        //Zoom is multiplicative so it feels the same close to the target and far away from it
+        let zoom = input.scroll_delta().y as f64 + input.action_axis(ZOOM_OUT, ZOOM_IN) as f64 * delta_time * 10.0;
+        self.distance = (self.distance * (1.0 - zoom * self.zoom_speed)).clamp(self.min_distance, self.max_distance);
+
+        camera.transform.pos = self.position();
+        camera.look_at(self.target);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,333 @@
+use super::{GameComponent};
+
+use std::{
+    sync::{Arc}
+};
+use anyhow::{anyhow, Result};
+use nalgebra_glm as glm;
+
+use crate::{
+    frame_info::{FrameInfo},
+    input::{InputState},
+    game::{
+        can_be_enabled::{CanBeEnabled},
+        transform::{Transform}
+    },
+    resources::{Skeleton, AnimationClip, JointPose, AnimationRef, AnimationProperties, ResourceLoader, LoadStatus},
+    shader_input::uniform_buffer_object::{MAX_JOINTS}
+};
+
+#[derive(Debug, Clone)]
+struct AnimationLayer {
+    clip: usize,
+    //Seconds
+    time: f32,
+    weight: f32,
+    target_weight: f32,
+    //Weight per second. Layers fading to 0 are removed once they get there
+    fade_speed: f32
+}
+
+//Skins the models of every component on the same game object, which need a skinned vertex type and shader like shader_input::standard_skinned and "standard_skinned"
+#[derive(Debug)]
+pub struct AnimatorComponent {
+    enabled: bool,
+    is_loaded: bool,
+    gltf_path: Option<&'static str>,
+    animation: Option<AnimationRef>,
+    //Played as soon as the clips are loaded
+    autoplay: Option<String>,
+
+    skeleton: Arc<Skeleton>,
+    clips: Vec<Arc<AnimationClip>>,
+    layers: Vec<AnimationLayer>,
+
+    //Multiplies the frame's delta time
+    pub speed: f32,
+    //Clips wrap around instead of holding their last pose
+    pub looping: bool,
+
+    poses: Vec<JointPose>,
+    clip_poses: Vec<JointPose>,
+    palette: Vec<glm::Mat4>,
+    previous_palette: Vec<glm::Mat4>,
+    has_ticked: bool
+}
+
+impl AnimatorComponent {
+    pub fn new(skeleton: Arc<Skeleton>, clips: Vec<Arc<AnimationClip>>) -> Self {
+        let mut animator = Self::new_unloaded(None);
+        animator.set_animation(skeleton, clips);
+        animator.is_loaded = true;
+        animator
+    }
+
+    //Loads the first skin in the file and all of its animations on the resource loader's workers. Until then the models are drawn in their bind pose
+    pub fn from_gltf(path: &'static str) -> Self {
+        Self::new_unloaded(Some(path))
+    }
+
+    fn new_unloaded(gltf_path: Option<&'static str>) -> Self {
+        //Identity matrices leave every vertex where the bind pose put it, whichever joints it uses
+        let bind_palette = vec![glm::Mat4::identity(); MAX_JOINTS];
+
+        Self {
+            enabled: true,
+            is_loaded: false,
+            gltf_path,
+            animation: None,
+            autoplay: None,
+
+            skeleton: Default::default(),
+            clips: vec![],
+            layers: vec![],
+
+            speed: 1.0,
+            looping: true,
+
+            poses: vec![],
+            clip_poses: vec![],
+            previous_palette: bind_palette.clone(),
+            palette: bind_palette,
+            has_ticked: false
+        }
+    }
+
+    pub fn with_autoplay(mut self, name: &str) -> Result<Self> {
+        self.autoplay = Some(name.to_owned());
+        if self.is_loaded {
+            self.play(name)?;
+        }
+
+        Ok(self)
+    }
+
+    fn set_animation(&mut self, skeleton: Arc<Skeleton>, clips: Vec<Arc<AnimationClip>>) -> () {
+        self.poses = skeleton.rest_poses();
+        self.clip_poses = self.poses.clone();
+        skeleton.compute_palette(&self.poses, &mut self.palette);
+        self.previous_palette.clone_from(&self.palette);
+        self.has_ticked = false;
+        self.layers.clear();
+
+        self.skeleton = skeleton;
+        self.clips = clips;
+    }
+
+    //Animators made from a glTF file have no skeleton or clips until this is true
+    pub fn is_loaded(&self) -> bool {
+        self.is_loaded
+    }
+
+    pub fn skeleton(&self) -> &Skeleton {
+        &self.skeleton
+    }
+
+    pub fn clips(&self) -> &[Arc<AnimationClip>] {
+        &self.clips
+    }
+
+    pub fn clip_index(&self, name: &str) -> Option<usize> {
+        self.clips.iter().position(|clip| clip.name.as_deref() == Some(name))
+    }
+
+    fn find_clip(&self, name: &str) -> Result<usize> {
+        self.clip_index(name).ok_or_else(|| anyhow!("No animation clip named '{}'", name))
+    }
+
+    //Plays the clip from the start, stopping all others
+    pub fn play(&mut self, name: &str) -> Result<()> {
+        self.cross_fade(name, 0.0)
+    }
+
+    //Fades the clip in from the start while fading all others out, over the duration in seconds
+    pub fn cross_fade(&mut self, name: &str, duration: f32) -> Result<()> {
+        let clip = self.find_clip(name)?;
+        let fade_speed = if duration > 0.0 { 1.0 / duration } else { 0.0 };
+
+        for layer in self.layers.iter_mut() {
+            layer.target_weight = 0.0;
+            layer.fade_speed = fade_speed;
+        }
+        self.layers.retain(|layer| layer.clip != clip);
+        self.layers.push(AnimationLayer {
+            clip,
+            time: 0.0,
+            weight: if duration > 0.0 { 0.0 } else { 1.0 },
+            target_weight: 1.0,
+            fade_speed
+        });
+        if duration <= 0.0 {
+            self.layers.retain(|layer| layer.target_weight > 0.0);
+        }
+
+        Ok(())
+    }
+
+    //Blends the clip in at a fixed weight alongside the others, starting it if it isn't playing. Weights adding up to less than 1 leave the rest to the rest pose. 0 stops the clip
+    pub fn set_clip_weight(&mut self, name: &str, weight: f32) -> Result<()> {
+        let clip = self.find_clip(name)?;
+        let weight = weight.max(0.0);
+
+        match self.layers.iter_mut().find(|layer| layer.clip == clip) {
+            Some(layer) => {
+                layer.weight = weight;
+                layer.target_weight = weight;
+            },
+            None => self.layers.push(AnimationLayer {
+                clip,
+                time: 0.0,
+                weight,
+                target_weight: weight,
+                fade_speed: 0.0
+            })
+        }
+        self.layers.retain(|layer| layer.target_weight > 0.0 || layer.weight > 0.0);
+
+        Ok(())
+    }
+
+    //Back to the rest pose
+    pub fn stop(&mut self) -> () {
+        self.layers.clear();
+    }
+
+    pub fn is_playing(&self, name: &str) -> bool {
+        self.clip_index(name).map_or(false, |clip| self.layers.iter().any(|layer| layer.clip == clip && layer.target_weight > 0.0))
+    }
+
+    fn advance_layers(&mut self, delta_time: f32) -> () {
+        for layer in self.layers.iter_mut() {
+            let duration = self.clips[layer.clip].duration;
+            layer.time += delta_time;
+            if self.looping && duration > 0.0 {
+                layer.time = layer.time.rem_euclid(duration);
+            } else {
+                layer.time = layer.time.clamp(0.0, duration);
+            }
+
+            let max_change = layer.fade_speed * delta_time.abs();
+            if layer.weight < layer.target_weight {
+                layer.weight = (layer.weight + max_change).min(layer.target_weight);
+            } else {
+                layer.weight = (layer.weight - max_change).max(layer.target_weight);
+            }
+        }
+
+        self.layers.retain(|layer| layer.target_weight > 0.0 || layer.weight > 0.0);
+    }
+
+    //Weighted sum of each layer's pose, with whatever weight the layers leave over going to the rest pose
+    fn blend_poses(&mut self) -> () {
+        let total_weight = self.layers.iter().map(|layer| layer.weight).sum::<f32>();
+        let normalization = 1.0 / total_weight.max(1.0);
+        let rest_weight = (1.0 - total_weight).max(0.0);
+
+        for (pose, joint) in self.poses.iter_mut().zip(self.skeleton.joints.iter()) {
+            *pose = JointPose {
+                translation: joint.rest_pose.translation * rest_weight,
+                rotation: joint.rest_pose.rotation * rest_weight,
+                scale: joint.rest_pose.scale * rest_weight
+            };
+        }
+
+        for layer in self.layers.iter() {
+            let weight = layer.weight * normalization;
+            if weight <= 0.0 {
+                continue;
+            }
+
+            for (clip_pose, joint) in self.clip_poses.iter_mut().zip(self.skeleton.joints.iter()) {
+                *clip_pose = joint.rest_pose;
+            }
+            self.clips[layer.clip].sample(layer.time, &mut self.clip_poses);
+
+            for (pose, clip_pose) in self.poses.iter_mut().zip(self.clip_poses.iter()) {
+                pose.translation += clip_pose.translation * weight;
+                pose.scale += clip_pose.scale * weight;
+                //q and -q are the same rotation, so line them up before summing
+                let sign = if pose.rotation.coords.dot(&clip_pose.rotation.coords) < 0.0 { -1.0 } else { 1.0 };
+                pose.rotation += clip_pose.rotation * (weight * sign);
+            }
+        }
+
+        for pose in self.poses.iter_mut() {
+            pose.rotation = glm::quat_normalize(&pose.rotation);
+        }
+    }
+}
+
+impl CanBeEnabled for AnimatorComponent {
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    fn set_enabled(&mut self, enabled: bool) -> () {
+        self.enabled = enabled;
+    }
+}
+
+impl GameComponent for AnimatorComponent {
+    fn load_and_unload(&mut self, resource_loader: &mut ResourceLoader) -> Result<()> {
+        let gltf_path = match self.gltf_path {
+            Some(gltf_path) if !self.is_loaded => gltf_path,
+            _ => return Ok(())
+        };
+
+        let animation = match self.animation {
+            Some(animation) => animation,
+            None => {
+                let animation_props = AnimationProperties {
+                    gltf_path: Some(gltf_path.to_owned()),
+                    ..Default::default()
+                };
+                let animation = resource_loader.get_or_load_animation(&animation_props)?;
+                self.animation = Some(animation);
+                animation
+            }
+        };
+
+        //A failed load has already been logged, and leaves the models in their bind pose
+        if animation.load_status(resource_loader) == LoadStatus::Loaded {
+            let animation_set = resource_loader.get_animation_set(animation).unwrap();
+            self.set_animation(animation_set.skeleton.clone(), animation_set.clips.clone());
+            self.is_loaded = true;
+
+            if let Some(autoplay) = self.autoplay.clone() {
+                self.play(&autoplay)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn unload(&mut self, resource_loader: &mut ResourceLoader) -> () {
+        if let Some(animation) = self.animation.take() {
+            resource_loader.unload_animation(animation);
+            self.is_loaded = false;
+        }
+    }
+
+    fn tick(&mut self, frame_info: &FrameInfo, _input: &InputState, _transform: &mut Transform) -> Result<()> {
+        if !self.is_loaded {
+            return Ok(());
+        }
+
+        self.advance_layers(frame_info.current_frame_delta_time.as_secs_f32() * self.speed);
+        self.blend_poses();
+
+        //The depth and motion pass needs last frame's palette for motion vectors
+        std::mem::swap(&mut self.palette, &mut self.previous_palette);
+        self.skeleton.compute_palette(&self.poses, &mut self.palette);
+        //No motion on the first frame, rather than motion from the rest pose
+        if !self.has_ticked {
+            self.previous_palette.clone_from(&self.palette);
+            self.has_ticked = true;
+        }
+
+        Ok(())
+    }
+
+    fn joint_palette(&self) -> Option<(&[glm::Mat4], &[glm::Mat4])> {
+        Some((&self.palette, &self.previous_palette))
+    }
+}
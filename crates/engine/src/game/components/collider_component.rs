@@ -0,0 +1,42 @@
+use super::{GameComponent};
+
+use crate::{
+    game::{
+        can_be_enabled::{CanBeEnabled}
+    },
+    physics::{Collider}
+};
+
+//Static unless the game object also has a RigidBodyComponent
+#[derive(Debug, Clone)]
+pub struct ColliderComponent {
+    enabled: bool,
+    pub collider: Collider
+}
+
+impl ColliderComponent {
+    pub fn new(collider: Collider) -> Self {
+        Self {
+            enabled: true,
+            collider
+        }
+    }
+}
+
+impl CanBeEnabled for ColliderComponent {
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    fn set_enabled(&mut self, enabled: bool) -> () {
+        self.enabled = enabled;
+    }
+}
+
+impl GameComponent for ColliderComponent {
+    fn collider(&self) -> Option<&Collider> {
+        Some(&self.collider)
+    }
+    fn collider_mut(&mut self) -> Option<&mut Collider> {
+        Some(&mut self.collider)
+    }
+}
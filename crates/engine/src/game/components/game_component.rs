@@ -4,15 +4,22 @@ use anyhow::{Result};
 
 use crate::{
     frame_info::{FrameInfo},
+    input::{InputState},
     game::{
         can_be_enabled::{CanBeEnabled},
         transform::{Transform}
     },
-    resources::{SingleFrameRenderInfo, ResourceLoader}
+    physics::{Collider, RigidBody},
+    resources::{SingleFrameRenderInfo, ResourceLoader, ModelRef}
 };
 
 pub trait GameComponent : Debug + CanBeEnabled {
-    fn tick(&mut self, _frame_info: &FrameInfo, _transform: &mut Transform) -> Result<()> {
+    fn tick(&mut self, _frame_info: &FrameInfo, _input: &InputState, _transform: &mut Transform) -> Result<()> {
+        Ok(())
+    }
+
+    //Runs at frame_info.fixed_delta_time intervals, independent of the frame rate. Simulation belongs here so it behaves the same at any frame rate
+    fn fixed_tick(&mut self, _frame_info: &FrameInfo, _input: &InputState, _transform: &mut Transform) -> Result<()> {
         Ok(())
     }
 
@@ -25,4 +32,30 @@ pub trait GameComponent : Debug + CanBeEnabled {
     fn create_frame_render_info(&self, _frame_info: &mut SingleFrameRenderInfo, _viewmodel: &glm::Mat4, _previous_viewmodel: Option<&glm::Mat4>) -> Result<()> {
         Ok(())
     }
+
+    //The model Scene::raycast tests against, placed by the game object's transform. None isn't hit by raycasts
+    fn raycast_model(&self) -> Option<ModelRef> {
+        None
+    }
+
+    //The current and previous frame's joint palettes, which skin the models of every component on the game object. See Skeleton::compute_palette
+    fn joint_palette(&self) -> Option<(&[glm::Mat4], &[glm::Mat4])> {
+        None
+    }
+
+    //The game object's collider. Mesh colliders are built from the raycast model once it's loaded
+    fn collider(&self) -> Option<&Collider> {
+        None
+    }
+    fn collider_mut(&mut self) -> Option<&mut Collider> {
+        None
+    }
+
+    //Game objects with a collider and no rigid body are static
+    fn rigid_body(&self) -> Option<&RigidBody> {
+        None
+    }
+    fn rigid_body_mut(&mut self) -> Option<&mut RigidBody> {
+        None
+    }
 }
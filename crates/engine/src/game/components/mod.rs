@@ -1,5 +1,11 @@
+pub mod animator_component;
+pub mod collider_component;
 pub mod game_component;
 pub mod render_model_component;
+pub mod rigid_body_component;
 
+pub use animator_component::{AnimatorComponent};
+pub use collider_component::{ColliderComponent};
 pub use game_component::{GameComponent};
 pub use render_model_component::{RenderModelComponent};
+pub use rigid_body_component::{RigidBodyComponent};
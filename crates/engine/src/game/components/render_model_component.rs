@@ -8,37 +8,97 @@ use crate::{
     game::{
         can_be_enabled::{CanBeEnabled}
     },
-    resources::{CanBeVertexBufferType, CanBeInstVertexBufferType, SingleFrameRenderInfo, MaterialRef, ModelRef, MaterialProperties, ModelProperties, ResourceLoader},
+    resources::{CanBeVertexBufferType, CanBeInstVertexBufferType, SingleFrameRenderInfo, MaterialRef, ModelRef, MaterialProperties, ModelProperties, ResourceLoader, GltfAssetRef, GltfAssetProperties, GltfMaterial, GltfTextureInfo, TranslucentBlendMode, LoadStatus},
     shader_input::empty_vertex::{EmptyVertex}
 };
 
+//One glTF primitive, drawn with the material the file gives it
+#[derive(Debug, Copy, Clone)]
+struct RenderModelPart {
+    material: MaterialRef,
+    model: ModelRef,
+    //Where the primitive's node places it on the game object
+    transform: glm::Mat4,
+    is_opaque: bool
+}
+
 #[derive(Debug)]
 pub struct RenderModelComponent<TVert, TInstVert = EmptyVertex> where TVert : CanBeVertexBufferType + 'static, TInstVert : CanBeInstVertexBufferType {
     enabled: bool,
     is_loaded: bool,
     path: &'static str,
+    base_color_texture: Option<&'static str>,
+    normal_texture: Option<&'static str>,
+    occlusion_roughness_metallic_texture: Option<&'static str>,
+    shader_name: &'static str,
+    //None for opaque. glTF files with blended materials are drawn as translucent with alpha blending unless this is set
+    translucent_blend_mode: Option<TranslucentBlendMode>,
+    is_opaque: bool,
 
     phantom_vert: PhantomData<TVert>,
     phantom_inst_vert: PhantomData<TInstVert>,
 
+    //glTF files are read on a worker before their materials and models are known
+    gltf_asset: Option<GltfAssetRef>,
     material: Option<MaterialRef>,
-    model: Option<ModelRef>
+    //For glTF files with parts, this is only raycast, and isn't drawn
+    model: Option<ModelRef>,
+    parts: Vec<RenderModelPart>
 }
 
 impl<TVert, TInstVert> RenderModelComponent<TVert, TInstVert> where TVert : CanBeVertexBufferType + 'static, TInstVert : CanBeInstVertexBufferType {
     pub fn new(path: &'static str) -> Result<Self> {
+        Self::new_with_textures(path, None, None, None)
+    }
+    pub fn new_with_textures(path: &'static str, base_color_texture: Option<&'static str>, normal_texture: Option<&'static str>, occlusion_roughness_metallic_texture: Option<&'static str>) -> Result<Self> {
         Ok(Self {
             enabled: true,
             is_loaded: false,
             path: path,
+            base_color_texture,
+            normal_texture,
+            occlusion_roughness_metallic_texture,
+            shader_name: "standard",
+            translucent_blend_mode: None,
+            is_opaque: true,
 
             phantom_vert: Default::default(),
             phantom_inst_vert: Default::default(),
 
+            gltf_asset: None,
             model: None,
-            material: None
+            material: None,
+            parts: vec![]
         })
     }
+
+    //Skinned models need a shader matching their vertex type, like "standard_skinned"
+    pub fn with_shader_name(mut self, shader_name: &'static str) -> Self {
+        self.shader_name = shader_name;
+        self
+    }
+
+    pub fn with_translucent_blend_mode(mut self, blend_mode: TranslucentBlendMode) -> Self {
+        self.translucent_blend_mode = Some(blend_mode);
+        self
+    }
+
+    //Textures embedded in the glTF file's buffers aren't loaded, and are left as the defaults
+    fn create_gltf_material_properties(&self, material: Option<&GltfMaterial>) -> MaterialProperties<TVert, TInstVert> {
+        let texture_path = |texture: Option<&GltfTextureInfo>| texture.and_then(|t| t.path.as_ref()).map(|p| p.to_string_lossy().into_owned());
+
+        //Primitives without a material get glTF's default material, which is opaque, white, and untextured
+        MaterialProperties::<TVert, TInstVert> {
+            is_opaque: material.map_or(true, |m| m.is_opaque()),
+            translucent_blend_mode: self.translucent_blend_mode.unwrap_or_default(),
+            base_color_texture: texture_path(material.and_then(|m| m.base_color_texture.as_ref())),
+            normal_texture: texture_path(material.and_then(|m| m.normal_texture.as_ref())),
+            occlusion_roughness_metallic_texture: texture_path(material.and_then(|m| m.occlusion_roughness_metallic_texture())),
+            factors: material.map(|m| m.material_uniform()).unwrap_or_default(),
+            shader_name: self.shader_name,
+            ..Default::default()
+        }
+    }
 }
 
 impl<TVert, TInstVert> CanBeEnabled for RenderModelComponent<TVert, TInstVert> where TVert : CanBeVertexBufferType + 'static, TInstVert : CanBeInstVertexBufferType {
@@ -56,14 +116,79 @@ impl<TVert, TInstVert> GameComponent for RenderModelComponent<TVert, TInstVert>
             return Ok(());
         }
 
-        let mat_props = MaterialProperties::<TVert, TInstVert> {
-            ..Default::default()
+        let is_gltf = self.path.ends_with(".gltf") || self.path.ends_with(".glb");
+        let gltf_asset = if is_gltf {
+            let gltf_asset = match self.gltf_asset {
+                Some(gltf_asset) => gltf_asset,
+                None => {
+                    let gltf_asset_props = GltfAssetProperties {
+                        gltf_path: Some(self.path.to_owned())
+                    };
+                    let gltf_asset = resource_loader.get_or_load_gltf_asset(&gltf_asset_props)?;
+                    self.gltf_asset = Some(gltf_asset);
+                    gltf_asset
+                }
+            };
+
+            match gltf_asset.load_status(resource_loader) {
+                LoadStatus::Loaded => resource_loader.get_gltf_asset(gltf_asset),
+                //A failed load has already been logged, and leaves nothing to draw
+                LoadStatus::Failed => {
+                    self.is_loaded = true;
+                    return Ok(());
+                },
+                _ => return Ok(())
+            }
+        } else {
+            None
         };
 
-        self.material = Some(resource_loader.get_or_load_material(&mat_props)?);
+        let has_texture_overrides = self.base_color_texture.is_some() || self.normal_texture.is_some() || self.occlusion_roughness_metallic_texture.is_some();
+
+        match gltf_asset.as_deref() {
+            Some(gltf_asset) if !has_texture_overrides => {
+                for primitive in gltf_asset.primitives.iter() {
+                    let mat_props = self.create_gltf_material_properties(gltf_asset.material_for(primitive));
+                    let model_props = ModelProperties::<TVert> {
+                        gltf_path: Some(self.path.to_owned()),
+                        gltf_primitive: Some((primitive.mesh_index, primitive.primitive_index)),
+                        ..Default::default()
+                    };
+
+                    self.parts.push(RenderModelPart {
+                        material: resource_loader.get_or_load_material(&mat_props)?,
+                        model: resource_loader.get_or_load_model(&model_props)?,
+                        transform: primitive.transform,
+                        is_opaque: mat_props.is_opaque
+                    });
+                }
+            },
+            _ => {
+                let translucent_blend_mode = match self.translucent_blend_mode {
+                    Some(blend_mode) => Some(blend_mode),
+                    None if gltf_asset.as_deref().map_or(false, |gltf_asset| !gltf_asset.is_opaque()) => Some(TranslucentBlendMode::Alpha),
+                    None => None
+                };
+                self.is_opaque = translucent_blend_mode.is_none();
+
+                let mat_props = MaterialProperties::<TVert, TInstVert> {
+                    is_opaque: self.is_opaque,
+                    translucent_blend_mode: translucent_blend_mode.unwrap_or_default(),
+                    base_color_texture: self.base_color_texture.map(|t| t.to_owned()),
+                    normal_texture: self.normal_texture.map(|t| t.to_owned()),
+                    occlusion_roughness_metallic_texture: self.occlusion_roughness_metallic_texture.map(|t| t.to_owned()),
+                    shader_name: self.shader_name,
+                    ..Default::default()
+                };
+
+                self.material = Some(resource_loader.get_or_load_material(&mat_props)?);
+            }
+        }
 
+        //Raycasts and mesh colliders test against the whole file merged into one mesh
         let model_props = ModelProperties::<TVert> {
-            obj_path: Some(self.path.to_owned()),
+            obj_path: if is_gltf { None } else { Some(self.path.to_owned()) },
+            gltf_path: if is_gltf { Some(self.path.to_owned()) } else { None },
             ..Default::default()
         };
 
@@ -78,6 +203,10 @@ impl<TVert, TInstVert> GameComponent for RenderModelComponent<TVert, TInstVert>
     }
 
     fn unload(&mut self, resource_loader: &mut ResourceLoader) -> () {
+        if let Some(gltf_asset) = self.gltf_asset.take() {
+            resource_loader.unload_gltf_asset(gltf_asset);
+        }
+
         if self.is_loaded {
             if let Some(model) = self.model.take() {
                 resource_loader.unload_model(model);
@@ -87,15 +216,30 @@ impl<TVert, TInstVert> GameComponent for RenderModelComponent<TVert, TInstVert>
                 resource_loader.unload_material(material);
             }
 
+            for part in self.parts.drain(..) {
+                resource_loader.unload_model(part.model);
+                resource_loader.unload_material(part.material);
+            }
+
             self.is_loaded = false;
         }
     }
 
     fn create_frame_render_info(&self, frame_info: &mut SingleFrameRenderInfo, viewmodel: &glm::Mat4, previous_viewmodel: Option<&glm::Mat4>) -> Result<()> {
         if let (Some(material), Some(model)) = (self.material, self.model) {
-            model.create_frame_render_info(frame_info, material, false, true, viewmodel, previous_viewmodel)?;
+            model.create_frame_render_info(frame_info, material, false, self.is_opaque, viewmodel, previous_viewmodel)?;
+        }
+
+        for part in self.parts.iter() {
+            let part_viewmodel = viewmodel * part.transform;
+            let part_previous_viewmodel = previous_viewmodel.map(|previous_viewmodel| previous_viewmodel * part.transform);
+            part.model.create_frame_render_info(frame_info, part.material, false, part.is_opaque, &part_viewmodel, part_previous_viewmodel.as_ref())?;
         }
 
         Ok(())
     }
+
+    fn raycast_model(&self) -> Option<ModelRef> {
+        self.model
+    }
 }
@@ -0,0 +1,62 @@
+use super::{GameComponent};
+
+use anyhow::{Result};
+use nalgebra_glm as glm;
+
+use crate::{
+    frame_info::{FrameInfo},
+    input::{InputState},
+    game::{
+        can_be_enabled::{CanBeEnabled},
+        transform::{Transform}
+    },
+    physics::{RigidBody, RigidBodyKind}
+};
+
+//Needs a ColliderComponent on the same game object to be simulated. Dynamic bodies are simulated in world space and write their pose straight into the transform, so they belong on top level game objects
+#[derive(Debug, Clone)]
+pub struct RigidBodyComponent {
+    enabled: bool,
+    pub rigid_body: RigidBody
+}
+
+impl RigidBodyComponent {
+    pub fn new(rigid_body: RigidBody) -> Self {
+        Self {
+            enabled: true,
+            rigid_body
+        }
+    }
+}
+
+impl CanBeEnabled for RigidBodyComponent {
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    fn set_enabled(&mut self, enabled: bool) -> () {
+        self.enabled = enabled;
+    }
+}
+
+impl GameComponent for RigidBodyComponent {
+    //Scene::fixed_tick steps the simulation, so this only interpolates between the last two simulated poses
+    fn tick(&mut self, frame_info: &FrameInfo, _input: &InputState, transform: &mut Transform) -> Result<()> {
+        if self.rigid_body.kind != RigidBodyKind::Dynamic {
+            return Ok(());
+        }
+
+        if let Some(pose) = self.rigid_body.interpolated_pose(frame_info.fixed_update_alpha) {
+            transform.pos = glm::convert::<glm::Vec3, glm::DVec3>(pose.position);
+            transform.orient = pose.orientation;
+        }
+
+        Ok(())
+    }
+
+    fn rigid_body(&self) -> Option<&RigidBody> {
+        Some(&self.rigid_body)
+    }
+    fn rigid_body_mut(&mut self) -> Option<&mut RigidBody> {
+        Some(&mut self.rigid_body)
+    }
+}
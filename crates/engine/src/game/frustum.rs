@@ -0,0 +1,135 @@
+use nalgebra_glm as glm;
+
+use crate::resources::{Bounds};
+
+//Six inward facing planes as (normal, distance), in whatever space the matrix it was made from transforms out of
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Frustum {
+    planes: [glm::Vec4; 6]
+}
+
+impl Frustum {
+    //Works for any projection with Vulkan's 0 to 1 clip space depth. Pass a projection alone for a view space frustum, or projection * view for world space
+    pub fn from_matrix(matrix: &glm::Mat4) -> Self {
+        let x = glm::row(matrix, 0);
+        let y = glm::row(matrix, 1);
+        let z = glm::row(matrix, 2);
+        let w = glm::row(matrix, 3);
+
+        let mut planes = [w + x, w - x, w + y, w - y, z, w - z];
+        for plane in planes.iter_mut() {
+            let length = glm::length(&plane.xyz());
+            if length > 0.0 {
+                *plane /= length;
+            }
+        }
+
+        Self {
+            planes
+        }
+    }
+
+    fn distance_to(plane: &glm::Vec4, point: &glm::Vec3) -> f32 {
+        glm::dot(&plane.xyz(), point) + plane.w
+    }
+
+    pub fn intersects_sphere(&self, center: &glm::Vec3, radius: f32) -> bool {
+        self.planes.iter().all(|plane| Self::distance_to(plane, center) >= -radius)
+    }
+
+    pub fn intersects_aabb(&self, min: &glm::Vec3, max: &glm::Vec3) -> bool {
+        //Only the corner furthest along each plane's normal needs to be inside it
+        self.planes.iter().all(|plane| {
+            let corner = glm::vec3(
+                if plane.x >= 0.0 { max.x } else { min.x },
+                if plane.y >= 0.0 { max.y } else { min.y },
+                if plane.z >= 0.0 { max.z } else { min.z }
+            );
+            Self::distance_to(plane, &corner) >= 0.0
+        })
+    }
+
+    //The sphere is checked first since it's cheaper and rejects most things that are far outside
+    pub fn intersects_bounds(&self, bounds: &Bounds) -> bool {
+        self.intersects_sphere(&bounds.sphere_center, bounds.sphere_radius) && self.intersects_aabb(&bounds.min, &bounds.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Set up like Camera's perspective projection, looking down +Z in view space with Y flipped for Vulkan. A 90 degree fov makes the sides 45 degree slopes
+    fn view_frustum() -> Frustum {
+        let mut projection = glm::perspective_lh_zo(1.0, std::f32::consts::FRAC_PI_2, 0.1, 100.0);
+        projection[(1, 1)] *= -1.0;
+        Frustum::from_matrix(&projection)
+    }
+
+    fn assert_near(a: &glm::Vec4, b: &glm::Vec4) -> () {
+        assert!(glm::distance(a, b) < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn planes_are_normalized_and_face_inward() {
+        let frustum = view_frustum();
+        let side = std::f32::consts::FRAC_1_SQRT_2;
+
+        assert_near(&frustum.planes[0], &glm::vec4(side, 0.0, side, 0.0));
+        assert_near(&frustum.planes[1], &glm::vec4(-side, 0.0, side, 0.0));
+        //The flipped Y swaps which of these is the top, but together they still bound both sides
+        assert_near(&frustum.planes[2], &glm::vec4(0.0, -side, side, 0.0));
+        assert_near(&frustum.planes[3], &glm::vec4(0.0, side, side, 0.0));
+        assert_near(&frustum.planes[4], &glm::vec4(0.0, 0.0, 1.0, -0.1));
+        assert_near(&frustum.planes[5], &glm::vec4(0.0, 0.0, -1.0, 100.0));
+    }
+
+    #[test]
+    fn points() {
+        let frustum = view_frustum();
+        let contains = |point: glm::Vec3| frustum.intersects_sphere(&point, 0.0);
+
+        assert!(contains(glm::vec3(0.0, 0.0, 10.0)));
+        assert!(contains(glm::vec3(4.0, -9.0, 10.0)));
+        assert!(contains(glm::vec3(0.0, 9.0, 10.0)));
+
+        assert!(!contains(glm::vec3(0.0, 0.0, 0.05)));
+        assert!(!contains(glm::vec3(0.0, 0.0, -10.0)));
+        assert!(!contains(glm::vec3(0.0, 0.0, 101.0)));
+        assert!(!contains(glm::vec3(11.0, 0.0, 10.0)));
+        assert!(!contains(glm::vec3(-11.0, 0.0, 10.0)));
+        assert!(!contains(glm::vec3(0.0, 11.0, 10.0)));
+        assert!(!contains(glm::vec3(0.0, -11.0, 10.0)));
+    }
+
+    #[test]
+    fn spheres() {
+        let frustum = view_frustum();
+
+        assert!(frustum.intersects_sphere(&glm::vec3(0.0, 0.0, 10.0), 1.0));
+        assert!(!frustum.intersects_sphere(&glm::vec3(0.0, 0.0, -5.0), 1.0));
+        assert!(!frustum.intersects_sphere(&glm::vec3(0.0, 20.0, 10.0), 1.0));
+
+        //Straddling the near plane, a side, and the far plane
+        assert!(frustum.intersects_sphere(&glm::vec3(0.0, 0.0, 0.0), 0.5));
+        assert!(frustum.intersects_sphere(&glm::vec3(10.5, 0.0, 10.0), 1.0));
+        assert!(frustum.intersects_sphere(&glm::vec3(0.0, -10.5, 10.0), 1.0));
+        assert!(frustum.intersects_sphere(&glm::vec3(0.0, 0.0, 100.5), 1.0));
+    }
+
+    #[test]
+    fn boxes() {
+        let frustum = view_frustum();
+
+        assert!(frustum.intersects_aabb(&glm::vec3(-1.0, -1.0, 9.0), &glm::vec3(1.0, 1.0, 11.0)));
+        assert!(!frustum.intersects_aabb(&glm::vec3(-1.0, -1.0, -11.0), &glm::vec3(1.0, 1.0, -9.0)));
+        assert!(!frustum.intersects_aabb(&glm::vec3(-1.0, 20.0, 9.0), &glm::vec3(1.0, 22.0, 11.0)));
+        assert!(!frustum.intersects_aabb(&glm::vec3(-22.0, -1.0, 9.0), &glm::vec3(-20.0, 1.0, 11.0)));
+
+        //Straddling the near plane, a side, and the far plane
+        assert!(frustum.intersects_aabb(&glm::vec3(-1.0, -1.0, -1.0), &glm::vec3(1.0, 1.0, 1.0)));
+        assert!(frustum.intersects_aabb(&glm::vec3(-1.0, 9.0, 9.0), &glm::vec3(1.0, 12.0, 11.0)));
+        assert!(frustum.intersects_aabb(&glm::vec3(-12.0, -1.0, 9.0), &glm::vec3(-9.0, 1.0, 11.0)));
+        assert!(frustum.intersects_aabb(&glm::vec3(-1.0, -1.0, 99.0), &glm::vec3(1.0, 1.0, 101.0)));
+    }
+}
@@ -1,21 +1,48 @@
 use super::{
     components::{GameComponent},
     can_be_enabled::{CanBeEnabled},
-    transform::{Transform}
+    transform::{Transform},
+    raycast::{Ray, RaycastHit}
 };
 
+use std::{
+    sync::atomic::{AtomicU64, Ordering}
+};
 use nalgebra_glm as glm;
 use anyhow::{Result};
 
 use crate::{
     frame_info::{FrameInfo},
+    input::{InputState},
+    physics::{Collider, RigidBody, PhysicsBody},
     resources::{SingleFrameRenderInfo, ResourceLoader}
 };
 
+static NEXT_GAME_OBJECT_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct GameObjectId {
+    id: u64
+}
+
+impl GameObjectId {
+    fn next() -> Self {
+        Self {
+            id: NEXT_GAME_OBJECT_ID.fetch_add(1, Ordering::Relaxed)
+        }
+    }
+
+    pub fn get_id(&self) -> u64 {
+        self.id
+    }
+}
+
 #[derive(Debug)]
 pub struct GameObject {
+    id: GameObjectId,
     enabled: bool,
     components: Vec<Box<dyn GameComponent>>,
+    children: Vec<Box<GameObject>>,
     pub transform: Transform,
     previous_viewmodel: Option<glm::Mat4>
 }
@@ -23,8 +50,10 @@ pub struct GameObject {
 impl Default for GameObject {
     fn default() -> Self {
         Self {
+            id: GameObjectId::next(),
             enabled: true,
             components: Default::default(),
+            children: Default::default(),
             transform: Default::default(),
             previous_viewmodel: Default::default()
         }
@@ -45,16 +74,114 @@ impl GameObject {
         Self::default()
     }
 
+    pub fn id(&self) -> GameObjectId {
+        self.id
+    }
+
     pub fn add_component(&mut self, component: Box<dyn GameComponent>) -> Result<()> {
         self.components.push(component);
 
         Ok(())
     }
 
-    pub fn tick(&mut self, frame_info: &FrameInfo) -> Result<()> {
+    pub fn add_child(&mut self, child: Box<GameObject>) -> Result<GameObjectId> {
+        let id = child.id;
+        self.children.push(child);
+
+        Ok(id)
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = &GameObject> {
+        self.children.iter().map(|child| &**child)
+    }
+
+    pub fn children_mut(&mut self) -> impl Iterator<Item = &mut GameObject> {
+        self.children.iter_mut().map(|child| &mut **child)
+    }
+
+    //The first enabled collider among the components
+    pub fn collider(&self) -> Option<&Collider> {
+        self.components.iter().filter(|component| component.is_enabled()).find_map(|component| component.collider())
+    }
+
+    pub fn rigid_body(&self) -> Option<&RigidBody> {
+        self.components.iter().filter(|component| component.is_enabled()).find_map(|component| component.rigid_body())
+    }
+
+    pub fn rigid_body_mut(&mut self) -> Option<&mut RigidBody> {
+        self.components.iter_mut().filter(|component| component.is_enabled()).find_map(|component| component.rigid_body_mut())
+    }
+
+    //The first enabled component's current and previous joint palettes
+    pub fn joint_palette(&self) -> Option<(&[glm::Mat4], &[glm::Mat4])> {
+        self.components.iter().filter(|component| component.is_enabled()).find_map(|component| component.joint_palette())
+    }
+
+    pub fn find(&self, id: GameObjectId) -> Option<&GameObject> {
+        if self.id == id {
+            return Some(self);
+        }
+
+        self.children.iter().find_map(|child| child.find(id))
+    }
+
+    pub fn find_mut(&mut self, id: GameObjectId) -> Option<&mut GameObject> {
+        if self.id == id {
+            return Some(self);
+        }
+
+        self.children.iter_mut().find_map(|child| child.find_mut(id))
+    }
+
+    pub(crate) fn take_descendant(&mut self, id: GameObjectId) -> Option<Box<GameObject>> {
+        if let Some(idx) = self.children.iter().position(|child| child.id == id) {
+            return Some(self.children.remove(idx));
+        }
+
+        self.children.iter_mut().find_map(|child| child.take_descendant(id))
+    }
+
+    pub(crate) fn find_world_matrix(&self, id: GameObjectId, parent: &glm::DMat4) -> Result<Option<glm::DMat4>> {
+        let world = parent * self.transform.as_matrix()?;
+        if self.id == id {
+            return Ok(Some(world));
+        }
+
+        for child in self.children.iter() {
+            if let Some(matrix) = child.find_world_matrix(id, &world)? {
+                return Ok(Some(matrix));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn tick(&mut self, frame_info: &FrameInfo, input: &InputState) -> Result<()> {
         for component in self.components.iter_mut() {
             if component.is_enabled() {
-                component.tick(frame_info, &mut self.transform)?;
+                component.tick(frame_info, input, &mut self.transform)?;
+            }
+        }
+
+        for child in self.children.iter_mut() {
+            if child.is_enabled() {
+                child.tick(frame_info, input)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn fixed_tick(&mut self, frame_info: &FrameInfo, input: &InputState) -> Result<()> {
+        for component in self.components.iter_mut() {
+            if component.is_enabled() {
+                component.fixed_tick(frame_info, input, &mut self.transform)?;
+            }
+        }
+
+        for child in self.children.iter_mut() {
+            if child.is_enabled() {
+                child.fixed_tick(frame_info, input)?;
             }
         }
 
@@ -62,9 +189,37 @@ impl GameObject {
     }
 
     pub fn load_and_unload(&mut self, resource_loader: &mut ResourceLoader) -> Result<()> {
+        //Disabled components and children give their resources back until they're enabled again
         for component in self.components.iter_mut() {
             if component.is_enabled() {
                 component.load_and_unload(resource_loader)?;
+            } else {
+                component.unload(resource_loader);
+            }
+        }
+
+        //Mesh colliders use the raycast model, which won't have mesh data until it finishes loading
+        let needs_mesh = self.components.iter()
+            .filter_map(|component| component.collider())
+            .any(|collider| collider.shape.needs_mesh());
+        if needs_mesh {
+            let mesh_data = self.components.iter()
+                .filter(|component| component.is_enabled())
+                .find_map(|component| component.raycast_model())
+                .and_then(|model| resource_loader.get_mesh_data(model));
+
+            for component in self.components.iter_mut() {
+                if let Some(collider) = component.collider_mut().filter(|collider| collider.shape.needs_mesh()) {
+                    collider.set_mesh_data(mesh_data.clone());
+                }
+            }
+        }
+
+        for child in self.children.iter_mut() {
+            if child.is_enabled() {
+                child.load_and_unload(resource_loader)?;
+            } else {
+                child.unload(resource_loader);
             }
         }
 
@@ -74,27 +229,152 @@ impl GameObject {
     pub fn unload(&mut self, resource_loader: &mut ResourceLoader) -> () {
         for component in self.components.iter_mut() {
             component.unload(resource_loader);
+
+            if let Some(collider) = component.collider_mut() {
+                collider.set_mesh_data(None);
+            }
+        }
+
+        for child in self.children.iter_mut() {
+            child.unload(resource_loader);
         }
     }
 
-    pub fn create_frame_render_info(&self, frame_info: &mut SingleFrameRenderInfo, view: &glm::DMat4) -> Result<()> {
+    pub fn create_frame_render_info(&self, frame_info: &mut SingleFrameRenderInfo, parent_view: &glm::DMat4) -> Result<()> {
         let model = self.transform.as_matrix()?;
-        let viewmodel = glm::convert::<glm::DMat4, glm::Mat4>(view * model);
+        let view = parent_view * model;
+        let viewmodel = glm::convert::<glm::DMat4, glm::Mat4>(view);
 
+        let first_model = frame_info.models_to_render.len();
         for component in self.components.iter() {
             if component.is_enabled() {
                 component.create_frame_render_info(frame_info, &viewmodel, self.previous_viewmodel.as_ref())?;
             }
         }
 
+        //An animator skins every model the game object's components render
+        if let Some((palette, previous_palette)) = self.joint_palette() {
+            frame_info.skin_models(first_model, palette, previous_palette);
+        }
+
+        for child in self.children.iter() {
+            if child.is_enabled() {
+                child.create_frame_render_info(frame_info, &view)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    //Keeps the closest hit in closest_hit
+    pub(crate) fn raycast(&self, ray: &Ray, parent_world: &glm::DMat4, resource_loader: &ResourceLoader, closest_hit: &mut Option<RaycastHit>) -> Result<()> {
+        let world = parent_world * self.transform.as_matrix()?;
+        //The meshes are tested in model space, then hits are brought back into world space
+        let inverse_world = glm::inverse(&world);
+
+        for component in self.components.iter() {
+            if !component.is_enabled() {
+                continue;
+            }
+
+            let mesh_data = match component.raycast_model().and_then(|model| resource_loader.get_mesh_data(model)) {
+                Some(mesh_data) => mesh_data,
+                None => continue
+            };
+
+            let local_origin = glm::convert::<glm::DVec3, glm::Vec3>((inverse_world * glm::vec4(ray.origin.x, ray.origin.y, ray.origin.z, 1.0)).xyz());
+            let local_direction = glm::convert::<glm::DVec3, glm::Vec3>((inverse_world * glm::vec4(ray.direction.x, ray.direction.y, ray.direction.z, 0.0)).xyz());
+
+            if let Some((local_distance, local_normal)) = mesh_data.raycast(&local_origin, &local_direction) {
+                let local_position = glm::convert::<glm::Vec3, glm::DVec3>(local_origin + local_direction * local_distance);
+                let position = (world * glm::vec4(local_position.x, local_position.y, local_position.z, 1.0)).xyz();
+                let distance = glm::distance(&ray.origin, &position);
+
+                let is_closer = match closest_hit {
+                    Some(hit) => distance < hit.distance,
+                    None => true
+                };
+                if is_closer {
+                    let normal_matrix = glm::transpose(&glm::inverse(&glm::mat4_to_mat3(&world)));
+                    let local_normal = glm::convert::<glm::Vec3, glm::DVec3>(local_normal);
+
+                    *closest_hit = Some(RaycastHit {
+                        game_object: self.id,
+                        distance,
+                        position,
+                        normal: glm::normalize(&(normal_matrix * local_normal))
+                    });
+                }
+            }
+        }
+
+        for child in self.children.iter() {
+            if child.is_enabled() {
+                child.raycast(ray, &world, resource_loader, closest_hit)?;
+            }
+        }
+
         Ok(())
     }
 
-    pub fn end_frame(&mut self, view: &glm::DMat4) -> Result<()> {
+    //Only game objects with a collider that's ready take part. apply_physics_bodies walks the same objects in the same order
+    fn physics_body(&self, world: &glm::DMat4) -> Option<PhysicsBody> {
+        let collider = self.collider().filter(|collider| collider.is_ready())?;
+        Some(PhysicsBody::new(collider.clone(), self.rigid_body().copied(), world))
+    }
+
+    pub(crate) fn collect_physics_bodies(&self, parent_world: &glm::DMat4, bodies: &mut Vec<PhysicsBody>) -> Result<()> {
+        let world = parent_world * self.transform.as_matrix()?;
+        if let Some(body) = self.physics_body(&world) {
+            bodies.push(body);
+        }
+
+        for child in self.children.iter() {
+            if child.is_enabled() {
+                child.collect_physics_bodies(&world, bodies)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn apply_physics_bodies(&mut self, bodies: &[PhysicsBody], next_body: &mut usize) -> () {
+        if self.collider().filter(|collider| collider.is_ready()).is_some() {
+            let body = &bodies[*next_body];
+            *next_body += 1;
+
+            if let (Some(rigid_body), Some(simulated_body)) = (self.rigid_body_mut(), body.rigid_body.as_ref()) {
+                rigid_body.velocity = simulated_body.velocity;
+                rigid_body.angular_velocity = simulated_body.angular_velocity;
+                rigid_body.set_simulated_pose(body.pose);
+            }
+
+            //Kinematic bodies follow their transform, so only dynamic ones are moved here
+            if body.is_dynamic() {
+                self.transform.pos = glm::convert::<glm::Vec3, glm::DVec3>(body.pose.position);
+                self.transform.orient = body.pose.orientation;
+            }
+        }
+
+        for child in self.children.iter_mut() {
+            if child.is_enabled() {
+                child.apply_physics_bodies(bodies, next_body);
+            }
+        }
+    }
+
+    pub fn end_frame(&mut self, parent_view: &glm::DMat4) -> Result<()> {
         let model = self.transform.as_matrix()?;
-        let viewmodel = glm::convert::<glm::DMat4, glm::Mat4>(view * model);
+        let view = parent_view * model;
+        let viewmodel = glm::convert::<glm::DMat4, glm::Mat4>(view);
         self.previous_viewmodel = Some(viewmodel);
 
+        for child in self.children.iter_mut() {
+            if child.is_enabled() {
+                child.end_frame(&view)?;
+            }
+        }
+
         Ok(())
     }
 }
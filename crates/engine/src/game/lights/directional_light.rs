@@ -1,7 +1,112 @@
+use super::{ShadowSettings};
+
 use nalgebra_glm as glm;
+use anyhow::{Result};
+use vulkanalia::{
+    prelude::v1_0::*
+};
+
+use crate::{
+    game::camera::{Camera, CameraKind},
+    resources::{ShadowRenderInfo, ShadowCascadeRenderInfo}
+};
 
 #[derive(Debug, Copy, Clone, Default)]
 pub struct DirectionalLight {
     pub direction: glm::Vec3,
-    pub color: glm::Vec3
+    pub color: glm::Vec3,
+    pub shadows: Option<ShadowSettings>
+}
+
+impl DirectionalLight {
+    pub(crate) fn create_shadow_render_info(&self, camera: &Camera, view: &glm::DMat4, bounds: vk::Extent2D) -> Result<Option<ShadowRenderInfo>> {
+        let settings = match self.shadows {
+            Some(settings) => settings,
+            None => return Ok(None)
+        };
+        if camera.kind() != CameraKind::Perspective {
+            return Ok(None);
+        }
+
+        let near = camera.near() as f64;
+        let far = f64::min(camera.far() as f64, settings.max_distance as f64);
+        if far <= near || glm::length(&self.direction) == 0.0 {
+            return Ok(None);
+        }
+
+        let aspect_ratio = bounds.width as f64 / bounds.height as f64;
+        let tan_half_fovy = ((camera.fovy() as f64).to_radians() / 2.0).tan();
+        //Squared distance of a frustum corner from the view axis, per unit of depth
+        let corner_factor = tan_half_fovy * tan_half_fovy * (1.0 + aspect_ratio * aspect_ratio);
+
+        let inverse_view = glm::inverse(view);
+        let light_dir = glm::normalize(&glm::convert::<glm::Vec3, glm::DVec3>(self.direction));
+        let up: glm::DVec3 = if light_dir.z.abs() > 0.99 { glm::vec3(0.0, 1.0, 0.0) } else { glm::vec3(0.0, 0.0, 1.0) };
+
+        let cascade_count = settings.clamped_cascade_count();
+        let atlas_extent = settings.atlas_extent();
+        let resolution = settings.resolution as f64;
+        let lambda = settings.cascade_split_lambda.clamp(0.0, 1.0) as f64;
+
+        let mut cascades = Vec::with_capacity(cascade_count);
+        let mut split_near = near;
+        for cascade_index in 0..cascade_count {
+            let p = (cascade_index + 1) as f64 / cascade_count as f64;
+            let log_split = near * (far / near).powf(p);
+            let uniform_split = near + (far - near) * p;
+            let split_far = lambda * log_split + (1.0 - lambda) * uniform_split;
+
+            //Fitting a sphere instead of a box keeps the cascade's size constant as the camera rotates, which avoids shimmering
+            let center_depth = f64::min((split_near + split_far) * (1.0 + corner_factor) / 2.0, split_far);
+            let radius = ((split_far - center_depth).powi(2) + split_far * split_far * corner_factor).sqrt();
+
+            let center = (inverse_view * glm::vec4(0.0, 0.0, center_depth, 1.0)).xyz();
+            let eye_distance = radius + settings.max_distance as f64;
+            let eye = center - light_dir * eye_distance;
+            let light_view = glm::look_at_lh(&eye, &center, &up);
+
+            let mut light_proj = glm::ortho_lh_zo(-radius, radius, -radius, radius, 0.0, eye_distance + radius);
+            light_proj[(1, 1)] *= -1.0;
+
+            //Snap to whole texels so that moving the camera doesn't make the shadow edges crawl
+            let origin = light_proj * light_view * glm::vec4(0.0, 0.0, 0.0, 1.0);
+            let origin_texels = origin.xy() * resolution / 2.0;
+            let offset = (glm::round(&origin_texels) - origin_texels) * 2.0 / resolution;
+            light_proj[(0, 3)] += offset.x;
+            light_proj[(1, 3)] += offset.y;
+
+            let shadow_matrix = light_proj * light_view * inverse_view;
+
+            let viewport = settings.cascade_viewport(cascade_index);
+            let scale_x = viewport.extent.width as f64 / atlas_extent.width as f64;
+            let scale_y = viewport.extent.height as f64 / atlas_extent.height as f64;
+            let offset_x = viewport.offset.x as f64 / atlas_extent.width as f64;
+            let offset_y = viewport.offset.y as f64 / atlas_extent.height as f64;
+            let atlas_matrix = glm::DMat4::new(
+                0.5 * scale_x, 0.0, 0.0, offset_x + 0.5 * scale_x,
+                0.0, 0.5 * scale_y, 0.0, offset_y + 0.5 * scale_y,
+                0.0, 0.0, 1.0, 0.0,
+                0.0, 0.0, 0.0, 1.0
+            );
+
+            cascades.push(ShadowCascadeRenderInfo {
+                shadow_matrix: glm::convert::<glm::DMat4, glm::Mat4>(shadow_matrix),
+                sampling_matrix: glm::convert::<glm::DMat4, glm::Mat4>(atlas_matrix * shadow_matrix),
+                split_depth: split_far as f32,
+                viewport
+            });
+
+            split_near = split_far;
+        }
+
+        Ok(Some(ShadowRenderInfo {
+            atlas_extent,
+            cascades,
+            cascade_casters: vec![],
+
+            depth_bias_constant: settings.depth_bias_constant,
+            depth_bias_slope: settings.depth_bias_slope,
+            sample_bias: settings.sample_bias
+        }))
+    }
 }
@@ -0,0 +1,24 @@
+use nalgebra_glm as glm;
+
+#[derive(Debug, Copy, Clone)]
+pub struct LightAttenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32
+}
+
+impl Default for LightAttenuation {
+    fn default() -> Self {
+        Self {
+            constant: 1.0,
+            linear: 0.0,
+            quadratic: 1.0
+        }
+    }
+}
+
+impl LightAttenuation {
+    pub fn as_vec3(&self) -> glm::Vec3 {
+        glm::vec3(self.constant, self.linear, self.quadratic)
+    }
+}
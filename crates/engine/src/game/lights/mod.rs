@@ -1,3 +1,11 @@
 mod directional_light;
+mod light_attenuation;
+mod point_light;
+mod shadow_settings;
+mod spot_light;
 
 pub use directional_light::{DirectionalLight};
+pub use light_attenuation::{LightAttenuation};
+pub use point_light::{PointLight};
+pub use shadow_settings::{ShadowSettings};
+pub use spot_light::{SpotLight};
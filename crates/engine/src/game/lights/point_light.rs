@@ -0,0 +1,42 @@
+use super::{LightAttenuation};
+
+use nalgebra_glm as glm;
+
+use crate::{
+    shader_input::uniform_buffer_object::{LightUniform, LIGHT_KIND_POINT}
+};
+
+#[derive(Debug, Copy, Clone)]
+pub struct PointLight {
+    pub position: glm::DVec3,
+    pub color: glm::Vec3,
+    pub range: f32,
+    pub attenuation: LightAttenuation
+}
+
+impl Default for PointLight {
+    fn default() -> Self {
+        Self {
+            position: Default::default(),
+            color: glm::vec3(1.0, 1.0, 1.0),
+            range: 10.0,
+            attenuation: Default::default()
+        }
+    }
+}
+
+impl PointLight {
+    pub(crate) fn as_uniform(&self, view: &glm::DMat4) -> LightUniform {
+        let position = view * glm::vec4(self.position.x, self.position.y, self.position.z, 1.0);
+
+        LightUniform {
+            position: glm::convert::<glm::DVec3, glm::Vec3>(position.xyz()),
+            range: self.range,
+            color: self.color,
+            attenuation: self.attenuation.as_vec3(),
+            kind: LIGHT_KIND_POINT,
+
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use vulkanalia::{
+    prelude::v1_0::*
+};
+
+use crate::{
+    shader_input::uniform_buffer_object::{MAX_SHADOW_CASCADES}
+};
+
+#[derive(Debug, Copy, Clone)]
+pub struct ShadowSettings {
+    //Size of a single cascade; cascades are packed two per row into one shadow map
+    pub resolution: u32,
+    pub cascade_count: u32,
+    pub max_distance: f32,
+    //Blends between uniform (0.0) and logarithmic (1.0) cascade splits
+    pub cascade_split_lambda: f32,
+
+    pub depth_bias_constant: f32,
+    pub depth_bias_slope: f32,
+    pub sample_bias: f32
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 2048,
+            cascade_count: 4,
+            max_distance: 50.0,
+            cascade_split_lambda: 0.75,
+
+            depth_bias_constant: 1.25,
+            depth_bias_slope: 1.75,
+            sample_bias: 0.0005
+        }
+    }
+}
+
+impl ShadowSettings {
+    pub fn clamped_cascade_count(&self) -> usize {
+        (self.cascade_count as usize).clamp(1, MAX_SHADOW_CASCADES)
+    }
+
+    pub fn atlas_extent(&self) -> vk::Extent2D {
+        let cascade_count = self.clamped_cascade_count() as u32;
+        let columns = u32::min(cascade_count, 2);
+        let rows = (cascade_count + 1) / 2;
+
+        vk::Extent2D {
+            width: self.resolution * columns,
+            height: self.resolution * rows
+        }
+    }
+
+    pub fn cascade_viewport(&self, cascade_index: usize) -> vk::Rect2D {
+        let column = (cascade_index % 2) as u32;
+        let row = (cascade_index / 2) as u32;
+
+        vk::Rect2D {
+            offset: vk::Offset2D {
+                x: (column * self.resolution) as i32,
+                y: (row * self.resolution) as i32
+            },
+            extent: vk::Extent2D {
+                width: self.resolution,
+                height: self.resolution
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use super::{LightAttenuation};
+
+use nalgebra_glm as glm;
+
+use crate::{
+    shader_input::uniform_buffer_object::{LightUniform, LIGHT_KIND_SPOT}
+};
+
+#[derive(Debug, Copy, Clone)]
+pub struct SpotLight {
+    pub position: glm::DVec3,
+    pub direction: glm::Vec3,
+    pub color: glm::Vec3,
+    pub range: f32,
+    pub attenuation: LightAttenuation,
+
+    //Angles are in degrees, measured from the spot direction to the edge of the cone
+    pub inner_cone_angle: f32,
+    pub outer_cone_angle: f32
+}
+
+impl Default for SpotLight {
+    fn default() -> Self {
+        Self {
+            position: Default::default(),
+            direction: glm::vec3(0.0, 0.0, -1.0),
+            color: glm::vec3(1.0, 1.0, 1.0),
+            range: 10.0,
+            attenuation: Default::default(),
+
+            inner_cone_angle: 20.0,
+            outer_cone_angle: 30.0
+        }
+    }
+}
+
+impl SpotLight {
+    pub(crate) fn as_uniform(&self, view: &glm::DMat4, normal_matrix: &glm::Mat4) -> LightUniform {
+        let position = view * glm::vec4(self.position.x, self.position.y, self.position.z, 1.0);
+        let dir = self.direction.normalize();
+        let direction = (normal_matrix * glm::vec4(dir.x, dir.y, dir.z, 0.0)).xyz().normalize();
+
+        let outer_cone_angle = self.outer_cone_angle.max(self.inner_cone_angle);
+
+        LightUniform {
+            position: glm::convert::<glm::DVec3, glm::Vec3>(position.xyz()),
+            range: self.range,
+            direction,
+            kind: LIGHT_KIND_SPOT,
+            color: self.color,
+            inner_cone_cos: glm::radians(&glm::vec1(self.inner_cone_angle))[0].cos(),
+            attenuation: self.attenuation.as_vec3(),
+            outer_cone_cos: glm::radians(&glm::vec1(outer_cone_angle))[0].cos()
+        }
+    }
+}
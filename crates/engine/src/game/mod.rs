@@ -1,9 +1,13 @@
+pub mod camera_controllers;
 pub mod components;
 pub mod lights;
 
 pub mod camera;
 pub mod can_be_enabled;
+pub mod frustum;
 pub mod game_object;
 pub mod has_camera_matrix;
+pub mod post_settings;
+pub mod raycast;
 pub mod scene;
 pub mod transform;
@@ -0,0 +1,64 @@
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum ToneMappingOperator {
+    Reinhard,
+    #[default]
+    Aces,
+    AgX,
+    //Hable's curve from Uncharted 2
+    Filmic
+}
+
+impl ToneMappingOperator {
+    //Must match the operator constants in the tone mapping shader
+    pub fn shader_index(&self) -> u32 {
+        match self {
+            ToneMappingOperator::Reinhard => 0,
+            ToneMappingOperator::Aces => 1,
+            ToneMappingOperator::AgX => 2,
+            ToneMappingOperator::Filmic => 3
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AutoExposureSettings {
+    //The range of average scene luminance that exposure adapts to. Anything outside of it is clamped
+    pub min_luminance: f32,
+    pub max_luminance: f32,
+    //How quickly exposure adapts (roughly 1/seconds) when the scene gets brighter or darker
+    pub adaptation_speed_up: f32,
+    pub adaptation_speed_down: f32
+}
+
+impl Default for AutoExposureSettings {
+    fn default() -> Self {
+        Self {
+            min_luminance: 0.01,
+            max_luminance: 64.0,
+            adaptation_speed_up: 3.0,
+            adaptation_speed_down: 1.0
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ExposureMode {
+    //Scene color is multiplied by this before tone mapping
+    Manual(f32),
+    //Exposure is picked from the average luminance of the base render, with eye adaptation
+    Auto(AutoExposureSettings)
+}
+
+impl Default for ExposureMode {
+    fn default() -> Self {
+        ExposureMode::Auto(Default::default())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct CameraPostSettings {
+    pub tone_mapping: ToneMappingOperator,
+    pub exposure: ExposureMode,
+    //In stops, applied on top of either exposure mode
+    pub exposure_compensation: f32
+}
@@ -0,0 +1,35 @@
+use super::{
+    game_object::{GameObjectId}
+};
+
+use nalgebra_glm as glm;
+
+//In world space. The direction doesn't need to be normalized
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ray {
+    pub origin: glm::DVec3,
+    pub direction: glm::DVec3
+}
+
+impl Ray {
+    pub fn new(origin: glm::DVec3, direction: glm::DVec3) -> Self {
+        Self {
+            origin,
+            direction
+        }
+    }
+
+    pub fn point_at(&self, distance: f64) -> glm::DVec3 {
+        self.origin + glm::normalize(&self.direction) * distance
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RaycastHit {
+    pub game_object: GameObjectId,
+    //From the ray's origin, in world units
+    pub distance: f64,
+    pub position: glm::DVec3,
+    //Facing back towards the ray's origin
+    pub normal: glm::DVec3
+}
@@ -1,28 +1,37 @@
 use super::{
     camera::{Camera, HasCameraMatrix},
-    game_object::{GameObject},
+    camera_controllers::{CameraController},
+    game_object::{GameObject, GameObjectId},
     can_be_enabled::{CanBeEnabled},
-    lights::{DirectionalLight}
+    lights::{DirectionalLight, PointLight, SpotLight},
+    raycast::{Ray, RaycastHit}
 };
 
 use nalgebra_glm as glm;
-use anyhow::{Result};
+use anyhow::{anyhow, Result};
 use vulkanalia::{
     prelude::v1_0::*
 };
 
 use crate::{
     frame_info::{FrameInfo},
+    input::{InputState},
+    physics::{PhysicsWorld},
     resources::{SingleFrameRenderInfo, ResourceLoader}
 };
 
 #[derive(Debug, Default)]
 pub struct Scene {
     pub render_camera: Camera,
+    pub camera_controller: Option<Box<dyn CameraController>>,
     pub clear_color: glm::Vec3,
     pub ambient_light: glm::Vec3,
     pub directional_light: Option<DirectionalLight>,
-    objects: Vec<Box<GameObject>>
+    pub point_lights: Vec<PointLight>,
+    pub spot_lights: Vec<SpotLight>,
+    pub physics: PhysicsWorld,
+    objects: Vec<Box<GameObject>>,
+    removed_objects: Vec<Box<GameObject>>
 }
 
 impl Scene {
@@ -30,16 +39,153 @@ impl Scene {
         Self::default()
     }
 
-    pub fn add_game_object(&mut self, game_object: Box<GameObject>) -> Result<()> {
+    pub fn add_game_object(&mut self, game_object: Box<GameObject>) -> Result<GameObjectId> {
+        let id = game_object.id();
         self.objects.push(game_object);
 
+        Ok(id)
+    }
+
+    pub fn add_child_game_object(&mut self, parent: GameObjectId, game_object: Box<GameObject>) -> Result<GameObjectId> {
+        let parent_object = self.find_game_object_mut(parent).ok_or_else(|| anyhow!("Parent game object {:?} is not in the scene", parent))?;
+        parent_object.add_child(game_object)
+    }
+
+    pub fn remove_game_object(&mut self, id: GameObjectId) -> Result<()> {
+        let game_object = self.take_game_object(id).ok_or_else(|| anyhow!("Game object {:?} is not in the scene", id))?;
+
+        //Resources are released on the next load_and_unload, since we don't have the resource loader here
+        self.removed_objects.push(game_object);
+
+        Ok(())
+    }
+
+    pub fn reparent_game_object(&mut self, id: GameObjectId, new_parent: Option<GameObjectId>) -> Result<()> {
+        let game_object = self.find_game_object(id).ok_or_else(|| anyhow!("Game object {:?} is not in the scene", id))?;
+
+        if let Some(new_parent) = new_parent {
+            if game_object.find(new_parent).is_some() {
+                return Err(anyhow!("Cannot reparent game object {:?} under itself or one of its descendants", id));
+            }
+            if self.find_game_object(new_parent).is_none() {
+                return Err(anyhow!("Parent game object {:?} is not in the scene", new_parent));
+            }
+        }
+
+        let game_object = self.take_game_object(id).unwrap();
+        match new_parent {
+            Some(new_parent) => { self.add_child_game_object(new_parent, game_object)?; },
+            None => { self.add_game_object(game_object)?; }
+        }
+
         Ok(())
     }
 
-    pub fn tick(&mut self, frame_info: &FrameInfo) -> Result<()> {
+    pub fn find_game_object(&self, id: GameObjectId) -> Option<&GameObject> {
+        self.objects.iter().find_map(|obj| obj.find(id))
+    }
+
+    pub fn find_game_object_mut(&mut self, id: GameObjectId) -> Option<&mut GameObject> {
+        self.objects.iter_mut().find_map(|obj| obj.find_mut(id))
+    }
+
+    pub fn get_world_matrix(&self, id: GameObjectId) -> Result<glm::DMat4> {
+        for obj in self.objects.iter() {
+            if let Some(matrix) = obj.find_world_matrix(id, &glm::DMat4::identity())? {
+                return Ok(matrix);
+            }
+        }
+
+        Err(anyhow!("Game object {:?} is not in the scene", id))
+    }
+
+    //Tests each enabled object's raycast model, first against its bounds and then its triangles. Models that haven't finished loading are never hit
+    pub fn raycast(&self, origin: glm::DVec3, direction: glm::DVec3, resource_loader: &ResourceLoader) -> Result<Option<RaycastHit>> {
+        let ray = Ray::new(origin, direction);
+        if glm::length(&ray.direction) == 0.0 {
+            return Err(anyhow!("Can't raycast without a direction"));
+        }
+
+        let mut closest_hit = None;
+        for obj in self.objects.iter() {
+            if obj.is_enabled() {
+                obj.raycast(&ray, &glm::DMat4::identity(), resource_loader, &mut closest_hit)?;
+            }
+        }
+
+        Ok(closest_hit)
+    }
+
+    fn take_game_object(&mut self, id: GameObjectId) -> Option<Box<GameObject>> {
+        if let Some(idx) = self.objects.iter().position(|obj| obj.id() == id) {
+            return Some(self.objects.remove(idx));
+        }
+
+        self.objects.iter_mut().find_map(|obj| obj.take_descendant(id))
+    }
+
+    fn update_camera_parent(&mut self) -> Result<()> {
+        if let Some(parent) = self.render_camera.parent() {
+            match self.get_world_matrix(parent) {
+                Ok(parent_matrix) => self.render_camera.set_parent_matrix(parent_matrix),
+                Err(_) => {
+                    warn!("Render camera parent {:?} is no longer in the scene, detaching", parent);
+                    self.render_camera.detach();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn tick(&mut self, frame_info: &FrameInfo, input: &InputState) -> Result<()> {
+        for obj in self.objects.iter_mut() {
+            if obj.is_enabled() {
+                obj.tick(frame_info, input)?;
+            }
+        }
+
+        if let Some(camera_controller) = self.camera_controller.as_mut() {
+            camera_controller.update(frame_info, input, &mut self.render_camera)?;
+        }
+
+        self.update_camera_parent()?;
+
+        Ok(())
+    }
+
+    //Camera controllers stay on the per-frame tick so the view responds to input at the display rate
+    pub fn fixed_tick(&mut self, frame_info: &FrameInfo, input: &InputState) -> Result<()> {
+        for obj in self.objects.iter_mut() {
+            if obj.is_enabled() {
+                obj.fixed_tick(frame_info, input)?;
+            }
+        }
+
+        self.step_physics(frame_info.fixed_delta_time.as_secs_f32())?;
+
+        Ok(())
+    }
+
+    //Runs after the objects' fixed ticks, so kinematic bodies moved there are where they should be for this step
+    fn step_physics(&mut self, delta_time: f32) -> Result<()> {
+        let mut bodies = vec![];
+        for obj in self.objects.iter() {
+            if obj.is_enabled() {
+                obj.collect_physics_bodies(&glm::DMat4::identity(), &mut bodies)?;
+            }
+        }
+
+        if bodies.is_empty() {
+            return Ok(());
+        }
+
+        self.physics.step(&mut bodies, delta_time);
+
+        let mut next_body = 0;
         for obj in self.objects.iter_mut() {
             if obj.is_enabled() {
-                obj.tick(frame_info)?;
+                obj.apply_physics_bodies(&bodies, &mut next_body);
             }
         }
 
@@ -47,9 +193,15 @@ impl Scene {
     }
 
     pub fn load_and_unload(&mut self, resource_loader: &mut ResourceLoader) -> Result<()> {
+        for mut obj in self.removed_objects.drain(..) {
+            obj.unload(resource_loader);
+        }
+
         for obj in self.objects.iter_mut() {
             if obj.is_enabled() {
                 obj.load_and_unload(resource_loader)?;
+            } else {
+                obj.unload(resource_loader);
             }
         }
 
@@ -57,6 +209,10 @@ impl Scene {
     }
 
     pub fn unload(&mut self, resource_loader: &mut ResourceLoader) -> () {
+        for mut obj in self.removed_objects.drain(..) {
+            obj.unload(resource_loader);
+        }
+
         for obj in self.objects.iter_mut() {
             obj.unload(resource_loader);
         }
@@ -72,15 +228,27 @@ impl Scene {
 
         frame_info.clear_color = self.clear_color;
 
+        frame_info.post_settings = self.render_camera.post_settings;
+
         frame_info.ambient_light = self.ambient_light;
 
+        let normal_matrix = glm::convert::<glm::DMat4, glm::Mat4>(glm::transpose(&glm::inverse(&view)));
+
         if let Some(directional_light) = self.directional_light {
             let dir = directional_light.direction.normalize();
-            let normal_matrix = glm::convert::<glm::DMat4, glm::Mat4>(glm::transpose(&glm::inverse(&view)));
             let actual_direction: glm::Vec3 = (normal_matrix * glm::vec4(dir.x, dir.y, dir.z, 0.0)).xyz().normalize();
 
             frame_info.directional_light_color = directional_light.color;
             frame_info.directional_light_direction = actual_direction;
+
+            frame_info.shadows = directional_light.create_shadow_render_info(&self.render_camera, &view, bounds)?;
+        }
+
+        for point_light in self.point_lights.iter() {
+            frame_info.lights.push(point_light.as_uniform(&view));
+        }
+        for spot_light in self.spot_lights.iter() {
+            frame_info.lights.push(spot_light.as_uniform(&view, &normal_matrix));
         }
 
         for obj in self.objects.iter() {
@@ -1,5 +1,5 @@
 use nalgebra_glm as glm;
-use anyhow::{Result};
+use anyhow::{anyhow, Result};
 use lazy_static::{lazy_static};
 
 lazy_static! {
@@ -10,14 +10,16 @@ lazy_static! {
 #[derive(Debug, Copy, Clone)]
 pub struct Transform {
     pub pos: glm::DVec3,
-    pub orient: glm::Quat
+    pub orient: glm::Quat,
+    pub scale: glm::DVec3
 }
 
 impl Default for Transform {
     fn default() -> Self {
         Self {
             pos: Default::default(),
-            orient: glm::Quat::identity()
+            orient: glm::Quat::identity(),
+            scale: glm::vec3(1.0, 1.0, 1.0)
         }
     }
 }
@@ -31,17 +33,27 @@ impl Transform {
         self.orient = glm::quat_look_at(&dir, &up);
     }
 
+    pub fn set_uniform_scale(&mut self, scale: f64) -> () {
+        self.scale = glm::vec3(scale, scale, scale);
+    }
+
     pub fn as_matrix(&self) -> Result<glm::DMat4> {
         let view_mat4 = glm::quat_to_mat4(&self.orient);
         let orient = glm::convert::<glm::Mat4, glm::DMat4>(view_mat4);
         let translate = glm::translate(&glm::DMat4::identity(), &self.pos);
+        let scale = glm::scaling(&self.scale);
 
-        Ok(translate * orient)
+        Ok(translate * orient * scale)
     }
 
     pub fn as_matrix_inverse(&self) -> Result<glm::DMat4> {
+        if self.scale.iter().any(|s| *s == 0.0) {
+            return Err(anyhow!("Cannot invert a transform with a zero scale component: {:?}", self.scale));
+        }
+
         let view_mat4 = glm::quat_to_mat4(&self.orient);
-        let mut mat = glm::convert::<glm::Mat4, glm::DMat4>(view_mat4);
+        let mut mat = glm::scaling(&glm::vec3(1.0 / self.scale.x, 1.0 / self.scale.y, 1.0 / self.scale.z));
+        mat = mat * glm::convert::<glm::Mat4, glm::DMat4>(view_mat4);
         mat = glm::translate(&mat, &-self.pos);
 
         Ok(mat)
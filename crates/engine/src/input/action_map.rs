@@ -0,0 +1,46 @@
+use std::{
+    collections::{HashMap}
+};
+use winit::event::{VirtualKeyCode, MouseButton};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum InputBinding {
+    Key(VirtualKeyCode),
+    MouseButton(MouseButton),
+    #[cfg(feature = "gamepad")]
+    GamepadButton(gilrs::Button),
+    #[cfg(feature = "gamepad")]
+    GamepadAxis { axis: gilrs::Axis, positive: bool }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ActionMap {
+    bindings: HashMap<String, Vec<InputBinding>>
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bind(&mut self, action: &str, binding: InputBinding) -> () {
+        let bindings = self.bindings.entry(action.to_owned()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind(&mut self, action: &str, binding: InputBinding) -> () {
+        if let Some(bindings) = self.bindings.get_mut(action) {
+            bindings.retain(|b| *b != binding);
+        }
+    }
+
+    pub fn clear_action(&mut self, action: &str) -> () {
+        self.bindings.remove(action);
+    }
+
+    pub fn get_bindings(&self, action: &str) -> &[InputBinding] {
+        self.bindings.get(action).map(|bindings| bindings.as_slice()).unwrap_or(&[])
+    }
+}
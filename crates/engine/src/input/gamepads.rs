@@ -0,0 +1,59 @@
+use std::{
+    fmt::{self, Debug}
+};
+use gilrs::{Gilrs, Event, EventType};
+
+pub enum GamepadEvent {
+    ButtonPressed(gilrs::Button),
+    ButtonReleased(gilrs::Button),
+    AxisChanged(gilrs::Axis, f32),
+    Disconnected
+}
+
+pub struct Gamepads {
+    gilrs: Option<Gilrs>
+}
+
+impl Debug for Gamepads {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Gamepads")
+            .field("available", &self.gilrs.is_some())
+            .finish()
+    }
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(error) => {
+                warn!("Gamepad input is unavailable: {}", error);
+                None
+            }
+        };
+
+        Self {
+            gilrs
+        }
+    }
+}
+
+impl Gamepads {
+    pub fn next_event(&mut self) -> Option<GamepadEvent> {
+        let gilrs = self.gilrs.as_mut()?;
+
+        while let Some(Event { event, .. }) = gilrs.next_event() {
+            let gamepad_event = match event {
+                EventType::ButtonPressed(button, _) => GamepadEvent::ButtonPressed(button),
+                EventType::ButtonReleased(button, _) => GamepadEvent::ButtonReleased(button),
+                EventType::AxisChanged(axis, value, _) => GamepadEvent::AxisChanged(axis, value),
+                EventType::Disconnected => GamepadEvent::Disconnected,
+                _ => continue
+            };
+
+            return Some(gamepad_event);
+        }
+
+        None
+    }
+}
@@ -0,0 +1,258 @@
+use super::{
+    action_map::{ActionMap, InputBinding}
+};
+#[cfg(feature = "gamepad")]
+use super::gamepads::{Gamepads, GamepadEvent};
+
+use std::{
+    collections::{HashSet}
+};
+#[cfg(feature = "gamepad")]
+use std::collections::{HashMap};
+use nalgebra_glm as glm;
+use winit::event::{
+    WindowEvent,
+    DeviceEvent,
+    ElementState,
+    KeyboardInput,
+    MouseScrollDelta,
+    VirtualKeyCode,
+    MouseButton
+};
+
+#[cfg(feature = "gamepad")]
+const GAMEPAD_AXIS_DEADZONE: f32 = 0.15;
+const PIXELS_PER_SCROLL_LINE: f32 = 20.0;
+
+#[derive(Debug, Default)]
+pub struct InputState {
+    pub actions: ActionMap,
+
+    keys_held: HashSet<VirtualKeyCode>,
+    keys_pressed: HashSet<VirtualKeyCode>,
+    keys_released: HashSet<VirtualKeyCode>,
+
+    mouse_buttons_held: HashSet<MouseButton>,
+    mouse_buttons_pressed: HashSet<MouseButton>,
+    mouse_buttons_released: HashSet<MouseButton>,
+
+    mouse_delta: glm::DVec2,
+    scroll_delta: glm::Vec2,
+    cursor_position: Option<glm::DVec2>,
+
+    #[cfg(feature = "gamepad")]
+    gamepad_buttons_held: HashSet<gilrs::Button>,
+    #[cfg(feature = "gamepad")]
+    gamepad_buttons_pressed: HashSet<gilrs::Button>,
+    #[cfg(feature = "gamepad")]
+    gamepad_buttons_released: HashSet<gilrs::Button>,
+    #[cfg(feature = "gamepad")]
+    gamepad_axes: HashMap<gilrs::Axis, f32>,
+
+    #[cfg(feature = "gamepad")]
+    gamepads: Gamepads
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_key_held(&self, key: VirtualKeyCode) -> bool {
+        self.keys_held.contains(&key)
+    }
+    pub fn was_key_pressed(&self, key: VirtualKeyCode) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+    pub fn was_key_released(&self, key: VirtualKeyCode) -> bool {
+        self.keys_released.contains(&key)
+    }
+
+    pub fn is_mouse_button_held(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_held.contains(&button)
+    }
+    pub fn was_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_pressed.contains(&button)
+    }
+    pub fn was_mouse_button_released(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_released.contains(&button)
+    }
+
+    pub fn mouse_delta(&self) -> glm::DVec2 {
+        self.mouse_delta
+    }
+    pub fn scroll_delta(&self) -> glm::Vec2 {
+        self.scroll_delta
+    }
+    pub fn cursor_position(&self) -> Option<glm::DVec2> {
+        self.cursor_position
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub fn is_gamepad_button_held(&self, button: gilrs::Button) -> bool {
+        self.gamepad_buttons_held.contains(&button)
+    }
+    #[cfg(feature = "gamepad")]
+    pub fn was_gamepad_button_pressed(&self, button: gilrs::Button) -> bool {
+        self.gamepad_buttons_pressed.contains(&button)
+    }
+    #[cfg(feature = "gamepad")]
+    pub fn was_gamepad_button_released(&self, button: gilrs::Button) -> bool {
+        self.gamepad_buttons_released.contains(&button)
+    }
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_axis(&self, axis: gilrs::Axis) -> f32 {
+        let value = self.gamepad_axes.get(&axis).copied().unwrap_or(0.0);
+        if value.abs() < GAMEPAD_AXIS_DEADZONE { 0.0 } else { value }
+    }
+
+    pub fn is_action_held(&self, action: &str) -> bool {
+        self.actions.get_bindings(action).iter().any(|binding| self.binding_value(binding) > 0.0)
+    }
+    pub fn was_action_pressed(&self, action: &str) -> bool {
+        self.actions.get_bindings(action).iter().any(|binding| match binding {
+            InputBinding::Key(key) => self.was_key_pressed(*key),
+            InputBinding::MouseButton(button) => self.was_mouse_button_pressed(*button),
+            #[cfg(feature = "gamepad")]
+            InputBinding::GamepadButton(button) => self.was_gamepad_button_pressed(*button),
+            #[cfg(feature = "gamepad")]
+            InputBinding::GamepadAxis { .. } => false
+        })
+    }
+    pub fn was_action_released(&self, action: &str) -> bool {
+        self.actions.get_bindings(action).iter().any(|binding| match binding {
+            InputBinding::Key(key) => self.was_key_released(*key),
+            InputBinding::MouseButton(button) => self.was_mouse_button_released(*button),
+            #[cfg(feature = "gamepad")]
+            InputBinding::GamepadButton(button) => self.was_gamepad_button_released(*button),
+            #[cfg(feature = "gamepad")]
+            InputBinding::GamepadAxis { .. } => false
+        })
+    }
+    pub fn action_value(&self, action: &str) -> f32 {
+        self.actions.get_bindings(action).iter()
+            .map(|binding| self.binding_value(binding))
+            .fold(0.0, f32::max)
+    }
+    pub fn action_axis(&self, negative_action: &str, positive_action: &str) -> f32 {
+        self.action_value(positive_action) - self.action_value(negative_action)
+    }
+
+    fn binding_value(&self, binding: &InputBinding) -> f32 {
+        let held = match binding {
+            InputBinding::Key(key) => self.is_key_held(*key),
+            InputBinding::MouseButton(button) => self.is_mouse_button_held(*button),
+            #[cfg(feature = "gamepad")]
+            InputBinding::GamepadButton(button) => self.is_gamepad_button_held(*button),
+            #[cfg(feature = "gamepad")]
+            InputBinding::GamepadAxis { axis, positive } => {
+                let value = self.gamepad_axis(*axis);
+                return if *positive { value.max(0.0) } else { (-value).max(0.0) };
+            }
+        };
+
+        if held { 1.0 } else { 0.0 }
+    }
+
+    pub(crate) fn handle_window_event(&mut self, event: &WindowEvent) -> () {
+        match event {
+            WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(key), state, .. }, .. } => {
+                match state {
+                    ElementState::Pressed => {
+                        //Key repeat sends additional press events without a release in between
+                        if self.keys_held.insert(*key) {
+                            self.keys_pressed.insert(*key);
+                        }
+                    },
+                    ElementState::Released => {
+                        if self.keys_held.remove(key) {
+                            self.keys_released.insert(*key);
+                        }
+                    }
+                }
+            },
+            WindowEvent::MouseInput { state, button, .. } => {
+                match state {
+                    ElementState::Pressed => {
+                        if self.mouse_buttons_held.insert(*button) {
+                            self.mouse_buttons_pressed.insert(*button);
+                        }
+                    },
+                    ElementState::Released => {
+                        if self.mouse_buttons_held.remove(button) {
+                            self.mouse_buttons_released.insert(*button);
+                        }
+                    }
+                }
+            },
+            WindowEvent::MouseWheel { delta, .. } => {
+                match delta {
+                    MouseScrollDelta::LineDelta(x, y) => {
+                        self.scroll_delta += glm::vec2(*x, *y);
+                    },
+                    MouseScrollDelta::PixelDelta(position) => {
+                        self.scroll_delta += glm::vec2(position.x as f32, position.y as f32) / PIXELS_PER_SCROLL_LINE;
+                    }
+                }
+            },
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_position = Some(glm::vec2(position.x, position.y));
+            },
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor_position = None;
+            },
+            WindowEvent::Focused(false) => {
+                //We won't receive release events while unfocused, so treat everything as released
+                self.keys_released.extend(self.keys_held.drain());
+                self.mouse_buttons_released.extend(self.mouse_buttons_held.drain());
+            },
+            _ => { }
+        }
+    }
+
+    pub(crate) fn handle_device_event(&mut self, event: &DeviceEvent) -> () {
+        if let DeviceEvent::MouseMotion { delta: (x, y) } = event {
+            self.mouse_delta += glm::vec2(*x, *y);
+        }
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub(crate) fn poll_gamepads(&mut self) -> () {
+        while let Some(event) = self.gamepads.next_event() {
+            match event {
+                GamepadEvent::ButtonPressed(button) => {
+                    if self.gamepad_buttons_held.insert(button) {
+                        self.gamepad_buttons_pressed.insert(button);
+                    }
+                },
+                GamepadEvent::ButtonReleased(button) => {
+                    if self.gamepad_buttons_held.remove(&button) {
+                        self.gamepad_buttons_released.insert(button);
+                    }
+                },
+                GamepadEvent::AxisChanged(axis, value) => {
+                    self.gamepad_axes.insert(axis, value);
+                },
+                GamepadEvent::Disconnected => {
+                    self.gamepad_buttons_released.extend(self.gamepad_buttons_held.drain());
+                    self.gamepad_axes.clear();
+                }
+            }
+        }
+    }
+
+    pub(crate) fn end_frame(&mut self) -> () {
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.mouse_buttons_pressed.clear();
+        self.mouse_buttons_released.clear();
+        #[cfg(feature = "gamepad")]
+        {
+            self.gamepad_buttons_pressed.clear();
+            self.gamepad_buttons_released.clear();
+        }
+
+        self.mouse_delta = glm::zero();
+        self.scroll_delta = glm::zero();
+    }
+}
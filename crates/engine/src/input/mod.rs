@@ -0,0 +1,11 @@
+mod action_map;
+#[cfg(feature = "gamepad")]
+mod gamepads;
+mod input_state;
+
+pub use action_map::{ActionMap, InputBinding};
+pub use input_state::{InputState};
+
+pub use winit::event::{VirtualKeyCode, MouseButton};
+#[cfg(feature = "gamepad")]
+pub use gilrs::{Button as GamepadButton, Axis as GamepadAxis};
@@ -1,5 +1,8 @@
 pub mod bootstrap;
 pub mod game;
+pub mod input;
+pub mod physics;
+pub mod postprocessing;
 pub mod resources;
 pub mod shader_input;
 pub mod util;
@@ -8,5 +11,6 @@ pub mod app_data;
 pub mod app;
 pub mod builder;
 pub mod frame_info;
+pub mod frame_stats;
 
 #[macro_use] extern crate log;
@@ -0,0 +1,136 @@
+use super::{
+    triangle_bvh::{TriangleBvh}
+};
+
+use std::{
+    sync::{Arc}
+};
+use nalgebra_glm as glm;
+
+use crate::resources::{MeshData};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ColliderShape {
+    Sphere { radius: f32 },
+    Box { half_extents: glm::Vec3 },
+    //Along the local Z axis, which is up in this engine. The half height doesn't include the caps
+    Capsule { radius: f32, half_height: f32 },
+    //The hull of the game object's raycast model's vertices
+    ConvexHull,
+    //The game object's raycast model's triangles. Never moved by the simulation, so dynamic bodies treat it as kinematic
+    TriangleMesh
+}
+
+impl ColliderShape {
+    pub fn needs_mesh(&self) -> bool {
+        matches!(self, ColliderShape::ConvexHull | ColliderShape::TriangleMesh)
+    }
+}
+
+#[derive(Debug)]
+pub struct ColliderMesh {
+    pub data: Arc<MeshData>,
+    //Only built for triangle meshes
+    pub(super) bvh: Option<TriangleBvh>
+}
+
+#[derive(Debug, Clone)]
+pub struct Collider {
+    pub shape: ColliderShape,
+    pub friction: f32,
+    //How much of the closing speed bounces back, from 0 to 1
+    pub restitution: f32,
+
+    mesh: Option<Arc<ColliderMesh>>
+}
+
+impl Collider {
+    pub fn new(shape: ColliderShape) -> Self {
+        Self {
+            shape,
+            friction: 0.5,
+            restitution: 0.2,
+
+            mesh: None
+        }
+    }
+
+    pub fn sphere(radius: f32) -> Self {
+        Self::new(ColliderShape::Sphere { radius })
+    }
+
+    pub fn cuboid(half_extents: glm::Vec3) -> Self {
+        Self::new(ColliderShape::Box { half_extents })
+    }
+
+    pub fn capsule(radius: f32, half_height: f32) -> Self {
+        Self::new(ColliderShape::Capsule { radius, half_height })
+    }
+
+    pub fn convex_hull() -> Self {
+        Self::new(ColliderShape::ConvexHull)
+    }
+
+    pub fn triangle_mesh() -> Self {
+        Self::new(ColliderShape::TriangleMesh)
+    }
+
+    //Primitive shapes are always ready. Mesh shapes wait for the game object's model to load
+    pub fn is_ready(&self) -> bool {
+        !self.shape.needs_mesh() || self.mesh.is_some()
+    }
+
+    pub fn mesh(&self) -> Option<&Arc<ColliderMesh>> {
+        self.mesh.as_ref()
+    }
+
+    pub(crate) fn set_mesh_data(&mut self, data: Option<Arc<MeshData>>) -> () {
+        let is_same = match (self.mesh.as_ref(), data.as_ref()) {
+            (Some(mesh), Some(data)) => Arc::ptr_eq(&mesh.data, data),
+            (None, None) => true,
+            _ => false
+        };
+        if is_same {
+            return;
+        }
+
+        self.mesh = data.map(|data| {
+            let bvh = if self.shape == ColliderShape::TriangleMesh { Some(TriangleBvh::new(&data)) } else { None };
+            Arc::new(ColliderMesh {
+                data,
+                bvh
+            })
+        });
+    }
+
+    //The diagonal of the inertia tensor at the local origin, scaled by the game object's scale. Hulls are treated as their bounding box
+    pub(super) fn local_inertia(&self, mass: f32, scale: &glm::Vec3) -> glm::Vec3 {
+        let box_inertia = |half_extents: glm::Vec3| {
+            let size = glm::matrix_comp_mult(&(half_extents * 2.0), &(half_extents * 2.0));
+            glm::vec3(size.y + size.z, size.x + size.z, size.x + size.y) * (mass / 12.0)
+        };
+        let uniform_scale = scale.x.max(scale.y).max(scale.z);
+
+        match self.shape {
+            ColliderShape::Sphere { radius } => {
+                let radius = radius * uniform_scale;
+                let inertia = 0.4 * mass * radius * radius;
+                glm::vec3(inertia, inertia, inertia)
+            },
+            ColliderShape::Box { half_extents } => box_inertia(glm::matrix_comp_mult(&half_extents, scale)),
+            ColliderShape::Capsule { radius, half_height } => {
+                //As a cylinder as long as the whole capsule
+                let radius = radius * uniform_scale;
+                let height = 2.0 * (half_height * scale.z + radius);
+                let across = mass * (3.0 * radius * radius + height * height) / 12.0;
+                glm::vec3(across, across, 0.5 * mass * radius * radius)
+            },
+            ColliderShape::ConvexHull | ColliderShape::TriangleMesh => {
+                match self.mesh.as_ref().and_then(|mesh| mesh.data.bounds) {
+                    Some(bounds) => box_inertia(glm::matrix_comp_mult(&bounds.half_extents(), scale)),
+                    None => glm::vec3(mass, mass, mass)
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,491 @@
+use nalgebra_glm as glm;
+
+const MAX_ITERATIONS: usize = 32;
+//Box and hull cores are shrunk by this much and rounded back out, so shallow contacts get exact normals from GJK instead of the overlap fallback
+const POLYHEDRON_MARGIN: f32 = 0.02;
+//Vertices within this distance of a face's deepest point rest on it along with the deepest point
+const FEATURE_TOLERANCE: f32 = 0.02;
+
+//The convex hull of the vertices, grown by the radius. Spheres are a point, capsules a segment
+#[derive(Debug, Clone)]
+pub struct ConvexShape {
+    vertices: Vec<glm::Vec3>,
+    radius: f32,
+    center: glm::Vec3,
+    //Separating directions to try when the cores overlap
+    face_normals: Vec<glm::Vec3>,
+    //Boxes and hulls can rest on several vertices at once
+    is_polyhedron: bool,
+    is_triangle: bool
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ContactPoint {
+    pub position: glm::Vec3,
+    //From the first shape to the second
+    pub normal: glm::Vec3,
+    //Negative while the shapes are still apart
+    pub depth: f32
+}
+
+#[derive(Debug, Copy, Clone)]
+struct SupportPoint {
+    point: glm::Vec3,
+    a: glm::Vec3,
+    b: glm::Vec3
+}
+
+impl ConvexShape {
+    fn new(vertices: Vec<glm::Vec3>, radius: f32, face_normals: Vec<glm::Vec3>, is_polyhedron: bool, is_triangle: bool) -> Self {
+        let center = vertices.iter().fold(glm::Vec3::zeros(), |sum, vertex| sum + vertex) / vertices.len() as f32;
+
+        Self {
+            vertices,
+            radius,
+            center,
+            face_normals,
+            is_polyhedron,
+            is_triangle
+        }
+    }
+
+    pub fn sphere(center: glm::Vec3, radius: f32) -> Self {
+        Self::new(vec![center], radius, vec![], false, false)
+    }
+
+    pub fn capsule(start: glm::Vec3, end: glm::Vec3, radius: f32) -> Self {
+        Self::new(vec![start, end], radius, vec![], false, false)
+    }
+
+    //The columns of the rotation are the box's axes
+    pub fn oriented_box(center: glm::Vec3, rotation: &glm::Mat3, half_extents: &glm::Vec3) -> Self {
+        let margin = POLYHEDRON_MARGIN.min(0.25 * half_extents.min());
+        let core = half_extents.add_scalar(-margin);
+        let axes = [rotation.column(0).into_owned(), rotation.column(1).into_owned(), rotation.column(2).into_owned()];
+
+        let mut vertices = Vec::with_capacity(8);
+        for corner in 0..8 {
+            let sign = |bit: usize| if corner & bit == 0 { -1.0 } else { 1.0 };
+            vertices.push(center + axes[0] * (core.x * sign(1)) + axes[1] * (core.y * sign(2)) + axes[2] * (core.z * sign(4)));
+        }
+
+        Self::new(vertices, margin, axes.to_vec(), true, false)
+    }
+
+    //Points are pulled in toward their center by the margin, which is close enough to shrinking the hull for rounding it back out
+    pub fn hull(points: Vec<glm::Vec3>, half_extents: &glm::Vec3) -> Self {
+        let margin = POLYHEDRON_MARGIN.min(0.25 * half_extents.min());
+        let center = points.iter().fold(glm::Vec3::zeros(), |sum, point| sum + point) / points.len() as f32;
+        let vertices = points.into_iter()
+            .map(|point| {
+                let offset = point - center;
+                let length = glm::length(&offset);
+                if length > margin { point - offset * (margin / length) } else { center }
+            })
+            .collect();
+
+        Self::new(vertices, margin, vec![], true, false)
+    }
+
+    pub fn triangle(pt1: glm::Vec3, pt2: glm::Vec3, pt3: glm::Vec3) -> Self {
+        let normal = glm::cross(&(pt2 - pt1), &(pt3 - pt1));
+        let length = glm::length(&normal);
+        let face_normals = if length > 0.0 { vec![normal / length] } else { vec![] };
+
+        Self::new(vec![pt1, pt2, pt3], 0.0, face_normals, false, true)
+    }
+
+    pub fn aabb(&self) -> (glm::Vec3, glm::Vec3) {
+        let mut min = self.vertices[0];
+        let mut max = self.vertices[0];
+        for vertex in self.vertices.iter() {
+            min = glm::min2(&min, vertex);
+            max = glm::max2(&max, vertex);
+        }
+
+        (min.add_scalar(-self.radius), max.add_scalar(self.radius))
+    }
+
+    //The range of distances along the direction the shape covers
+    pub fn extent_along(&self, direction: &glm::Vec3) -> (f32, f32) {
+        let (min, max) = self.vertices.iter()
+            .map(|vertex| glm::dot(vertex, direction))
+            .fold((f32::MAX, f32::MIN), |(min, max), distance| (min.min(distance), max.max(distance)));
+
+        (min - self.radius, max + self.radius)
+    }
+
+    fn support(&self, direction: &glm::Vec3) -> glm::Vec3 {
+        *self.vertices.iter()
+            .max_by(|a, b| glm::dot(a, direction).total_cmp(&glm::dot(b, direction)))
+            .unwrap()
+    }
+
+    fn extent(&self) -> f32 {
+        self.vertices.iter()
+            .map(|vertex| glm::distance(vertex, &self.center))
+            .fold(0.0f32, f32::max) + self.radius
+    }
+}
+
+//Appends contacts between the shapes if they're closer than max_gap
+pub fn collide(a: &ConvexShape, b: &ConvexShape, max_gap: f32, contacts: &mut Vec<ContactPoint>) -> () {
+    let total_radius = a.radius + b.radius;
+
+    let separated = closest_points(a, b)
+        .map(|(point_a, point_b)| (point_a, point_b, glm::distance(&point_a, &point_b)))
+        .filter(|(_, _, distance)| *distance > 1e-6);
+
+    let (normal, depth, point_a, point_b) = match separated {
+        Some((point_a, point_b, distance)) => {
+            if distance - total_radius > max_gap {
+                return;
+            }
+
+            let normal = (point_b - point_a) / distance;
+            (normal, total_radius - distance, point_a, point_b)
+        },
+        None => penetration(a, b)
+    };
+
+    //Boxes and hulls lying flat get a contact at each vertex of the resting face, or they'd rock back and forth on one point
+    let feature_shape = match (a.is_polyhedron, b.is_polyhedron) {
+        (true, true) => Some(a.extent() <= b.extent()),
+        (true, false) => Some(true),
+        (false, true) => Some(false),
+        (false, false) => None
+    };
+
+    if let Some(is_a) = feature_shape {
+        let (shape, direction) = if is_a { (a, normal) } else { (b, -normal) };
+        let deepest = shape.vertices.iter().map(|vertex| glm::dot(vertex, &direction)).fold(f32::MIN, f32::max);
+        let feature_contacts = shape.vertices.iter()
+            .filter(|vertex| glm::dot(vertex, &direction) >= deepest - FEATURE_TOLERANCE)
+            .map(|vertex| {
+                let vertex_depth = depth - (deepest - glm::dot(vertex, &direction));
+                let surface = vertex + direction * shape.radius;
+                ContactPoint {
+                    position: surface - direction * (vertex_depth * 0.5),
+                    normal,
+                    depth: vertex_depth
+                }
+            })
+            .filter(|contact| -contact.depth <= max_gap)
+            .collect::<Vec<_>>();
+
+        if feature_contacts.len() >= 2 {
+            contacts.extend(feature_contacts);
+            return;
+        }
+    }
+
+    //Taken from the simpler shape, since a triangle or box's support point could be any of several equally deep vertices
+    let position = if a.vertices.len() <= b.vertices.len() {
+        point_a + normal * (a.radius - depth * 0.5)
+    } else {
+        point_b - normal * (b.radius - depth * 0.5)
+    };
+
+    contacts.push(ContactPoint {
+        position,
+        normal,
+        depth
+    });
+}
+
+//Spheres against spheres, capsules and triangles are common enough to skip straight to the answer
+fn closest_to_point(point: &glm::Vec3, shape: &ConvexShape) -> Option<glm::Vec3> {
+    let offsets = shape.vertices.iter().map(|vertex| vertex - point).collect::<Vec<_>>();
+    let weights = match offsets.len() {
+        1 => vec![1.0],
+        2 => closest_on_segment(&offsets[0], &offsets[1]).to_vec(),
+        3 => closest_on_triangle(&offsets[0], &offsets[1], &offsets[2]).to_vec(),
+        _ => return None
+    };
+
+    Some(shape.vertices.iter().zip(weights.iter()).fold(glm::Vec3::zeros(), |sum, (vertex, weight)| sum + vertex * *weight))
+}
+
+//Returns the closest points on the cores, or None if they overlap
+fn closest_points(a: &ConvexShape, b: &ConvexShape) -> Option<(glm::Vec3, glm::Vec3)> {
+    if a.vertices.len() == 1 {
+        if let Some(point_b) = closest_to_point(&a.vertices[0], b) {
+            return Some((a.vertices[0], point_b));
+        }
+    } else if b.vertices.len() == 1 {
+        if let Some(point_a) = closest_to_point(&b.vertices[0], a) {
+            return Some((point_a, b.vertices[0]));
+        }
+    }
+
+    let support = |direction: &glm::Vec3| {
+        let point_a = a.support(direction);
+        let point_b = b.support(&-direction);
+        SupportPoint { point: point_a - point_b, a: point_a, b: point_b }
+    };
+
+    let mut simplex = vec![support(&(b.center - a.center))];
+    let mut weights = vec![1.0];
+    let mut closest = simplex[0].point;
+
+    for _ in 0..MAX_ITERATIONS {
+        let distance_squared = glm::length2(&closest);
+        if distance_squared < 1e-12 {
+            return None;
+        }
+
+        let new_point = support(&-closest);
+        //No new point gets meaningfully closer to the origin, so this is as close as it gets
+        if distance_squared - glm::dot(&closest, &new_point.point) <= distance_squared * 1e-6 {
+            break;
+        }
+        if simplex.iter().any(|point| glm::distance2(&point.point, &new_point.point) < 1e-12) {
+            break;
+        }
+
+        simplex.push(new_point);
+        weights = reduce_simplex(&mut simplex)?;
+        closest = simplex.iter().zip(weights.iter()).fold(glm::Vec3::zeros(), |sum, (point, weight)| sum + point.point * *weight);
+    }
+
+    let point_a = simplex.iter().zip(weights.iter()).fold(glm::Vec3::zeros(), |sum, (point, weight)| sum + point.a * *weight);
+    let point_b = simplex.iter().zip(weights.iter()).fold(glm::Vec3::zeros(), |sum, (point, weight)| sum + point.b * *weight);
+    Some((point_a, point_b))
+}
+
+//Keeps only the points needed for the simplex's closest point to the origin, and returns their weights. None if the simplex contains the origin
+fn reduce_simplex(simplex: &mut Vec<SupportPoint>) -> Option<Vec<f32>> {
+    let weights = match simplex.len() {
+        1 => vec![1.0],
+        2 => closest_on_segment(&simplex[0].point, &simplex[1].point).to_vec(),
+        3 => closest_on_triangle(&simplex[0].point, &simplex[1].point, &simplex[2].point).to_vec(),
+        _ => closest_on_tetrahedron(simplex)?
+    };
+
+    let mut kept_weights = Vec::with_capacity(weights.len());
+    let mut index = 0;
+    simplex.retain(|_| {
+        let weight = weights[index];
+        index += 1;
+        if weight > 0.0 {
+            kept_weights.push(weight);
+        }
+        weight > 0.0
+    });
+
+    Some(kept_weights)
+}
+
+fn ratio(numerator: f32, denominator: f32) -> f32 {
+    if denominator > 0.0 { numerator / denominator } else { 0.0 }
+}
+
+fn closest_on_segment(a: &glm::Vec3, b: &glm::Vec3) -> [f32; 2] {
+    let t = ratio(glm::dot(&-a, &(b - a)), glm::length2(&(b - a))).clamp(0.0, 1.0);
+    [1.0 - t, t]
+}
+
+//From Real-Time Collision Detection 5.1.5, as barycentric weights
+fn closest_on_triangle(a: &glm::Vec3, b: &glm::Vec3, c: &glm::Vec3) -> [f32; 3] {
+    let ab = b - a;
+    let ac = c - a;
+
+    let d1 = glm::dot(&ab, &-a);
+    let d2 = glm::dot(&ac, &-a);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return [1.0, 0.0, 0.0];
+    }
+
+    let d3 = glm::dot(&ab, &-b);
+    let d4 = glm::dot(&ac, &-b);
+    if d3 >= 0.0 && d4 <= d3 {
+        return [0.0, 1.0, 0.0];
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        let v = ratio(d1, d1 - d3);
+        return [1.0 - v, v, 0.0];
+    }
+
+    let d5 = glm::dot(&ab, &-c);
+    let d6 = glm::dot(&ac, &-c);
+    if d6 >= 0.0 && d5 <= d6 {
+        return [0.0, 0.0, 1.0];
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        let w = ratio(d2, d2 - d6);
+        return [1.0 - w, 0.0, w];
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        let w = ratio(d4 - d3, (d4 - d3) + (d5 - d6));
+        return [0.0, 1.0 - w, w];
+    }
+
+    let denominator = va + vb + vc;
+    if denominator <= 0.0 {
+        //Degenerate, so the closest edge will do
+        let [u, v] = closest_on_segment(a, b);
+        return [u, v, 0.0];
+    }
+
+    let v = vb / denominator;
+    let w = vc / denominator;
+    [1.0 - v - w, v, w]
+}
+
+fn closest_on_tetrahedron(simplex: &[SupportPoint]) -> Option<Vec<f32>> {
+    //Each face, then the vertex opposite it
+    const FACES: [[usize; 4]; 4] = [[0, 1, 2, 3], [0, 2, 3, 1], [0, 3, 1, 2], [1, 3, 2, 0]];
+
+    let mut best: Option<(f32, Vec<f32>)> = None;
+    for face in FACES.iter() {
+        let a = simplex[face[0]].point;
+        let b = simplex[face[1]].point;
+        let c = simplex[face[2]].point;
+        let d = simplex[face[3]].point;
+
+        let normal = glm::cross(&(b - a), &(c - a));
+        let origin_side = glm::dot(&-a, &normal);
+        let opposite_side = glm::dot(&(d - a), &normal);
+        if origin_side * opposite_side > 0.0 {
+            continue;
+        }
+
+        let face_weights = closest_on_triangle(&a, &b, &c);
+        let closest = a * face_weights[0] + b * face_weights[1] + c * face_weights[2];
+        let distance_squared = glm::length2(&closest);
+
+        let is_closer = match best.as_ref() {
+            Some((best_distance_squared, _)) => distance_squared < *best_distance_squared,
+            None => true
+        };
+        if is_closer {
+            let mut weights = vec![0.0; 4];
+            for (vertex, weight) in face.iter().take(3).zip(face_weights.iter()) {
+                weights[*vertex] = *weight;
+            }
+            best = Some((distance_squared, weights));
+        }
+    }
+
+    //The origin is inside every face
+    best.map(|(_, weights)| weights)
+}
+
+//For overlapping cores, picks whichever candidate axis separates the shapes with the least movement
+fn penetration(a: &ConvexShape, b: &ConvexShape) -> (glm::Vec3, f32, glm::Vec3, glm::Vec3) {
+    let center_offset = b.center - a.center;
+
+    //Triangles only push along their normal, so mesh surfaces don't shove things sideways off their edges
+    let mut candidates = if a.is_triangle {
+        a.face_normals.clone()
+    } else if b.is_triangle {
+        b.face_normals.clone()
+    } else {
+        let mut candidates = a.face_normals.clone();
+        candidates.extend(b.face_normals.iter());
+        candidates.push(center_offset);
+        candidates
+    };
+    candidates.retain(|axis| glm::length2(axis) > 1e-12);
+    if candidates.is_empty() {
+        candidates.push(glm::vec3(0.0, 0.0, 1.0));
+    }
+
+    let mut best: Option<(glm::Vec3, f32, glm::Vec3, glm::Vec3)> = None;
+    for axis in candidates.iter() {
+        let mut normal = glm::normalize(axis);
+        if glm::dot(&normal, &center_offset) < 0.0 {
+            normal = -normal;
+        }
+
+        let point_a = a.support(&normal);
+        let point_b = b.support(&-normal);
+        let depth = glm::dot(&(point_a - point_b), &normal) + a.radius + b.radius;
+
+        let is_shallower = match best.as_ref() {
+            Some((_, best_depth, _, _)) => depth < *best_depth,
+            None => true
+        };
+        if is_shallower {
+            best = Some((normal, depth, point_a, point_b));
+        }
+    }
+
+    best.unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collide_all(a: &ConvexShape, b: &ConvexShape, max_gap: f32) -> Vec<ContactPoint> {
+        let mut contacts = vec![];
+        collide(a, b, max_gap, &mut contacts);
+        contacts
+    }
+
+    #[test]
+    fn overlapping_spheres() {
+        let a = ConvexShape::sphere(glm::vec3(0.0, 0.0, 0.0), 1.0);
+        let b = ConvexShape::sphere(glm::vec3(1.5, 0.0, 0.0), 1.0);
+
+        let contacts = collide_all(&a, &b, 0.0);
+        assert_eq!(contacts.len(), 1);
+        assert!(glm::distance(&contacts[0].normal, &glm::vec3(1.0, 0.0, 0.0)) < 1e-4);
+        assert!((contacts[0].depth - 0.5).abs() < 1e-4);
+        assert!(contacts[0].position.x > 0.4 && contacts[0].position.x < 1.1);
+    }
+
+    #[test]
+    fn separated_spheres() {
+        let a = ConvexShape::sphere(glm::vec3(0.0, 0.0, 0.0), 1.0);
+        let b = ConvexShape::sphere(glm::vec3(2.05, 0.0, 0.0), 1.0);
+
+        assert!(collide_all(&a, &b, 0.01).is_empty());
+
+        //Within the gap, the contact is kept with a negative depth
+        let contacts = collide_all(&a, &b, 0.1);
+        assert_eq!(contacts.len(), 1);
+        assert!((contacts[0].depth + 0.05).abs() < 1e-4);
+    }
+
+    #[test]
+    fn box_resting_on_triangle() {
+        let triangle = ConvexShape::triangle(glm::vec3(-5.0, -5.0, 0.0), glm::vec3(5.0, -5.0, 0.0), glm::vec3(0.0, 5.0, 0.0));
+        let cube = ConvexShape::oriented_box(glm::vec3(0.0, 0.0, 0.5), &glm::Mat3::identity(), &glm::vec3(0.5, 0.5, 0.5));
+
+        let contacts = collide_all(&cube, &triangle, 0.01);
+        //The whole bottom face rests on the triangle, so it needs more than one point to not tip over
+        assert!(contacts.len() >= 3);
+        for contact in contacts.iter() {
+            assert!(glm::distance(&contact.normal, &glm::vec3(0.0, 0.0, -1.0)) < 1e-3);
+            assert!(contact.depth.abs() < 1e-3);
+            assert!(contact.position.z.abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn deep_overlap() {
+        //Deep enough that the cores overlap, which GJK alone can't find a normal for
+        let a = ConvexShape::oriented_box(glm::vec3(0.0, 0.0, 0.0), &glm::Mat3::identity(), &glm::vec3(1.0, 1.0, 1.0));
+        let b = ConvexShape::oriented_box(glm::vec3(0.0, 0.0, 1.2), &glm::Mat3::identity(), &glm::vec3(1.0, 1.0, 1.0));
+
+        let contacts = collide_all(&a, &b, 0.0);
+        assert!(!contacts.is_empty());
+        for contact in contacts.iter() {
+            assert!(glm::distance(&contact.normal, &glm::vec3(0.0, 0.0, 1.0)) < 1e-3);
+            assert!((contact.depth - 0.8).abs() < 1e-3);
+        }
+
+        let sphere = ConvexShape::sphere(glm::vec3(0.0, 0.0, 0.0), 0.5);
+        let contacts = collide_all(&a, &sphere, 0.0);
+        assert!(!contacts.is_empty());
+        assert!(contacts.iter().all(|contact| contact.depth > 0.5));
+    }
+}
@@ -0,0 +1,9 @@
+mod collider;
+mod gjk;
+mod physics_world;
+mod rigid_body;
+mod triangle_bvh;
+
+pub use collider::{Collider, ColliderShape, ColliderMesh};
+pub use physics_world::{PhysicsWorld, PhysicsBody};
+pub use rigid_body::{RigidBody, RigidBodyKind, BodyPose};
@@ -0,0 +1,548 @@
+use super::{
+    collider::{Collider, ColliderShape, ColliderMesh},
+    gjk::{self, ConvexShape, ContactPoint},
+    rigid_body::{RigidBody, RigidBodyKind, BodyPose},
+    triangle_bvh::{TriangleBvh}
+};
+
+use nalgebra_glm as glm;
+
+use crate::resources::{Bounds};
+
+//Contacts are kept while shapes are this close, so resting bodies don't lose and regain them every step
+const CONTACT_MARGIN: f32 = 0.01;
+//Slower impacts don't bounce, or resting bodies would jitter
+const RESTITUTION_THRESHOLD: f32 = 0.5;
+
+#[derive(Debug, Copy, Clone)]
+pub struct PhysicsWorld {
+    pub gravity: glm::Vec3,
+    pub solver_iterations: u32,
+    //Fraction of the penetration pushed out each step
+    pub position_correction: f32,
+    //Penetration left alone so resting contacts stay touching
+    pub allowed_penetration: f32
+}
+
+impl Default for PhysicsWorld {
+    fn default() -> Self {
+        Self {
+            //Z is up
+            gravity: glm::vec3(0.0, 0.0, -9.81),
+            solver_iterations: 10,
+            position_correction: 0.2,
+            allowed_penetration: 0.01
+        }
+    }
+}
+
+//A collider and its rigid body, if it has one, copied out of the scene for a step
+#[derive(Debug, Clone)]
+pub struct PhysicsBody {
+    pub collider: Collider,
+    pub rigid_body: Option<RigidBody>,
+    //World space
+    pub pose: BodyPose,
+    pub scale: glm::Vec3
+}
+
+impl PhysicsBody {
+    //Dynamic bodies carry on from their simulated pose, and everything else is placed by its world matrix
+    pub fn new(collider: Collider, rigid_body: Option<RigidBody>, world: &glm::DMat4) -> Self {
+        let world = glm::convert::<glm::DMat4, glm::Mat4>(*world);
+        let scale = glm::vec3(
+            glm::length(&glm::column(&world, 0).xyz()),
+            glm::length(&glm::column(&world, 1).xyz()),
+            glm::length(&glm::column(&world, 2).xyz())
+        );
+
+        let mut rotation = world;
+        for column in 0..3 {
+            if scale[column] > 0.0 {
+                for row in 0..3 {
+                    rotation[(row, column)] /= scale[column];
+                }
+            }
+        }
+
+        let world_pose = BodyPose {
+            position: glm::column(&world, 3).xyz(),
+            orientation: glm::to_quat(&rotation)
+        };
+
+        let mut body = Self {
+            collider,
+            rigid_body,
+            pose: world_pose,
+            scale
+        };
+        if body.is_dynamic() {
+            if let Some(pose) = body.rigid_body.and_then(|rigid_body| rigid_body.pose()) {
+                body.pose = pose;
+            }
+        }
+
+        body
+    }
+
+    //Triangle meshes can't be moved by the simulation, so they're treated as kinematic
+    pub fn is_dynamic(&self) -> bool {
+        let is_dynamic_body = match self.rigid_body.as_ref() {
+            Some(rigid_body) => rigid_body.kind == RigidBodyKind::Dynamic,
+            None => false
+        };
+
+        is_dynamic_body && self.collider.shape != ColliderShape::TriangleMesh
+    }
+
+    fn world_matrix(&self) -> glm::Mat4 {
+        glm::translate(&glm::Mat4::identity(), &self.pose.position) * glm::quat_to_mat4(&self.pose.orientation) * glm::scaling(&self.scale)
+    }
+
+    //None for triangle meshes, which are collided a triangle at a time
+    fn convex_shape(&self) -> Option<ConvexShape> {
+        let rotation = glm::quat_to_mat3(&self.pose.orientation);
+        let position = self.pose.position;
+
+        match self.collider.shape {
+            ColliderShape::Sphere { radius } => Some(ConvexShape::sphere(position, radius * self.scale.max())),
+            ColliderShape::Box { half_extents } => Some(ConvexShape::oriented_box(position, &rotation, &glm::matrix_comp_mult(&half_extents, &self.scale))),
+            ColliderShape::Capsule { radius, half_height } => {
+                let axis = rotation * glm::vec3(0.0, 0.0, half_height * self.scale.z);
+                Some(ConvexShape::capsule(position - axis, position + axis, radius * self.scale.x.max(self.scale.y)))
+            },
+            ColliderShape::ConvexHull => {
+                let mesh = self.collider.mesh()?;
+                let bounds = mesh.data.bounds?;
+                let points = mesh.data.positions.iter()
+                    .map(|point| position + rotation * glm::matrix_comp_mult(point, &self.scale))
+                    .collect();
+                Some(ConvexShape::hull(points, &glm::matrix_comp_mult(&bounds.half_extents(), &self.scale)))
+            },
+            ColliderShape::TriangleMesh => None
+        }
+    }
+
+    fn world_mesh(&self) -> Option<WorldMesh<'_>> {
+        if self.collider.shape != ColliderShape::TriangleMesh {
+            return None;
+        }
+
+        let mesh = self.collider.mesh()?;
+        let world = self.world_matrix();
+
+        Some(WorldMesh {
+            mesh,
+            bvh: mesh.bvh.as_ref()?,
+            inverse_world: glm::inverse(&world),
+            positions: mesh.data.positions.iter().map(|point| (world * glm::vec4(point.x, point.y, point.z, 1.0)).xyz()).collect()
+        })
+    }
+}
+
+//A triangle mesh with its vertices moved into world space once per step, rather than once for each body near it
+struct WorldMesh<'a> {
+    mesh: &'a ColliderMesh,
+    bvh: &'a TriangleBvh,
+    inverse_world: glm::Mat4,
+    positions: Vec<glm::Vec3>
+}
+
+#[derive(Debug, Copy, Clone)]
+struct SolverBody {
+    position: glm::Vec3,
+    inverse_mass: f32,
+    inverse_inertia: glm::Mat3,
+    velocity: glm::Vec3,
+    angular_velocity: glm::Vec3
+}
+
+impl SolverBody {
+    fn velocity_at(&self, offset: &glm::Vec3) -> glm::Vec3 {
+        self.velocity + glm::cross(&self.angular_velocity, offset)
+    }
+
+    fn apply_impulse(&mut self, offset: &glm::Vec3, impulse: &glm::Vec3) -> () {
+        self.velocity += impulse * self.inverse_mass;
+        self.angular_velocity += self.inverse_inertia * glm::cross(offset, impulse);
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Contact {
+    body_a: usize,
+    body_b: usize,
+    //From a to b
+    normal: glm::Vec3,
+    tangents: [glm::Vec3; 2],
+    offset_a: glm::Vec3,
+    offset_b: glm::Vec3,
+    normal_mass: f32,
+    tangent_masses: [f32; 2],
+    friction: f32,
+    //The separating speed the solver aims for
+    bias: f32,
+    normal_impulse: f32,
+    tangent_impulses: [f32; 2]
+}
+
+fn effective_mass(a: &SolverBody, b: &SolverBody, offset_a: &glm::Vec3, offset_b: &glm::Vec3, direction: &glm::Vec3) -> f32 {
+    let cross_a = glm::cross(offset_a, direction);
+    let cross_b = glm::cross(offset_b, direction);
+    let k = a.inverse_mass + b.inverse_mass + glm::dot(&cross_a, &(a.inverse_inertia * cross_a)) + glm::dot(&cross_b, &(b.inverse_inertia * cross_b));
+
+    if k > 0.0 { 1.0 / k } else { 0.0 }
+}
+
+fn tangents(normal: &glm::Vec3) -> [glm::Vec3; 2] {
+    let tangent = if normal.x.abs() >= 0.57735 {
+        glm::normalize(&glm::vec3(normal.y, -normal.x, 0.0))
+    } else {
+        glm::normalize(&glm::vec3(0.0, normal.z, -normal.y))
+    };
+
+    [tangent, glm::cross(normal, &tangent)]
+}
+
+fn pair_mut(bodies: &mut [SolverBody], a: usize, b: usize) -> (&mut SolverBody, &mut SolverBody) {
+    if a < b {
+        let (first, second) = bodies.split_at_mut(b);
+        (&mut first[a], &mut second[0])
+    } else {
+        let (first, second) = bodies.split_at_mut(a);
+        (&mut second[0], &mut first[b])
+    }
+}
+
+fn overlaps(a: &(glm::Vec3, glm::Vec3), b: &(glm::Vec3, glm::Vec3)) -> bool {
+    (0..3).all(|axis| a.0[axis] <= b.1[axis] && a.1[axis] >= b.0[axis])
+}
+
+//Sort and sweep along the axis the boxes' centers are most spread out on. Pairs have the lower index first and are in index order, so the solver sees contacts in the same order every step
+fn overlapping_pairs(aabbs: &[(glm::Vec3, glm::Vec3)]) -> Vec<(usize, usize)> {
+    if aabbs.is_empty() {
+        return vec![];
+    }
+
+    let centers = aabbs.iter().map(|(min, max)| (min + max) * 0.5).collect::<Vec<_>>();
+    let mean = centers.iter().fold(glm::Vec3::zeros(), |sum, center| sum + center) / centers.len() as f32;
+    let variance = centers.iter().fold(glm::Vec3::zeros(), |sum, center| sum + (center - mean).component_mul(&(center - mean)));
+    let axis = variance.imax();
+
+    let mut order = (0..aabbs.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| aabbs[*a].0[axis].total_cmp(&aabbs[*b].0[axis]));
+
+    let mut pairs = vec![];
+    //Boxes that the sweep hasn't passed the end of yet
+    let mut active: Vec<usize> = vec![];
+    for index in order {
+        let min = aabbs[index].0[axis];
+        active.retain(|other| aabbs[*other].1[axis] >= min);
+
+        for other in active.iter() {
+            if overlaps(&aabbs[index], &aabbs[*other]) {
+                pairs.push((index.min(*other), index.max(*other)));
+            }
+        }
+        active.push(index);
+    }
+
+    pairs.sort_unstable();
+    pairs
+}
+
+impl PhysicsWorld {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //Advances the bodies by delta_time, writing the new poses and velocities back into them
+    pub fn step(&self, bodies: &mut [PhysicsBody], delta_time: f32) -> () {
+        if delta_time <= 0.0 {
+            return;
+        }
+
+        let mut solver_bodies = bodies.iter_mut()
+            .map(|body| self.integrate_velocity(body, delta_time))
+            .collect::<Vec<_>>();
+
+        let mut contacts = self.find_contacts(bodies, &solver_bodies, delta_time);
+
+        for _ in 0..self.solver_iterations {
+            for contact in contacts.iter_mut() {
+                Self::solve_contact(contact, &mut solver_bodies);
+            }
+        }
+
+        for (body, solver_body) in bodies.iter_mut().zip(solver_bodies.iter()) {
+            if !body.is_dynamic() {
+                continue;
+            }
+
+            let angular_velocity = solver_body.angular_velocity;
+            let spin = glm::quat(angular_velocity.x, angular_velocity.y, angular_velocity.z, 0.0) * body.pose.orientation;
+            body.pose.position += solver_body.velocity * delta_time;
+            body.pose.orientation = glm::quat_normalize(&(body.pose.orientation + spin * (0.5 * delta_time)));
+
+            if let Some(rigid_body) = body.rigid_body.as_mut() {
+                rigid_body.velocity = solver_body.velocity;
+                rigid_body.angular_velocity = solver_body.angular_velocity;
+            }
+        }
+    }
+
+    //Applies gravity and damping to dynamic bodies, and works out kinematic bodies' velocities from how far they moved since the last step
+    fn integrate_velocity(&self, body: &mut PhysicsBody, delta_time: f32) -> SolverBody {
+        let is_dynamic = body.is_dynamic();
+        let mut solver_body = SolverBody {
+            position: body.pose.position,
+            inverse_mass: 0.0,
+            inverse_inertia: glm::Mat3::zeros(),
+            velocity: glm::Vec3::zeros(),
+            angular_velocity: glm::Vec3::zeros()
+        };
+
+        let pose = body.pose;
+        let rigid_body = match body.rigid_body.as_mut() {
+            Some(rigid_body) => rigid_body,
+            None => return solver_body
+        };
+
+        if is_dynamic {
+            if rigid_body.mass > 0.0 {
+                let inertia = body.collider.local_inertia(rigid_body.mass, &body.scale);
+                let inverse_inertia = glm::vec3(
+                    if inertia.x > 0.0 { 1.0 / inertia.x } else { 0.0 },
+                    if inertia.y > 0.0 { 1.0 / inertia.y } else { 0.0 },
+                    if inertia.z > 0.0 { 1.0 / inertia.z } else { 0.0 }
+                );
+                let rotation = glm::quat_to_mat3(&pose.orientation);
+
+                solver_body.inverse_mass = 1.0 / rigid_body.mass;
+                solver_body.inverse_inertia = rotation * glm::Mat3::from_diagonal(&inverse_inertia) * glm::transpose(&rotation);
+            }
+
+            rigid_body.velocity += self.gravity * (rigid_body.gravity_scale * delta_time);
+            rigid_body.velocity /= 1.0 + rigid_body.linear_damping * delta_time;
+            rigid_body.angular_velocity /= 1.0 + rigid_body.angular_damping * delta_time;
+        } else {
+            match rigid_body.pose() {
+                Some(previous_pose) => {
+                    let delta_orientation = pose.orientation * glm::quat_conjugate(&previous_pose.orientation);
+                    let delta_orientation = if delta_orientation.w < 0.0 { -delta_orientation } else { delta_orientation };
+
+                    rigid_body.velocity = (pose.position - previous_pose.position) / delta_time;
+                    //Small angle approximation, since the rotation in one step is small
+                    rigid_body.angular_velocity = glm::vec3(delta_orientation.i, delta_orientation.j, delta_orientation.k) * (2.0 / delta_time);
+                },
+                None => {
+                    rigid_body.velocity = glm::Vec3::zeros();
+                    rigid_body.angular_velocity = glm::Vec3::zeros();
+                }
+            }
+        }
+
+        solver_body.velocity = rigid_body.velocity;
+        solver_body.angular_velocity = rigid_body.angular_velocity;
+        solver_body
+    }
+
+    fn find_contacts(&self, bodies: &[PhysicsBody], solver_bodies: &[SolverBody], delta_time: f32) -> Vec<Contact> {
+        let shapes = bodies.iter().map(|body| body.convex_shape()).collect::<Vec<_>>();
+        let meshes = bodies.iter().map(|body| body.world_mesh()).collect::<Vec<_>>();
+
+        //Grown by how far the body could move this step, so fast bodies find contacts before they pass through
+        let aabbs = bodies.iter().zip(shapes.iter()).zip(solver_bodies.iter())
+            .map(|((body, shape), solver_body)| {
+                let (min, max) = match (shape, body.collider.mesh().and_then(|mesh| mesh.data.bounds)) {
+                    (Some(shape), _) => shape.aabb(),
+                    (None, Some(bounds)) => {
+                        let bounds = bounds.transformed(&body.world_matrix());
+                        (bounds.min, bounds.max)
+                    },
+                    (None, None) => (glm::Vec3::zeros(), glm::Vec3::zeros())
+                };
+                let growth = glm::length(&solver_body.velocity) * delta_time + CONTACT_MARGIN;
+                (min.add_scalar(-growth), max.add_scalar(growth))
+            })
+            .collect::<Vec<_>>();
+
+        let mut contacts = vec![];
+        let mut points = vec![];
+        for (a, b) in overlapping_pairs(&aabbs) {
+            if !bodies[a].is_dynamic() && !bodies[b].is_dynamic() {
+                continue;
+            }
+
+            let max_gap = CONTACT_MARGIN + glm::length(&(solver_bodies[a].velocity - solver_bodies[b].velocity)) * delta_time;
+
+            points.clear();
+            match (shapes[a].as_ref(), shapes[b].as_ref(), meshes[a].as_ref(), meshes[b].as_ref()) {
+                (Some(shape_a), Some(shape_b), _, _) => gjk::collide(shape_a, shape_b, max_gap, &mut points),
+                (Some(shape_a), None, _, Some(mesh_b)) => Self::collide_mesh(shape_a, &aabbs[a], mesh_b, max_gap, &mut points),
+                (None, Some(shape_b), Some(mesh_a), _) => {
+                    Self::collide_mesh(shape_b, &aabbs[b], mesh_a, max_gap, &mut points);
+                    for point in points.iter_mut() {
+                        point.normal = -point.normal;
+                    }
+                },
+                _ => {}
+            }
+
+            for point in points.iter() {
+                contacts.push(self.create_contact((a, b), point, bodies, solver_bodies, delta_time));
+            }
+        }
+
+        contacts
+    }
+
+    //Contacts between a shape and each nearby triangle of a mesh, with normals from the shape to the mesh
+    fn collide_mesh(shape: &ConvexShape, aabb: &(glm::Vec3, glm::Vec3), mesh: &WorldMesh, max_gap: f32, points: &mut Vec<ContactPoint>) -> () {
+        let local_aabb = match Bounds::from_points(&[aabb.0, aabb.1]) {
+            Some(bounds) => bounds.transformed(&mesh.inverse_world),
+            None => return
+        };
+
+        mesh.bvh.query(&local_aabb.min, &local_aabb.max, |triangle| {
+            let face = &mesh.mesh.data.indices[triangle * 3..triangle * 3 + 3];
+            let (pt1, pt2, pt3) = (mesh.positions[face[0] as usize], mesh.positions[face[1] as usize], mesh.positions[face[2] as usize]);
+
+            //The leaves' boxes are loose, so most triangles they return don't come near the shape. These checks are much cheaper than GJK
+            let triangle_aabb = (glm::min2(&glm::min2(&pt1, &pt2), &pt3), glm::max2(&glm::max2(&pt1, &pt2), &pt3));
+            if !overlaps(&triangle_aabb, aabb) {
+                return;
+            }
+
+            let normal = glm::cross(&(pt2 - pt1), &(pt3 - pt1));
+            let length = glm::length(&normal);
+            if length > 0.0 {
+                let normal = normal / length;
+                let plane_distance = glm::dot(&pt1, &normal);
+                let (min, max) = shape.extent_along(&normal);
+                if min - plane_distance > max_gap || plane_distance - max > max_gap {
+                    return;
+                }
+            }
+
+            gjk::collide(shape, &ConvexShape::triangle(pt1, pt2, pt3), max_gap, points);
+        });
+    }
+
+    fn create_contact(&self, (body_a, body_b): (usize, usize), point: &ContactPoint, bodies: &[PhysicsBody], solver_bodies: &[SolverBody], delta_time: f32) -> Contact {
+        let a = &solver_bodies[body_a];
+        let b = &solver_bodies[body_b];
+        let friction = (bodies[body_a].collider.friction * bodies[body_b].collider.friction).sqrt();
+        let restitution = bodies[body_a].collider.restitution.max(bodies[body_b].collider.restitution);
+
+        let offset_a = point.position - a.position;
+        let offset_b = point.position - b.position;
+        let normal_speed = glm::dot(&(b.velocity_at(&offset_b) - a.velocity_at(&offset_a)), &point.normal);
+
+        //Speculative contacts let the bodies close the gap this step but no further
+        let mut bias = if point.depth < 0.0 {
+            point.depth / delta_time
+        } else {
+            self.position_correction * (point.depth - self.allowed_penetration).max(0.0) / delta_time
+        };
+        let will_touch = point.depth >= 0.0 || normal_speed * delta_time < point.depth;
+        if will_touch && normal_speed < -RESTITUTION_THRESHOLD {
+            bias = bias.max(-restitution * normal_speed);
+        }
+
+        let tangents = tangents(&point.normal);
+
+        Contact {
+            body_a,
+            body_b,
+            normal: point.normal,
+            tangents,
+            offset_a,
+            offset_b,
+            normal_mass: effective_mass(a, b, &offset_a, &offset_b, &point.normal),
+            tangent_masses: [effective_mass(a, b, &offset_a, &offset_b, &tangents[0]), effective_mass(a, b, &offset_a, &offset_b, &tangents[1])],
+            friction,
+            bias,
+            normal_impulse: 0.0,
+            tangent_impulses: [0.0; 2]
+        }
+    }
+
+    //Sequential impulses, clamping the accumulated impulse rather than each one so later iterations can take back too much push
+    fn solve_contact(contact: &mut Contact, solver_bodies: &mut [SolverBody]) -> () {
+        let (a, b) = pair_mut(solver_bodies, contact.body_a, contact.body_b);
+
+        let relative_velocity = b.velocity_at(&contact.offset_b) - a.velocity_at(&contact.offset_a);
+        let normal_speed = glm::dot(&relative_velocity, &contact.normal);
+        let new_normal_impulse = (contact.normal_impulse + contact.normal_mass * (contact.bias - normal_speed)).max(0.0);
+        let normal_impulse = contact.normal * (new_normal_impulse - contact.normal_impulse);
+        contact.normal_impulse = new_normal_impulse;
+        a.apply_impulse(&contact.offset_a, &-normal_impulse);
+        b.apply_impulse(&contact.offset_b, &normal_impulse);
+
+        let max_friction = contact.friction * contact.normal_impulse;
+        let tangents = contact.tangents.iter().zip(contact.tangent_masses.iter()).zip(contact.tangent_impulses.iter_mut());
+        for ((tangent, tangent_mass), accumulated_impulse) in tangents {
+            let relative_velocity = b.velocity_at(&contact.offset_b) - a.velocity_at(&contact.offset_a);
+            let tangent_speed = glm::dot(&relative_velocity, tangent);
+            let new_tangent_impulse = (*accumulated_impulse - tangent_mass * tangent_speed).clamp(-max_friction, max_friction);
+            let tangent_impulse = tangent * (new_tangent_impulse - *accumulated_impulse);
+            *accumulated_impulse = new_tangent_impulse;
+            a.apply_impulse(&contact.offset_a, &-tangent_impulse);
+            b.apply_impulse(&contact.offset_b, &tangent_impulse);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body_at(collider: Collider, rigid_body: Option<RigidBody>, position: glm::Vec3) -> PhysicsBody {
+        PhysicsBody::new(collider, rigid_body, &glm::translation(&glm::convert::<glm::Vec3, glm::DVec3>(position)))
+    }
+
+    #[test]
+    fn resting_box_stays_put() {
+        let world = PhysicsWorld::default();
+        let start = glm::vec3(0.0, 0.0, 0.5);
+        let mut bodies = vec![
+            body_at(Collider::cuboid(glm::vec3(5.0, 5.0, 0.5)), None, glm::vec3(0.0, 0.0, -0.5)),
+            body_at(Collider::cuboid(glm::vec3(0.5, 0.5, 0.5)), Some(RigidBody::dynamic(1.0)), start)
+        ];
+
+        for _ in 0..240 {
+            world.step(&mut bodies, 1.0 / 60.0);
+        }
+
+        //The static box isn't moved at all, and the resting one only sinks into it as far as the allowed penetration
+        assert_eq!(bodies[0].pose.position, glm::vec3(0.0, 0.0, -0.5));
+        let resting = &bodies[1];
+        assert!(glm::distance(&resting.pose.position, &start) < world.allowed_penetration + 0.01, "Moved to {:?}", resting.pose.position);
+        assert!(glm::quat_angle(&resting.pose.orientation) < 0.01);
+        assert!(glm::length(&resting.rigid_body.unwrap().velocity) < 0.05);
+    }
+
+    #[test]
+    fn sweep_matches_all_pairs() {
+        let aabbs = (0..40)
+            .map(|index| {
+                let index = index as f32;
+                let min = glm::vec3((index * 0.37).sin() * 3.0, (index * 0.71).cos() * 3.0, (index * 0.13).sin());
+                (min, min + glm::vec3(0.5, 0.8, 0.3 + (index * 0.5).sin().abs()))
+            })
+            .collect::<Vec<_>>();
+
+        let mut expected = vec![];
+        for a in 0..aabbs.len() {
+            for b in (a + 1)..aabbs.len() {
+                if overlaps(&aabbs[a], &aabbs[b]) {
+                    expected.push((a, b));
+                }
+            }
+        }
+
+        assert!(!expected.is_empty());
+        assert_eq!(overlapping_pairs(&aabbs), expected);
+    }
+}
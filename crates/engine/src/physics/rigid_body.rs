@@ -0,0 +1,103 @@
+use nalgebra_glm as glm;
+
+//Game objects with a collider but no rigid body are static
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RigidBodyKind {
+    //Moved by the simulation, which writes its pose into the game object's transform
+    Dynamic,
+    //Moved by the game object's transform. Pushes dynamic bodies out of its way, but nothing pushes back
+    Kinematic
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BodyPose {
+    pub position: glm::Vec3,
+    pub orientation: glm::Quat
+}
+
+impl BodyPose {
+    //Normalized lerp is close enough for the small rotations between fixed updates
+    pub fn lerp(&self, other: &BodyPose, alpha: f32) -> Self {
+        let other_orientation = if glm::quat_dot(&self.orientation, &other.orientation) < 0.0 { -other.orientation } else { other.orientation };
+
+        Self {
+            position: glm::lerp(&self.position, &other.position, alpha),
+            orientation: glm::quat_normalize(&glm::quat_lerp(&self.orientation, &other_orientation, alpha))
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct RigidBody {
+    pub kind: RigidBodyKind,
+    pub mass: f32,
+    //World space, per second
+    pub velocity: glm::Vec3,
+    //World space axis scaled by radians per second
+    pub angular_velocity: glm::Vec3,
+    //Fraction of the velocity lost per second
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    pub gravity_scale: f32,
+
+    //None until the body is first simulated, when it's taken from the game object's transform
+    pose: Option<BodyPose>,
+    //The pose before the last fixed update, so the rendered pose can be interpolated between fixed updates
+    previous_pose: Option<BodyPose>
+}
+
+impl Default for RigidBody {
+    fn default() -> Self {
+        Self {
+            kind: RigidBodyKind::Dynamic,
+            mass: 1.0,
+            velocity: glm::Vec3::zeros(),
+            angular_velocity: glm::Vec3::zeros(),
+            linear_damping: 0.01,
+            angular_damping: 0.05,
+            gravity_scale: 1.0,
+
+            pose: None,
+            previous_pose: None
+        }
+    }
+}
+
+impl RigidBody {
+    pub fn dynamic(mass: f32) -> Self {
+        Self {
+            mass,
+            ..Default::default()
+        }
+    }
+
+    pub fn kinematic() -> Self {
+        Self {
+            kind: RigidBodyKind::Kinematic,
+            ..Default::default()
+        }
+    }
+
+    pub fn pose(&self) -> Option<BodyPose> {
+        self.pose
+    }
+
+    //Moves the body without interpolating from where it was. Dynamic bodies ignore their transform once they're simulated, so use this instead
+    pub fn teleport(&mut self, pose: BodyPose) -> () {
+        self.pose = Some(pose);
+        self.previous_pose = Some(pose);
+    }
+
+    pub fn interpolated_pose(&self, alpha: f32) -> Option<BodyPose> {
+        match (self.previous_pose, self.pose) {
+            (Some(previous_pose), Some(pose)) => Some(previous_pose.lerp(&pose, alpha)),
+            (None, pose) => pose,
+            (_, None) => None
+        }
+    }
+
+    pub(crate) fn set_simulated_pose(&mut self, pose: BodyPose) -> () {
+        self.previous_pose = Some(self.pose.unwrap_or(pose));
+        self.pose = Some(pose);
+    }
+}
@@ -0,0 +1,177 @@
+use nalgebra_glm as glm;
+
+use crate::resources::{MeshData};
+
+const MAX_LEAF_TRIANGLES: usize = 4;
+
+#[derive(Debug, Copy, Clone)]
+struct BvhNode {
+    min: glm::Vec3,
+    max: glm::Vec3,
+    //Leaves have triangles, and other nodes have their children at first_child and first_child + 1
+    first: u32,
+    count: u32
+}
+
+//Bounding volume hierarchy over a mesh's triangles, in model space
+#[derive(Debug)]
+pub struct TriangleBvh {
+    nodes: Vec<BvhNode>,
+    //Triangle indices, ordered so each leaf's are contiguous
+    triangles: Vec<u32>
+}
+
+impl TriangleBvh {
+    pub fn new(mesh: &MeshData) -> Self {
+        let triangle_bounds = mesh.indices.chunks_exact(3)
+            .map(|face| {
+                let pt1 = mesh.positions[face[0] as usize];
+                let pt2 = mesh.positions[face[1] as usize];
+                let pt3 = mesh.positions[face[2] as usize];
+                (glm::min2(&glm::min2(&pt1, &pt2), &pt3), glm::max2(&glm::max2(&pt1, &pt2), &pt3))
+            })
+            .collect::<Vec<_>>();
+
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(triangle_bounds.len() * 2 / MAX_LEAF_TRIANGLES + 1),
+            triangles: (0..triangle_bounds.len() as u32).collect()
+        };
+        if !triangle_bounds.is_empty() {
+            bvh.nodes.push(BvhNode { min: glm::Vec3::zeros(), max: glm::Vec3::zeros(), first: 0, count: 0 });
+            bvh.build_node(0, 0, triangle_bounds.len(), &triangle_bounds);
+        }
+
+        bvh
+    }
+
+    fn build_node(&mut self, node_index: usize, start: usize, end: usize, triangle_bounds: &[(glm::Vec3, glm::Vec3)]) -> () {
+        let mut min = triangle_bounds[self.triangles[start] as usize].0;
+        let mut max = triangle_bounds[self.triangles[start] as usize].1;
+        for triangle in self.triangles[start..end].iter() {
+            let (triangle_min, triangle_max) = triangle_bounds[*triangle as usize];
+            min = glm::min2(&min, &triangle_min);
+            max = glm::max2(&max, &triangle_max);
+        }
+
+        if end - start <= MAX_LEAF_TRIANGLES {
+            self.nodes[node_index] = BvhNode { min, max, first: start as u32, count: (end - start) as u32 };
+            return;
+        }
+
+        //Median split along the longest axis
+        let extents = max - min;
+        let axis = if extents.x >= extents.y && extents.x >= extents.z { 0 } else if extents.y >= extents.z { 1 } else { 2 };
+        let centroid = |triangle: &u32| {
+            let (triangle_min, triangle_max) = triangle_bounds[*triangle as usize];
+            triangle_min[axis] + triangle_max[axis]
+        };
+        self.triangles[start..end].sort_by(|a, b| centroid(a).total_cmp(&centroid(b)));
+        let middle = (start + end) / 2;
+
+        let first_child = self.nodes.len();
+        self.nodes[node_index] = BvhNode { min, max, first: first_child as u32, count: 0 };
+        self.nodes.push(BvhNode { min: glm::Vec3::zeros(), max: glm::Vec3::zeros(), first: 0, count: 0 });
+        self.nodes.push(BvhNode { min: glm::Vec3::zeros(), max: glm::Vec3::zeros(), first: 0, count: 0 });
+
+        self.build_node(first_child, start, middle, triangle_bounds);
+        self.build_node(first_child + 1, middle, end, triangle_bounds);
+    }
+
+    //Calls back with the index of each triangle whose box overlaps the given box
+    pub fn query(&self, min: &glm::Vec3, max: &glm::Vec3, mut callback: impl FnMut(usize)) -> () {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut stack = vec![0usize];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            let overlaps = (0..3).all(|axis| node.min[axis] <= max[axis] && node.max[axis] >= min[axis]);
+            if !overlaps {
+                continue;
+            }
+
+            if node.count > 0 {
+                for triangle in self.triangles[node.first as usize..(node.first + node.count) as usize].iter() {
+                    callback(*triangle as usize);
+                }
+            } else {
+                stack.push(node.first as usize);
+                stack.push(node.first as usize + 1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //A grid of quads on the XY plane, one unit apart
+    fn grid(size: usize) -> MeshData {
+        let mut positions = vec![];
+        for y in 0..=size {
+            for x in 0..=size {
+                positions.push(glm::vec3(x as f32, y as f32, 0.0));
+            }
+        }
+
+        let mut indices = vec![];
+        let row = size as u32 + 1;
+        for y in 0..size as u32 {
+            for x in 0..size as u32 {
+                let corner = y * row + x;
+                indices.extend_from_slice(&[corner, corner + 1, corner + row + 1, corner, corner + row + 1, corner + row]);
+            }
+        }
+
+        MeshData::new(positions, indices)
+    }
+
+    fn query(bvh: &TriangleBvh, min: glm::Vec3, max: glm::Vec3) -> Vec<usize> {
+        let mut triangles = vec![];
+        bvh.query(&min, &max, |triangle| triangles.push(triangle));
+        triangles
+    }
+
+    #[test]
+    fn query_finds_overlapping_triangles() {
+        let mesh = grid(16);
+        let bvh = TriangleBvh::new(&mesh);
+        let (min, max) = (glm::vec3(3.2, 5.2, -0.1), glm::vec3(4.8, 6.8, 0.1));
+
+        let mut found = query(&bvh, min, max);
+        found.sort_unstable();
+        let before_dedup = found.len();
+        found.dedup();
+        assert_eq!(found.len(), before_dedup);
+
+        for (triangle, face) in mesh.indices.chunks_exact(3).enumerate() {
+            let points = face.iter().map(|index| mesh.positions[*index as usize]).collect::<Vec<_>>();
+            let triangle_min = glm::min2(&glm::min2(&points[0], &points[1]), &points[2]);
+            let triangle_max = glm::max2(&glm::max2(&points[0], &points[1]), &points[2]);
+            let overlaps = (0..3).all(|axis| triangle_min[axis] <= max[axis] && triangle_max[axis] >= min[axis]);
+
+            if overlaps {
+                assert!(found.binary_search(&triangle).is_ok(), "Missed triangle {}", triangle);
+            }
+        }
+        //Leaves are loose, but they shouldn't return most of the mesh
+        assert!(found.len() < mesh.indices.len() / 3 / 4);
+    }
+
+    #[test]
+    fn query_outside_mesh() {
+        let bvh = TriangleBvh::new(&grid(8));
+
+        assert!(query(&bvh, glm::vec3(20.0, 20.0, -1.0), glm::vec3(21.0, 21.0, 1.0)).is_empty());
+        assert!(query(&bvh, glm::vec3(1.0, 1.0, 0.5), glm::vec3(2.0, 2.0, 1.0)).is_empty());
+    }
+
+    #[test]
+    fn query_empty_mesh() {
+        let bvh = TriangleBvh::new(&MeshData::new(vec![], vec![]));
+
+        assert!(query(&bvh, glm::vec3(-1.0, -1.0, -1.0), glm::vec3(1.0, 1.0, 1.0)).is_empty());
+    }
+}
@@ -0,0 +1,65 @@
+use super::{PostprocessEffect, PostprocessPass, PostprocessImage};
+
+use crate::{
+    resources::shader_source::{ShaderSource}
+};
+
+pub const BLOOM_EFFECT_NAME: &str = "bloom";
+
+#[derive(Debug, Copy, Clone)]
+pub struct BloomSettings {
+    //Luminance above which pixels start to bloom
+    pub threshold: f32,
+    pub intensity: f32,
+    //In texels of the (quarter resolution) bloom image
+    pub radius: f32
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            threshold: 4.0,
+            intensity: 0.15,
+            radius: 1.5
+        }
+    }
+}
+
+impl From<BloomSettings> for PostprocessEffect {
+    fn from(settings: BloomSettings) -> Self {
+        let extract_bytes = include_bytes!("../../shaders/bloom/extract.frag.spv");
+        let blur_horizontal_bytes = include_bytes!("../../shaders/bloom/blur_horizontal.frag.spv");
+        let blur_vertical_bytes = include_bytes!("../../shaders/bloom/blur_vertical.frag.spv");
+        let composite_bytes = include_bytes!("../../shaders/bloom/composite.frag.spv");
+
+        //Bright parts are pulled out at quarter resolution, blurred there separably, then added back over the input
+        let passes = vec![
+            PostprocessPass {
+                fragment_shader: ShaderSource::Source(extract_bytes.to_vec().into_boxed_slice(), "main"),
+                input: PostprocessImage::EffectInput,
+                output: PostprocessImage::Intermediate(0)
+            },
+            PostprocessPass {
+                fragment_shader: ShaderSource::Source(blur_horizontal_bytes.to_vec().into_boxed_slice(), "main"),
+                input: PostprocessImage::Intermediate(0),
+                output: PostprocessImage::Intermediate(1)
+            },
+            PostprocessPass {
+                fragment_shader: ShaderSource::Source(blur_vertical_bytes.to_vec().into_boxed_slice(), "main"),
+                input: PostprocessImage::Intermediate(1),
+                output: PostprocessImage::Intermediate(0)
+            },
+            PostprocessPass {
+                fragment_shader: ShaderSource::Source(composite_bytes.to_vec().into_boxed_slice(), "main"),
+                input: PostprocessImage::Intermediate(0),
+                output: PostprocessImage::EffectOutput
+            }
+        ];
+
+        PostprocessEffect::new_with_parameters(BLOOM_EFFECT_NAME, passes, vec![0.25, 0.25], [
+            ("threshold", settings.threshold),
+            ("intensity", settings.intensity),
+            ("radius", settings.radius)
+        ])
+    }
+}
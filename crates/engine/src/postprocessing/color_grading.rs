@@ -0,0 +1,49 @@
+use nalgebra_glm as glm;
+
+use super::{PostprocessEffect, PostprocessPass, PostprocessImage};
+
+use crate::{
+    resources::shader_source::{ShaderSource}
+};
+
+pub const COLOR_GRADING_EFFECT_NAME: &str = "color_grading";
+
+#[derive(Debug, Copy, Clone)]
+pub struct ColorGradingSettings {
+    //0.0 is greyscale, 1.0 leaves colors unchanged
+    pub saturation: f32,
+    pub contrast: f32,
+    //Multiplied into the color before anything else
+    pub tint: glm::Vec3
+}
+
+impl Default for ColorGradingSettings {
+    fn default() -> Self {
+        Self {
+            saturation: 1.0,
+            contrast: 1.0,
+            tint: glm::vec3(1.0, 1.0, 1.0)
+        }
+    }
+}
+
+impl From<ColorGradingSettings> for PostprocessEffect {
+    fn from(settings: ColorGradingSettings) -> Self {
+        let frag_bytes = include_bytes!("../../shaders/color_grading/shader.frag.spv").to_vec();
+        let passes = vec![
+            PostprocessPass {
+                fragment_shader: ShaderSource::Source(frag_bytes.into_boxed_slice(), "main"),
+                input: PostprocessImage::EffectInput,
+                output: PostprocessImage::EffectOutput
+            }
+        ];
+
+        PostprocessEffect::new_with_parameters(COLOR_GRADING_EFFECT_NAME, passes, vec![], [
+            ("saturation", settings.saturation),
+            ("contrast", settings.contrast),
+            ("tint_r", settings.tint.x),
+            ("tint_g", settings.tint.y),
+            ("tint_b", settings.tint.z)
+        ])
+    }
+}
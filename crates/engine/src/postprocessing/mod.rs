@@ -0,0 +1,13 @@
+mod postprocess_effect;
+
+mod bloom;
+mod color_grading;
+mod motion_blur;
+mod tone_mapping;
+
+pub use postprocess_effect::{PostprocessEffect, PostprocessPass, PostprocessImage, MAX_POSTPROCESS_PARAMETERS};
+
+pub use bloom::{BloomSettings, BLOOM_EFFECT_NAME};
+pub use color_grading::{ColorGradingSettings, COLOR_GRADING_EFFECT_NAME};
+pub use motion_blur::{MotionBlurSettings, MOTION_BLUR_EFFECT_NAME};
+pub use tone_mapping::{ToneMappingSettings, TONE_MAPPING_EFFECT_NAME};
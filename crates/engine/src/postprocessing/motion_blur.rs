@@ -0,0 +1,38 @@
+use super::{PostprocessEffect, PostprocessPass, PostprocessImage};
+
+use crate::{
+    resources::shader_source::{ShaderSource}
+};
+
+pub const MOTION_BLUR_EFFECT_NAME: &str = "motion_blur";
+
+#[derive(Debug, Copy, Clone)]
+pub struct MotionBlurSettings {
+    //Fraction of the frame the shutter stays open for. 0.5 blurs over half of each object's motion since the last frame
+    pub shutter: f32
+}
+
+impl Default for MotionBlurSettings {
+    fn default() -> Self {
+        Self {
+            shutter: 0.5
+        }
+    }
+}
+
+impl From<MotionBlurSettings> for PostprocessEffect {
+    fn from(settings: MotionBlurSettings) -> Self {
+        let frag_bytes = include_bytes!("../../shaders/motion_blur/shader.frag.spv").to_vec();
+        let passes = vec![
+            PostprocessPass {
+                fragment_shader: ShaderSource::Source(frag_bytes.into_boxed_slice(), "main"),
+                input: PostprocessImage::EffectInput,
+                output: PostprocessImage::EffectOutput
+            }
+        ];
+
+        PostprocessEffect::new_with_parameters(MOTION_BLUR_EFFECT_NAME, passes, vec![], [
+            ("shutter", settings.shutter)
+        ])
+    }
+}
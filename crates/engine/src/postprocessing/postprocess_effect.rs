@@ -0,0 +1,124 @@
+use anyhow::{anyhow, Result};
+
+use crate::{
+    resources::shader_source::{ShaderSource}
+};
+
+//Must match the size of the params array in the postprocessing shaders
+pub const MAX_POSTPROCESS_PARAMETERS: usize = 8;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PostprocessImage {
+    //Whatever the previous enabled effect produced, or the (upscaled) base render for the first one
+    EffectInput,
+    //What the rest of the chain sees as this effect's result
+    EffectOutput,
+    //One of the effect's own images, indexed into PostprocessEffect::intermediate_scales
+    Intermediate(usize)
+}
+
+#[derive(Debug, Clone)]
+pub struct PostprocessPass {
+    pub fragment_shader: ShaderSource,
+    pub input: PostprocessImage,
+    pub output: PostprocessImage
+}
+
+#[derive(Debug, Clone)]
+pub struct PostprocessEffect {
+    pub name: &'static str,
+    pub enabled: bool,
+    pub passes: Vec<PostprocessPass>,
+    //Each intermediate image is this fraction of the output resolution on each axis
+    pub intermediate_scales: Vec<f32>,
+
+    parameter_names: Vec<&'static str>,
+    parameters: [f32; MAX_POSTPROCESS_PARAMETERS]
+}
+
+impl PostprocessEffect {
+    pub fn new(name: &'static str, passes: Vec<PostprocessPass>, intermediate_scales: Vec<f32>) -> Self {
+        Self {
+            name,
+            enabled: true,
+            passes,
+            intermediate_scales,
+
+            parameter_names: vec![],
+            parameters: [0.0; MAX_POSTPROCESS_PARAMETERS]
+        }
+    }
+
+    //For the built in effects, whose parameter counts are checked at compile time
+    pub(crate) fn new_with_parameters<const N: usize>(name: &'static str, passes: Vec<PostprocessPass>, intermediate_scales: Vec<f32>, parameters: [(&'static str, f32); N]) -> Self {
+        const { assert!(N <= MAX_POSTPROCESS_PARAMETERS) };
+
+        let mut effect = Self::new(name, passes, intermediate_scales);
+        for (index, (name, value)) in parameters.into_iter().enumerate() {
+            effect.parameter_names.push(name);
+            effect.parameters[index] = value;
+        }
+
+        effect
+    }
+
+    //Parameters are handed to every pass of the effect in declaration order
+    pub fn with_parameter(mut self, name: &'static str, value: f32) -> Result<Self> {
+        let index = self.parameter_names.len();
+        if index >= MAX_POSTPROCESS_PARAMETERS {
+            return Err(anyhow!("Postprocess effect {} can't have more than {} parameters", self.name, MAX_POSTPROCESS_PARAMETERS));
+        }
+
+        self.parameter_names.push(name);
+        self.parameters[index] = value;
+
+        Ok(self)
+    }
+
+    pub fn parameter(&self, name: &str) -> Option<f32> {
+        self.parameter_names.iter()
+            .position(|parameter_name| *parameter_name == name)
+            .map(|index| self.parameters[index])
+    }
+
+    pub fn set_parameter(&mut self, name: &str, value: f32) -> Result<()> {
+        let index = self.parameter_names.iter()
+            .position(|parameter_name| *parameter_name == name)
+            .ok_or_else(|| anyhow!("Postprocess effect {} has no parameter named {}", self.name, name))?;
+        self.parameters[index] = value;
+
+        Ok(())
+    }
+
+    pub fn parameters(&self) -> &[f32; MAX_POSTPROCESS_PARAMETERS] {
+        &self.parameters
+    }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.passes.is_empty() {
+            return Err(anyhow!("Postprocess effect {} has no passes", self.name));
+        }
+
+        for pass in self.passes.iter() {
+            if pass.output == PostprocessImage::EffectInput {
+                return Err(anyhow!("A pass in postprocess effect {} writes to the effect's input", self.name));
+            }
+            if pass.input == pass.output {
+                return Err(anyhow!("A pass in postprocess effect {} reads from the same image it writes to", self.name));
+            }
+            for image in [pass.input, pass.output] {
+                if let PostprocessImage::Intermediate(index) = image {
+                    if index >= self.intermediate_scales.len() {
+                        return Err(anyhow!("A pass in postprocess effect {} uses intermediate image {}, but only {} were declared", self.name, index, self.intermediate_scales.len()));
+                    }
+                }
+            }
+        }
+
+        if self.passes.last().unwrap().output != PostprocessImage::EffectOutput {
+            return Err(anyhow!("The last pass in postprocess effect {} must write to the effect's output", self.name));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+use super::{PostprocessEffect, PostprocessPass, PostprocessImage};
+
+use crate::{
+    resources::shader_source::{ShaderSource}
+};
+
+pub const TONE_MAPPING_EFFECT_NAME: &str = "tone_mapping";
+
+//The operator and exposure come from the render camera's CameraPostSettings, so they can change per camera
+#[derive(Debug, Copy, Clone, Default)]
+pub struct ToneMappingSettings;
+
+impl From<ToneMappingSettings> for PostprocessEffect {
+    fn from(_settings: ToneMappingSettings) -> Self {
+        let frag_bytes = include_bytes!("../../shaders/tone_mapping/shader.frag.spv").to_vec();
+        let passes = vec![
+            PostprocessPass {
+                fragment_shader: ShaderSource::Source(frag_bytes.into_boxed_slice(), "main"),
+                input: PostprocessImage::EffectInput,
+                output: PostprocessImage::EffectOutput
+            }
+        ];
+
+        PostprocessEffect::new(TONE_MAPPING_EFFECT_NAME, passes, vec![])
+    }
+}
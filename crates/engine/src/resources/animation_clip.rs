@@ -0,0 +1,235 @@
+use super::{Skeleton, JointPose};
+
+use std::{
+    path::{Path}
+};
+use gltf::animation::{Interpolation, Property, util::ReadOutputs};
+use nalgebra_glm as glm;
+
+//One animated property of one joint. Vec3 values leave w unused, rotations are xyzw
+#[derive(Debug, Clone)]
+struct Keyframes {
+    interpolation: Interpolation,
+    times: Vec<f32>,
+    //Cubic splines store an in tangent, the value and an out tangent per keyframe
+    values: Vec<glm::Vec4>
+}
+
+impl Keyframes {
+    fn value(&self, keyframe: usize) -> glm::Vec4 {
+        match self.interpolation {
+            Interpolation::CubicSpline => self.values[keyframe * 3 + 1],
+            _ => self.values[keyframe]
+        }
+    }
+
+    fn sample(&self, time: f32, is_rotation: bool) -> glm::Vec4 {
+        let last = self.times.len() - 1;
+        if time <= self.times[0] {
+            return self.value(0);
+        }
+        if time >= self.times[last] {
+            return self.value(last);
+        }
+
+        let next = self.times.partition_point(|t| *t <= time);
+        let previous = next - 1;
+        let dt = self.times[next] - self.times[previous];
+        let t = if dt > 0.0 { (time - self.times[previous]) / dt } else { 0.0 };
+
+        match self.interpolation {
+            Interpolation::Step => self.value(previous),
+            Interpolation::Linear => {
+                let a = self.values[previous];
+                let mut b = self.values[next];
+                //Take the short way around
+                if is_rotation && a.dot(&b) < 0.0 {
+                    b = -b;
+                }
+                let value = glm::lerp(&a, &b, t);
+                if is_rotation { value.normalize() } else { value }
+            },
+            Interpolation::CubicSpline => {
+                let a = self.values[previous * 3 + 1];
+                let a_out = self.values[previous * 3 + 2] * dt;
+                let b_in = self.values[next * 3] * dt;
+                let b = self.values[next * 3 + 1];
+
+                let t2 = t * t;
+                let t3 = t2 * t;
+                let value = a * (2.0 * t3 - 3.0 * t2 + 1.0)
+                    + a_out * (t3 - 2.0 * t2 + t)
+                    + b * (-2.0 * t3 + 3.0 * t2)
+                    + b_in * (t3 - t2);
+                if is_rotation { value.normalize() } else { value }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct JointTrack {
+    translation: Option<Keyframes>,
+    rotation: Option<Keyframes>,
+    scale: Option<Keyframes>
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationClip {
+    pub name: Option<String>,
+    //Seconds
+    pub duration: f32,
+
+    //In the skeleton's joint order
+    tracks: Vec<JointTrack>
+}
+
+impl AnimationClip {
+    //Reads every animation in the document. Channels targeting nodes that aren't joints of the skeleton, and morph target weights, are ignored. The path is only for logging
+    pub(super) fn from_gltf(document: &gltf::Document, buffers: &[gltf::buffer::Data], path: &Path, skeleton: &Skeleton) -> Vec<Self> {
+        let clips = document.animations()
+            .map(|animation| {
+                let mut tracks = vec![JointTrack::default(); skeleton.joints.len()];
+                let mut duration = 0.0f32;
+
+                for channel in animation.channels() {
+                    let target = channel.target();
+                    let joint_index = match skeleton.joint_index_for_node(target.node().index()) {
+                        Some(joint_index) => joint_index,
+                        None => continue
+                    };
+
+                    let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+                    let times = match reader.read_inputs() {
+                        Some(inputs) => inputs.collect::<Vec<_>>(),
+                        None => continue
+                    };
+                    let values = match reader.read_outputs() {
+                        Some(ReadOutputs::Translations(translations)) => translations.map(|t| glm::vec4(t[0], t[1], t[2], 0.0)).collect::<Vec<_>>(),
+                        Some(ReadOutputs::Rotations(rotations)) => rotations.into_f32().map(|r| glm::Vec4::from(r)).collect::<Vec<_>>(),
+                        Some(ReadOutputs::Scales(scales)) => scales.map(|s| glm::vec4(s[0], s[1], s[2], 0.0)).collect::<Vec<_>>(),
+                        Some(ReadOutputs::MorphTargetWeights(_)) | None => continue
+                    };
+
+                    let interpolation = channel.sampler().interpolation();
+                    let values_per_keyframe = if interpolation == Interpolation::CubicSpline { 3 } else { 1 };
+                    if times.is_empty() || values.len() != times.len() * values_per_keyframe {
+                        warn!("Skipping animation channel in {:?} with {} keyframes and {} values", path, times.len(), values.len());
+                        continue;
+                    }
+
+                    duration = duration.max(*times.last().unwrap());
+                    let keyframes = Some(Keyframes {
+                        interpolation,
+                        times,
+                        values
+                    });
+                    let track = &mut tracks[joint_index];
+                    match target.property() {
+                        Property::Translation => track.translation = keyframes,
+                        Property::Rotation => track.rotation = keyframes,
+                        Property::Scale => track.scale = keyframes,
+                        Property::MorphTargetWeights => {}
+                    }
+                }
+
+                Self {
+                    name: animation.name().map(|n| n.to_owned()),
+                    duration,
+                    tracks
+                }
+            })
+            .collect::<Vec<_>>();
+
+        debug!("Loaded {} animation clips from {:?}", clips.len(), path);
+        clips
+    }
+
+    //Overwrites the animated properties of each joint's pose at the given time in seconds, leaving the rest alone
+    pub fn sample(&self, time: f32, poses: &mut [JointPose]) -> () {
+        for (track, pose) in self.tracks.iter().zip(poses.iter_mut()) {
+            if let Some(translation) = &track.translation {
+                pose.translation = translation.sample(time, false).xyz();
+            }
+            if let Some(rotation) = &track.rotation {
+                let r = rotation.sample(time, true);
+                pose.rotation = glm::quat(r.x, r.y, r.z, r.w);
+            }
+            if let Some(scale) = &track.scale {
+                pose.scale = scale.sample(time, false).xyz();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scalar_keyframes(interpolation: Interpolation, times: Vec<f32>, values: Vec<f32>) -> Keyframes {
+        Keyframes {
+            interpolation,
+            times,
+            values: values.into_iter().map(|value| glm::vec4(value, 0.0, 0.0, 0.0)).collect()
+        }
+    }
+
+    fn assert_near(a: f32, b: f32) -> () {
+        assert!((a - b).abs() < 1e-5, "{} isn't {}", a, b);
+    }
+
+    #[test]
+    fn linear() {
+        let keyframes = scalar_keyframes(Interpolation::Linear, vec![1.0, 2.0, 4.0], vec![0.0, 1.0, 5.0]);
+
+        assert_near(keyframes.sample(1.5, false).x, 0.5);
+        assert_near(keyframes.sample(3.0, false).x, 3.0);
+        assert_near(keyframes.sample(2.0, false).x, 1.0);
+        //Held before the first keyframe and after the last
+        assert_near(keyframes.sample(0.0, false).x, 0.0);
+        assert_near(keyframes.sample(10.0, false).x, 5.0);
+    }
+
+    #[test]
+    fn linear_rotation_takes_the_short_way() {
+        let quarter_turn = glm::quat_angle_axis(std::f32::consts::FRAC_PI_2, &glm::vec3(0.0, 0.0, 1.0));
+        let keyframes = Keyframes {
+            interpolation: Interpolation::Linear,
+            times: vec![0.0, 1.0],
+            //The same rotations as identity and a quarter turn, written the long way around
+            values: vec![glm::vec4(0.0, 0.0, 0.0, 1.0), -quarter_turn.coords]
+        };
+
+        let halfway = keyframes.sample(0.5, true);
+        let expected = glm::quat_angle_axis(std::f32::consts::FRAC_PI_4, &glm::vec3(0.0, 0.0, 1.0)).coords;
+        assert_near(halfway.norm(), 1.0);
+        assert_near(halfway.dot(&expected).abs(), 1.0);
+    }
+
+    #[test]
+    fn step() {
+        let keyframes = scalar_keyframes(Interpolation::Step, vec![0.0, 1.0, 2.0], vec![3.0, 7.0, 11.0]);
+
+        assert_near(keyframes.sample(0.0, false).x, 3.0);
+        assert_near(keyframes.sample(0.99, false).x, 3.0);
+        assert_near(keyframes.sample(1.0, false).x, 7.0);
+        assert_near(keyframes.sample(1.5, false).x, 7.0);
+        assert_near(keyframes.sample(2.5, false).x, 11.0);
+    }
+
+    #[test]
+    fn cubic_spline() {
+        //In tangent, value and out tangent per keyframe
+        let keyframes = scalar_keyframes(Interpolation::CubicSpline, vec![0.0, 2.0], vec![0.0, 0.0, 1.0, 0.0, 1.0, 0.0]);
+
+        //Tangents are per second, so they're scaled by the 2 seconds between the keyframes
+        assert_near(keyframes.sample(1.0, false).x, 0.5 * 0.0 + 0.125 * 2.0 + 0.5 * 1.0 - 0.125 * 0.0);
+        assert_near(keyframes.sample(0.0, false).x, 0.0);
+        assert_near(keyframes.sample(2.0, false).x, 1.0);
+
+        //With flat tangents it eases in and out
+        let flat = scalar_keyframes(Interpolation::CubicSpline, vec![0.0, 1.0], vec![0.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+        assert_near(flat.sample(0.5, false).x, 0.5);
+        assert_near(flat.sample(0.25, false).x, 0.15625);
+    }
+}
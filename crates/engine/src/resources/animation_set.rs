@@ -0,0 +1,32 @@
+use super::{Skeleton, AnimationClip};
+
+use std::{
+    path::{Path},
+    sync::{Arc}
+};
+use anyhow::{Result};
+
+//A skin's skeleton and every animation clip for it, read from the same glTF file
+#[derive(Debug, Clone)]
+pub struct AnimationSet {
+    pub skeleton: Arc<Skeleton>,
+    pub clips: Vec<Arc<AnimationClip>>
+}
+
+impl AnimationSet {
+    //Loads the first skin in the file if skin_index is None. The file and its buffers are only read once, for the skeleton and the clips
+    pub fn load_gltf<P: AsRef<Path>>(path: P, skin_index: Option<usize>) -> Result<Self> {
+        let path = path.as_ref();
+        let gltf = gltf::Gltf::open(path)?;
+        let document = &gltf.document;
+        let buffers = gltf::import_buffers(document, path.parent(), gltf.blob.clone())?;
+
+        let skeleton = Skeleton::from_gltf(document, &buffers, path, skin_index)?;
+        let clips = AnimationClip::from_gltf(document, &buffers, path, &skeleton);
+
+        Ok(Self {
+            skeleton: Arc::new(skeleton),
+            clips: clips.into_iter().map(Arc::new).collect()
+        })
+    }
+}
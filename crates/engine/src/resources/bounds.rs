@@ -0,0 +1,91 @@
+use nalgebra_glm as glm;
+
+//An axis aligned box and a bounding sphere around the same points
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Bounds {
+    pub min: glm::Vec3,
+    pub max: glm::Vec3,
+    pub sphere_center: glm::Vec3,
+    pub sphere_radius: f32
+}
+
+impl Bounds {
+    //None if there are no points
+    pub fn from_points(points: &[glm::Vec3]) -> Option<Self> {
+        let first = points.first()?;
+
+        let mut min = *first;
+        let mut max = *first;
+        for point in points.iter() {
+            min = glm::min2(&min, point);
+            max = glm::max2(&max, point);
+        }
+
+        //Centering the sphere on the box is tighter than Ritter's method for most meshes, and much simpler
+        let sphere_center = (min + max) * 0.5;
+        let sphere_radius = points.iter()
+            .map(|point| glm::distance(&sphere_center, point))
+            .fold(0.0f32, f32::max);
+
+        Some(Self {
+            min,
+            max,
+            sphere_center,
+            sphere_radius
+        })
+    }
+
+    pub fn center(&self) -> glm::Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> glm::Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    //Where the ray enters the box, as a distance along the direction. Zero if it starts inside
+    pub fn intersect_ray(&self, origin: &glm::Vec3, direction: &glm::Vec3) -> Option<f32> {
+        let mut enter = 0.0f32;
+        let mut exit = f32::INFINITY;
+
+        for axis in 0..3 {
+            if direction[axis] == 0.0 {
+                if origin[axis] < self.min[axis] || origin[axis] > self.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+
+            let t1 = (self.min[axis] - origin[axis]) / direction[axis];
+            let t2 = (self.max[axis] - origin[axis]) / direction[axis];
+            enter = enter.max(t1.min(t2));
+            exit = exit.min(t1.max(t2));
+        }
+
+        if enter <= exit { Some(enter) } else { None }
+    }
+
+    //The result still contains everything, but the box grows if the transform rotates it
+    pub fn transformed(&self, matrix: &glm::Mat4) -> Self {
+        let center = (matrix * glm::vec4(self.center().x, self.center().y, self.center().z, 1.0)).xyz();
+        let half_extents = self.half_extents();
+
+        let mut new_half_extents = glm::Vec3::zeros();
+        for row in 0..3 {
+            for column in 0..3 {
+                new_half_extents[row] += matrix[(row, column)].abs() * half_extents[column];
+            }
+        }
+
+        let max_scale = (0..3)
+            .map(|column| glm::length(&glm::vec3(matrix[(0, column)], matrix[(1, column)], matrix[(2, column)])))
+            .fold(0.0f32, f32::max);
+
+        Self {
+            min: center - new_half_extents,
+            max: center + new_half_extents,
+            sphere_center: (matrix * glm::vec4(self.sphere_center.x, self.sphere_center.y, self.sphere_center.z, 1.0)).xyz(),
+            sphere_radius: self.sphere_radius * max_scale
+        }
+    }
+}
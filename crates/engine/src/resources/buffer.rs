@@ -169,6 +169,24 @@ impl<T> Buffer<T> where T : Copy + Clone {
             Ok(())
         }
     }
+    //Only works for buffers that don't require a submit, because those are the ones that live in host visible memory
+    pub fn get_data(&self, device: &Device) -> Result<Vec<T>> {
+        if self.require_submit {
+            return Err(anyhow!("Buffer requires a submit, so its memory isn't host visible. Can't call get_data!"));
+        }
+        let buff_memory = self.buffer_memory.ok_or_else(|| anyhow!("Buffer is not created. Can't call get_data until it is created."))?;
+
+        let count = self.max_element_count;
+        let mut data = Vec::<T>::with_capacity(count);
+        unsafe {
+            let memory = device.map_memory(buff_memory, 0, self.allocated_buffer_size(), vk::MemoryMapFlags::empty())?;
+            memcpy(memory.cast::<T>(), data.as_mut_ptr(), count);
+            data.set_len(count);
+            device.unmap_memory(buff_memory);
+        }
+
+        Ok(data)
+    }
     unsafe fn set_data_from_ptr(&mut self, device: &Device, data_ptr: *const T, count: usize) -> Result<()> {
         let buff_memory = if self.require_submit { self.staging_buffer_memory.unwrap() } else { self.buffer_memory.unwrap() };
 
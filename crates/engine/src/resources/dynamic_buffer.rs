@@ -0,0 +1,15 @@
+use super::{Buffer};
+
+//For data that's rewritten every frame, like instances moved by physics. Each write goes to the next of several host visible copies, so frames still in flight keep reading the copy they were recorded with
+#[derive(Debug, Clone)]
+pub struct DynamicBuffer<T> where T : Copy + Clone {
+    pub(crate) copies: Vec<Buffer<T>>,
+    pub(crate) current: usize
+}
+
+impl<T> DynamicBuffer<T> where T : Copy + Clone {
+    //The copy that was written last, which is the one to draw with this frame
+    pub fn current(&self) -> Buffer<T> {
+        self.copies[self.current]
+    }
+}
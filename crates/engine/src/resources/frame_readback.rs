@@ -0,0 +1,36 @@
+use std::{
+    fs::{File},
+    io::{BufWriter},
+    path::{Path}
+};
+use anyhow::{anyhow, Result};
+use vulkanalia::{
+    prelude::v1_0::*
+};
+
+//A finished frame copied back from the GPU. Pixels are tightly packed, sRGB encoded RGBA, top row first
+#[derive(Debug, Clone)]
+pub struct FrameReadback {
+    pub frame_index: u32,
+    pub extent: vk::Extent2D,
+    pub pixels: Vec<u8>
+}
+
+impl FrameReadback {
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<()> {
+        let expected_len = (self.extent.width * self.extent.height * 4) as usize;
+        if self.pixels.len() != expected_len {
+            return Err(anyhow!("Frame readback has {} bytes, but a {}x{} RGBA image needs {}", self.pixels.len(), self.extent.width, self.extent.height, expected_len));
+        }
+
+        let file = File::create(path)?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.extent.width, self.extent.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+
+        Ok(())
+    }
+}
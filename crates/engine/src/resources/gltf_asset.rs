@@ -0,0 +1,224 @@
+use std::{
+    path::{Path, PathBuf}
+};
+use anyhow::{Result};
+use nalgebra_glm as glm;
+
+use crate::shader_input::uniform_buffer_object::{MaterialUniform};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GltfAlphaMode {
+    Opaque,
+    Mask,
+    Blend
+}
+
+#[derive(Debug, Clone)]
+pub struct GltfTextureInfo {
+    pub image_index: usize,
+    //Resolved relative to the glTF file. None if the image is embedded in a buffer
+    pub path: Option<PathBuf>,
+    pub tex_coord: u32
+}
+
+#[derive(Debug, Clone)]
+pub struct GltfMaterial {
+    pub name: Option<String>,
+
+    pub base_color_factor: glm::Vec4,
+    pub base_color_texture: Option<GltfTextureInfo>,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub metallic_roughness_texture: Option<GltfTextureInfo>,
+
+    pub normal_scale: f32,
+    pub normal_texture: Option<GltfTextureInfo>,
+    pub occlusion_strength: f32,
+    pub occlusion_texture: Option<GltfTextureInfo>,
+    pub emissive_factor: glm::Vec3,
+    pub emissive_texture: Option<GltfTextureInfo>,
+
+    pub alpha_mode: GltfAlphaMode,
+    pub alpha_cutoff: f32,
+    pub double_sided: bool
+}
+
+#[derive(Debug, Clone)]
+pub struct GltfPrimitive {
+    pub mesh_index: usize,
+    pub primitive_index: usize,
+    pub mesh_name: Option<String>,
+    pub material_index: Option<usize>,
+    //World transform of the node using this mesh, already converted to Z up
+    pub transform: glm::Mat4
+}
+
+#[derive(Debug, Clone)]
+pub struct GltfAsset {
+    pub path: PathBuf,
+    pub primitives: Vec<GltfPrimitive>,
+    pub materials: Vec<GltfMaterial>
+}
+
+impl GltfAsset {
+    //Only reads the document, buffers and images are left to the model and texture loaders
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let gltf = gltf::Gltf::open(path)?;
+        let document = &gltf.document;
+        let base_dir = path.parent();
+
+        let mut primitives = vec![];
+        for (mesh_index, transform) in collect_mesh_instances(document) {
+            let mesh = match document.meshes().nth(mesh_index) {
+                Some(mesh) => mesh,
+                None => continue
+            };
+
+            for primitive in mesh.primitives() {
+                primitives.push(GltfPrimitive {
+                    mesh_index,
+                    primitive_index: primitive.index(),
+                    mesh_name: mesh.name().map(|n| n.to_owned()),
+                    material_index: primitive.material().index(),
+                    transform: convert_to_z_up(&transform)
+                });
+            }
+        }
+
+        let materials = document.materials()
+            .map(|material| {
+                let pbr = material.pbr_metallic_roughness();
+                let normal_texture = material.normal_texture();
+                let occlusion_texture = material.occlusion_texture();
+
+                GltfMaterial {
+                    name: material.name().map(|n| n.to_owned()),
+
+                    base_color_factor: glm::Vec4::from(pbr.base_color_factor()),
+                    base_color_texture: pbr.base_color_texture().map(|t| create_texture_info(&t.texture(), t.tex_coord(), base_dir)),
+                    metallic_factor: pbr.metallic_factor(),
+                    roughness_factor: pbr.roughness_factor(),
+                    metallic_roughness_texture: pbr.metallic_roughness_texture().map(|t| create_texture_info(&t.texture(), t.tex_coord(), base_dir)),
+
+                    normal_scale: normal_texture.as_ref().map(|t| t.scale()).unwrap_or(1.0),
+                    normal_texture: normal_texture.as_ref().map(|t| create_texture_info(&t.texture(), t.tex_coord(), base_dir)),
+                    occlusion_strength: occlusion_texture.as_ref().map(|t| t.strength()).unwrap_or(1.0),
+                    occlusion_texture: occlusion_texture.as_ref().map(|t| create_texture_info(&t.texture(), t.tex_coord(), base_dir)),
+                    emissive_factor: glm::Vec3::from(material.emissive_factor()),
+                    emissive_texture: material.emissive_texture().map(|t| create_texture_info(&t.texture(), t.tex_coord(), base_dir)),
+
+                    alpha_mode: match material.alpha_mode() {
+                        gltf::material::AlphaMode::Opaque => GltfAlphaMode::Opaque,
+                        gltf::material::AlphaMode::Mask => GltfAlphaMode::Mask,
+                        gltf::material::AlphaMode::Blend => GltfAlphaMode::Blend
+                    },
+                    alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5),
+                    double_sided: material.double_sided()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Self {
+            path: path.to_owned(),
+            primitives,
+            materials
+        })
+    }
+
+    pub fn material_for(&self, primitive: &GltfPrimitive) -> Option<&GltfMaterial> {
+        primitive.material_index.and_then(|index| self.materials.get(index))
+    }
+
+    //Primitives without a material get glTF's default material, which is opaque
+    pub fn is_opaque(&self) -> bool {
+        self.primitives.iter().all(|primitive| self.material_for(primitive).map_or(true, |material| material.is_opaque()))
+    }
+}
+
+impl GltfMaterial {
+    //Blended materials need a translucent material. Masked ones are still opaque, they just discard below the cutoff
+    pub fn is_opaque(&self) -> bool {
+        self.alpha_mode != GltfAlphaMode::Blend
+    }
+
+    //Materials have one texture for occlusion, roughness, and metallic, packed the same way glTF packs them, so they're usually the same image
+    pub fn occlusion_roughness_metallic_texture(&self) -> Option<&GltfTextureInfo> {
+        self.metallic_roughness_texture.as_ref().or(self.occlusion_texture.as_ref())
+    }
+
+    pub fn material_uniform(&self) -> MaterialUniform {
+        //A separate occlusion image can't be bound, so its strength is dropped rather than reading occlusion from the metallic roughness image's unused red channel
+        let occlusion_image = self.occlusion_texture.as_ref().map(|t| t.image_index);
+        let is_occlusion_bound = occlusion_image.is_some() && occlusion_image == self.occlusion_roughness_metallic_texture().map(|t| t.image_index);
+
+        MaterialUniform {
+            base_color_factor: self.base_color_factor,
+            metallic_factor: self.metallic_factor,
+            roughness_factor: self.roughness_factor,
+            normal_scale: self.normal_scale,
+            occlusion_strength: if is_occlusion_bound { self.occlusion_strength } else { 0.0 }
+        }
+    }
+}
+
+fn create_texture_info(texture: &gltf::Texture, tex_coord: u32, base_dir: Option<&Path>) -> GltfTextureInfo {
+    let image = texture.source();
+    let path = match image.source() {
+        gltf::image::Source::Uri { uri, .. } if !uri.starts_with("data:") => {
+            Some(base_dir.map(|dir| dir.join(uri)).unwrap_or_else(|| PathBuf::from(uri)))
+        },
+        _ => None
+    };
+
+    GltfTextureInfo {
+        image_index: image.index(),
+        path,
+        tex_coord
+    }
+}
+
+//Mesh index and world transform (in glTF's Y up space) of every node in the default scene that uses a mesh
+pub(super) fn collect_mesh_instances(document: &gltf::Document) -> Vec<(usize, glm::Mat4)> {
+    let mut instances = vec![];
+
+    let scene = document.default_scene().or_else(|| document.scenes().next());
+    if let Some(scene) = scene {
+        for node in scene.nodes() {
+            collect_node_mesh_instances(&node, &glm::Mat4::identity(), &mut instances);
+        }
+    } else {
+        //No scenes means nothing says where meshes go, so show all of them untransformed
+        for mesh in document.meshes() {
+            instances.push((mesh.index(), glm::Mat4::identity()));
+        }
+    }
+
+    instances
+}
+
+fn collect_node_mesh_instances(node: &gltf::Node, parent_transform: &glm::Mat4, instances: &mut Vec<(usize, glm::Mat4)>) -> () {
+    let local_transform = glm::Mat4::from(node.transform().matrix());
+    let transform = parent_transform * local_transform;
+
+    if let Some(mesh) = node.mesh() {
+        //Skinned meshes are placed by their joints, so glTF says the node's own transform is ignored
+        let mesh_transform = if node.skin().is_some() { glm::Mat4::identity() } else { transform };
+        instances.push((mesh.index(), mesh_transform));
+    }
+
+    for child in node.children() {
+        collect_node_mesh_instances(&child, &transform, instances);
+    }
+}
+
+//Swap Y and Z - this engine uses Z as the up direction, but glTF uses Y as the up direction
+pub(super) fn convert_to_z_up(transform: &glm::Mat4) -> glm::Mat4 {
+    let swap_yz = glm::Mat4::new(
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 1.0, 0.0,
+        0.0, 1.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0
+    );
+    swap_yz * transform * swap_yz
+}
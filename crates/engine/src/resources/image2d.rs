@@ -11,9 +11,13 @@ use crate::{
     bootstrap::{CommandPoolsInfo}
 };
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum AttachmentKind {
     Color,
-    Depth
+    Depth,
+    ShadowDepth,
+    //Written by compute work (like DLSS) instead of a render pass
+    Storage
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -80,6 +84,18 @@ impl Image2D {
 
         Self::get_supported_format(inst, physical_device, candidates, vk::ImageTiling::OPTIMAL, vk::FormatFeatureFlags::COLOR_ATTACHMENT)
     }
+    unsafe fn choose_upscaled_render_image_format(inst: &Instance, physical_device: &vk::PhysicalDevice, attachment_kind: AttachmentKind) -> Result<vk::Format> {
+        let candidates = &[
+            vk::Format::R16G16B16A16_SFLOAT
+        ];
+
+        let features = match attachment_kind {
+            AttachmentKind::Storage => vk::FormatFeatureFlags::STORAGE_IMAGE | vk::FormatFeatureFlags::SAMPLED_IMAGE,
+            _ => vk::FormatFeatureFlags::COLOR_ATTACHMENT | vk::FormatFeatureFlags::SAMPLED_IMAGE
+        };
+
+        Self::get_supported_format(inst, physical_device, candidates, vk::ImageTiling::OPTIMAL, features)
+    }
     unsafe fn choose_depth_stencil_format(inst: &Instance, physical_device: &vk::PhysicalDevice) -> Result<vk::Format> {
         let candidates = &[
             vk::Format::D32_SFLOAT_S8_UINT,
@@ -89,6 +105,14 @@ impl Image2D {
 
         Self::get_supported_format(inst, physical_device, candidates, vk::ImageTiling::OPTIMAL, vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT)
     }
+    unsafe fn choose_shadow_map_format(inst: &Instance, physical_device: &vk::PhysicalDevice) -> Result<vk::Format> {
+        let candidates = &[
+            vk::Format::D32_SFLOAT,
+            vk::Format::D16_UNORM
+        ];
+
+        Self::get_supported_format(inst, physical_device, candidates, vk::ImageTiling::OPTIMAL, vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT | vk::FormatFeatureFlags::SAMPLED_IMAGE)
+    }
     unsafe fn choose_motion_vector_format(inst: &Instance, physical_device: &vk::PhysicalDevice) -> Result<vk::Format> {
         let candidates = &[
             vk::Format::R16G16_SFLOAT,
@@ -138,6 +162,13 @@ impl Image2D {
     }
 
     fn create_image_view(&mut self, device: &Device, aspect_flags: vk::ImageAspectFlags) -> Result<()> {
+        let image_view = self.create_view(device, aspect_flags)?;
+        self.image_view = Some(image_view);
+
+        Ok(())
+    }
+
+    fn create_view(&self, device: &Device, aspect_flags: vk::ImageAspectFlags) -> Result<vk::ImageView> {
         let components = vk::ComponentMapping::builder()
             .r(vk::ComponentSwizzle::IDENTITY)
             .g(vk::ComponentSwizzle::IDENTITY)
@@ -158,14 +189,14 @@ impl Image2D {
             .components(components)
             .subresource_range(subresource_range);
 
-        let image_view: vk::ImageView;
         unsafe {
-            image_view = device.create_image_view(&image_view_info, None)?;
+            Ok(device.create_image_view(&image_view_info, None)?)
         }
+    }
 
-        self.image_view = Some(image_view);
-
-        Ok(())
+    //Depth/stencil images can only be sampled through a view with a single aspect. The caller owns the returned view
+    pub fn create_depth_sample_view(&self, device: &Device) -> Result<vk::ImageView> {
+        self.create_view(device, vk::ImageAspectFlags::DEPTH)
     }
 
     fn create_image_sampler(&mut self, device: &Device) -> Result<()> {
@@ -196,15 +227,40 @@ impl Image2D {
         Ok(())
     }
 
+    fn create_shadow_sampler(&mut self, device: &Device) -> Result<()> {
+        //Everything outside of the shadow map counts as lit
+        let sampler_info = vk::SamplerCreateInfo::builder()
+            .mag_filter(vk::Filter::LINEAR)
+            .min_filter(vk::Filter::LINEAR)
+            .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+            .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+            .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+            .anisotropy_enable(false)
+            .max_anisotropy(1.0)
+            .border_color(vk::BorderColor::FLOAT_OPAQUE_WHITE)
+            .unnormalized_coordinates(false)
+            .compare_enable(true)
+            .compare_op(vk::CompareOp::LESS_OR_EQUAL)
+            .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+            .mip_lod_bias(0.0)
+            .min_lod(0.0)
+            .max_lod(0.0);
+
+        let sampler: vk::Sampler;
+        unsafe {
+            sampler = device.create_sampler(&sampler_info, None)?;
+        }
+
+        self.image_sampler = Some(sampler);
+
+        Ok(())
+    }
+
     fn transition_image_layout(&self, device: &Device, old_layout: vk::ImageLayout, new_layout: vk::ImageLayout, command_buffer: &vk::CommandBuffer) -> Result<()> {
         let format = self.format.unwrap();
-        let aspect_mask: vk::ImageAspectFlags = match new_layout {
-            vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL => {
-                match format {
-                    vk::Format::D32_SFLOAT_S8_UINT | vk::Format::D24_UNORM_S8_UINT => vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL,
-                    _ => vk::ImageAspectFlags::COLOR
-                }
-            },
+        let aspect_mask: vk::ImageAspectFlags = match format {
+            vk::Format::D32_SFLOAT_S8_UINT | vk::Format::D24_UNORM_S8_UINT => vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL,
+            vk::Format::D32_SFLOAT | vk::Format::D16_UNORM => vk::ImageAspectFlags::DEPTH,
             _ => vk::ImageAspectFlags::COLOR
         };
 
@@ -219,6 +275,7 @@ impl Image2D {
             (vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL) => (vk::AccessFlags::empty(), vk::AccessFlags::TRANSFER_WRITE, vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::TRANSFER),
             (vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL) => (vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::SHADER_READ, vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::FRAGMENT_SHADER),
             (vk::ImageLayout::UNDEFINED, vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL) => (vk::AccessFlags::empty(), vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE, vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS),
+            (vk::ImageLayout::UNDEFINED, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL) => (vk::AccessFlags::empty(), vk::AccessFlags::SHADER_READ, vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::FRAGMENT_SHADER),
             (vk::ImageLayout::UNDEFINED, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL) => (vk::AccessFlags::empty(), vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE, vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT),
             _ => return Err(anyhow!("Unsupported image layout transition in Image2D::transition_image_layout"))
         };
@@ -312,8 +369,11 @@ impl Image2D {
         let mut usage_flags: vk::ImageUsageFlags;
         let aspect_flags: vk::ImageAspectFlags;
         (usage_flags, aspect_flags) = match attachment_kind {
-            AttachmentKind::Color => (vk::ImageUsageFlags::COLOR_ATTACHMENT, vk::ImageAspectFlags::COLOR),
-            AttachmentKind::Depth => (vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT, vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL)
+            //Color and storage images can be copied out for HDR screenshots
+            AttachmentKind::Color => (vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC, vk::ImageAspectFlags::COLOR),
+            AttachmentKind::Depth => (vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT, vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL),
+            AttachmentKind::ShadowDepth => (vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT, vk::ImageAspectFlags::DEPTH),
+            AttachmentKind::Storage => (vk::ImageUsageFlags::STORAGE | vk::ImageUsageFlags::TRANSFER_SRC, vk::ImageAspectFlags::COLOR)
         };
         if sampled {
             usage_flags |= vk::ImageUsageFlags::SAMPLED;
@@ -323,7 +383,10 @@ impl Image2D {
         self.create_image_view(device, aspect_flags)?;
 
         if sampled {
-            self.create_image_sampler(device)?;
+            match attachment_kind {
+                AttachmentKind::ShadowDepth => self.create_shadow_sampler(device)?,
+                _ => self.create_image_sampler(device)?
+            }
         }
 
         self.initialized = true;
@@ -354,6 +417,30 @@ impl Image2D {
 
         Ok(depth_stencil_buffers)
     }
+    pub fn new_and_create_shadow_maps(image_count: u32, inst: &Instance, device: &Device, physical_device: &vk::PhysicalDevice, memory_properties: &PhysicalDeviceMemoryProperties, extent: &vk::Extent2D, command_pool_info: &CommandPoolsInfo) -> Result<Vec<Self>> {
+        let format = unsafe { Self::choose_shadow_map_format(inst, physical_device)? };
+
+        let shadow_maps = (0..image_count)
+            .map(|_| -> Result<Self> {
+                let mut image = Image2D::new();
+                image.create_attachment_buffer(device, memory_properties, format, extent, AttachmentKind::ShadowDepth, true)?;
+
+                Ok(image)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        //Shadow maps are sampled every frame, even on frames where the shadow pass doesn't run
+        let shadow_maps_ref = &shadow_maps;
+        command_pool_info.submit_command_transient_sync(device, |command_buffer| {
+            for shadow_map in shadow_maps_ref {
+                shadow_map.transition_image_layout(device, vk::ImageLayout::UNDEFINED, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, command_buffer)?;
+            }
+
+            Ok(())
+        })?;
+
+        Ok(shadow_maps)
+    }
     pub fn new_and_create_render_images(image_count: u32, inst: &Instance, device: &Device, physical_device: &vk::PhysicalDevice, memory_properties: &PhysicalDeviceMemoryProperties, extent: &vk::Extent2D, sampled: bool, command_pool_info: &CommandPoolsInfo) -> Result<Vec<Self>> {
         let format = unsafe { Self::choose_render_image_format(inst, physical_device)? };
 
@@ -401,6 +488,47 @@ impl Image2D {
         Ok(motion_vector_buffers)
     }
 
+    //Written by either compute work (Storage) or a render pass (Color), then sampled by postprocessing
+    pub fn new_and_create_upscaled_render_images(image_count: u32, inst: &Instance, device: &Device, physical_device: &vk::PhysicalDevice, memory_properties: &PhysicalDeviceMemoryProperties, extent: &vk::Extent2D, attachment_kind: AttachmentKind, command_pool_info: &CommandPoolsInfo) -> Result<Vec<Self>> {
+        let format = unsafe { Self::choose_upscaled_render_image_format(inst, physical_device, attachment_kind)? };
+
+        let upscaled_render_images = (0..image_count)
+            .map(|_| -> Result<Self> {
+                let mut image = Image2D::new();
+                image.create_attachment_buffer(device, memory_properties, format, extent, attachment_kind, true)?;
+
+                Ok(image)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let upscaled_render_images_ref = &upscaled_render_images;
+        command_pool_info.submit_command_transient_sync(device, |command_buffer| {
+            for upscaled_render_image in upscaled_render_images_ref {
+                upscaled_render_image.transition_image_layout(device, vk::ImageLayout::UNDEFINED, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, command_buffer)?;
+            }
+
+            Ok(())
+        })?;
+
+        Ok(upscaled_render_images)
+    }
+
+    //Stands in for swapchain images when rendering headless. They can be copied from, so that frames can be read back
+    pub fn new_and_create_offscreen_targets(image_count: u32, device: &Device, memory_properties: &PhysicalDeviceMemoryProperties, format: vk::Format, extent: &vk::Extent2D) -> Result<Vec<Self>> {
+        (0..image_count)
+            .map(|_| -> Result<Self> {
+                let mut image = Image2D::new();
+                image.format = Some(format);
+                image.size = Some(*extent);
+                image.create_image(device, memory_properties, *extent, format, vk::ImageTiling::OPTIMAL, vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC, vk::MemoryPropertyFlags::DEVICE_LOCAL)?;
+                image.create_image_view(device, vk::ImageAspectFlags::COLOR)?;
+                image.initialized = true;
+
+                Ok(image)
+            })
+            .collect::<Result<Vec<_>, _>>()
+    }
+
     pub fn create_from_swapchain_images(swapchain_images: &[vk::Image], format: vk::Format, size: vk::Extent2D, device: &Device) -> Result<Vec<Image2D>> {
         swapchain_images.iter()
             .map(|i| {
@@ -426,52 +554,76 @@ impl Image2D {
     }
 
     pub fn create_from_png<R: Read>(&mut self, reader: &mut png::Reader<R>, device: &Device, memory_properties: &vk::PhysicalDeviceMemoryProperties, command_pool_info: &CommandPoolsInfo, is_srgb: bool) -> Result<()> {
+        let (pixels, size) = Self::read_png_as_rgba(reader)?;
+
+        self.create_from_rgba_pixels(&pixels, size, device, memory_properties, command_pool_info, is_srgb)
+    }
+    pub fn read_png_as_rgba<R: Read>(reader: &mut png::Reader<R>) -> Result<(Vec<u8>, vk::Extent2D)> {
         let buff_size = reader.info().raw_bytes();
         let color_type = reader.info().color_type;
         let mut pixels = vec![0; buff_size];
         reader.next_frame(&mut pixels)?;
 
-        let format = if is_srgb { vk::Format::R8G8B8A8_SRGB } else { vk::Format::R8G8B8A8_UNORM };
-        self.format = Some(format);
-
         let (width, height) = reader.info().size();
         let size = vk::Extent2D { width, height };
-        self.size = Some(size);
 
-        let mut buffer: Buffer::<u8>;
         match color_type {
             ColorType::Rgba => { },
             ColorType::Rgb => {
                 let expected_pixel_count = size.width * size.height;
                 let mut new_pixels = vec![0; (expected_pixel_count * 4) as usize];
-                self.convert_rgb_to_rgba(&pixels, &mut new_pixels, expected_pixel_count as usize)?;
+                Self::convert_rgb_to_rgba(&pixels, &mut new_pixels, expected_pixel_count as usize)?;
                 pixels = new_pixels;
             },
             _ => return Err(anyhow!("Unsupported color type when loading PNG: {:?}", color_type))
         }
 
-        buffer = Buffer::new(vk::BufferUsageFlags::TRANSFER_SRC, pixels.len(), false);
-        buffer.create(device, memory_properties)?;
-        buffer.set_data(device, &pixels)?;
-
-        self.create_image(device, memory_properties, size, format, vk::ImageTiling::OPTIMAL, vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST, vk::MemoryPropertyFlags::DEVICE_LOCAL)?;
+        Ok((pixels, size))
+    }
+    pub fn create_from_rgba_pixels(&mut self, pixels: &Vec<u8>, size: vk::Extent2D, device: &Device, memory_properties: &vk::PhysicalDeviceMemoryProperties, command_pool_info: &CommandPoolsInfo, is_srgb: bool) -> Result<()> {
+        let mut staging_buffer = self.create_for_upload(pixels, size, device, memory_properties, is_srgb)?;
 
         command_pool_info.submit_command_transient_sync(device, |command_buffer| {
-            self.transition_image_layout(device, vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL, command_buffer)?;
-            self.copy_buffer_to_image(device, &buffer, command_buffer)?;
-            self.transition_image_layout(device, vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, command_buffer)?;
-
-            Ok(())
+            self.write_upload_to_command_buffer(device, &staging_buffer, command_buffer)
         })?;
 
-        buffer.destroy(device);
+        staging_buffer.destroy(device);
 
+        Ok(())
+    }
+    //Creates the image and a staging buffer holding the pixels. The image can't be sampled until the upload has been written to a command buffer and that command buffer has finished, after which the staging buffer should be destroyed
+    pub fn create_for_upload(&mut self, pixels: &Vec<u8>, size: vk::Extent2D, device: &Device, memory_properties: &vk::PhysicalDeviceMemoryProperties, is_srgb: bool) -> Result<Buffer<u8>> {
+        if self.initialized {
+            return Err(anyhow!("This image has already been initialized. It can't be created again!"));
+        }
+        if pixels.len() != (size.width * size.height * 4) as usize {
+            return Err(anyhow!("Expected {} bytes of RGBA pixel data for a {}x{} image, got {}", size.width * size.height * 4, size.width, size.height, pixels.len()));
+        }
+
+        let format = if is_srgb { vk::Format::R8G8B8A8_SRGB } else { vk::Format::R8G8B8A8_UNORM };
+        self.format = Some(format);
+        self.size = Some(size);
+
+        let mut staging_buffer = Buffer::<u8>::new(vk::BufferUsageFlags::TRANSFER_SRC, pixels.len(), false);
+        staging_buffer.create(device, memory_properties)?;
+        staging_buffer.set_data(device, pixels)?;
+
+        self.create_image(device, memory_properties, size, format, vk::ImageTiling::OPTIMAL, vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST, vk::MemoryPropertyFlags::DEVICE_LOCAL)?;
         self.create_image_view(device, vk::ImageAspectFlags::COLOR)?;
         self.create_image_sampler(device)?;
 
-        Ok({})
+        self.initialized = true;
+
+        Ok(staging_buffer)
+    }
+    pub fn write_upload_to_command_buffer(&self, device: &Device, staging_buffer: &Buffer<u8>, command_buffer: &vk::CommandBuffer) -> Result<()> {
+        self.transition_image_layout(device, vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL, command_buffer)?;
+        self.copy_buffer_to_image(device, staging_buffer, command_buffer)?;
+        self.transition_image_layout(device, vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, command_buffer)?;
+
+        Ok(())
     }
-    pub fn convert_rgb_to_rgba(&self, pixels: &Vec<u8>, new_pixels: &mut Vec<u8>, pixel_count: usize) -> Result<()> {
+    pub fn convert_rgb_to_rgba(pixels: &Vec<u8>, new_pixels: &mut Vec<u8>, pixel_count: usize) -> Result<()> {
         for q in 0..pixel_count {
             let from = q * 3;
             let to = q * 4;
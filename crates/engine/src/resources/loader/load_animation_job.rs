@@ -0,0 +1,56 @@
+use super::{
+    resource_load_job::{ResourceLoadJob},
+    ResourceLoader,
+    AnimationProperties,
+    AnimationRef
+};
+
+use anyhow::{Result, anyhow};
+use vulkanalia::{
+    prelude::v1_0::*
+};
+
+use crate::{
+    resources::{AnimationSet},
+    app_data::{AppData}
+};
+
+#[derive(Debug)]
+pub struct LoadAnimationJob {
+    animation_ref: AnimationRef,
+    gltf_path: Option<String>,
+    skin_index: Option<usize>,
+
+    animation_set: Option<AnimationSet>
+}
+
+impl LoadAnimationJob {
+    pub(super) fn create_for(animation_ref: AnimationRef, animation_props: &AnimationProperties) -> Self {
+        Self {
+            animation_ref,
+            gltf_path: animation_props.gltf_path.clone(),
+            skin_index: animation_props.skin_index,
+
+            animation_set: None
+        }
+    }
+}
+
+impl ResourceLoadJob for LoadAnimationJob {
+    fn prepare(&mut self) -> Result<()> {
+        let path = self.gltf_path.as_ref().ok_or_else(|| anyhow!("Can not load animation without glTF path"))?;
+        self.animation_set = Some(AnimationSet::load_gltf(path, self.skin_index)?);
+
+        Ok(())
+    }
+
+    //Nothing goes to the GPU. Skinned shaders get their joint palettes from the frame's render info
+    fn load(&mut self, resource_loader: &mut ResourceLoader, _device: &Device, _app_data: &AppData) -> Result<()> {
+        resource_loader.finish_loading_animation(self.animation_ref, self.animation_set.take().unwrap())
+    }
+
+    fn failed(&mut self, resource_loader: &mut ResourceLoader, error: &anyhow::Error) -> () {
+        error!("Failed to load animation {:?} from {:?}: {:?}", self.animation_ref, self.gltf_path, error);
+        resource_loader.fail_loading_animation(self.animation_ref);
+    }
+}
@@ -0,0 +1,54 @@
+use super::{
+    resource_load_job::{ResourceLoadJob},
+    ResourceLoader,
+    GltfAssetProperties,
+    GltfAssetRef
+};
+
+use anyhow::{Result, anyhow};
+use vulkanalia::{
+    prelude::v1_0::*
+};
+
+use crate::{
+    resources::{GltfAsset},
+    app_data::{AppData}
+};
+
+#[derive(Debug)]
+pub struct LoadGltfAssetJob {
+    gltf_asset_ref: GltfAssetRef,
+    gltf_path: Option<String>,
+
+    gltf_asset: Option<GltfAsset>
+}
+
+impl LoadGltfAssetJob {
+    pub(super) fn create_for(gltf_asset_ref: GltfAssetRef, gltf_asset_props: &GltfAssetProperties) -> Self {
+        Self {
+            gltf_asset_ref,
+            gltf_path: gltf_asset_props.gltf_path.clone(),
+
+            gltf_asset: None
+        }
+    }
+}
+
+impl ResourceLoadJob for LoadGltfAssetJob {
+    fn prepare(&mut self) -> Result<()> {
+        let path = self.gltf_path.as_ref().ok_or_else(|| anyhow!("Can not load glTF asset without glTF path"))?;
+        self.gltf_asset = Some(GltfAsset::open(path)?);
+
+        Ok(())
+    }
+
+    //Only the document is read, so nothing goes to the GPU. Models and textures from it are loaded separately
+    fn load(&mut self, resource_loader: &mut ResourceLoader, _device: &Device, _app_data: &AppData) -> Result<()> {
+        resource_loader.finish_loading_gltf_asset(self.gltf_asset_ref, self.gltf_asset.take().unwrap())
+    }
+
+    fn failed(&mut self, resource_loader: &mut ResourceLoader, error: &anyhow::Error) -> () {
+        error!("Failed to load glTF asset {:?} from {:?}: {:?}", self.gltf_asset_ref, self.gltf_path, error);
+        resource_loader.fail_loading_gltf_asset(self.gltf_asset_ref);
+    }
+}
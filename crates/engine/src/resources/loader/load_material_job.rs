@@ -2,9 +2,15 @@ use super::{
     resource_load_job::{ResourceLoadJob},
     MaterialProperties,
     MaterialRef,
-    ResourceLoader
+    ResourceLoader,
+    TextureRef,
+    LoadStatus,
+    MATERIAL_TEXTURE_COUNT
 };
 
+use std::{
+    path::{Path}
+};
 use anyhow::{Result};
 use vulkanalia::{
     prelude::v1_0::*
@@ -14,9 +20,11 @@ use crate::{
     resources::{
         CanBeVertexBufferType,
         CanBeInstVertexBufferType,
-        shader_source::{create_shader_sources, ShaderSources, DepthBufferUsageMode, BlendStateDescriptor, create_pipeline},
-        material::{Material}
+        shader_source::{create_shader_sources, ShaderSource, ShaderSources, DepthBufferUsageMode, BlendStateDescriptor, RasterizationStateDescriptor, create_pipeline, create_pipeline_with_rasterization},
+        material::{Material, TranslucentBlendMode},
+        Buffer
     },
+    shader_input::uniform_buffer_object::{MaterialUniform},
     app_data::{AppData}
 };
 
@@ -27,11 +35,17 @@ pub struct LoadMaterialJob {
     attribute_descriptions: Vec<vk::VertexInputAttributeDescription>,
 
     depth_and_motion: Option<ShaderSources>,
-    base_render: Option<ShaderSources>
+    base_render: Option<ShaderSources>,
+    translucent: Option<ShaderSources>,
+    shadow: Option<ShaderSources>,
+    translucent_blend_mode: TranslucentBlendMode,
+
+    textures: [Option<TextureRef>; MATERIAL_TEXTURE_COUNT as usize],
+    factors: MaterialUniform
 }
 
 impl LoadMaterialJob {
-    pub(super) fn create_for<TVert, TInstVert>(mat_ref: MaterialRef, mat_props: &MaterialProperties<TVert, TInstVert>) -> Self where TVert : CanBeVertexBufferType, TInstVert : CanBeInstVertexBufferType {
+    pub(super) fn create_for<TVert, TInstVert>(mat_ref: MaterialRef, mat_props: &MaterialProperties<TVert, TInstVert>, textures: [Option<TextureRef>; MATERIAL_TEXTURE_COUNT as usize]) -> Self where TVert : CanBeVertexBufferType, TInstVert : CanBeInstVertexBufferType {
         let binding_descriptions = [TVert::binding_descriptions(), TInstVert::binding_descriptions()].concat();
         let attribute_descriptions = [TVert::attribute_descriptions(), TInstVert::attribute_descriptions()].concat();
 
@@ -40,18 +54,54 @@ impl LoadMaterialJob {
 
         let depth_and_motion: Option<ShaderSources> = if !mat_props.is_opaque { None } else { Some(create_shader_sources("depth_and_motion_", mat_props.shader_name, suffix, mat_props.shader_entry)) };
         let base_render: Option<ShaderSources> = if !mat_props.is_opaque { None } else { Some(create_shader_sources("", mat_props.shader_name, suffix, mat_props.shader_entry)) };
+        //Translucent materials reuse the base render shader, blended in their own subpass
+        let translucent: Option<ShaderSources> = if mat_props.is_opaque { None } else { Some(create_shader_sources("", mat_props.shader_name, suffix, mat_props.shader_entry)) };
+
+        let mut shadow: Option<ShaderSources> = if !mat_props.is_opaque { None } else { Some(create_shader_sources("shadow_", mat_props.shader_name, suffix, mat_props.shader_entry)) };
+        if let Some(ShaderSources { vertex: ShaderSource::SourcePath(path, _), .. }) = shadow.as_ref() {
+            if !Path::new(path).exists() {
+                warn!("No shadow shader found for material shader '{}{}'. Objects using it won't cast shadows.", mat_props.shader_name, suffix);
+                shadow = None;
+            }
+        }
 
         Self {
             mat_ref,
             binding_descriptions,
             attribute_descriptions,
             depth_and_motion,
-            base_render
+            base_render,
+            translucent,
+            shadow,
+            translucent_blend_mode: mat_props.translucent_blend_mode,
+            textures,
+            factors: mat_props.factors
         }
     }
 }
 
 impl ResourceLoadJob for LoadMaterialJob {
+    fn prepare(&mut self) -> Result<()> {
+        //Read the shader files here so that pipeline creation on the render thread doesn't wait on disk
+        for sources in [&mut self.depth_and_motion, &mut self.base_render, &mut self.translucent, &mut self.shadow] {
+            if let Some(ShaderSources { vertex, fragment }) = sources.take() {
+                *sources = Some(ShaderSources {
+                    vertex: vertex.flatten()?,
+                    fragment: fragment.flatten()?
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_ready(&self, resource_loader: &ResourceLoader) -> bool {
+        //Textures that fail to load are replaced by the defaults, so only wait for the ones still loading
+        self.textures.iter()
+            .filter_map(|t| *t)
+            .all(|t| resource_loader.get_texture_load_status(t) != LoadStatus::Loading)
+    }
+
     fn load(&mut self, resource_loader: &mut ResourceLoader, device: &Device, app_data: &AppData) -> Result<()> {
         let pipeline_info = app_data.pipeline.as_ref().unwrap();
         let layout = pipeline_info.base_render_layout;
@@ -70,18 +120,54 @@ impl ResourceLoadJob for LoadMaterialJob {
 
         let mut base_render_pipeline = None;
         if let Some(base_render_sources) = self.base_render.take() {
+            //Shaders output their real alpha for the translucent subpass, so it's masked off here. The base render keeps its cleared alpha
             let blend_state = &[
-                BlendStateDescriptor::default()
+                BlendStateDescriptor {
+                    components: vk::ColorComponentFlags::R | vk::ColorComponentFlags::G | vk::ColorComponentFlags::B,
+                    ..Default::default()
+                }
             ][..];
             base_render_pipeline = Some(create_pipeline(base_render_sources.vertex, base_render_sources.fragment, device, None, layout, render_pass, 1, blend_state, DepthBufferUsageMode::WriteIfEqual, &self.binding_descriptions[..], &self.attribute_descriptions[..])?);
         }
 
+        let mut translucent_pipeline = None;
+        if let Some(translucent_sources) = self.translucent.take() {
+            let blend_state = &[
+                self.translucent_blend_mode.blend_state()
+            ][..];
+            translucent_pipeline = Some(create_pipeline(translucent_sources.vertex, translucent_sources.fragment, device, None, layout, render_pass, 2, blend_state, DepthBufferUsageMode::TestIfLess, &self.binding_descriptions[..], &self.attribute_descriptions[..])?);
+        }
+
+        let mut shadow_pipeline = None;
+        if let Some(shadow_sources) = self.shadow.take() {
+            let rasterization = RasterizationStateDescriptor {
+                dynamic_depth_bias: true,
+                ..Default::default()
+            };
+            shadow_pipeline = Some(create_pipeline_with_rasterization(shadow_sources.vertex, shadow_sources.fragment, device, None, pipeline_info.shadow_layout, pipeline_info.shadow_render_pass, 0, &[], DepthBufferUsageMode::WriteIfLess, rasterization, &self.binding_descriptions[..], &self.attribute_descriptions[..])?);
+        }
+
+        let mut uniform_buffer = Buffer::<MaterialUniform>::new(vk::BufferUsageFlags::UNIFORM_BUFFER, 1, false);
+        uniform_buffer.create(device, &app_data.memory_properties)?;
+        uniform_buffer.set_data(device, &self.factors)?;
+
+        let descriptor_set = resource_loader.create_material_descriptor_set(app_data, &self.textures, &uniform_buffer)?;
+
         let material = Material {
             is_loaded: true,
             depth_motion: depth_and_motion_pipeline,
-            base_render: base_render_pipeline
+            base_render: base_render_pipeline,
+            translucent: translucent_pipeline,
+            shadow: shadow_pipeline,
+            descriptor_set: Some(descriptor_set),
+            uniform_buffer: Some(uniform_buffer)
         };
 
         resource_loader.finish_loading_material(self.mat_ref, material)
     }
+
+    fn failed(&mut self, resource_loader: &mut ResourceLoader, error: &anyhow::Error) -> () {
+        error!("Failed to load material {:?}: {:?}", self.mat_ref, error);
+        resource_loader.fail_loading_material(self.mat_ref);
+    }
 }
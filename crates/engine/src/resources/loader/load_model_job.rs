@@ -13,7 +13,8 @@ use vulkanalia::{
 use crate::{
     resources::{
         CanBeVertexBufferType,
-        Model
+        Model,
+        MeshData
     },
     app_data::{AppData}
 };
@@ -22,6 +23,10 @@ use crate::{
 pub struct LoadModelJob<TVert> where TVert : CanBeVertexBufferType {
     model_ref: ModelRef,
     obj_path: Option<String>,
+    gltf_path: Option<String>,
+    gltf_primitive: Option<(usize, usize)>,
+    vertices: Vec<TVert>,
+    mesh_data: Option<MeshData>,
     model: Option<Model<TVert>>
 }
 
@@ -30,22 +35,41 @@ impl<TVert> LoadModelJob<TVert> where TVert : CanBeVertexBufferType {
         Self {
             model_ref,
             obj_path: model_props.obj_path.clone(),
+            gltf_path: model_props.gltf_path.clone(),
+            gltf_primitive: model_props.gltf_primitive,
+            vertices: vec![],
+            mesh_data: None,
             model: None
         }
     }
 }
 
 impl<TVert> ResourceLoadJob for LoadModelJob<TVert> where TVert : CanBeVertexBufferType {
+    fn prepare(&mut self) -> Result<()> {
+        let (vertices, mesh_data) = if let Some(path) = self.obj_path.as_ref() {
+            Model::<TVert>::read_obj_file(path)?
+        } else if let Some(path) = self.gltf_path.as_ref() {
+            Model::<TVert>::read_gltf_file(path, self.gltf_primitive)?
+        } else {
+            return Err(anyhow!("Can not load model without OBJ or glTF path"));
+        };
+
+        self.vertices = vertices;
+        self.mesh_data = Some(mesh_data);
+
+        Ok(())
+    }
+
     fn needs_transient_command(&self) -> bool {
         true
     }
 
     fn load(&mut self, _resource_loader: &mut ResourceLoader, device: &Device, app_data: &AppData) -> Result<()> {
-        if let Some(path) = self.obj_path.clone() {
-            self.model = Some(Model::<TVert>::new_and_create_from_obj_file(path, device, &app_data.memory_properties)?);
-        } else {
-            return Err(anyhow!("Can not load model without OBJ path"));
-        }
+        let mesh_data = self.mesh_data.as_ref().unwrap();
+        self.model = Some(Model::<TVert>::new_and_create_from_data(&self.vertices, &mesh_data.indices, mesh_data.bounds, device, &app_data.memory_properties)?);
+
+        //The data has been copied into the staging buffers. The mesh data is kept for raycasts
+        self.vertices = vec![];
 
         Ok(())
     }
@@ -59,6 +83,11 @@ impl<TVert> ResourceLoadJob for LoadModelJob<TVert> where TVert : CanBeVertexBuf
 
     fn after_command(&mut self, resource_loader: &mut ResourceLoader, _device: &Device, _app_data: &AppData) -> Result<()> {
         let model = self.model.unwrap();
-        resource_loader.finish_loading_model(self.model_ref, model.reinterpret_readonly())
+        resource_loader.finish_loading_model(self.model_ref, model.reinterpret_readonly(), self.mesh_data.take().unwrap())
+    }
+
+    fn failed(&mut self, resource_loader: &mut ResourceLoader, error: &anyhow::Error) -> () {
+        error!("Failed to load model {:?} from {:?}: {:?}", self.model_ref, self.obj_path.as_ref().or(self.gltf_path.as_ref()), error);
+        resource_loader.fail_loading_model(self.model_ref);
     }
 }
@@ -0,0 +1,96 @@
+use super::{
+    resource_load_job::{ResourceLoadJob},
+    ResourceLoader,
+    TextureProperties,
+    TextureRef
+};
+
+use std::{
+    fs::{File}
+};
+use anyhow::{Result, anyhow};
+use vulkanalia::{
+    prelude::v1_0::*
+};
+
+use crate::{
+    resources::{Image2D, Buffer},
+    app_data::{AppData}
+};
+
+#[derive(Debug)]
+pub struct LoadTextureJob {
+    texture_ref: TextureRef,
+    path: Option<String>,
+    is_srgb: bool,
+
+    pixels: Vec<u8>,
+    size: vk::Extent2D,
+    texture: Option<Image2D>,
+    staging_buffer: Option<Buffer<u8>>
+}
+
+impl LoadTextureJob {
+    pub(super) fn create_for(texture_ref: TextureRef, texture_props: &TextureProperties) -> Self {
+        Self {
+            texture_ref,
+            path: texture_props.path.clone(),
+            is_srgb: texture_props.is_srgb,
+
+            pixels: vec![],
+            size: Default::default(),
+            texture: None,
+            staging_buffer: None
+        }
+    }
+}
+
+impl ResourceLoadJob for LoadTextureJob {
+    fn prepare(&mut self) -> Result<()> {
+        let path = self.path.as_ref().ok_or_else(|| anyhow!("Can not load texture without PNG path"))?;
+
+        let image_file = File::open(path).map_err(|_| anyhow!("Problem loading PNG file at {:?}", path))?;
+        let mut decoder = png::Decoder::new(image_file);
+        decoder.set_ignore_text_chunk(true);
+        let mut reader = decoder.read_info()?;
+
+        let (pixels, size) = Image2D::read_png_as_rgba(&mut reader)?;
+        self.pixels = pixels;
+        self.size = size;
+
+        Ok(())
+    }
+
+    fn needs_transient_command(&self) -> bool {
+        true
+    }
+
+    fn load(&mut self, _resource_loader: &mut ResourceLoader, device: &Device, app_data: &AppData) -> Result<()> {
+        let mut texture = Image2D::new();
+        self.staging_buffer = Some(texture.create_for_upload(&self.pixels, self.size, device, &app_data.memory_properties, self.is_srgb)?);
+        self.texture = Some(texture);
+
+        self.pixels = vec![];
+
+        Ok(())
+    }
+
+    fn write_to_command_buffer(&self, _resource_loader: &mut ResourceLoader, device: &Device, _app_data: &AppData, command_buffer: &vk::CommandBuffer) -> Result<()> {
+        let texture = self.texture.as_ref().unwrap();
+        texture.write_upload_to_command_buffer(device, self.staging_buffer.as_ref().unwrap(), command_buffer)
+    }
+
+    fn after_command(&mut self, resource_loader: &mut ResourceLoader, device: &Device, _app_data: &AppData) -> Result<()> {
+        if let Some(mut staging_buffer) = self.staging_buffer.take() {
+            staging_buffer.destroy(device);
+        }
+
+        let texture = self.texture.take().unwrap();
+        resource_loader.finish_loading_texture(self.texture_ref, texture)
+    }
+
+    fn failed(&mut self, resource_loader: &mut ResourceLoader, error: &anyhow::Error) -> () {
+        error!("Failed to load texture {:?} from {:?}: {:?}", self.texture_ref, self.path, error);
+        resource_loader.fail_loading_texture(self.texture_ref);
+    }
+}
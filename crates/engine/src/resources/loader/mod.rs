@@ -1,7 +1,11 @@
 mod buffer_submit_job;
+mod load_animation_job;
+mod load_gltf_asset_job;
 mod load_material_job;
 mod load_model_job;
+mod load_texture_job;
 mod resource_loader;
 mod resource_load_job;
+mod resource_load_workers;
 
 pub use resource_loader::*;
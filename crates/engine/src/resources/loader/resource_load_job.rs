@@ -7,7 +7,15 @@ use vulkanalia::{
 
 use crate::app_data::{AppData};
 
-pub trait ResourceLoadJob : std::fmt::Debug {
+pub trait ResourceLoadJob : std::fmt::Debug + Send {
+    //Runs on a worker thread before anything else. File I/O and parsing go here; the GPU must not be touched
+    fn prepare(&mut self) -> Result<()> {
+        Ok(())
+    }
+    //Jobs that aren't ready yet are held back and checked again next tick
+    fn is_ready(&self, _resource_loader: &ResourceLoader) -> bool {
+        true
+    }
     fn needs_transient_command(&self) -> bool {
         false
     }
@@ -18,4 +26,7 @@ pub trait ResourceLoadJob : std::fmt::Debug {
     fn after_command(&mut self, _resource_loader: &mut ResourceLoader, _device: &Device, _app_data: &AppData) -> Result<()> {
         Ok(())
     }
+    fn failed(&mut self, _resource_loader: &mut ResourceLoader, error: &anyhow::Error) -> () {
+        error!("Resource load job failed: {:?}; {:?}", self, error);
+    }
 }
@@ -0,0 +1,90 @@
+use super::{
+    resource_load_job::{ResourceLoadJob}
+};
+
+use std::{
+    sync::{Arc, Mutex, mpsc::{self, Sender, Receiver}},
+    thread::{self, JoinHandle}
+};
+use anyhow::{anyhow, Result};
+
+pub(super) type PreparedJob = (Box<dyn ResourceLoadJob>, Result<()>);
+
+const MAX_WORKER_COUNT: usize = 4;
+
+#[derive(Debug)]
+pub(super) struct ResourceLoadWorkers {
+    job_sender: Option<Sender<Box<dyn ResourceLoadJob>>>,
+    prepared_receiver: Receiver<PreparedJob>,
+    threads: Vec<JoinHandle<()>>
+}
+
+impl ResourceLoadWorkers {
+    pub(super) fn new() -> Result<Self> {
+        let worker_count = thread::available_parallelism()
+            .map(|count| count.get().saturating_sub(1))
+            .unwrap_or(1)
+            .clamp(1, MAX_WORKER_COUNT);
+
+        let (job_sender, job_receiver) = mpsc::channel::<Box<dyn ResourceLoadJob>>();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let (prepared_sender, prepared_receiver) = mpsc::channel::<PreparedJob>();
+
+        let threads = (0..worker_count)
+            .map(|q| {
+                let job_receiver = job_receiver.clone();
+                let prepared_sender = prepared_sender.clone();
+
+                thread::Builder::new()
+                    .name(format!("resource-loader-{}", q))
+                    .spawn(move || {
+                        loop {
+                            //The lock is only held while waiting for the next job
+                            let job = job_receiver.lock().unwrap().recv();
+                            let mut job = match job {
+                                Ok(job) => job,
+                                Err(_) => break
+                            };
+
+                            let result = job.prepare();
+                            if prepared_sender.send((job, result)).is_err() {
+                                break;
+                            }
+                        }
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        debug!("Started {} resource loader worker threads", threads.len());
+
+        Ok(Self {
+            job_sender: Some(job_sender),
+            prepared_receiver,
+            threads
+        })
+    }
+
+    pub(super) fn queue(&self, job: Box<dyn ResourceLoadJob>) -> Result<()> {
+        self.job_sender.as_ref()
+            .ok_or_else(|| anyhow!("Resource loader workers have been stopped"))?
+            .send(job)
+            .map_err(|_| anyhow!("Resource loader workers have stopped unexpectedly"))
+    }
+
+    pub(super) fn try_receive(&self) -> Option<PreparedJob> {
+        self.prepared_receiver.try_recv().ok()
+    }
+}
+
+impl Drop for ResourceLoadWorkers {
+    fn drop(&mut self) {
+        //Closing the channel makes every worker's recv fail, which ends its loop
+        self.job_sender.take();
+
+        for thread in self.threads.drain(..) {
+            if thread.join().is_err() {
+                warn!("A resource loader worker thread panicked");
+            }
+        }
+    }
+}
@@ -1,6 +1,10 @@
 use super::{
     resource_load_job::{ResourceLoadJob},
-    load_model_job::{LoadModelJob}
+    resource_load_workers::{ResourceLoadWorkers},
+    load_model_job::{LoadModelJob},
+    load_animation_job::{LoadAnimationJob},
+    load_gltf_asset_job::{LoadGltfAssetJob},
+    load_texture_job::{LoadTextureJob}
 };
 
 use std::{
@@ -8,22 +12,31 @@ use std::{
     collections::{
         VecDeque,
         HashMap,
+        HashSet,
         hash_map::{DefaultHasher}
     },
-    hash::{Hash, Hasher}
+    hash::{Hash, Hasher},
+    sync::{Arc}
 };
-use anyhow::{Result, Ok};
+use anyhow::{anyhow, Result};
 use nalgebra_glm as glm;
 use vulkanalia::{
     prelude::v1_0::*
 };
 
 use crate::{
-    shader_input::empty_vertex::{EmptyVertex},
+    shader_input::{
+        empty_vertex::{EmptyVertex},
+        uniform_buffer_object::{MaterialUniform}
+    },
     resources::{
         CanBeVertexBufferType,
         CanBeInstVertexBufferType,
+        AnimationSet,
+        GltfAsset,
         Buffer,
+        DynamicBuffer,
+        Image2D,
         IntoBufferData,
         SingleFrameRenderInfo,
         SingleModelRenderInfo,
@@ -31,13 +44,22 @@ use crate::{
             buffer_submit_job::{BufferSubmitJob},
             load_material_job::{LoadMaterialJob}
         },
-        material::{Material},
+        material::{Material, TranslucentBlendMode},
         model::{ReadonlyModel},
+        mesh_data::{MeshData},
         buffer::{ReadonlyBuffer}
     },
     app_data::{AppData}
 };
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LoadStatus {
+    NotLoaded,
+    Loading,
+    Loaded,
+    Failed
+}
+
 #[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
 pub struct MaterialRef {
     id: u32
@@ -47,6 +69,25 @@ impl MaterialRef {
     pub fn get_id(&self) -> u32 {
         self.id
     }
+
+    pub fn load_status(&self, resource_loader: &ResourceLoader) -> LoadStatus {
+        resource_loader.get_material_load_status(*self)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TextureRef {
+    id: u32
+}
+
+impl TextureRef {
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn load_status(&self, resource_loader: &ResourceLoader) -> LoadStatus {
+        resource_loader.get_texture_load_status(*self)
+    }
 }
 
 #[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
@@ -55,7 +96,7 @@ pub struct ModelRef {
 }
 
 impl ModelRef {
-    pub fn create_frame_render_info_impl<TInstVert>(&self, frame_info: &mut SingleFrameRenderInfo, material: MaterialRef, is_static: bool, is_opaque: bool, viewmodel: &glm::Mat4, previous_viewmodel: Option<&glm::Mat4>, inst_vertex_buffer: Option<Buffer<TInstVert>>) -> Result<()> where TInstVert : CanBeInstVertexBufferType {
+    pub fn create_frame_render_info_impl<TInstVert>(&self, frame_info: &mut SingleFrameRenderInfo, material: MaterialRef, is_static: bool, is_opaque: bool, viewmodel: &glm::Mat4, previous_viewmodel: Option<&glm::Mat4>, inst_vertex_buffer: Option<Buffer<TInstVert>>, instance_transforms: Option<&[glm::Mat4]>) -> Result<()> where TInstVert : CanBeInstVertexBufferType {
         let previous_viewmodel: glm::Mat4 = if let Some(prev_vm) = previous_viewmodel { *prev_vm } else { *viewmodel };
 
         let mut instance_count = 1;
@@ -65,6 +106,16 @@ impl ModelRef {
             raw_inst_vertex_buffer = unsafe { Some(inst_vertex_buffer.raw_buffer().unwrap()) };
         }
 
+        let mut instance_transforms_offset = None;
+        if let Some(instance_transforms) = instance_transforms {
+            if instance_transforms.len() < instance_count as usize {
+                return Err(anyhow!("Got {} instance transforms for {} instances", instance_transforms.len(), instance_count));
+            }
+
+            instance_transforms_offset = Some(frame_info.instance_transforms.len());
+            frame_info.instance_transforms.extend_from_slice(&instance_transforms[..instance_count as usize]);
+        }
+
         let model_render_info = SingleModelRenderInfo {
             is_static,
             is_opaque,
@@ -75,6 +126,7 @@ impl ModelRef {
             material,
 
             inst_vertex_buffer: raw_inst_vertex_buffer,
+            instance_transforms_offset,
             instance_count,
 
             ..Default::default()
@@ -85,24 +137,69 @@ impl ModelRef {
         Ok(())
     }
     pub fn create_frame_render_info(&self, frame_info: &mut SingleFrameRenderInfo, material: MaterialRef, is_static: bool, is_opaque: bool, viewmodel: &glm::Mat4, previous_viewmodel: Option<&glm::Mat4>) -> Result<()> {
-        self.create_frame_render_info_impl(frame_info, material, is_static, is_opaque, viewmodel, previous_viewmodel, None as Option<Buffer<EmptyVertex>>)
+        self.create_frame_render_info_impl(frame_info, material, is_static, is_opaque, viewmodel, previous_viewmodel, None as Option<Buffer<EmptyVertex>>, None)
     }
-    pub fn create_frame_render_info_instanced<TInstVert>(&self, frame_info: &mut SingleFrameRenderInfo, material: MaterialRef, is_static: bool, is_opaque: bool, viewmodel: &glm::Mat4, previous_viewmodel: Option<&glm::Mat4>, inst_vertex_buffer: Buffer<TInstVert>) -> Result<()> where TInstVert : CanBeInstVertexBufferType {
-        self.create_frame_render_info_impl(frame_info, material, is_static, is_opaque, viewmodel, previous_viewmodel, Some(inst_vertex_buffer))
+    //Instance transforms are in model space, applied before the viewmodel, and let instances be culled one by one. Without them the draw is never culled, since the model's bounds don't say where the instances are
+    pub fn create_frame_render_info_instanced<TInstVert>(&self, frame_info: &mut SingleFrameRenderInfo, material: MaterialRef, is_static: bool, is_opaque: bool, viewmodel: &glm::Mat4, previous_viewmodel: Option<&glm::Mat4>, inst_vertex_buffer: Buffer<TInstVert>, instance_transforms: Option<&[glm::Mat4]>) -> Result<()> where TInstVert : CanBeInstVertexBufferType {
+        self.create_frame_render_info_impl(frame_info, material, is_static, is_opaque, viewmodel, previous_viewmodel, Some(inst_vertex_buffer), instance_transforms)
     }
 
     #[allow(unused)]
     pub fn get_id(&self) -> u32 {
         self.id
     }
+
+    pub fn load_status(&self, resource_loader: &ResourceLoader) -> LoadStatus {
+        resource_loader.get_model_load_status(*self)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct AnimationRef {
+    id: u32
+}
+
+impl AnimationRef {
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn load_status(&self, resource_loader: &ResourceLoader) -> LoadStatus {
+        resource_loader.get_animation_load_status(*self)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct GltfAssetRef {
+    id: u32
+}
+
+impl GltfAssetRef {
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn load_status(&self, resource_loader: &ResourceLoader) -> LoadStatus {
+        resource_loader.get_gltf_asset_load_status(*self)
+    }
 }
 
 #[derive(Debug)]
 pub struct MaterialProperties<TVert, TInstVert = EmptyVertex> where TVert : CanBeVertexBufferType, TInstVert : CanBeInstVertexBufferType {
+    //Translucent materials are drawn after everything opaque, back to front, with the base render shader. They don't cast shadows
     pub is_opaque: bool,
+    //Ignored for opaque materials
+    pub translucent_blend_mode: TranslucentBlendMode,
     pub shader_name: &'static str,
     pub shader_entry: &'static str,
 
+    //Bound to the material descriptor set. Textures that aren't set are replaced with neutral defaults
+    pub base_color_texture: Option<String>,
+    pub normal_texture: Option<String>,
+    pub occlusion_roughness_metallic_texture: Option<String>,
+    //Multiplied with the textures, or with the defaults if they aren't set
+    pub factors: MaterialUniform,
+
     #[doc(hidden)]
     pub phantom_vert: PhantomData<TVert>,
     #[doc(hidden)]
@@ -113,9 +210,15 @@ impl<TVert, TInstVert> Default for MaterialProperties<TVert, TInstVert> where TV
     fn default() -> Self {
         Self {
             is_opaque: true,
+            translucent_blend_mode: Default::default(),
             shader_name: "standard",
             shader_entry: "main",
 
+            base_color_texture: None,
+            normal_texture: None,
+            occlusion_roughness_metallic_texture: None,
+            factors: Default::default(),
+
             phantom_vert: Default::default(),
             phantom_inst_vert: Default::default()
         }
@@ -125,9 +228,18 @@ impl<TVert, TInstVert> Default for MaterialProperties<TVert, TInstVert> where TV
 impl<TVert, TInstVert> ::core::hash::Hash for MaterialProperties<TVert, TInstVert> where TVert : CanBeVertexBufferType, TInstVert : CanBeInstVertexBufferType {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.is_opaque.hash(state);
+        self.translucent_blend_mode.hash(state);
         self.shader_name.hash(state);
         self.shader_entry.hash(state);
 
+        self.base_color_texture.hash(state);
+        self.normal_texture.hash(state);
+        self.occlusion_roughness_metallic_texture.hash(state);
+        //Floats aren't Hash, but identical factors have identical bits
+        for factor in self.factors.base_color_factor.iter().chain([&self.factors.metallic_factor, &self.factors.roughness_factor, &self.factors.normal_scale, &self.factors.occlusion_strength]) {
+            factor.to_bits().hash(state);
+        }
+
         self.phantom_vert.hash(state);
         self.phantom_inst_vert.hash(state);
 
@@ -138,9 +250,18 @@ impl<TVert, TInstVert> ::core::hash::Hash for MaterialProperties<TVert, TInstVer
     }
 }
 
+#[derive(Debug, Default, Hash)]
+pub struct TextureProperties {
+    pub path: Option<String>,
+    pub is_srgb: bool
+}
+
 #[derive(Debug, Default)]
 pub struct ModelProperties<TVert> where TVert : CanBeVertexBufferType {
     pub obj_path: Option<String>,
+    pub gltf_path: Option<String>,
+    //Mesh and primitive index to load from the glTF file. If None, the whole default scene is merged into a single model
+    pub gltf_primitive: Option<(usize, usize)>,
 
     #[doc(hidden)]
     pub phantom_vert: PhantomData<TVert>
@@ -149,6 +270,8 @@ pub struct ModelProperties<TVert> where TVert : CanBeVertexBufferType {
 impl<TVert> ::core::hash::Hash for ModelProperties<TVert> where TVert : CanBeVertexBufferType {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.obj_path.hash(state);
+        self.gltf_path.hash(state);
+        self.gltf_primitive.hash(state);
 
         self.phantom_vert.hash(state);
 
@@ -157,40 +280,147 @@ impl<TVert> ::core::hash::Hash for ModelProperties<TVert> where TVert : CanBeVer
     }
 }
 
+#[derive(Debug, Default, Hash)]
+pub struct AnimationProperties {
+    pub gltf_path: Option<String>,
+    //If None, the first skin in the file
+    pub skin_index: Option<usize>
+}
+
+#[derive(Debug, Default, Hash)]
+pub struct GltfAssetProperties {
+    pub gltf_path: Option<String>
+}
+
+const MAX_MATERIAL_DESCRIPTOR_SETS: u32 = 1024;
+pub(crate) const MATERIAL_TEXTURE_COUNT: u32 = 3;
+
+#[derive(Debug, Copy, Clone)]
+struct RefCount {
+    hashed_props: u64,
+    count: u32
+}
+
+//Resources that have been unloaded but may still be in use by frames in flight
+#[derive(Debug)]
+enum UnloadedResource {
+    Material(Material),
+    Model(ReadonlyModel),
+    Texture(Image2D),
+    Buffer(ReadonlyBuffer)
+}
+
+#[derive(Debug)]
+struct InFlightUpload {
+    command_buffer: vk::CommandBuffer,
+    fence: vk::Fence,
+    jobs: Vec<Box<dyn ResourceLoadJob>>
+}
+
 #[derive(Debug)]
 pub struct ResourceLoader {
     device: Device,
     memory: vk::PhysicalDeviceMemoryProperties,
     job_queue: VecDeque<Box<dyn ResourceLoadJob>>,
+    workers: ResourceLoadWorkers,
+    preparing_job_count: usize,
+    prepared_jobs: VecDeque<Box<dyn ResourceLoadJob>>,
+    in_flight_uploads: Vec<InFlightUpload>,
 
     buffers: Vec<ReadonlyBuffer>,
 
     hashed_material_props: HashMap<u64, MaterialRef>,
+    material_ref_counts: HashMap<MaterialRef, RefCount>,
+    material_textures: HashMap<MaterialRef, Vec<TextureRef>>,
     materials: HashMap<MaterialRef, Material>,
+    failed_materials: HashSet<MaterialRef>,
     next_mat_ref_id: u32,
 
     hashed_model_props: HashMap<u64, ModelRef>,
+    model_ref_counts: HashMap<ModelRef, RefCount>,
     models: HashMap<ModelRef, ReadonlyModel>,
-    next_model_ref_id: u32
+    mesh_data: HashMap<ModelRef, Arc<MeshData>>,
+    failed_models: HashSet<ModelRef>,
+    next_model_ref_id: u32,
+
+    hashed_texture_props: HashMap<u64, TextureRef>,
+    texture_ref_counts: HashMap<TextureRef, RefCount>,
+    textures: HashMap<TextureRef, Image2D>,
+    failed_textures: HashSet<TextureRef>,
+    next_texture_ref_id: u32,
+    default_textures: Option<[Image2D; MATERIAL_TEXTURE_COUNT as usize]>,
+
+    hashed_animation_props: HashMap<u64, AnimationRef>,
+    animation_ref_counts: HashMap<AnimationRef, RefCount>,
+    animations: HashMap<AnimationRef, Arc<AnimationSet>>,
+    failed_animations: HashSet<AnimationRef>,
+    next_animation_ref_id: u32,
+
+    hashed_gltf_asset_props: HashMap<u64, GltfAssetRef>,
+    gltf_asset_ref_counts: HashMap<GltfAssetRef, RefCount>,
+    gltf_assets: HashMap<GltfAssetRef, Arc<GltfAsset>>,
+    failed_gltf_assets: HashSet<GltfAssetRef>,
+    next_gltf_asset_ref_id: u32,
+
+    material_descriptor_pool: Option<vk::DescriptorPool>,
+
+    max_frames_in_flight: u32,
+    tick_count: u64,
+    pending_destruction: VecDeque<(u64, UnloadedResource)>
 }
 
 impl ResourceLoader {
-    pub fn new(device: Device, memory: vk::PhysicalDeviceMemoryProperties) -> Self {
-        ResourceLoader {
+    pub fn new(device: Device, memory: vk::PhysicalDeviceMemoryProperties, max_frames_in_flight: u32) -> Result<Self> {
+        Ok(ResourceLoader {
             device,
             memory,
             job_queue: VecDeque::new(),
+            workers: ResourceLoadWorkers::new()?,
+            preparing_job_count: 0,
+            prepared_jobs: VecDeque::new(),
+            in_flight_uploads: Vec::new(),
 
             buffers: Vec::new(),
 
             hashed_material_props: HashMap::new(),
+            material_ref_counts: HashMap::new(),
+            material_textures: HashMap::new(),
             materials: HashMap::new(),
+            failed_materials: HashSet::new(),
             next_mat_ref_id: 1,
 
             hashed_model_props: HashMap::new(),
+            model_ref_counts: HashMap::new(),
             models: HashMap::new(),
-            next_model_ref_id: 1
-        }
+            mesh_data: HashMap::new(),
+            failed_models: HashSet::new(),
+            next_model_ref_id: 1,
+
+            hashed_texture_props: HashMap::new(),
+            texture_ref_counts: HashMap::new(),
+            textures: HashMap::new(),
+            failed_textures: HashSet::new(),
+            next_texture_ref_id: 1,
+            default_textures: None,
+
+            hashed_animation_props: HashMap::new(),
+            animation_ref_counts: HashMap::new(),
+            animations: HashMap::new(),
+            failed_animations: HashSet::new(),
+            next_animation_ref_id: 1,
+
+            hashed_gltf_asset_props: HashMap::new(),
+            gltf_asset_ref_counts: HashMap::new(),
+            gltf_assets: HashMap::new(),
+            failed_gltf_assets: HashSet::new(),
+            next_gltf_asset_ref_id: 1,
+
+            material_descriptor_pool: None,
+
+            max_frames_in_flight,
+            tick_count: 0,
+            pending_destruction: VecDeque::new()
+        })
     }
 
     fn add_job(&mut self, job: impl ResourceLoadJob + 'static) -> Result<()> {
@@ -206,30 +436,217 @@ impl ResourceLoader {
             hasher.finish()
         };
         if let Some(mat_ref) = self.hashed_material_props.get(&hashed_props) {
-            //TODO: reference count
+            self.material_ref_counts.get_mut(mat_ref).unwrap().count += 1;
             return Ok(*mat_ref);
         }
 
         let mat_ref = MaterialRef { id: self.next_mat_ref_id };
         self.next_mat_ref_id += 1;
         self.hashed_material_props.insert(hashed_props, mat_ref);
+        self.material_ref_counts.insert(mat_ref, RefCount { hashed_props, count: 1 });
+
+        //Queued before the material so they're done by the time the material's descriptor set is written
+        let textures = [
+            self.get_or_load_texture_opt(&props.base_color_texture, true)?,
+            self.get_or_load_texture_opt(&props.normal_texture, false)?,
+            self.get_or_load_texture_opt(&props.occlusion_roughness_metallic_texture, false)?
+        ];
+
+        self.material_textures.insert(mat_ref, textures.iter().filter_map(|t| *t).collect());
 
-        let job = LoadMaterialJob::create_for(mat_ref, props);
+        let job = LoadMaterialJob::create_for(mat_ref, props, textures);
         self.add_job(job)?;
 
         Ok(mat_ref)
     }
     pub(super) fn finish_loading_material(&mut self, mat_ref: MaterialRef, mat: Material) -> Result<()> {
+        if !self.material_ref_counts.contains_key(&mat_ref) {
+            //Unloaded while the load job was queued
+            self.defer_destruction(UnloadedResource::Material(mat));
+            return Ok(());
+        }
+
         self.materials.insert(mat_ref, mat);
 
         Ok(())
     }
+    pub(super) fn fail_loading_material(&mut self, mat_ref: MaterialRef) -> () {
+        if self.material_ref_counts.contains_key(&mat_ref) {
+            self.failed_materials.insert(mat_ref);
+        }
+    }
+    pub fn get_material_load_status(&self, mat_ref: MaterialRef) -> LoadStatus {
+        if self.materials.contains_key(&mat_ref) {
+            LoadStatus::Loaded
+        } else if self.failed_materials.contains(&mat_ref) {
+            LoadStatus::Failed
+        } else if self.material_ref_counts.contains_key(&mat_ref) {
+            LoadStatus::Loading
+        } else {
+            LoadStatus::NotLoaded
+        }
+    }
     pub fn get_render_material(&self, mat_ref: MaterialRef) -> Option<Material> {
         self.materials.get(&mat_ref)
             .filter(|m| m.is_loaded)
             .map(|m| *m)
     }
 
+    pub fn get_or_load_texture(&mut self, props: &TextureProperties) -> Result<TextureRef> {
+        let hashed_props = {
+            let mut hasher = DefaultHasher::new();
+            props.hash(&mut hasher);
+            hasher.finish()
+        };
+        if let Some(texture_ref) = self.hashed_texture_props.get(&hashed_props) {
+            self.texture_ref_counts.get_mut(texture_ref).unwrap().count += 1;
+            return Ok(*texture_ref);
+        }
+
+        let texture_ref = TextureRef { id: self.next_texture_ref_id };
+        self.next_texture_ref_id += 1;
+        self.hashed_texture_props.insert(hashed_props, texture_ref);
+        self.texture_ref_counts.insert(texture_ref, RefCount { hashed_props, count: 1 });
+
+        let job = LoadTextureJob::create_for(texture_ref, props);
+        self.add_job(job)?;
+
+        Ok(texture_ref)
+    }
+    fn get_or_load_texture_opt(&mut self, path: &Option<String>, is_srgb: bool) -> Result<Option<TextureRef>> {
+        if let Some(path) = path {
+            let props = TextureProperties {
+                path: Some(path.clone()),
+                is_srgb
+            };
+            Ok(Some(self.get_or_load_texture(&props)?))
+        } else {
+            Ok(None)
+        }
+    }
+    pub(super) fn finish_loading_texture(&mut self, texture_ref: TextureRef, texture: Image2D) -> Result<()> {
+        if !self.texture_ref_counts.contains_key(&texture_ref) {
+            //Unloaded while the load job was queued
+            self.defer_destruction(UnloadedResource::Texture(texture));
+            return Ok(());
+        }
+
+        self.textures.insert(texture_ref, texture);
+
+        Ok(())
+    }
+    pub(super) fn fail_loading_texture(&mut self, texture_ref: TextureRef) -> () {
+        if self.texture_ref_counts.contains_key(&texture_ref) {
+            self.failed_textures.insert(texture_ref);
+        }
+    }
+    pub fn get_texture_load_status(&self, texture_ref: TextureRef) -> LoadStatus {
+        if self.textures.contains_key(&texture_ref) {
+            LoadStatus::Loaded
+        } else if self.failed_textures.contains(&texture_ref) {
+            LoadStatus::Failed
+        } else if self.texture_ref_counts.contains_key(&texture_ref) {
+            LoadStatus::Loading
+        } else {
+            LoadStatus::NotLoaded
+        }
+    }
+    pub fn get_texture(&self, texture_ref: TextureRef) -> Option<Image2D> {
+        self.textures.get(&texture_ref).map(|t| *t)
+    }
+
+    fn get_or_create_material_descriptor_pool(&mut self) -> Result<vk::DescriptorPool> {
+        if let Some(pool) = self.material_descriptor_pool {
+            return Ok(pool);
+        }
+
+        let sampler_size = vk::DescriptorPoolSize::builder()
+            .type_(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .descriptor_count(MAX_MATERIAL_DESCRIPTOR_SETS * MATERIAL_TEXTURE_COUNT);
+
+        let uniform_size = vk::DescriptorPoolSize::builder()
+            .type_(vk::DescriptorType::UNIFORM_BUFFER)
+            .descriptor_count(MAX_MATERIAL_DESCRIPTOR_SETS);
+
+        let pool_sizes = &[sampler_size, uniform_size];
+        let desc_pool_info = vk::DescriptorPoolCreateInfo::builder()
+            .flags(vk::DescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET)
+            .pool_sizes(pool_sizes)
+            .max_sets(MAX_MATERIAL_DESCRIPTOR_SETS);
+
+        let pool = unsafe { self.device.create_descriptor_pool(&desc_pool_info, None)? };
+        debug!("Material descriptor pool created: {:?}", pool);
+        self.material_descriptor_pool = Some(pool);
+
+        Ok(pool)
+    }
+    fn get_or_create_default_textures(&mut self, app_data: &AppData) -> Result<[Image2D; MATERIAL_TEXTURE_COUNT as usize]> {
+        if let Some(default_textures) = self.default_textures {
+            return Ok(default_textures);
+        }
+
+        let command_pools_info = app_data.command_pools.as_ref().unwrap();
+        let size = vk::Extent2D { width: 1, height: 1 };
+
+        //White base color, flat normal, and no occlusion, fully rough, not metallic
+        let mut base_color = Image2D::new();
+        base_color.create_from_rgba_pixels(&vec![255, 255, 255, 255], size, &self.device, &self.memory, command_pools_info, true)?;
+        let mut normal = Image2D::new();
+        normal.create_from_rgba_pixels(&vec![128, 128, 255, 255], size, &self.device, &self.memory, command_pools_info, false)?;
+        let mut occlusion_roughness_metallic = Image2D::new();
+        occlusion_roughness_metallic.create_from_rgba_pixels(&vec![255, 255, 0, 255], size, &self.device, &self.memory, command_pools_info, false)?;
+
+        let default_textures = [base_color, normal, occlusion_roughness_metallic];
+        self.default_textures = Some(default_textures);
+
+        Ok(default_textures)
+    }
+    pub(super) fn create_material_descriptor_set(&mut self, app_data: &AppData, textures: &[Option<TextureRef>; MATERIAL_TEXTURE_COUNT as usize], uniform_buffer: &Buffer<MaterialUniform>) -> Result<vk::DescriptorSet> {
+        let layout = app_data.uniforms.as_ref().unwrap().material_descriptor_set_layout;
+        let pool = self.get_or_create_material_descriptor_pool()?;
+        let default_textures = self.get_or_create_default_textures(app_data)?;
+
+        let layouts = &[layout];
+        let desc_set_info = vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(pool)
+            .set_layouts(layouts);
+        let desc_set = unsafe { self.device.allocate_descriptor_sets(&desc_set_info)?[0] };
+
+        let images = textures.iter()
+            .zip(default_textures.iter())
+            .map(|(texture_ref, default_texture)| {
+                texture_ref.and_then(|t| self.get_texture(t)).unwrap_or(*default_texture)
+            })
+            .collect::<Vec<_>>();
+        let image_info = images.iter()
+            .map(|image| image.get_descriptor_image_info().build())
+            .collect::<Vec<_>>();
+        let sampler_write = vk::WriteDescriptorSet::builder()
+            .dst_set(desc_set)
+            .dst_binding(0)
+            .dst_array_element(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(&image_info[..]);
+
+        let buffer_info = vk::DescriptorBufferInfo::builder()
+            .buffer(unsafe { uniform_buffer.raw_buffer().unwrap() })
+            .offset(0)
+            .range(uniform_buffer.allocated_buffer_size());
+        let buffer_info = &[buffer_info];
+        let uniform_write = vk::WriteDescriptorSet::builder()
+            .dst_set(desc_set)
+            .dst_binding(1)
+            .dst_array_element(0)
+            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+            .buffer_info(buffer_info);
+
+        unsafe {
+            self.device.update_descriptor_sets(&[sampler_write, uniform_write], &[] as &[vk::CopyDescriptorSet]);
+        }
+
+        Ok(desc_set)
+    }
+
     pub fn get_or_load_model<TVert>(&mut self, props: &ModelProperties<TVert>) -> Result<ModelRef> where TVert : CanBeVertexBufferType + 'static {
         let hashed_props = {
             let mut hasher = DefaultHasher::new();
@@ -237,28 +654,157 @@ impl ResourceLoader {
             hasher.finish()
         };
         if let Some(model_ref) = self.hashed_model_props.get(&hashed_props) {
-            //TODO: reference count
+            self.model_ref_counts.get_mut(model_ref).unwrap().count += 1;
             return Ok(*model_ref);
         }
 
         let model_ref = ModelRef { id: self.next_model_ref_id };
         self.next_model_ref_id += 1;
         self.hashed_model_props.insert(hashed_props, model_ref);
+        self.model_ref_counts.insert(model_ref, RefCount { hashed_props, count: 1 });
 
         let job = LoadModelJob::create_for(model_ref, props);
         self.add_job(job)?;
 
         Ok(model_ref)
     }
-    pub(super) fn finish_loading_model(&mut self, model_ref: ModelRef, model: ReadonlyModel) -> Result<()> {
+    pub(super) fn finish_loading_model(&mut self, model_ref: ModelRef, model: ReadonlyModel, mesh_data: MeshData) -> Result<()> {
+        if !self.model_ref_counts.contains_key(&model_ref) {
+            //Unloaded while the load job was queued
+            self.defer_destruction(UnloadedResource::Model(model));
+            return Ok(());
+        }
+
         self.models.insert(model_ref, model);
+        self.mesh_data.insert(model_ref, Arc::new(mesh_data));
 
         Ok(())
     }
+    pub(super) fn fail_loading_model(&mut self, model_ref: ModelRef) -> () {
+        if self.model_ref_counts.contains_key(&model_ref) {
+            self.failed_models.insert(model_ref);
+        }
+    }
+    pub fn get_model_load_status(&self, model_ref: ModelRef) -> LoadStatus {
+        if self.models.contains_key(&model_ref) {
+            LoadStatus::Loaded
+        } else if self.failed_models.contains(&model_ref) {
+            LoadStatus::Failed
+        } else if self.model_ref_counts.contains_key(&model_ref) {
+            LoadStatus::Loading
+        } else {
+            LoadStatus::NotLoaded
+        }
+    }
     pub fn get_render_model(&self, model_ref: ModelRef) -> Option<ReadonlyModel> {
         self.models.get(&model_ref).map(|m| *m)
     }
 
+    //Only for loaded models
+    pub fn get_mesh_data(&self, model_ref: ModelRef) -> Option<Arc<MeshData>> {
+        self.mesh_data.get(&model_ref).cloned()
+    }
+
+    pub fn get_or_load_animation(&mut self, props: &AnimationProperties) -> Result<AnimationRef> {
+        let hashed_props = {
+            let mut hasher = DefaultHasher::new();
+            props.hash(&mut hasher);
+            hasher.finish()
+        };
+        if let Some(animation_ref) = self.hashed_animation_props.get(&hashed_props) {
+            self.animation_ref_counts.get_mut(animation_ref).unwrap().count += 1;
+            return Ok(*animation_ref);
+        }
+
+        let animation_ref = AnimationRef { id: self.next_animation_ref_id };
+        self.next_animation_ref_id += 1;
+        self.hashed_animation_props.insert(hashed_props, animation_ref);
+        self.animation_ref_counts.insert(animation_ref, RefCount { hashed_props, count: 1 });
+
+        let job = LoadAnimationJob::create_for(animation_ref, props);
+        self.add_job(job)?;
+
+        Ok(animation_ref)
+    }
+    pub(super) fn finish_loading_animation(&mut self, animation_ref: AnimationRef, animation_set: AnimationSet) -> Result<()> {
+        //Dropped if it was unloaded while the load job was queued, since it has nothing on the GPU
+        if self.animation_ref_counts.contains_key(&animation_ref) {
+            self.animations.insert(animation_ref, Arc::new(animation_set));
+        }
+
+        Ok(())
+    }
+    pub(super) fn fail_loading_animation(&mut self, animation_ref: AnimationRef) -> () {
+        if self.animation_ref_counts.contains_key(&animation_ref) {
+            self.failed_animations.insert(animation_ref);
+        }
+    }
+    pub fn get_animation_load_status(&self, animation_ref: AnimationRef) -> LoadStatus {
+        if self.animations.contains_key(&animation_ref) {
+            LoadStatus::Loaded
+        } else if self.failed_animations.contains(&animation_ref) {
+            LoadStatus::Failed
+        } else if self.animation_ref_counts.contains_key(&animation_ref) {
+            LoadStatus::Loading
+        } else {
+            LoadStatus::NotLoaded
+        }
+    }
+    //Only for loaded animations
+    pub fn get_animation_set(&self, animation_ref: AnimationRef) -> Option<Arc<AnimationSet>> {
+        self.animations.get(&animation_ref).cloned()
+    }
+
+    pub fn get_or_load_gltf_asset(&mut self, props: &GltfAssetProperties) -> Result<GltfAssetRef> {
+        let hashed_props = {
+            let mut hasher = DefaultHasher::new();
+            props.hash(&mut hasher);
+            hasher.finish()
+        };
+        if let Some(gltf_asset_ref) = self.hashed_gltf_asset_props.get(&hashed_props) {
+            self.gltf_asset_ref_counts.get_mut(gltf_asset_ref).unwrap().count += 1;
+            return Ok(*gltf_asset_ref);
+        }
+
+        let gltf_asset_ref = GltfAssetRef { id: self.next_gltf_asset_ref_id };
+        self.next_gltf_asset_ref_id += 1;
+        self.hashed_gltf_asset_props.insert(hashed_props, gltf_asset_ref);
+        self.gltf_asset_ref_counts.insert(gltf_asset_ref, RefCount { hashed_props, count: 1 });
+
+        let job = LoadGltfAssetJob::create_for(gltf_asset_ref, props);
+        self.add_job(job)?;
+
+        Ok(gltf_asset_ref)
+    }
+    pub(super) fn finish_loading_gltf_asset(&mut self, gltf_asset_ref: GltfAssetRef, gltf_asset: GltfAsset) -> Result<()> {
+        //Dropped if it was unloaded while the load job was queued, since it has nothing on the GPU
+        if self.gltf_asset_ref_counts.contains_key(&gltf_asset_ref) {
+            self.gltf_assets.insert(gltf_asset_ref, Arc::new(gltf_asset));
+        }
+
+        Ok(())
+    }
+    pub(super) fn fail_loading_gltf_asset(&mut self, gltf_asset_ref: GltfAssetRef) -> () {
+        if self.gltf_asset_ref_counts.contains_key(&gltf_asset_ref) {
+            self.failed_gltf_assets.insert(gltf_asset_ref);
+        }
+    }
+    pub fn get_gltf_asset_load_status(&self, gltf_asset_ref: GltfAssetRef) -> LoadStatus {
+        if self.gltf_assets.contains_key(&gltf_asset_ref) {
+            LoadStatus::Loaded
+        } else if self.failed_gltf_assets.contains(&gltf_asset_ref) {
+            LoadStatus::Failed
+        } else if self.gltf_asset_ref_counts.contains_key(&gltf_asset_ref) {
+            LoadStatus::Loading
+        } else {
+            LoadStatus::NotLoaded
+        }
+    }
+    //Only for loaded glTF assets
+    pub fn get_gltf_asset(&self, gltf_asset_ref: GltfAssetRef) -> Option<Arc<GltfAsset>> {
+        self.gltf_assets.get(&gltf_asset_ref).cloned()
+    }
+
     fn create_buffer<T>(&mut self, data: &impl IntoBufferData<T>, usage: vk::BufferUsageFlags) -> Result<Buffer<T>> where T : Copy + Clone + std::fmt::Debug {
         let mut buffer = Buffer::<T>::new(usage, data.element_count(), true);
         buffer.create(&self.device, &self.memory)?;
@@ -274,21 +820,168 @@ impl ResourceLoader {
     pub fn create_inst_buffer<T>(&mut self, data: &impl IntoBufferData<T>) -> Result<Buffer<T>> where T : Copy + Clone + std::fmt::Debug {
         self.create_buffer(data, vk::BufferUsageFlags::VERTEX_BUFFER)
     }
+    //Components write these from load_and_unload, before the frame waits for the oldest frame in flight, so there's one more copy than there are frames in flight
+    pub fn create_dynamic_inst_buffer<T>(&mut self, data: &impl IntoBufferData<T>) -> Result<DynamicBuffer<T>> where T : Copy + Clone + std::fmt::Debug {
+        let copy_count = self.max_frames_in_flight as usize + 1;
+        let mut copies = Vec::with_capacity(copy_count);
+        for _ in 0..copy_count {
+            //Host visible, so writes don't need an upload
+            let mut buffer = Buffer::<T>::new(vk::BufferUsageFlags::VERTEX_BUFFER, data.element_count(), false);
+            buffer.create(&self.device, &self.memory)?;
+            buffer.set_data(&self.device, data)?;
+
+            self.buffers.push(buffer.clone().reinterpret_readonly());
+            copies.push(buffer);
+        }
+
+        Ok(DynamicBuffer {
+            copies,
+            current: 0
+        })
+    }
+    //At most once a frame
+    pub fn write_dynamic_buffer<T>(&self, buffer: &mut DynamicBuffer<T>, data: &impl IntoBufferData<T>) -> Result<()> where T : Copy + Clone + std::fmt::Debug {
+        buffer.current = (buffer.current + 1) % buffer.copies.len();
+        buffer.copies[buffer.current].set_data(&self.device, data)
+    }
+    pub fn unload_dynamic_buffer<T>(&mut self, buffer: DynamicBuffer<T>) -> () where T : Copy + Clone + std::fmt::Debug {
+        for copy in buffer.copies {
+            self.unload_inst_buffer(copy);
+        }
+    }
+    pub fn unload_inst_buffer<T>(&mut self, buffer: Buffer<T>) -> () where T : Copy + Clone + std::fmt::Debug {
+        let raw_buffer = unsafe { buffer.raw_buffer() };
+        if let Some(index) = self.buffers.iter().position(|b| unsafe { b.raw_buffer() } == raw_buffer) {
+            let buffer = self.buffers.swap_remove(index);
+            self.defer_destruction(UnloadedResource::Buffer(buffer));
+        } else {
+            warn!("Tried to unload a buffer that wasn't created by this resource loader: {:?}", raw_buffer);
+        }
+    }
+
+    //Returns the hashed props for the resource if this was the last reference to it
+    fn release_ref<TRef>(ref_counts: &mut HashMap<TRef, RefCount>, resource_ref: TRef) -> Option<u64> where TRef : Eq + Hash + std::fmt::Debug {
+        let ref_count = match ref_counts.get_mut(&resource_ref) {
+            Some(ref_count) => ref_count,
+            None => {
+                warn!("Tried to unload {:?}, which isn't loaded", resource_ref);
+                return None;
+            }
+        };
+
+        ref_count.count -= 1;
+        if ref_count.count > 0 {
+            return None;
+        }
+
+        ref_counts.remove(&resource_ref).map(|r| r.hashed_props)
+    }
+
+    pub fn unload_material(&mut self, material: MaterialRef) -> () {
+        if let Some(hashed_props) = Self::release_ref(&mut self.material_ref_counts, material) {
+            self.hashed_material_props.remove(&hashed_props);
+            self.failed_materials.remove(&material);
+            if let Some(mat) = self.materials.remove(&material) {
+                self.defer_destruction(UnloadedResource::Material(mat));
+            }
+
+            for texture in self.material_textures.remove(&material).unwrap_or_default() {
+                self.unload_texture(texture);
+            }
+        }
+    }
 
-    pub fn unload_material(&mut self, _material: MaterialRef) -> () {
-        //TODO: reference count
+    pub fn unload_model(&mut self, model: ModelRef) -> () {
+        if let Some(hashed_props) = Self::release_ref(&mut self.model_ref_counts, model) {
+            self.hashed_model_props.remove(&hashed_props);
+            self.failed_models.remove(&model);
+            self.mesh_data.remove(&model);
+            if let Some(model) = self.models.remove(&model) {
+                self.defer_destruction(UnloadedResource::Model(model));
+            }
+        }
     }
 
-    pub fn unload_model(&mut self, _model: ModelRef) -> () {
-        //TODO: reference count
+    pub fn unload_animation(&mut self, animation: AnimationRef) -> () {
+        if let Some(hashed_props) = Self::release_ref(&mut self.animation_ref_counts, animation) {
+            self.hashed_animation_props.remove(&hashed_props);
+            self.failed_animations.remove(&animation);
+            self.animations.remove(&animation);
+        }
+    }
+
+    pub fn unload_gltf_asset(&mut self, gltf_asset: GltfAssetRef) -> () {
+        if let Some(hashed_props) = Self::release_ref(&mut self.gltf_asset_ref_counts, gltf_asset) {
+            self.hashed_gltf_asset_props.remove(&hashed_props);
+            self.failed_gltf_assets.remove(&gltf_asset);
+            self.gltf_assets.remove(&gltf_asset);
+        }
+    }
+
+    pub fn unload_texture(&mut self, texture: TextureRef) -> () {
+        if let Some(hashed_props) = Self::release_ref(&mut self.texture_ref_counts, texture) {
+            self.hashed_texture_props.remove(&hashed_props);
+            self.failed_textures.remove(&texture);
+            if let Some(texture) = self.textures.remove(&texture) {
+                self.defer_destruction(UnloadedResource::Texture(texture));
+            }
+        }
+    }
+
+    fn defer_destruction(&mut self, resource: UnloadedResource) -> () {
+        self.pending_destruction.push_back((self.tick_count, resource));
+    }
+    fn destroy_unloaded_resource(&mut self, resource: UnloadedResource) -> () {
+        trace!("Destroying unloaded resource: {:?}", resource);
+
+        match resource {
+            UnloadedResource::Material(mut mat) => {
+                if let (Some(pool), Some(descriptor_set)) = (self.material_descriptor_pool, mat.descriptor_set.take()) {
+                    unsafe {
+                        if let Err(e) = self.device.free_descriptor_sets(pool, &[descriptor_set]) {
+                            warn!("Failed to free material descriptor set: {:?}", e);
+                        }
+                    }
+                }
+                mat.destroy(&self.device);
+            },
+            UnloadedResource::Model(mut model) => model.destroy(&self.device),
+            UnloadedResource::Texture(mut texture) => texture.destroy(&self.device),
+            UnloadedResource::Buffer(mut buffer) => buffer.destroy(&self.device)
+        }
+    }
+    fn destroy_retired_resources(&mut self, frames_in_flight: u64) -> () {
+        //Everything unloaded before the oldest frame still in flight was recorded can't be in use anymore
+        while let Some((unload_tick, _)) = self.pending_destruction.front() {
+            if *unload_tick + frames_in_flight > self.tick_count {
+                break;
+            }
+
+            let (_, resource) = self.pending_destruction.pop_front().unwrap();
+            self.destroy_unloaded_resource(resource);
+        }
     }
 
     pub fn force_unload_all(&mut self) -> () {
+        //Only called once the device is idle, so nothing is in flight anymore
+        while let Some((_, resource)) = self.pending_destruction.pop_front() {
+            self.destroy_unloaded_resource(resource);
+        }
+
+        self.material_ref_counts.clear();
+        self.material_textures.clear();
+        self.model_ref_counts.clear();
+        self.texture_ref_counts.clear();
+        self.failed_materials.clear();
+        self.failed_models.clear();
+        self.failed_textures.clear();
+
         self.hashed_model_props.clear();
         for model in self.models.values_mut() {
             model.destroy(&self.device);
         }
         self.models.clear();
+        self.mesh_data.clear();
 
         self.hashed_material_props.clear();
         for mat in self.materials.values_mut() {
@@ -296,41 +989,163 @@ impl ResourceLoader {
         }
         self.materials.clear();
 
+        self.hashed_texture_props.clear();
+        for texture in self.textures.values_mut() {
+            texture.destroy(&self.device);
+        }
+        self.textures.clear();
+
+        self.hashed_animation_props.clear();
+        self.animation_ref_counts.clear();
+        self.animations.clear();
+        self.failed_animations.clear();
+
+        self.hashed_gltf_asset_props.clear();
+        self.gltf_asset_ref_counts.clear();
+        self.gltf_assets.clear();
+        self.failed_gltf_assets.clear();
+
+        if let Some(mut default_textures) = self.default_textures.take() {
+            for texture in default_textures.iter_mut() {
+                texture.destroy(&self.device);
+            }
+        }
+
+        //Destroying the pool frees all of the material descriptor sets allocated from it
+        if let Some(pool) = self.material_descriptor_pool.take() {
+            unsafe {
+                self.device.destroy_descriptor_pool(pool, None);
+            }
+        }
+
         for buffer in self.buffers.iter_mut() {
             buffer.destroy(&self.device);
         }
         self.buffers.clear();
     }
 
+    pub fn has_pending_loads(&self) -> bool {
+        !self.job_queue.is_empty() || self.preparing_job_count > 0 || !self.prepared_jobs.is_empty() || !self.in_flight_uploads.is_empty()
+    }
+
+    fn finish_uploads(&mut self, app_data: &AppData, wait: bool) -> Result<()> {
+        let device = self.device.clone();
+        let command_pools = app_data.command_pools.as_ref().unwrap();
+
+        let mut q = 0;
+        while q < self.in_flight_uploads.len() {
+            let fence = self.in_flight_uploads[q].fence;
+            let is_done = unsafe {
+                if wait {
+                    device.wait_for_fences(&[fence], true, u64::MAX)?;
+                    true
+                } else {
+                    device.get_fence_status(fence)? == vk::SuccessCode::SUCCESS
+                }
+            };
+            if !is_done {
+                q += 1;
+                continue;
+            }
+
+            let mut upload = self.in_flight_uploads.swap_remove(q);
+            command_pools.free_command_transient(&device, upload.command_buffer, upload.fence);
+
+            //One job failing doesn't stop the others in the same upload from finishing
+            for job in upload.jobs.iter_mut() {
+                if let Err(e) = job.after_command(self, &device, app_data) {
+                    job.failed(self, &e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+    //Blocks until everything that's been submitted to the GPU has been uploaded. Jobs that are still being prepared are left alone
+    pub fn finish_pending_uploads(&mut self, app_data: &AppData) -> Result<()> {
+        self.finish_uploads(app_data, true)
+    }
+
     pub fn tick(&mut self, app_data: &AppData) -> Result<()> {
-        if self.job_queue.len() == 0 {
+        self.tick_count += 1;
+        self.destroy_retired_resources(app_data.max_frames_in_flight() as u64);
+
+        self.finish_uploads(app_data, false)?;
+
+        //File I/O and parsing happen on the worker threads
+        while let Some(job) = self.job_queue.pop_front() {
+            self.workers.queue(job)?;
+            self.preparing_job_count += 1;
+        }
+        while let Some((mut job, result)) = self.workers.try_receive() {
+            self.preparing_job_count -= 1;
+            match result {
+                Ok(()) => self.prepared_jobs.push_back(job),
+                Err(e) => job.failed(self, &e)
+            }
+        }
+
+        if self.prepared_jobs.is_empty() {
             return Ok(());
         }
 
         let device = self.device.clone();
 
         let mut jobs_to_write_to_command_buffer = vec![];
+        let mut waiting_jobs = VecDeque::new();
+
+        while let Some(mut job) = self.prepared_jobs.pop_front() {
+            if !job.is_ready(self) {
+                waiting_jobs.push_back(job);
+                continue;
+            }
+
+            if let Err(e) = job.load(self, &device, app_data) {
+                job.failed(self, &e);
+                continue;
+            }
 
-        while let Some(mut job) = self.job_queue.pop_front() {
-            job.load(self, &device, app_data)?;
             if job.needs_transient_command() {
                 jobs_to_write_to_command_buffer.push(job);
             }
         }
+        self.prepared_jobs = waiting_jobs;
+
+        if jobs_to_write_to_command_buffer.is_empty() {
+            return Ok(());
+        }
 
         let command_pools = app_data.command_pools.as_ref().unwrap();
         let jobs_to_write_to_command_buffer2 = &jobs_to_write_to_command_buffer[..];
-        command_pools.submit_command_transient_sync(&device, |command_buffer| {
+        let (command_buffer, fence) = command_pools.submit_command_transient_fenced(&device, |command_buffer| {
             for job in jobs_to_write_to_command_buffer2 {
                 job.write_to_command_buffer(self, &device, app_data, command_buffer)?;
             }
 
+            //Anything submitted to the queue after this (like the frame) sees the uploaded data, so buffers handed out before the upload finishes are still safe to draw with
+            let barrier = vk::MemoryBarrier::builder()
+                .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                .dst_access_mask(vk::AccessFlags::MEMORY_READ);
+            unsafe {
+                device.cmd_pipeline_barrier(
+                    *command_buffer,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::PipelineStageFlags::ALL_COMMANDS,
+                    vk::DependencyFlags::empty(),
+                    &[barrier],
+                    &[] as &[vk::BufferMemoryBarrier],
+                    &[] as &[vk::ImageMemoryBarrier]
+                );
+            }
+
             Ok(())
         })?;
 
-        for job in jobs_to_write_to_command_buffer.iter_mut() {
-            job.after_command(self, &device, app_data)?;
-        }
+        self.in_flight_uploads.push(InFlightUpload {
+            command_buffer,
+            fence,
+            jobs: jobs_to_write_to_command_buffer
+        });
 
         Ok(())
     }
@@ -2,11 +2,60 @@ use vulkanalia::{
     prelude::v1_0::*
 };
 
+use super::{
+    Buffer,
+    shader_source::{BlendStateDescriptor}
+};
+
+use crate::shader_input::uniform_buffer_object::{MaterialUniform};
+
+//How translucent materials are blended over what's behind them
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TranslucentBlendMode {
+    //Blended by the shader's alpha, for things like glass
+    Alpha,
+    //Added on top, scaled by the shader's alpha, for things like fire and glows
+    Additive
+}
+
+impl Default for TranslucentBlendMode {
+    fn default() -> Self {
+        TranslucentBlendMode::Alpha
+    }
+}
+
+impl TranslucentBlendMode {
+    pub fn blend_state(&self) -> BlendStateDescriptor {
+        //The base render's alpha is left alone either way
+        let blend_state = BlendStateDescriptor {
+            enable_blend: true,
+            src_alpha_blend_factor: vk::BlendFactor::ZERO,
+            dst_alpha_blend_factor: vk::BlendFactor::ONE,
+            ..Default::default()
+        };
+
+        match self {
+            TranslucentBlendMode::Alpha => blend_state,
+            TranslucentBlendMode::Additive => BlendStateDescriptor {
+                dst_color_blend_factor: vk::BlendFactor::ONE,
+                ..blend_state
+            }
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Default)]
 pub struct Material {
     pub is_loaded: bool,
     pub depth_motion: Option<vk::Pipeline>,
-    pub base_render: Option<vk::Pipeline>
+    pub base_render: Option<vk::Pipeline>,
+    //Only translucent materials have this, and they have no depth/motion, base render, or shadow pipelines
+    pub translucent: Option<vk::Pipeline>,
+    pub shadow: Option<vk::Pipeline>,
+    //Allocated from the resource loader's material descriptor pool, which owns it
+    pub descriptor_set: Option<vk::DescriptorSet>,
+    //Host visible, and never written after the material loads
+    pub uniform_buffer: Option<Buffer<MaterialUniform>>
 }
 
 impl Material {
@@ -19,6 +68,18 @@ impl Material {
             if let Some(base_render) = self.base_render {
                 device.destroy_pipeline(base_render, None);
             }
+
+            if let Some(translucent) = self.translucent {
+                device.destroy_pipeline(translucent, None);
+            }
+
+            if let Some(shadow) = self.shadow {
+                device.destroy_pipeline(shadow, None);
+            }
+        }
+
+        if let Some(mut uniform_buffer) = self.uniform_buffer.take() {
+            uniform_buffer.destroy(device);
         }
     }
 }
@@ -0,0 +1,84 @@
+use super::{Bounds};
+
+use nalgebra_glm as glm;
+
+//Positions and indices kept on the CPU once a model is loaded, so it can be raycast against. In model space
+#[derive(Debug, Clone, Default)]
+pub struct MeshData {
+    pub positions: Vec<glm::Vec3>,
+    pub indices: Vec<u32>,
+    pub bounds: Option<Bounds>
+}
+
+impl MeshData {
+    pub fn new(positions: Vec<glm::Vec3>, indices: Vec<u32>) -> Self {
+        let bounds = Bounds::from_points(&positions);
+
+        Self {
+            positions,
+            indices,
+            bounds
+        }
+    }
+
+    //The closest triangle hit as the distance along the direction and the triangle's normal, facing back along the ray. Triangles are hit from both sides
+    pub fn raycast(&self, origin: &glm::Vec3, direction: &glm::Vec3) -> Option<(f32, glm::Vec3)> {
+        let bounds = self.bounds.as_ref()?;
+        bounds.intersect_ray(origin, direction)?;
+
+        let mut closest: Option<(f32, glm::Vec3)> = None;
+        for face in self.indices.chunks_exact(3) {
+            let pt1 = self.positions[face[0] as usize];
+            let pt2 = self.positions[face[1] as usize];
+            let pt3 = self.positions[face[2] as usize];
+
+            if let Some(distance) = intersect_triangle(origin, direction, &pt1, &pt2, &pt3) {
+                let is_closer = match closest {
+                    Some((closest_distance, _)) => distance < closest_distance,
+                    None => true
+                };
+                if is_closer {
+                    let mut normal = glm::normalize(&glm::cross(&(pt2 - pt1), &(pt3 - pt1)));
+                    if glm::dot(&normal, direction) > 0.0 {
+                        normal = -normal;
+                    }
+                    closest = Some((distance, normal));
+                }
+            }
+        }
+
+        closest
+    }
+}
+
+//Möller-Trumbore
+fn intersect_triangle(origin: &glm::Vec3, direction: &glm::Vec3, pt1: &glm::Vec3, pt2: &glm::Vec3, pt3: &glm::Vec3) -> Option<f32> {
+    let edge1 = pt2 - pt1;
+    let edge2 = pt3 - pt1;
+
+    let p = glm::cross(direction, &edge2);
+    let determinant = glm::dot(&edge1, &p);
+    if determinant.abs() < f32::EPSILON {
+        return None;
+    }
+    let inverse_determinant = 1.0 / determinant;
+
+    let t = origin - pt1;
+    let u = glm::dot(&t, &p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = glm::cross(&t, &edge1);
+    let v = glm::dot(direction, &q) * inverse_determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let distance = glm::dot(&edge2, &q) * inverse_determinant;
+    if distance < 0.0 {
+        return None;
+    }
+
+    Some(distance)
+}
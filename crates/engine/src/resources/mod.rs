@@ -1,20 +1,38 @@
 mod loader;
 
+mod animation_clip;
+mod animation_set;
+mod bounds;
 mod buffer;
+mod dynamic_buffer;
+mod frame_readback;
+mod gltf_asset;
 mod image2d;
 mod into_buffer_data;
 mod material;
+mod mesh_data;
 mod model;
+mod screenshot;
 pub mod shader_source;
+mod skeleton;
 mod single_frame_render_info;
 mod single_model_render_info;
 
 pub use loader::*;
 
+pub use animation_clip::{AnimationClip};
+pub use animation_set::{AnimationSet};
+pub use bounds::{Bounds};
 pub use buffer::{Buffer, get_memory_type_index};
-pub use image2d::{Image2D};
+pub use dynamic_buffer::{DynamicBuffer};
+pub use frame_readback::{FrameReadback};
+pub use gltf_asset::{GltfAsset, GltfPrimitive, GltfMaterial, GltfTextureInfo, GltfAlphaMode};
+pub use image2d::{Image2D, AttachmentKind};
 pub use into_buffer_data::{IntoBufferData};
-pub use material::{Material};
-pub use model::{Model, CanBeVertexBufferType, CanBeInstVertexBufferType};
-pub use single_frame_render_info::{SingleFrameRenderInfo};
-pub use single_model_render_info::{SingleModelRenderInfo};
+pub use material::{Material, TranslucentBlendMode};
+pub use mesh_data::{MeshData};
+pub use model::{Model, CanBeVertexBufferType, CanBeInstVertexBufferType, VertexSkin};
+pub use screenshot::{ScreenshotSource, ScreenshotSettings, ScreenshotRequest, ScreenshotCapture, write_screenshot};
+pub use skeleton::{Skeleton, Joint, JointPose};
+pub use single_frame_render_info::{SingleFrameRenderInfo, ShadowRenderInfo, ShadowCascadeRenderInfo};
+pub use single_model_render_info::{SingleModelRenderInfo, ModelRenderPass};
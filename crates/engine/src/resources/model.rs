@@ -1,4 +1,4 @@
-use super::{Buffer, IntoBufferData};
+use super::{Buffer, Bounds, MeshData, IntoBufferData, gltf_asset};
 
 use core::hash::{Hash};
 use std::{
@@ -18,8 +18,16 @@ use crate::shader_input::{
     vertex_attribute_builder::{HasVertexAttributeBindings}
 };
 
-pub trait CanBeVertexBufferType : HasVertexAttributeBindings + Copy + Clone + Default + Hash + PartialEq + Eq + ::std::fmt::Debug {
-    fn create_vertex_from_opts(pos: glm::Vec3, normal: Option<glm::Vec3>, color: Option<glm::Vec3>, uv: Option<glm::Vec2>, face_normal: Option<glm::Vec3>, face_tangent: Option<glm::Vec3>) -> Self;
+//Which of the skeleton's joints move a vertex, and by how much. The weights add up to 1
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct VertexSkin {
+    pub joints: glm::UVec4,
+    pub weights: glm::Vec4
+}
+
+pub trait CanBeVertexBufferType : HasVertexAttributeBindings + Copy + Clone + Default + Hash + PartialEq + Eq + Send + ::std::fmt::Debug {
+    //Skins only come from glTF primitives with joints and weights. Tangents carry their handedness in w, so that the bitangent is cross(normal, tangent) * w
+    fn create_vertex_from_opts(pos: glm::Vec3, normal: Option<glm::Vec3>, color: Option<glm::Vec3>, uv: Option<glm::Vec2>, face_normal: Option<glm::Vec3>, face_tangent: Option<glm::Vec4>, skin: Option<VertexSkin>) -> Self;
 }
 pub trait CanBeInstVertexBufferType : HasVertexAttributeBindings + Copy + Clone + Default + Hash + PartialEq + Eq + ::std::fmt::Debug {
 }
@@ -36,7 +44,7 @@ impl HasVertexAttributeBindings for u8 {
 }
 #[doc(hidden)]
 impl CanBeVertexBufferType for u8 {
-    fn create_vertex_from_opts(_pos: glm::Vec3, _normal: Option<glm::Vec3>, _color: Option<glm::Vec3>, _uv: Option<glm::Vec2>, _face_normal: Option<glm::Vec3>, _face_tangent: Option<glm::Vec3>) -> Self {
+    fn create_vertex_from_opts(_pos: glm::Vec3, _normal: Option<glm::Vec3>, _color: Option<glm::Vec3>, _uv: Option<glm::Vec2>, _face_normal: Option<glm::Vec3>, _face_tangent: Option<glm::Vec4>, _skin: Option<VertexSkin>) -> Self {
         panic!("Not actually supported.")
     }
 }
@@ -50,6 +58,8 @@ pub struct Model<TVert> where TVert : CanBeVertexBufferType {
     index_buffer_32: Option<Buffer<u32>>,
     index_type: vk::IndexType,
     require_submit: bool,
+    //Models without bounds are never culled
+    bounds: Option<Bounds>,
 
     is_readonly: bool
 }
@@ -80,12 +90,24 @@ impl<TVert> Model<TVert> where TVert : CanBeVertexBufferType {
             index_buffer_32,
             index_type,
             require_submit,
+            bounds: None,
 
             is_readonly: false
         })
     }
 
-    pub(super) fn new_and_create_from_obj_file<P: AsRef<Path>>(path: P, device: &Device, memory_properties: &vk::PhysicalDeviceMemoryProperties) -> Result<Self> {
+    pub(super) fn new_and_create_from_data(vertices: &Vec<TVert>, indices: &Vec<u32>, bounds: Option<Bounds>, device: &Device, memory_properties: &vk::PhysicalDeviceMemoryProperties) -> Result<Self> {
+        let mut model = Self::new(vertices.len(), indices.len(), true)?;
+        model.bounds = bounds;
+
+        model.create(device, memory_properties)?;
+        model.set_data(device, vertices, indices)?;
+
+        Ok(model)
+    }
+
+    //Doesn't touch the GPU, so this can be called from a worker thread
+    pub(super) fn read_obj_file<P: AsRef<Path>>(path: P) -> Result<(Vec<TVert>, MeshData)> {
         let obj_file = File::open(path)?;
         let mut reader = BufReader::new(obj_file);
 
@@ -99,6 +121,7 @@ impl<TVert> Model<TVert> where TVert : CanBeVertexBufferType {
         //Merge vertices and indices for all meshes into a single mesh
         let mut vertices: Vec<TVert> = vec![];
         let mut indices: Vec<u32> = vec![];
+        let mut positions: Vec<glm::Vec3> = vec![];
         let mut vertex_indices = HashMap::<TVert, u32>::new();
         for model in &models {
             let mesh = &model.mesh;
@@ -130,20 +153,7 @@ impl<TVert> Model<TVert> where TVert : CanBeVertexBufferType {
                     let pt2_uv = glm::vec2(mesh.texcoords[pt2_uv_offset], 1.0 - mesh.texcoords[pt2_uv_offset + 1]);
                     let pt3_uv = glm::vec2(mesh.texcoords[pt3_uv_offset], 1.0 - mesh.texcoords[pt3_uv_offset + 1]);
 
-                    let edge1 = pt2 - pt1;
-                    let edge2 = pt3 - pt1;
-                    let deltauv1 = pt2_uv - pt1_uv;
-                    let deltauv2 = pt3_uv - pt1_uv;
-                    let f = 1.0 / (deltauv1[0] * deltauv2[1] - deltauv2[0] * deltauv1[1]);
-
-                    let mut tangent = f * glm::vec3(
-                        deltauv2[1] * edge1[0] - deltauv1[1] * edge2[0],
-                        deltauv2[1] * edge1[1] - deltauv1[1] * edge2[1],
-                        deltauv2[1] * edge1[2] - deltauv1[1] * edge2[2]
-                    );
-                    tangent = tangent - (face_normal * glm::dot(&face_normal, &tangent));
-                    tangent = glm::normalize(&tangent);
-                    face_tangents.push(Some(tangent));
+                    face_tangents.push(Some(calculate_face_tangent(&pt1, &pt2, &pt3, &pt1_uv, &pt2_uv, &pt3_uv, &face_normal)));
                 }
 
                 next_face = end;
@@ -154,9 +164,10 @@ impl<TVert> Model<TVert> where TVert : CanBeVertexBufferType {
                 let face_tangent = *face_tangents.get(q / 3).unwrap_or(&None);
 
                 let vertex: TVert;
+                let pos: glm::Vec3;
                 {
                     let pos_offset = (mesh_index * 3) as usize;
-                    let pos = glm::vec3(mesh.positions[pos_offset], mesh.positions[pos_offset + 2], mesh.positions[pos_offset + 1]); //Swap Y and Z - this engine uses Z as the up direction, but assets are created with Y as the up direction
+                    pos = glm::vec3(mesh.positions[pos_offset], mesh.positions[pos_offset + 2], mesh.positions[pos_offset + 1]); //Swap Y and Z - this engine uses Z as the up direction, but assets are created with Y as the up direction
                     // let pos = glm::vec3(mesh.positions[pos_offset + 1], mesh.positions[pos_offset + 0], mesh.positions[pos_offset + 2]);
 
                     let normal_offset = (mesh_index * 3) as usize;
@@ -181,7 +192,7 @@ impl<TVert> Model<TVert> where TVert : CanBeVertexBufferType {
                         Some(glm::vec2(mesh.texcoords[uv_offset], 1.0 - mesh.texcoords[uv_offset + 1]))
                     };
 
-                    vertex = TVert::create_vertex_from_opts(pos, normal, color, uv, face_normal, face_tangent);
+                    vertex = TVert::create_vertex_from_opts(pos, normal, color, uv, face_normal, face_tangent, None);
                 }
 
                 if let Some(model_index) = vertex_indices.get(&vertex) {
@@ -192,16 +203,149 @@ impl<TVert> Model<TVert> where TVert : CanBeVertexBufferType {
 
                     vertices.push(vertex);
                     indices.push(model_index);
+                    positions.push(pos);
                 }
             }
         }
 
-        let mut model = Self::new(vertices.len(), indices.len(), true)?;
+        Ok((vertices, MeshData::new(positions, indices)))
+    }
 
-        model.create(device, memory_properties)?;
-        model.set_data(device, &vertices, &indices)?;
+    //Doesn't touch the GPU, so this can be called from a worker thread
+    pub(super) fn read_gltf_file<P: AsRef<Path>>(path: P, primitive: Option<(usize, usize)>) -> Result<(Vec<TVert>, MeshData)> {
+        let path = path.as_ref();
+        let gltf = gltf::Gltf::open(path)?;
+        let document = &gltf.document;
+        let buffers = gltf::import_buffers(document, path.parent(), gltf.blob.clone())?;
+
+        //Either a single primitive as authored, or every primitive in the scene merged into a single mesh like OBJ files
+        let mut selected_primitives = vec![];
+        if let Some((mesh_index, primitive_index)) = primitive {
+            let mesh = document.meshes().nth(mesh_index).ok_or_else(|| anyhow!("glTF file {:?} has no mesh {}", path, mesh_index))?;
+            let primitive = mesh.primitives().nth(primitive_index).ok_or_else(|| anyhow!("glTF mesh {} in {:?} has no primitive {}", mesh_index, path, primitive_index))?;
+            selected_primitives.push((primitive, glm::Mat4::identity()));
+        } else {
+            for (mesh_index, transform) in gltf_asset::collect_mesh_instances(document) {
+                if let Some(mesh) = document.meshes().nth(mesh_index) {
+                    let transform = gltf_asset::convert_to_z_up(&transform);
+                    for primitive in mesh.primitives() {
+                        selected_primitives.push((primitive, transform));
+                    }
+                }
+            }
+        }
 
-        Ok(model)
+        let mut vertices: Vec<TVert> = vec![];
+        let mut indices: Vec<u32> = vec![];
+        let mut vertex_positions: Vec<glm::Vec3> = vec![];
+        let mut vertex_indices = HashMap::<TVert, u32>::new();
+        for (primitive, transform) in &selected_primitives {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                warn!("Skipping glTF primitive with unsupported mode {:?} in {:?}", primitive.mode(), path);
+                continue;
+            }
+
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+            //Swap Y and Z - this engine uses Z as the up direction, but glTF uses Y as the up direction
+            let rotation = glm::mat4_to_mat3(transform);
+            let normal_matrix = glm::transpose(&glm::inverse(&rotation));
+            let positions = reader.read_positions()
+                .ok_or_else(|| anyhow!("glTF primitive without positions in {:?}", path))?
+                .map(|p| (transform * glm::vec4(p[0], p[2], p[1], 1.0)).xyz())
+                .collect::<Vec<_>>();
+            let normals = reader.read_normals()
+                .map(|normals| normals.map(|n| glm::normalize(&(normal_matrix * glm::vec3(n[0], n[2], n[1])))).collect::<Vec<_>>());
+            //Swapping Y and Z is a reflection, which flips the handedness, and so does a mirrored node transform
+            let handedness = if glm::determinant(&rotation) < 0.0 { 1.0 } else { -1.0 };
+            let tangents = reader.read_tangents()
+                .map(|tangents| tangents.map(|t| {
+                    let tangent = glm::normalize(&(rotation * glm::vec3(t[0], t[2], t[1])));
+                    glm::vec4(tangent.x, tangent.y, tangent.z, t[3] * handedness)
+                }).collect::<Vec<_>>());
+            let colors = reader.read_colors(0)
+                .map(|colors| colors.into_rgb_f32().map(|c| glm::vec3(c[0], c[1], c[2])).collect::<Vec<_>>());
+            let uvs = reader.read_tex_coords(0)
+                .map(|uvs| uvs.into_f32().map(|uv| glm::vec2(uv[0], uv[1])).collect::<Vec<_>>());
+            let skins = match (reader.read_joints(0), reader.read_weights(0)) {
+                (Some(joints), Some(weights)) => Some(joints.into_u16().zip(weights.into_f32())
+                    .map(|(j, w)| {
+                        let weights = glm::vec4(w[0], w[1], w[2], w[3]);
+                        let total_weight = weights.x + weights.y + weights.z + weights.w;
+                        VertexSkin {
+                            joints: glm::vec4(j[0] as u32, j[1] as u32, j[2] as u32, j[3] as u32),
+                            //Exporters don't always normalize the weights
+                            weights: if total_weight > 0.0 { weights / total_weight } else { glm::vec4(1.0, 0.0, 0.0, 0.0) }
+                        }
+                    })
+                    .collect::<Vec<_>>()),
+                _ => None
+            };
+            //Every attribute is indexed like the positions, so a short one would be read past its end
+            let attribute_lengths = [
+                ("normals", normals.as_ref().map(|n| n.len())),
+                ("tangents", tangents.as_ref().map(|t| t.len())),
+                ("colors", colors.as_ref().map(|c| c.len())),
+                ("UVs", uvs.as_ref().map(|u| u.len())),
+                ("skin weights", skins.as_ref().map(|s| s.len()))
+            ];
+            for (name, len) in attribute_lengths {
+                if let Some(len) = len.filter(|len| *len < positions.len()) {
+                    return Err(anyhow!("glTF primitive has {} {} for {} positions in {:?}", len, name, positions.len(), path));
+                }
+            }
+
+            let primitive_indices = match reader.read_indices() {
+                Some(primitive_indices) => primitive_indices.into_u32().collect::<Vec<_>>(),
+                None => (0..positions.len() as u32).collect::<Vec<_>>()
+            };
+
+            for face in primitive_indices.chunks_exact(3) {
+                if face.iter().any(|i| *i as usize >= positions.len()) {
+                    return Err(anyhow!("glTF primitive index out of range in {:?}", path));
+                }
+
+                let pt1 = positions[face[0] as usize];
+                let pt2 = positions[face[1] as usize];
+                let pt3 = positions[face[2] as usize];
+                let face_normal = glm::cross(&glm::normalize(&(pt1 - pt2)), &glm::normalize(&(pt3 - pt2)));
+
+                let face_tangent = match (&tangents, &uvs) {
+                    (Some(_), _) => None,
+                    (None, Some(uvs)) => Some(calculate_face_tangent(&pt1, &pt2, &pt3, &uvs[face[0] as usize], &uvs[face[1] as usize], &uvs[face[2] as usize], &face_normal)),
+                    (None, None) => None
+                };
+
+                for primitive_index in face {
+                    let primitive_index = *primitive_index as usize;
+
+                    let normal = normals.as_ref().map(|n| n[primitive_index]);
+                    let color = colors.as_ref().map(|c| c[primitive_index]);
+                    let uv = uvs.as_ref().map(|u| u[primitive_index]);
+                    let tangent = tangents.as_ref().map(|t| t[primitive_index]).or(face_tangent);
+                    let skin = skins.as_ref().map(|s| s[primitive_index]);
+
+                    let vertex = TVert::create_vertex_from_opts(positions[primitive_index], normal, color, uv, Some(face_normal), tangent, skin);
+
+                    if let Some(model_index) = vertex_indices.get(&vertex) {
+                        indices.push(*model_index);
+                    } else {
+                        let model_index = vertices.len() as u32;
+                        vertex_indices.insert(vertex, model_index);
+
+                        vertices.push(vertex);
+                        indices.push(model_index);
+                        vertex_positions.push(positions[primitive_index]);
+                    }
+                }
+            }
+        }
+
+        if vertices.is_empty() {
+            return Err(anyhow!("glTF file {:?} has no triangles to load", path));
+        }
+
+        Ok((vertices, MeshData::new(vertex_positions, indices)))
     }
 
     pub fn create(&mut self, device: &Device, memory: &vk::PhysicalDeviceMemoryProperties) -> Result<()> {
@@ -256,6 +400,15 @@ impl<TVert> Model<TVert> where TVert : CanBeVertexBufferType {
         Ok(())
     }
 
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.bounds
+    }
+
+    //Models filled in with set_data have no bounds until they're given some
+    pub fn set_bounds(&mut self, bounds: Option<Bounds>) -> () {
+        self.bounds = bounds;
+    }
+
     pub fn destroy(&mut self, device: &Device) {
         if let Some(index_buffer) = self.index_buffer_16.as_mut() {
             index_buffer.destroy(device);
@@ -273,12 +426,34 @@ impl<TVert> Model<TVert> where TVert : CanBeVertexBufferType {
             index_buffer_32: self.index_buffer_32,
             index_type: self.index_type,
             require_submit: self.require_submit,
+            bounds: self.bounds,
 
             is_readonly: true
         }
     }
 }
 
+fn calculate_face_tangent(pt1: &glm::Vec3, pt2: &glm::Vec3, pt3: &glm::Vec3, pt1_uv: &glm::Vec2, pt2_uv: &glm::Vec2, pt3_uv: &glm::Vec2, face_normal: &glm::Vec3) -> glm::Vec4 {
+    let edge1 = pt2 - pt1;
+    let edge2 = pt3 - pt1;
+    let deltauv1 = pt2_uv - pt1_uv;
+    let deltauv2 = pt3_uv - pt1_uv;
+    let f = 1.0 / (deltauv1[0] * deltauv2[1] - deltauv2[0] * deltauv1[1]);
+
+    let mut tangent = f * glm::vec3(
+        deltauv2[1] * edge1[0] - deltauv1[1] * edge2[0],
+        deltauv2[1] * edge1[1] - deltauv1[1] * edge2[1],
+        deltauv2[1] * edge1[2] - deltauv1[1] * edge2[2]
+    );
+    tangent = tangent - (face_normal * glm::dot(face_normal, &tangent));
+    tangent = glm::normalize(&tangent);
+
+    //Normal maps point +Y up the texture, which is towards -V
+    let bitangent = f * (deltauv1[0] * edge2 - deltauv2[0] * edge1);
+    let handedness = if glm::dot(&glm::cross(face_normal, &tangent), &bitangent) > 0.0 { -1.0 } else { 1.0 };
+    glm::vec4(tangent.x, tangent.y, tangent.z, handedness)
+}
+
 pub trait HasModelDetails {
     fn get_model_details(&self) -> Result<(vk::Buffer, vk::DeviceSize, Option<vk::Buffer>, vk::DeviceSize, vk::IndexType, u32)>;
 }
@@ -0,0 +1,292 @@
+use std::{
+    fs::{File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH}
+};
+use anyhow::{anyhow, Result};
+use vulkanalia::{
+    prelude::v1_0::*
+};
+use winit::event::{VirtualKeyCode};
+
+use crate::resources::{Buffer};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScreenshotSource {
+    //The final image, exactly as it was presented. HDR10 swapchains are written as PQ encoded 16 bit PNGs
+    Presented,
+    //The linear scene color before postprocessing and tone mapping, written as a half float EXR
+    Hdr
+}
+
+impl ScreenshotSource {
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            ScreenshotSource::Presented => "png",
+            ScreenshotSource::Hdr => "exr"
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScreenshotSettings {
+    //Hotkeys are optional. Screenshots can always be taken with App::capture_screenshot
+    pub key: Option<VirtualKeyCode>,
+    pub hdr_key: Option<VirtualKeyCode>,
+    //Where hotkey screenshots are saved. Created if it doesn't exist
+    pub directory: PathBuf
+}
+
+impl Default for ScreenshotSettings {
+    fn default() -> Self {
+        Self {
+            key: None,
+            hdr_key: None,
+            directory: PathBuf::from("screenshots")
+        }
+    }
+}
+
+impl ScreenshotSettings {
+    pub fn key_source(&self, keycode: VirtualKeyCode) -> Option<ScreenshotSource> {
+        if self.key == Some(keycode) {
+            Some(ScreenshotSource::Presented)
+        } else if self.hdr_key == Some(keycode) {
+            Some(ScreenshotSource::Hdr)
+        } else {
+            None
+        }
+    }
+
+    //Timestamped so that repeated presses don't overwrite each other
+    pub fn next_path(&self, source: ScreenshotSource) -> PathBuf {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_millis());
+        self.directory.join(format!("screenshot_{}.{}", timestamp, source.file_extension()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScreenshotRequest {
+    pub path: PathBuf,
+    pub source: ScreenshotSource
+}
+
+//A screenshot being copied out during the current frame
+#[derive(Debug)]
+pub struct ScreenshotCapture {
+    pub request: ScreenshotRequest,
+    pub format: vk::Format,
+    pub extent: vk::Extent2D,
+    pub buffer: Buffer<u8>
+}
+
+//Writes pixels copied straight out of an image. The format decides the file type
+pub fn write_screenshot(path: &Path, format: vk::Format, extent: vk::Extent2D, data: &[u8]) -> Result<()> {
+    let pixel_count = (extent.width * extent.height) as usize;
+
+    match format {
+        vk::Format::R8G8B8A8_SRGB | vk::Format::R8G8B8A8_UNORM => {
+            write_png(path, extent, png::BitDepth::Eight, &data[..(pixel_count * 4)])
+        },
+        vk::Format::B8G8R8A8_SRGB | vk::Format::B8G8R8A8_UNORM => {
+            let pixels = data[..(pixel_count * 4)].chunks_exact(4)
+                .flat_map(|bgra| [bgra[2], bgra[1], bgra[0], bgra[3]])
+                .collect::<Vec<_>>();
+
+            write_png(path, extent, png::BitDepth::Eight, &pixels)
+        },
+        vk::Format::A2B10G10R10_UNORM_PACK32 => {
+            //PNG stores 16 bit samples big endian. Each 10 bit channel is scaled up to the full 16 bit range
+            let expand = |value: u32, max: u32| -> [u8; 2] { ((value * 65535 / max) as u16).to_be_bytes() };
+            let pixels = data[..(pixel_count * 4)].chunks_exact(4)
+                .flat_map(|packed| {
+                    let packed = u32::from_le_bytes([packed[0], packed[1], packed[2], packed[3]]);
+                    let [r0, r1] = expand(packed & 0x3FF, 1023);
+                    let [g0, g1] = expand((packed >> 10) & 0x3FF, 1023);
+                    let [b0, b1] = expand((packed >> 20) & 0x3FF, 1023);
+                    let [a0, a1] = expand(packed >> 30, 3);
+
+                    [r0, r1, g0, g1, b0, b1, a0, a1]
+                })
+                .collect::<Vec<_>>();
+
+            write_png(path, extent, png::BitDepth::Sixteen, &pixels)
+        },
+        vk::Format::R16G16B16A16_SFLOAT => {
+            write_exr(path, extent, &data[..(pixel_count * 8)])
+        },
+        _ => Err(anyhow!("Can't write a screenshot from an image with format {:?}", format))
+    }
+}
+
+fn write_png(path: &Path, extent: vk::Extent2D, bit_depth: png::BitDepth, pixels: &[u8]) -> Result<()> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), extent.width, extent.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(bit_depth);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(pixels)?;
+
+    Ok(())
+}
+
+//Minimal uncompressed scanline OpenEXR writer. The pixels are already RGBA halfs, so they're only reordered into per-channel rows
+fn write_exr(path: &Path, extent: vk::Extent2D, pixels: &[u8]) -> Result<()> {
+    const HALF: i32 = 1;
+    //EXR requires channels sorted by name, so A comes first. Values are the half's index within an RGBA pixel
+    const CHANNELS: [(&str, usize); 4] = [("A", 3), ("B", 2), ("G", 1), ("R", 0)];
+
+    fn write_attribute(out: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) -> () {
+        out.extend_from_slice(name.as_bytes());
+        out.push(0);
+        out.extend_from_slice(kind.as_bytes());
+        out.push(0);
+        out.extend_from_slice(&(value.len() as i32).to_le_bytes());
+        out.extend_from_slice(value);
+    }
+
+    let width = extent.width as usize;
+    let height = extent.height as usize;
+
+    let mut header = vec![];
+    header.extend_from_slice(&20000630u32.to_le_bytes());
+    header.extend_from_slice(&2u32.to_le_bytes());
+
+    let mut channels = vec![];
+    for (name, _) in CHANNELS.iter() {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&HALF.to_le_bytes());
+        //pLinear and three reserved bytes
+        channels.extend_from_slice(&[0, 0, 0, 0]);
+        channels.extend_from_slice(&1i32.to_le_bytes());
+        channels.extend_from_slice(&1i32.to_le_bytes());
+    }
+    channels.push(0);
+    write_attribute(&mut header, "channels", "chlist", &channels);
+
+    write_attribute(&mut header, "compression", "compression", &[0]);
+
+    let window = [0i32, 0, width as i32 - 1, height as i32 - 1].iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect::<Vec<_>>();
+    write_attribute(&mut header, "dataWindow", "box2i", &window);
+    write_attribute(&mut header, "displayWindow", "box2i", &window);
+
+    write_attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    write_attribute(&mut header, "pixelAspectRatio", "float", &1.0f32.to_le_bytes());
+    write_attribute(&mut header, "screenWindowCenter", "v2f", &[0u8; 8]);
+    write_attribute(&mut header, "screenWindowWidth", "float", &1.0f32.to_le_bytes());
+    header.push(0);
+
+    //Each scanline is its own block: y coordinate, data size, then one row per channel
+    let row_size = width * 2 * CHANNELS.len();
+    let block_size = 8 + row_size;
+    let first_block_offset = header.len() + height * 8;
+
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(&header)?;
+
+    for y in 0..height {
+        writer.write_all(&((first_block_offset + y * block_size) as u64).to_le_bytes())?;
+    }
+
+    let mut block = Vec::with_capacity(block_size);
+    for y in 0..height {
+        block.clear();
+        block.extend_from_slice(&(y as i32).to_le_bytes());
+        block.extend_from_slice(&(row_size as i32).to_le_bytes());
+
+        let row = &pixels[(y * width * 8)..((y + 1) * width * 8)];
+        for (_, channel_index) in CHANNELS.iter() {
+            for pixel in row.chunks_exact(8) {
+                block.extend_from_slice(&pixel[(channel_index * 2)..(channel_index * 2 + 2)]);
+            }
+        }
+
+        writer.write_all(&block)?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Splits the header into its attributes, and returns them with the offset just past the header
+    fn read_attributes(bytes: &[u8]) -> (Vec<(String, String, Vec<u8>)>, usize) {
+        let read_string = |offset: &mut usize| -> String {
+            let end = *offset + bytes[*offset..].iter().position(|b| *b == 0).unwrap();
+            let string = String::from_utf8(bytes[*offset..end].to_vec()).unwrap();
+            *offset = end + 1;
+            string
+        };
+
+        let mut attributes = vec![];
+        let mut offset = 8;
+        loop {
+            let name = read_string(&mut offset);
+            if name.is_empty() {
+                return (attributes, offset);
+            }
+
+            let kind = read_string(&mut offset);
+            let size = i32::from_le_bytes(bytes[offset..(offset + 4)].try_into().unwrap()) as usize;
+            offset += 4;
+            attributes.push((name, kind, bytes[offset..(offset + size)].to_vec()));
+            offset += size;
+        }
+    }
+
+    #[test]
+    fn exr_layout() {
+        //Each half is unique, 0x1000 + pixel * 16 + channel, so the reordering can be checked
+        let extent = vk::Extent2D { width: 2, height: 2 };
+        let pixels = (0..4u16)
+            .flat_map(|pixel| (0..4u16).flat_map(move |channel| (0x1000 + pixel * 16 + channel).to_le_bytes()))
+            .collect::<Vec<_>>();
+
+        let path = std::env::temp_dir().join(format!("screenshot_exr_layout_{}.exr", std::process::id()));
+        write_screenshot(&path, vk::Format::R16G16B16A16_SFLOAT, extent, &pixels).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(&bytes[0..4], &20000630u32.to_le_bytes());
+        assert_eq!(&bytes[4..8], &2u32.to_le_bytes());
+
+        let (attributes, header_end) = read_attributes(&bytes);
+        let (_, kind, channels) = attributes.iter().find(|(name, _, _)| name == "channels").unwrap();
+        assert_eq!(kind, "chlist");
+        let mut expected_channels = vec![];
+        for name in ["A", "B", "G", "R"] {
+            expected_channels.extend_from_slice(name.as_bytes());
+            expected_channels.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+        }
+        expected_channels.push(0);
+        assert_eq!(channels, &expected_channels);
+
+        //One offset per scanline, then the blocks back to back
+        let row_size = 2 * 2 * 4;
+        let block_size = 8 + row_size;
+        let offsets = bytes[header_end..(header_end + 16)].chunks_exact(8)
+            .map(|offset| u64::from_le_bytes(offset.try_into().unwrap()) as usize)
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![header_end + 16, header_end + 16 + block_size]);
+        assert_eq!(bytes.len(), header_end + 16 + block_size * 2);
+
+        let block = &bytes[offsets[0]..(offsets[0] + block_size)];
+        assert_eq!(&block[0..4], &0i32.to_le_bytes());
+        assert_eq!(&block[4..8], &(row_size as i32).to_le_bytes());
+        let halfs = block[8..].chunks_exact(2)
+            .map(|half| u16::from_le_bytes([half[0], half[1]]))
+            .collect::<Vec<_>>();
+        //A, B, G, then R rows for the first two pixels
+        assert_eq!(halfs, vec![0x1003, 0x1013, 0x1002, 0x1012, 0x1001, 0x1011, 0x1000, 0x1010]);
+    }
+}
@@ -7,7 +7,7 @@ use vulkanalia::{
     prelude::v1_0::*
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ShaderSource {
     Source(Box<[u8]>, &'static str),
     SourcePath(String, &'static str)
@@ -69,7 +69,9 @@ impl Default for AttachmentDescriptor {
 pub enum DepthBufferUsageMode {
     DontUse,
     WriteIfLess,
-    WriteIfEqual
+    WriteIfEqual,
+    //Depth tested against what's already been drawn, but never written
+    TestIfLess
 }
 
 #[derive(Debug, Copy, Clone, Default)]
@@ -115,6 +117,21 @@ impl Default for BlendStateDescriptor {
     }
 }
 
+#[derive(Debug, Copy, Clone)]
+pub struct RasterizationStateDescriptor {
+    pub cull_mode: vk::CullModeFlags,
+    pub dynamic_depth_bias: bool
+}
+
+impl Default for RasterizationStateDescriptor {
+    fn default() -> Self {
+        Self {
+            cull_mode: vk::CullModeFlags::BACK,
+            dynamic_depth_bias: false
+        }
+    }
+}
+
 pub fn create_shader_sources(stage: &'static str, shader_name: &'static str, suffix: &'static str, shader_entry: &'static str) -> ShaderSources {
     let vertex_path_str = format!("crates/engine/shaders/{}{}{}/shader.vert.spv", stage, shader_name, suffix).to_owned();
     let fragment_path_str = format!("crates/engine/shaders/{}{}{}/shader.frag.spv", stage, shader_name, suffix).to_owned();
@@ -153,7 +170,11 @@ pub fn create_shader_module(device: &Device, bytecode: &[u8]) -> Result<vk::Shad
     }
 }
 
-pub fn create_pipeline(mut vertex_shader_source: ShaderSource, mut fragment_shader_source: ShaderSource, device: &Device, extent: Option<vk::Extent2D>, layout: vk::PipelineLayout, render_pass: vk::RenderPass, subpass_idx: u32, blend_state_descriptors: &[BlendStateDescriptor], depth_buffer_usage: DepthBufferUsageMode, binding_descriptions: &[impl vk::Cast<Target = vk::VertexInputBindingDescription>], attribute_descriptions: &[impl vk::Cast<Target = vk::VertexInputAttributeDescription>]) -> Result<vk::Pipeline> {
+pub fn create_pipeline(vertex_shader_source: ShaderSource, fragment_shader_source: ShaderSource, device: &Device, extent: Option<vk::Extent2D>, layout: vk::PipelineLayout, render_pass: vk::RenderPass, subpass_idx: u32, blend_state_descriptors: &[BlendStateDescriptor], depth_buffer_usage: DepthBufferUsageMode, binding_descriptions: &[impl vk::Cast<Target = vk::VertexInputBindingDescription>], attribute_descriptions: &[impl vk::Cast<Target = vk::VertexInputAttributeDescription>]) -> Result<vk::Pipeline> {
+    create_pipeline_with_rasterization(vertex_shader_source, fragment_shader_source, device, extent, layout, render_pass, subpass_idx, blend_state_descriptors, depth_buffer_usage, RasterizationStateDescriptor::default(), binding_descriptions, attribute_descriptions)
+}
+
+pub fn create_pipeline_with_rasterization(mut vertex_shader_source: ShaderSource, mut fragment_shader_source: ShaderSource, device: &Device, extent: Option<vk::Extent2D>, layout: vk::PipelineLayout, render_pass: vk::RenderPass, subpass_idx: u32, blend_state_descriptors: &[BlendStateDescriptor], depth_buffer_usage: DepthBufferUsageMode, rasterization: RasterizationStateDescriptor, binding_descriptions: &[impl vk::Cast<Target = vk::VertexInputBindingDescription>], attribute_descriptions: &[impl vk::Cast<Target = vk::VertexInputAttributeDescription>]) -> Result<vk::Pipeline> {
     vertex_shader_source = vertex_shader_source.flatten()?;
     let (vert, vert_entry_name) = vertex_shader_source.get_source()?;
     fragment_shader_source = fragment_shader_source.flatten()?;
@@ -210,9 +231,9 @@ pub fn create_pipeline(mut vertex_shader_source: ShaderSource, mut fragment_shad
         .rasterizer_discard_enable(false)
         .polygon_mode(vk::PolygonMode::FILL)
         .line_width(1.0)
-        .cull_mode(vk::CullModeFlags::BACK)
+        .cull_mode(rasterization.cull_mode)
         .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
-        .depth_bias_enable(false);
+        .depth_bias_enable(rasterization.dynamic_depth_bias);
 
     let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
         .sample_shading_enable(false)
@@ -244,6 +265,9 @@ pub fn create_pipeline(mut vertex_shader_source: ShaderSource, mut fragment_shad
         dynamic_states.push(vk::DynamicState::VIEWPORT);
         dynamic_states.push(vk::DynamicState::SCISSOR);
     }
+    if rasterization.dynamic_depth_bias {
+        dynamic_states.push(vk::DynamicState::DEPTH_BIAS);
+    }
 
     let dynamic_state = vk::PipelineDynamicStateCreateInfo::builder()
         .dynamic_states(&dynamic_states[..]);
@@ -284,6 +308,11 @@ pub fn create_pipeline(mut vertex_shader_source: ShaderSource, mut fragment_shad
                     .depth_write_enable(false)
                     .depth_compare_op(vk::CompareOp::EQUAL);
             },
+            DepthBufferUsageMode::TestIfLess => {
+                depth_stencil_state = depth_stencil_state
+                    .depth_write_enable(false)
+                    .depth_compare_op(vk::CompareOp::LESS);
+            },
             _ => return Err(anyhow!("Unrecognized or unsupported depth buffer usage mode: {:?}", depth_buffer_usage))
         }
 
@@ -303,3 +332,36 @@ pub fn create_pipeline(mut vertex_shader_source: ShaderSource, mut fragment_shad
 
     Ok(pipeline)
 }
+
+pub fn create_compute_pipeline(mut shader_source: ShaderSource, device: &Device, layout: vk::PipelineLayout) -> Result<vk::Pipeline> {
+    shader_source = shader_source.flatten()?;
+    let (comp, comp_entry_name) = shader_source.get_source()?;
+
+    let comp_module = create_shader_module(device, &*comp)?;
+
+    let mut comp_entry_name = comp_entry_name.to_owned();
+    comp_entry_name.push_str("\0");
+
+    let comp_stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::COMPUTE)
+        .module(comp_module)
+        .name(comp_entry_name.as_bytes());
+
+    let pipeline_create_info = vk::ComputePipelineCreateInfo::builder()
+        .stage(comp_stage)
+        .layout(layout)
+        .base_pipeline_handle(vk::Pipeline::null())
+        .base_pipeline_index(-1);
+
+    let pipeline_create_infos = &[pipeline_create_info];
+    let pipeline: vk::Pipeline;
+    unsafe {
+        pipeline = device.create_compute_pipelines(vk::PipelineCache::null(), pipeline_create_infos, None)?.0;
+    }
+
+    unsafe {
+        device.destroy_shader_module(comp_module, None);
+    }
+
+    Ok(pipeline)
+}
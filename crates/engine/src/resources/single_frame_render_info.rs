@@ -1,11 +1,48 @@
-use super::{SingleModelRenderInfo};
+use super::{SingleModelRenderInfo, ResourceLoader, Bounds};
 
 use nalgebra_glm as glm;
+use vulkanalia::{
+    prelude::v1_0::*
+};
+
+use crate::{
+    shader_input::uniform_buffer_object::{LightUniform, MAX_FRAME_JOINT_MATRICES, JOINT_PALETTE_ALIGNMENT_MATRICES},
+    game::{
+        post_settings::{CameraPostSettings},
+        frustum::{Frustum}
+    },
+    frame_stats::{CullingStats}
+};
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct ShadowCascadeRenderInfo {
+    //Camera view space to the cascade's clip space, used when rendering shadow casters
+    pub shadow_matrix: glm::Mat4,
+    //Camera view space to shadow map UVs and depth, used when sampling the shadow map
+    pub sampling_matrix: glm::Mat4,
+    pub split_depth: f32,
+    pub viewport: vk::Rect2D
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ShadowRenderInfo {
+    pub atlas_extent: vk::Extent2D,
+    pub cascades: Vec<ShadowCascadeRenderInfo>,
+    //The opaque models inside each cascade, filled in by SingleFrameRenderInfo::cull_models
+    pub cascade_casters: Vec<Vec<SingleModelRenderInfo>>,
+
+    pub depth_bias_constant: f32,
+    pub depth_bias_slope: f32,
+    pub sample_bias: f32
+}
 
 #[derive(Debug)]
 pub struct SingleFrameRenderInfo {
     pub frame_index: u32,
+    //Since the app started
     pub time_in_seconds: f32,
+    pub delta_time_in_seconds: f32,
+    pub fixed_update_alpha: f32,
 
     pub proj: glm::Mat4,
     pub previous_proj: glm::Mat4,
@@ -17,7 +54,19 @@ pub struct SingleFrameRenderInfo {
     pub directional_light_direction: glm::Vec3,
     pub directional_light_color: glm::Vec3,
 
-    pub models_to_render: Vec<SingleModelRenderInfo>
+    pub lights: Vec<LightUniform>,
+
+    pub shadows: Option<ShadowRenderInfo>,
+
+    pub post_settings: CameraPostSettings,
+
+    pub models_to_render: Vec<SingleModelRenderInfo>,
+    //Referenced by SingleModelRenderInfo::instance_transforms_offset
+    pub instance_transforms: Vec<glm::Mat4>,
+    //Referenced by SingleModelRenderInfo::joint_palette_offsets
+    pub joint_palettes: Vec<glm::Mat4>,
+
+    pub culling_stats: CullingStats
 }
 
 impl Default for SingleFrameRenderInfo {
@@ -33,10 +82,175 @@ impl Default for SingleFrameRenderInfo {
             directional_light_direction: glm::vec3(-1.0, 0.0, 0.0),
             directional_light_color: Default::default(),
 
+            lights: Vec::new(),
+
+            shadows: None,
+
+            post_settings: Default::default(),
+
             frame_index: Default::default(),
             time_in_seconds: Default::default(),
+            delta_time_in_seconds: Default::default(),
+            fixed_update_alpha: Default::default(),
+
+            models_to_render: Vec::with_capacity(200),
+            instance_transforms: Vec::new(),
+            joint_palettes: Vec::new(),
+
+            culling_stats: Default::default()
+        }
+    }
+}
+
+impl SingleFrameRenderInfo {
+    //Makes the models from first_model onwards skinned by the given palettes. They're dropped if the frame is out of joint palette space
+    pub fn skin_models(&mut self, first_model: usize, palette: &[glm::Mat4], previous_palette: &[glm::Mat4]) -> () {
+        if first_model >= self.models_to_render.len() {
+            return;
+        }
+
+        let aligned_len = |len: usize| len.div_ceil(JOINT_PALETTE_ALIGNMENT_MATRICES) * JOINT_PALETTE_ALIGNMENT_MATRICES;
+        if self.joint_palettes.len() + aligned_len(palette.len()) + aligned_len(previous_palette.len()) > MAX_FRAME_JOINT_MATRICES {
+            trace!("Too many joint matrices this frame, skipping {} skinned models", self.models_to_render.len() - first_model);
+            self.models_to_render.truncate(first_model);
+            return;
+        }
+
+        let offset = self.joint_palettes.len();
+        self.joint_palettes.extend_from_slice(palette);
+        self.joint_palettes.resize(offset + aligned_len(palette.len()), glm::Mat4::identity());
+
+        let previous_offset = self.joint_palettes.len();
+        self.joint_palettes.extend_from_slice(previous_palette);
+        self.joint_palettes.resize(previous_offset + aligned_len(previous_palette.len()), glm::Mat4::identity());
+
+        for model in self.models_to_render[first_model..].iter_mut() {
+            model.joint_palette_offsets = Some((offset, previous_offset));
+        }
+    }
+
+    //Leaves only the models the camera can see in models_to_render, and fills in each shadow cascade's casters
+    pub fn cull_models(&mut self, resource_loader: &ResourceLoader) -> () {
+        //Model render infos are in view space, so the projection alone is enough
+        let view_frustum = Frustum::from_matrix(&self.proj);
+        let cascade_frustums = match self.shadows.as_ref() {
+            Some(shadows) => shadows.cascades.iter().map(|cascade| Frustum::from_matrix(&cascade.shadow_matrix)).collect::<Vec<_>>(),
+            None => vec![]
+        };
+
+        let mut stats = CullingStats::default();
+        let mut visible_models = Vec::with_capacity(self.models_to_render.len());
+        let mut cascade_casters = vec![vec![]; cascade_frustums.len()];
+
+        for model in self.models_to_render.iter() {
+            stats.submitted_draws += 1;
+            stats.submitted_instances += model.instance_count;
+
+            let bounds = resource_loader.get_render_model(model.model).and_then(|render_model| render_model.bounds());
+
+            let first_visible = visible_models.len();
+            self.cull_model(model, bounds.as_ref(), &view_frustum, &mut visible_models);
+            for visible in visible_models[first_visible..].iter() {
+                stats.visible_draws += 1;
+                stats.visible_instances += visible.instance_count;
+            }
+
+            if model.is_opaque {
+                for (frustum, casters) in cascade_frustums.iter().zip(cascade_casters.iter_mut()) {
+                    let first_caster = casters.len();
+                    self.cull_model(model, bounds.as_ref(), frustum, casters);
+                    for caster in casters[first_caster..].iter() {
+                        stats.shadow_caster_draws += 1;
+                        stats.shadow_caster_instances += caster.instance_count;
+                    }
+                }
+            }
+        }
+
+        self.models_to_render = visible_models;
+        if let Some(shadows) = self.shadows.as_mut() {
+            shadows.cascade_casters = cascade_casters;
+        }
+        self.culling_stats = stats;
+    }
+
+    //Back to front. Instanced draws with instance transforms are split into a draw per instance, so that the instances are sorted too
+    pub fn sorted_translucent_draws(&self) -> Vec<SingleModelRenderInfo> {
+        let mut draws = vec![];
+        for model in self.models_to_render.iter().filter(|model| !model.is_opaque) {
+            match (model.inst_vertex_buffer, model.instance_transforms_offset) {
+                (Some(_), Some(offset)) => {
+                    for q in 0..model.instance_count {
+                        let instance_transform = &self.instance_transforms[offset + (model.first_instance + q) as usize];
+                        let draw = SingleModelRenderInfo {
+                            first_instance: model.first_instance + q,
+                            instance_count: 1,
+                            ..*model
+                        };
+                        draws.push(((model.viewmodel * instance_transform)[(2, 3)], draw));
+                    }
+                },
+                _ => draws.push((model.viewmodel[(2, 3)], *model))
+            }
+        }
+
+        //The projection is left handed, so the camera looks down +Z in view space
+        draws.sort_by(|a, b| b.0.total_cmp(&a.0));
+        draws.into_iter().map(|(_, draw)| draw).collect()
+    }
+
+    //Instances are split into a draw per run of visible instances, so the instance buffer doesn't need to change
+    fn cull_model(&self, model: &SingleModelRenderInfo, bounds: Option<&Bounds>, frustum: &Frustum, visible_models: &mut Vec<SingleModelRenderInfo>) -> () {
+        //Bounds are for the bind pose, which animated joints can move outside of
+        let bounds = match bounds {
+            Some(bounds) if model.joint_palette_offsets.is_none() => bounds,
+            _ => {
+                visible_models.push(*model);
+                return;
+            }
+        };
+
+        let instance_transforms = match (model.inst_vertex_buffer, model.instance_transforms_offset) {
+            (None, _) => {
+                if frustum.intersects_bounds(&bounds.transformed(&model.viewmodel)) {
+                    visible_models.push(*model);
+                }
+                return;
+            },
+            (Some(_), None) => {
+                visible_models.push(*model);
+                return;
+            },
+            (Some(_), Some(offset)) => {
+                let first = offset + model.first_instance as usize;
+                &self.instance_transforms[first..first + model.instance_count as usize]
+            }
+        };
+
+        let mut run_start: Option<u32> = None;
+        for (q, instance_transform) in instance_transforms.iter().enumerate() {
+            let is_visible = frustum.intersects_bounds(&bounds.transformed(&(model.viewmodel * instance_transform)));
+
+            match (is_visible, run_start) {
+                (true, None) => run_start = Some(q as u32),
+                (false, Some(start)) => {
+                    visible_models.push(SingleModelRenderInfo {
+                        first_instance: model.first_instance + start,
+                        instance_count: q as u32 - start,
+                        ..*model
+                    });
+                    run_start = None;
+                },
+                _ => {}
+            }
+        }
 
-            models_to_render: Vec::with_capacity(200)
+        if let Some(start) = run_start {
+            visible_models.push(SingleModelRenderInfo {
+                first_instance: model.first_instance + start,
+                instance_count: model.instance_count - start,
+                ..*model
+            });
         }
     }
 }
@@ -11,7 +11,14 @@ use vulkanalia::{
 };
 use anyhow::{Result};
 
-use crate::shader_input::push_constants::{DepthMotionPushConstants, BaseRenderPushConstants};
+use crate::shader_input::push_constants::{DepthMotionPushConstants, BaseRenderPushConstants, ShadowPushConstants};
+
+#[derive(Debug, Copy, Clone)]
+pub enum ModelRenderPass {
+    DepthMotion,
+    BaseRender,
+    Shadow { shadow_matrix: glm::Mat4 }
+}
 
 #[derive(Debug, Copy, Clone, Default)]
 pub struct SingleModelRenderInfo {
@@ -25,6 +32,10 @@ pub struct SingleModelRenderInfo {
 
     pub inst_vertex_buffer: Option<vk::Buffer>,
     pub inst_vertex_buffer_offset: vk::DeviceSize,
+    //Where instance 0's transform is in SingleFrameRenderInfo::instance_transforms, if the instances can be culled individually
+    pub instance_transforms_offset: Option<usize>,
+    //Where the current and previous frame's joint palettes are in SingleFrameRenderInfo::joint_palettes, for skinned models
+    pub joint_palette_offsets: Option<(usize, usize)>,
     pub first_element: u32,
     pub indexed_vertex_offset: i32,
 
@@ -45,7 +56,7 @@ impl SingleModelRenderInfo {
         }
     }
 
-    pub unsafe fn render(&self, device: &Device, command_buffer: &vk::CommandBuffer, pipeline_layout: &vk::PipelineLayout, is_depth_motion_pass: bool, resource_loader: &ResourceLoader) -> Result<()> {
+    pub unsafe fn render(&self, device: &Device, command_buffer: &vk::CommandBuffer, pipeline_layout: &vk::PipelineLayout, render_pass: ModelRenderPass, resource_loader: &ResourceLoader) -> Result<()> {
         let details = self.get_model_details_from_model_ref(self.model, resource_loader)?;
         if details.is_none() {
             return Ok(());
@@ -64,20 +75,30 @@ impl SingleModelRenderInfo {
 
         let dm_push_constants: DepthMotionPushConstants;
         let br_push_constants: BaseRenderPushConstants;
+        let shadow_push_constants: ShadowPushConstants;
         let push_constants_bytes: &[u8];
 
-        if is_depth_motion_pass {
-            dm_push_constants = DepthMotionPushConstants {
-                viewmodel: self.viewmodel,
-                previous_viewmodel: self.previous_viewmodel
-            };
-            push_constants_bytes = dm_push_constants.as_bytes();
-        } else {
-            br_push_constants = BaseRenderPushConstants {
-                viewmodel: self.viewmodel,
-                normal_viewmodel: glm::transpose(&glm::inverse(&self.viewmodel))
-            };
-            push_constants_bytes = br_push_constants.as_bytes();
+        match render_pass {
+            ModelRenderPass::DepthMotion => {
+                dm_push_constants = DepthMotionPushConstants {
+                    viewmodel: self.viewmodel,
+                    previous_viewmodel: self.previous_viewmodel
+                };
+                push_constants_bytes = dm_push_constants.as_bytes();
+            },
+            ModelRenderPass::BaseRender => {
+                br_push_constants = BaseRenderPushConstants {
+                    viewmodel: self.viewmodel,
+                    normal_viewmodel: glm::mat3_to_mat4(&glm::transpose(&glm::inverse(&glm::mat4_to_mat3(&self.viewmodel))))
+                };
+                push_constants_bytes = br_push_constants.as_bytes();
+            },
+            ModelRenderPass::Shadow { shadow_matrix } => {
+                shadow_push_constants = ShadowPushConstants {
+                    shadow_viewmodel: shadow_matrix * self.viewmodel
+                };
+                push_constants_bytes = shadow_push_constants.as_bytes();
+            }
         }
 
         device.cmd_push_constants(*command_buffer, *pipeline_layout, vk::ShaderStageFlags::ALL_GRAPHICS, 0, push_constants_bytes);
@@ -0,0 +1,252 @@
+use super::{gltf_asset};
+
+use std::{
+    path::{Path}
+};
+use anyhow::{anyhow, Result};
+use nalgebra_glm as glm;
+
+use crate::shader_input::uniform_buffer_object::{MAX_JOINTS};
+
+//A joint's transform relative to its parent, in glTF's Y up space
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct JointPose {
+    pub translation: glm::Vec3,
+    pub rotation: glm::Quat,
+    pub scale: glm::Vec3
+}
+
+impl Default for JointPose {
+    fn default() -> Self {
+        Self {
+            translation: glm::Vec3::zeros(),
+            rotation: glm::Quat::identity(),
+            scale: glm::vec3(1.0, 1.0, 1.0)
+        }
+    }
+}
+
+impl JointPose {
+    pub fn as_matrix(&self) -> glm::Mat4 {
+        glm::translation(&self.translation) * glm::quat_to_mat4(&self.rotation) * glm::scaling(&self.scale)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Joint {
+    pub name: Option<String>,
+    //Index into Skeleton::joints. None for root joints
+    pub parent: Option<usize>,
+    //The transforms of the nodes between the joint and its parent joint (or the scene root) that aren't joints themselves, like an armature node
+    pub parent_transform: glm::Mat4,
+    pub rest_pose: JointPose,
+    //Model space to the joint's space in the bind pose
+    pub inverse_bind_matrix: glm::Mat4,
+
+    //So animation channels, which target nodes, can find the joint
+    node_index: usize
+}
+
+//Empty by default, for animators still waiting for theirs to load
+#[derive(Debug, Clone, Default)]
+pub struct Skeleton {
+    //In the skin's order, which is what vertex joint indices refer to
+    pub joints: Vec<Joint>,
+
+    //Every joint comes after its parent
+    evaluation_order: Vec<usize>
+}
+
+impl Skeleton {
+    //Reads the first skin in the document if skin_index is None. The path is only for errors
+    pub(super) fn from_gltf(document: &gltf::Document, buffers: &[gltf::buffer::Data], path: &Path, skin_index: Option<usize>) -> Result<Self> {
+        let skin = match skin_index {
+            Some(skin_index) => document.skins().nth(skin_index).ok_or_else(|| anyhow!("glTF file {:?} has no skin {}", path, skin_index))?,
+            None => document.skins().next().ok_or_else(|| anyhow!("glTF file {:?} has no skins", path))?
+        };
+
+        let joint_nodes = skin.joints().collect::<Vec<_>>();
+        if joint_nodes.len() > MAX_JOINTS {
+            return Err(anyhow!("glTF skin in {:?} has {} joints, but skinned shaders only support {}", path, joint_nodes.len(), MAX_JOINTS));
+        }
+
+        //Missing inverse bind matrices are identity matrices
+        let inverse_bind_matrices = match skin.reader(|buffer| Some(&buffers[buffer.index()])).read_inverse_bind_matrices() {
+            Some(matrices) => matrices.map(glm::Mat4::from).collect::<Vec<_>>(),
+            None => vec![glm::Mat4::identity(); joint_nodes.len()]
+        };
+        if inverse_bind_matrices.len() < joint_nodes.len() {
+            return Err(anyhow!("glTF skin in {:?} has {} inverse bind matrices for {} joints", path, inverse_bind_matrices.len(), joint_nodes.len()));
+        }
+
+        //glTF nodes only know their children
+        let nodes = document.nodes().collect::<Vec<_>>();
+        let mut node_parents = vec![None; nodes.len()];
+        for node in nodes.iter() {
+            for child in node.children() {
+                node_parents[child.index()] = Some(node.index());
+            }
+        }
+
+        let joints = joint_nodes.iter().zip(inverse_bind_matrices.iter())
+            .map(|(node, inverse_bind_matrix)| {
+                let mut parent = None;
+                let mut parent_transform = glm::Mat4::identity();
+                let mut ancestor = node_parents[node.index()];
+                while let Some(ancestor_index) = ancestor {
+                    if let Some(joint_index) = joint_nodes.iter().position(|joint_node| joint_node.index() == ancestor_index) {
+                        parent = Some(joint_index);
+                        break;
+                    }
+
+                    parent_transform = glm::Mat4::from(nodes[ancestor_index].transform().matrix()) * parent_transform;
+                    ancestor = node_parents[ancestor_index];
+                }
+
+                let (translation, rotation, scale) = node.transform().decomposed();
+                Joint {
+                    name: node.name().map(|n| n.to_owned()),
+                    parent,
+                    parent_transform,
+                    rest_pose: JointPose {
+                        translation: glm::Vec3::from(translation),
+                        rotation: glm::quat(rotation[0], rotation[1], rotation[2], rotation[3]),
+                        scale: glm::Vec3::from(scale)
+                    },
+                    inverse_bind_matrix: *inverse_bind_matrix,
+
+                    node_index: node.index()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        //Sorting by depth puts every parent before its children
+        let depth = |mut index: usize| {
+            let mut depth = 0;
+            while let Some(parent) = joints[index].parent {
+                depth += 1;
+                index = parent;
+            }
+            depth
+        };
+        let mut evaluation_order = (0..joints.len()).collect::<Vec<_>>();
+        evaluation_order.sort_by_key(|index| depth(*index));
+
+        Ok(Self {
+            joints,
+            evaluation_order
+        })
+    }
+
+    pub fn joint_index(&self, name: &str) -> Option<usize> {
+        self.joints.iter().position(|joint| joint.name.as_deref() == Some(name))
+    }
+
+    pub(super) fn joint_index_for_node(&self, node_index: usize) -> Option<usize> {
+        self.joints.iter().position(|joint| joint.node_index == node_index)
+    }
+
+    pub fn rest_poses(&self) -> Vec<JointPose> {
+        self.joints.iter().map(|joint| joint.rest_pose).collect()
+    }
+
+    //Model space matrices that move vertices from the bind pose to the given joint poses, converted to Z up. Poses are in joint order
+    pub fn compute_palette(&self, poses: &[JointPose], palette: &mut Vec<glm::Mat4>) -> () {
+        palette.clear();
+        palette.resize(self.joints.len(), glm::Mat4::identity());
+
+        //Each joint's world transform first, since children need their parent's
+        for index in self.evaluation_order.iter() {
+            let joint = &self.joints[*index];
+            let pose = poses.get(*index).unwrap_or(&joint.rest_pose);
+            let local = joint.parent_transform * pose.as_matrix();
+            palette[*index] = match joint.parent {
+                Some(parent) => palette[parent] * local,
+                None => local
+            };
+        }
+
+        for (matrix, joint) in palette.iter_mut().zip(self.joints.iter()) {
+            *matrix = gltf_asset::convert_to_z_up(&(*matrix * joint.inverse_bind_matrix));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //A chain of joints, each one unit along Y from its parent and turned a little, bound in its rest pose
+    fn chain(length: usize) -> Skeleton {
+        let mut joints: Vec<Joint> = vec![];
+        let mut world = glm::Mat4::identity();
+        for index in 0..length {
+            let rest_pose = JointPose {
+                translation: if index == 0 { glm::vec3(0.5, 0.0, -0.25) } else { glm::vec3(0.0, 1.0, 0.0) },
+                rotation: glm::quat_angle_axis(0.3 * (index + 1) as f32, &glm::vec3(0.0, 0.0, 1.0)),
+                scale: glm::vec3(1.0, 1.0, 1.0)
+            };
+            world *= rest_pose.as_matrix();
+
+            joints.push(Joint {
+                name: Some(format!("Joint{}", index)),
+                parent: index.checked_sub(1),
+                parent_transform: glm::Mat4::identity(),
+                rest_pose,
+                inverse_bind_matrix: glm::inverse(&world),
+                node_index: index
+            });
+        }
+
+        Skeleton {
+            joints,
+            evaluation_order: (0..length).collect()
+        }
+    }
+
+    fn assert_near(a: &glm::Mat4, b: &glm::Mat4) -> () {
+        assert!((a - b).abs().max() < 1e-5, "{:?} isn't {:?}", a, b);
+    }
+
+    #[test]
+    fn rest_pose_palette_is_identity() {
+        let skeleton = chain(4);
+        let mut palette = vec![];
+        skeleton.compute_palette(&skeleton.rest_poses(), &mut palette);
+
+        assert_eq!(palette.len(), 4);
+        for matrix in palette.iter() {
+            assert_near(matrix, &glm::Mat4::identity());
+        }
+    }
+
+    #[test]
+    fn children_follow_their_parent() {
+        let skeleton = chain(3);
+        let mut poses = skeleton.rest_poses();
+        let turn = glm::quat_angle_axis(1.0, &glm::vec3(1.0, 0.0, 0.0));
+        poses[0].rotation *= turn;
+
+        let mut palette = vec![];
+        skeleton.compute_palette(&poses, &mut palette);
+
+        //Turning the root about its own origin turns the whole chain the same way
+        let root = glm::translation(&poses[0].translation) * glm::quat_to_mat4(&skeleton.joints[0].rest_pose.rotation);
+        let expected = gltf_asset::convert_to_z_up(&(root * glm::quat_to_mat4(&turn) * glm::inverse(&root)));
+        for matrix in palette.iter() {
+            assert_near(matrix, &expected);
+        }
+    }
+
+    #[test]
+    fn missing_poses_use_the_rest_pose() {
+        let skeleton = chain(3);
+        let mut palette = vec![];
+        skeleton.compute_palette(&skeleton.rest_poses()[..1], &mut palette);
+
+        assert_eq!(palette.len(), 3);
+        for matrix in palette.iter() {
+            assert_near(matrix, &glm::Mat4::identity());
+        }
+    }
+}
@@ -1,9 +1,11 @@
 pub mod motion_blur;
 pub mod standard;
+pub mod standard_skinned;
 
 pub mod can_be_vertex_attrib;
 pub mod create_box;
 pub mod empty_vertex;
+pub mod overlay_text;
 pub mod push_constants;
 pub mod uniform_buffer_object;
 pub mod vertex_attribute_builder;
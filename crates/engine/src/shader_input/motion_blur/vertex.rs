@@ -3,7 +3,7 @@ use lazy_static::{lazy_static};
 
 use crate::{
     vertex_type,
-    resources::{CanBeVertexBufferType}
+    resources::{CanBeVertexBufferType, VertexSkin}
 };
 
 vertex_type!{
@@ -20,7 +20,7 @@ lazy_static! {
 }
 
 impl CanBeVertexBufferType for Vertex {
-    fn create_vertex_from_opts(pos: glm::Vec3, _normal: Option<glm::Vec3>, _color: Option<glm::Vec3>, uv: Option<glm::Vec2>, _face_normal: Option<glm::Vec3>, _face_tangent: Option<glm::Vec3>) -> Self {
+    fn create_vertex_from_opts(pos: glm::Vec3, _normal: Option<glm::Vec3>, _color: Option<glm::Vec3>, uv: Option<glm::Vec2>, _face_normal: Option<glm::Vec3>, _face_tangent: Option<glm::Vec4>, _skin: Option<VertexSkin>) -> Self {
         Vertex::new(glm::vec2(pos.x, pos.y), uv.unwrap_or(*NO_UV))
     }
 }
@@ -0,0 +1,100 @@
+use nalgebra_glm as glm;
+
+use super::uniform_buffer_object::{OverlayUniformBufferObject, OVERLAY_COLUMNS, OVERLAY_ROWS, OVERLAY_GLYPH_COUNT};
+
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+
+//Each glyph is drawn top row first. Lowercase letters are drawn as uppercase, and anything missing is drawn as '?'
+const GLYPHS: &[(char, [&str; GLYPH_HEIGHT])] = &[
+    (' ', ["...", "...", "...", "...", "..."]),
+    ('?', ["###", "..#", ".##", "...", ".#."]),
+    ('0', ["###", "#.#", "#.#", "#.#", "###"]),
+    ('1', [".#.", "##.", ".#.", ".#.", "###"]),
+    ('2', ["###", "..#", "###", "#..", "###"]),
+    ('3', ["###", "..#", ".##", "..#", "###"]),
+    ('4', ["#.#", "#.#", "###", "..#", "..#"]),
+    ('5', ["###", "#..", "###", "..#", "###"]),
+    ('6', ["###", "#..", "###", "#.#", "###"]),
+    ('7', ["###", "..#", "..#", ".#.", ".#."]),
+    ('8', ["###", "#.#", "###", "#.#", "###"]),
+    ('9', ["###", "#.#", "###", "..#", "###"]),
+    ('A', [".#.", "#.#", "###", "#.#", "#.#"]),
+    ('B', ["##.", "#.#", "##.", "#.#", "##."]),
+    ('C', [".##", "#..", "#..", "#..", ".##"]),
+    ('D', ["##.", "#.#", "#.#", "#.#", "##."]),
+    ('E', ["###", "#..", "##.", "#..", "###"]),
+    ('F', ["###", "#..", "##.", "#..", "#.."]),
+    ('G', [".##", "#..", "#.#", "#.#", ".##"]),
+    ('H', ["#.#", "#.#", "###", "#.#", "#.#"]),
+    ('I', ["###", ".#.", ".#.", ".#.", "###"]),
+    ('J', ["..#", "..#", "..#", "#.#", ".#."]),
+    ('K', ["#.#", "#.#", "##.", "#.#", "#.#"]),
+    ('L', ["#..", "#..", "#..", "#..", "###"]),
+    ('M', ["#.#", "###", "###", "#.#", "#.#"]),
+    ('N', ["##.", "#.#", "#.#", "#.#", "#.#"]),
+    ('O', [".#.", "#.#", "#.#", "#.#", ".#."]),
+    ('P', ["##.", "#.#", "##.", "#..", "#.."]),
+    ('Q', [".#.", "#.#", "#.#", "##.", ".##"]),
+    ('R', ["##.", "#.#", "##.", "#.#", "#.#"]),
+    ('S', [".##", "#..", ".#.", "..#", "##."]),
+    ('T', ["###", ".#.", ".#.", ".#.", ".#."]),
+    ('U', ["#.#", "#.#", "#.#", "#.#", "###"]),
+    ('V', ["#.#", "#.#", "#.#", "#.#", ".#."]),
+    ('W', ["#.#", "#.#", "###", "###", "#.#"]),
+    ('X', ["#.#", "#.#", ".#.", "#.#", "#.#"]),
+    ('Y', ["#.#", "#.#", ".#.", ".#.", ".#."]),
+    ('Z', ["###", "..#", ".#.", "#..", "###"]),
+    ('.', ["...", "...", "...", "...", ".#."]),
+    (',', ["...", "...", "...", ".#.", "#.."]),
+    (':', ["...", ".#.", "...", ".#.", "..."]),
+    ('-', ["...", "...", "###", "...", "..."]),
+    ('_', ["...", "...", "...", "...", "###"]),
+    ('=', ["...", "###", "...", "###", "..."]),
+    ('%', ["#.#", "..#", ".#.", "#..", "#.#"]),
+    ('/', ["..#", "..#", ".#.", "#..", "#.."]),
+    ('(', ["..#", ".#.", ".#.", ".#.", "..#"]),
+    (')', ["#..", ".#.", ".#.", ".#.", "#.."])
+];
+
+const UNKNOWN_GLYPH_INDEX: u32 = 1;
+
+fn glyph_bits(rows: &[&str; GLYPH_HEIGHT]) -> u32 {
+    let mut bits = 0u32;
+    for (y, row) in rows.iter().enumerate() {
+        for (x, pixel) in row.chars().take(GLYPH_WIDTH).enumerate() {
+            if pixel == '#' {
+                bits |= 1 << (y * GLYPH_WIDTH + x);
+            }
+        }
+    }
+
+    bits
+}
+
+fn glyph_index(c: char) -> u32 {
+    let c = c.to_ascii_uppercase();
+    GLYPHS.iter()
+        .position(|(glyph, _)| *glyph == c)
+        .map_or(UNKNOWN_GLYPH_INDEX, |index| index as u32)
+}
+
+impl OverlayUniformBufferObject {
+    //Lines past OVERLAY_ROWS and characters past OVERLAY_COLUMNS are cut off
+    pub fn set_text(&mut self, lines: &[String]) -> () {
+        for (index, (_, rows)) in GLYPHS.iter().enumerate().take(OVERLAY_GLYPH_COUNT) {
+            self.glyphs[index / 4][index % 4] = glyph_bits(rows);
+        }
+
+        self.text = [glm::UVec4::zeros(); OVERLAY_COLUMNS * OVERLAY_ROWS / 16];
+        for (row, line) in lines.iter().enumerate().take(OVERLAY_ROWS) {
+            for (column, c) in line.chars().enumerate().take(OVERLAY_COLUMNS) {
+                let char_index = row * OVERLAY_COLUMNS + column;
+                let packed = &mut self.text[char_index / 16][(char_index / 4) % 4];
+                *packed |= glyph_index(c) << ((char_index % 4) * 8);
+            }
+        }
+
+        self.row_count = lines.len().min(OVERLAY_ROWS) as u32;
+    }
+}
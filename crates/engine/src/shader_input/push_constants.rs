@@ -29,3 +29,66 @@ impl BaseRenderPushConstants {
         model_bytes
     }
 }
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ShadowPushConstants {
+    pub shadow_viewmodel: glm::Mat4
+}
+
+impl ShadowPushConstants {
+    pub fn as_bytes(&self) -> &[u8] {
+        let (_, model_bytes, _) = unsafe { any_as_u8_slice(self).align_to::<u8>() };
+        model_bytes
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TaaPushConstants {
+    pub jitter: glm::Vec2,
+    pub render_texel_size: glm::Vec2,
+    pub history_weight: f32,
+    pub has_history: u32
+}
+
+impl TaaPushConstants {
+    pub fn as_bytes(&self) -> &[u8] {
+        let (_, model_bytes, _) = unsafe { any_as_u8_slice(self).align_to::<u8>() };
+        model_bytes
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PostprocessPushConstants {
+    //The effect's parameters, in the order they were declared
+    pub parameters: [glm::Vec4; 2],
+    pub input_texel_size: glm::Vec2,
+    pub output_texel_size: glm::Vec2
+}
+
+impl PostprocessPushConstants {
+    pub fn as_bytes(&self) -> &[u8] {
+        let (_, model_bytes, _) = unsafe { any_as_u8_slice(self).align_to::<u8>() };
+        model_bytes
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct AutoExposurePushConstants {
+    pub min_log_luminance: f32,
+    pub log_luminance_range: f32,
+    pub adaptation_speed_up: f32,
+    pub adaptation_speed_down: f32,
+    pub delta_time: f32,
+    pub pixel_count: u32
+}
+
+impl AutoExposurePushConstants {
+    pub fn as_bytes(&self) -> &[u8] {
+        let (_, model_bytes, _) = unsafe { any_as_u8_slice(self).align_to::<u8>() };
+        model_bytes
+    }
+}
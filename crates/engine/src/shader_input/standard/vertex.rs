@@ -3,7 +3,7 @@ use lazy_static::{lazy_static};
 
 use crate::{
     vertex_type,
-    resources::{CanBeVertexBufferType}
+    resources::{CanBeVertexBufferType, VertexSkin}
 };
 
 vertex_type!{
@@ -12,7 +12,7 @@ vertex_type!{
     pub struct Vertex {
         pos: glm::Vec3,
         normal: glm::Vec3,
-        tangent: glm::Vec3,
+        tangent: glm::Vec4,
         color: glm::Vec3,
         uv: glm::Vec2
     }
@@ -20,14 +20,15 @@ vertex_type!{
 
 lazy_static! {
     pub static ref NO_NORMAL: glm::Vec3 = glm::vec3(0.0, 0.0, 1.0);
-    pub static ref NO_TANGENT: glm::Vec3 = glm::vec3(1.0, 0.0, 0.0);
+    //Left handed, like tangents converted from Y up
+    pub static ref NO_TANGENT: glm::Vec4 = glm::vec4(1.0, 0.0, 0.0, -1.0);
     pub static ref NO_BITANGENT: glm::Vec3 = glm::vec3(0.0, 1.0, 0.0);
     pub static ref NO_UV: glm::Vec2 = glm::vec2(0.0, 0.0);
     pub static ref WHITE: glm::Vec3 = glm::vec3(1.0, 1.0, 1.0);
 }
 
 impl CanBeVertexBufferType for Vertex {
-    fn create_vertex_from_opts(pos: glm::Vec3, normal: Option<glm::Vec3>, color: Option<glm::Vec3>, uv: Option<glm::Vec2>, face_normal: Option<glm::Vec3>, face_tangent: Option<glm::Vec3>) -> Self {
+    fn create_vertex_from_opts(pos: glm::Vec3, normal: Option<glm::Vec3>, color: Option<glm::Vec3>, uv: Option<glm::Vec2>, face_normal: Option<glm::Vec3>, face_tangent: Option<glm::Vec4>, _skin: Option<VertexSkin>) -> Self {
         Vertex::new(
             pos,
             normal.unwrap_or_else(|| face_normal.unwrap_or(*NO_NORMAL)),
@@ -0,0 +1,3 @@
+mod vertex;
+
+pub use vertex::{Vertex};
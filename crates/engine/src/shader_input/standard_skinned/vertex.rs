@@ -0,0 +1,49 @@
+use nalgebra_glm as glm;
+use lazy_static::{lazy_static};
+
+use crate::{
+    vertex_type,
+    resources::{CanBeVertexBufferType, VertexSkin}
+};
+
+vertex_type!{
+    use crate as engine;
+
+    pub struct Vertex {
+        pos: glm::Vec3,
+        normal: glm::Vec3,
+        tangent: glm::Vec4,
+        color: glm::Vec3,
+        uv: glm::Vec2,
+        joints: glm::UVec4,
+        weights: glm::Vec4
+    }
+}
+
+lazy_static! {
+    pub static ref NO_NORMAL: glm::Vec3 = glm::vec3(0.0, 0.0, 1.0);
+    //Left handed, like tangents converted from Y up
+    pub static ref NO_TANGENT: glm::Vec4 = glm::vec4(1.0, 0.0, 0.0, -1.0);
+    pub static ref NO_UV: glm::Vec2 = glm::vec2(0.0, 0.0);
+    pub static ref WHITE: glm::Vec3 = glm::vec3(1.0, 1.0, 1.0);
+    //Unskinned vertices follow the first joint
+    pub static ref NO_SKIN: VertexSkin = VertexSkin {
+        joints: glm::UVec4::zeros(),
+        weights: glm::vec4(1.0, 0.0, 0.0, 0.0)
+    };
+}
+
+impl CanBeVertexBufferType for Vertex {
+    fn create_vertex_from_opts(pos: glm::Vec3, normal: Option<glm::Vec3>, color: Option<glm::Vec3>, uv: Option<glm::Vec2>, face_normal: Option<glm::Vec3>, face_tangent: Option<glm::Vec4>, skin: Option<VertexSkin>) -> Self {
+        let skin = skin.unwrap_or(*NO_SKIN);
+        Vertex::new(
+            pos,
+            normal.unwrap_or_else(|| face_normal.unwrap_or(*NO_NORMAL)),
+            face_tangent.unwrap_or(*NO_TANGENT),
+            color.unwrap_or(*WHITE),
+            uv.unwrap_or(*NO_UV),
+            skin.joints,
+            skin.weights
+        )
+    }
+}
@@ -3,6 +3,44 @@ use nalgebra_glm as glm;
 //Parameters must be aligned specifically. See https://registry.khronos.org/vulkan/specs/1.0-wsi_extensions/html/vkspec.html#interfaces-resources-layout
 //Also see https://stackoverflow.com/a/45641579/768597
 
+//Must match MAX_LIGHTS in the shaders
+pub const MAX_LIGHTS: usize = 32;
+
+//Must match MAX_SHADOW_CASCADES in the shaders
+pub const MAX_SHADOW_CASCADES: usize = 4;
+
+//Must match MAX_JOINTS in the skinned shaders
+pub const MAX_JOINTS: usize = 256;
+
+//Joint matrices all skinned models can upload in one frame, counting both the current and previous palettes
+pub const MAX_FRAME_JOINT_MATRICES: usize = 16384;
+
+//Palettes start on 256 byte boundaries, the largest minStorageBufferOffsetAlignment allowed. That's 4 Mat4s of 64 bytes each
+pub const JOINT_PALETTE_ALIGNMENT_MATRICES: usize = 4;
+
+pub const LIGHT_KIND_POINT: u32 = 0;
+pub const LIGHT_KIND_SPOT: u32 = 1;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LightUniform {
+    //0
+    pub position: glm::Vec3,
+    pub range: f32,
+
+    //16
+    pub direction: glm::Vec3,
+    pub kind: u32,
+
+    //32
+    pub color: glm::Vec3,
+    pub inner_cone_cos: f32,
+
+    //48
+    pub attenuation: glm::Vec3,
+    pub outer_cone_cos: f32
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Default)]
 pub struct UniformBufferObject {
@@ -34,8 +72,48 @@ pub struct UniformBufferObject {
     pub jitter_scale: f32,
     pub frame_index: u32,
     pub time_in_seconds: f32,
-    #[doc(hidden)]
-    pub __pad_jitter_scale: u32
+    pub light_count: u32,
+
+    //208
+    pub lights: [LightUniform; MAX_LIGHTS],
+
+    //2256
+    pub shadow_matrices: [glm::Mat4; MAX_SHADOW_CASCADES],
+    //2512
+    pub shadow_cascade_splits: glm::Vec4,
+    //2528
+    pub shadow_cascade_count: u32,
+    pub shadow_bias: f32,
+    pub shadow_texel_size: glm::Vec2,
+    //2544
+    //Each cascade's tile in the atlas as min xy, max xy in UVs, shrunk by a texel so PCF doesn't read the neighbouring tile
+    pub shadow_cascade_rects: [glm::Vec4; MAX_SHADOW_CASCADES]
+}
+
+//Bound next to a material's textures, which are multiplied by these. Matches glTF's metallic roughness material
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MaterialUniform {
+    //0
+    pub base_color_factor: glm::Vec4,
+
+    //16
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub normal_scale: f32,
+    pub occlusion_strength: f32
+}
+
+impl Default for MaterialUniform {
+    fn default() -> Self {
+        Self {
+            base_color_factor: glm::vec4(1.0, 1.0, 1.0, 1.0),
+            metallic_factor: 1.0,
+            roughness_factor: 1.0,
+            normal_scale: 1.0,
+            occlusion_strength: 1.0
+        }
+    }
 }
 
 #[repr(C)]
@@ -43,5 +121,76 @@ pub struct UniformBufferObject {
 pub struct PostprocessingUniformBufferObject {
     pub frame_index: u32,
     pub time_in_seconds: f32,
-    pub exposure: f32
+    pub tone_mapping_operator: u32,
+    //Non-zero when exposure comes from the auto exposure buffer rather than manual_exposure
+    pub auto_exposure: u32,
+    pub manual_exposure: f32,
+    pub exposure_compensation: f32,
+    //How far above 1.0 tone mapping may go, to use the extra range of an HDR display. 1.0 when presenting SDR
+    pub output_headroom: f32,
+    //Non-zero when the swapchain is HDR10, and the output pass has to PQ encode
+    pub encode_pq: u32,
+    pub paper_white_nits: f32,
+    pub max_nits: f32
+}
+
+//Must match the bin count and workgroup size in the auto exposure shaders
+pub const LUMINANCE_HISTOGRAM_BIN_COUNT: usize = 256;
+
+//Lives in a storage buffer that the auto exposure shaders keep up to date, and postprocess effects read from
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ExposureState {
+    //Zero until the first frame has been measured
+    pub adapted_luminance: f32
+}
+
+//Must match the defines in the overlay shader
+pub const OVERLAY_COLUMNS: usize = 40;
+pub const OVERLAY_ROWS: usize = 16;
+pub const OVERLAY_GLYPH_COUNT: usize = 64;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct OverlayUniformBufferObject {
+    //0
+    //Top left corner of the text box, in pixels
+    pub origin: glm::Vec2,
+    //Screen pixels per font pixel
+    pub scale: f32,
+    //Rows past this are left out of the text box
+    pub row_count: u32,
+
+    //16
+    pub encode_pq: u32,
+    pub paper_white_nits: f32,
+    #[doc(hidden)]
+    pub __pad_paper_white_nits: glm::Vec2,
+
+    //32
+    //One 3x5 bitmap per glyph, one bit per pixel, starting at the top left
+    pub glyphs: [glm::UVec4; OVERLAY_GLYPH_COUNT / 4],
+
+    //288
+    //Glyph indices, packed four to a u32
+    pub text: [glm::UVec4; OVERLAY_COLUMNS * OVERLAY_ROWS / 16]
+}
+
+//The text array is too long to derive Default
+impl Default for OverlayUniformBufferObject {
+    fn default() -> Self {
+        Self {
+            origin: Default::default(),
+            scale: Default::default(),
+            row_count: Default::default(),
+
+            encode_pq: Default::default(),
+            paper_white_nits: Default::default(),
+            __pad_paper_white_nits: Default::default(),
+
+            glyphs: Default::default(),
+
+            text: [glm::UVec4::zeros(); OVERLAY_COLUMNS * OVERLAY_ROWS / 16]
+        }
+    }
 }
@@ -0,0 +1,65 @@
+use std::{
+    cell::{RefCell},
+    rc::{Rc}
+};
+use nalgebra_glm as glm;
+use anyhow::{Result};
+
+use engine::{
+    frame_info::{FrameInfo},
+    input::{InputState},
+    game::{
+        components::{GameComponent},
+        can_be_enabled::{CanBeEnabled},
+        transform::{Transform}
+    }
+};
+
+use crate::{
+    shader_input::marble::{MarbleInstance}
+};
+
+//Goes on a marble's game object after its rigid body, and copies the marble's transform into the instances a RenderMarbleComponent draws, so every marble is drawn with one instanced draw
+#[derive(Debug)]
+pub struct MarbleBodyComponent {
+    enabled: bool,
+    index: usize,
+    color: glm::Vec4,
+    instances: Rc<RefCell<Vec<MarbleInstance>>>
+}
+
+impl MarbleBodyComponent {
+    pub fn new(instances: Rc<RefCell<Vec<MarbleInstance>>>, color: glm::Vec4) -> Self {
+        let index = {
+            let mut instances = instances.borrow_mut();
+            instances.push(MarbleInstance::new(color, glm::Mat4::identity()));
+            instances.len() - 1
+        };
+
+        Self {
+            enabled: true,
+            index,
+            color,
+            instances
+        }
+    }
+}
+
+impl CanBeEnabled for MarbleBodyComponent {
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    fn set_enabled(&mut self, enabled: bool) -> () {
+        self.enabled = enabled;
+    }
+}
+
+impl GameComponent for MarbleBodyComponent {
+    //The rigid body has already interpolated the transform by now. Marbles are top level game objects, so it's in world space
+    fn tick(&mut self, _frame_info: &FrameInfo, _input: &InputState, transform: &mut Transform) -> Result<()> {
+        let transform = glm::convert::<glm::DMat4, glm::Mat4>(transform.as_matrix()?);
+        self.instances.borrow_mut()[self.index] = MarbleInstance::new(self.color, transform);
+
+        Ok(())
+    }
+}
@@ -1,3 +1,7 @@
+use std::{
+    cell::{RefCell},
+    rc::{Rc}
+};
 use nalgebra_glm as glm;
 use anyhow::{Result};
 use engine::{
@@ -5,11 +9,11 @@ use engine::{
         components::{GameComponent},
         can_be_enabled::{CanBeEnabled}
     },
-    resources::{SingleFrameRenderInfo, ModelRef, MaterialRef, MaterialProperties, ModelProperties, ResourceLoader, Buffer}
+    resources::{SingleFrameRenderInfo, ModelRef, MaterialRef, MaterialProperties, ModelProperties, ResourceLoader, DynamicBuffer, TranslucentBlendMode}
 };
 
 use crate::{
-    shader_input::marble::{self, MARBLE_INSTANCES}
+    shader_input::marble::{self, MarbleInstance}
 };
 
 #[derive(Debug)]
@@ -19,18 +23,28 @@ pub struct RenderMarbleComponent {
     path: &'static str,
     material: Option<MaterialRef>,
     model: Option<ModelRef>,
-    inst_buffer: Option<Buffer<marble::MarbleInstance>>
+    is_opaque: bool,
+    //Written by each marble's MarbleBodyComponent
+    instances: Rc<RefCell<Vec<MarbleInstance>>>,
+    //Rewritten every frame as the marbles move
+    inst_buffer: Option<DynamicBuffer<marble::MarbleInstance>>,
+    //Lets the engine cull marbles one by one
+    instance_transforms: Vec<glm::Mat4>
 }
 
 impl RenderMarbleComponent {
-    pub fn new(path: &'static str) -> Result<Self> {
+    //Instance transforms are relative to the game object
+    pub fn new(path: &'static str, instances: Rc<RefCell<Vec<MarbleInstance>>>) -> Result<Self> {
         Ok(Self {
             enabled: true,
             is_loaded: false,
             path: path,
             material: None,
             model: None,
-            inst_buffer: None
+            is_opaque: true,
+            instances,
+            inst_buffer: None,
+            instance_transforms: vec![]
         })
     }
 }
@@ -47,15 +61,27 @@ impl CanBeEnabled for RenderMarbleComponent {
 impl GameComponent for RenderMarbleComponent {
     fn load_and_unload(&mut self, resource_loader: &mut ResourceLoader) -> Result<()> {
         if self.is_loaded {
+            //Runs after every marble has ticked
+            let instances = self.instances.borrow();
+            if let Some(inst_buffer) = self.inst_buffer.as_mut() {
+                resource_loader.write_dynamic_buffer(inst_buffer, &*instances)?;
+            }
+            self.instance_transforms.clear();
+            self.instance_transforms.extend(instances.iter().map(|instance| instance.transform()));
+
             return Ok(());
         }
 
+        //Some of the marbles are see-through, which the instance colors' alpha says
         let mat_props = MaterialProperties::<marble::Vertex, marble::MarbleInstance> {
+            is_opaque: false,
+            translucent_blend_mode: TranslucentBlendMode::Alpha,
             shader_name: "marble",
             ..Default::default()
         };
 
         self.material = Some(resource_loader.get_or_load_material(&mat_props)?);
+        self.is_opaque = mat_props.is_opaque;
 
         let model_props = ModelProperties::<marble::Vertex> {
             obj_path: Some(self.path.to_owned()),
@@ -64,7 +90,9 @@ impl GameComponent for RenderMarbleComponent {
 
         self.model = Some(resource_loader.get_or_load_model(&model_props)?);
 
-        self.inst_buffer = Some(resource_loader.create_inst_buffer(&*MARBLE_INSTANCES)?);
+        let instances = self.instances.borrow();
+        self.inst_buffer = Some(resource_loader.create_dynamic_inst_buffer(&*instances)?);
+        self.instance_transforms = instances.iter().map(|instance| instance.transform()).collect();
 
         self.is_loaded = true;
         Ok(())
@@ -74,23 +102,23 @@ impl GameComponent for RenderMarbleComponent {
         if self.is_loaded {
             if let Some(model) = self.model.take() {
                 resource_loader.unload_model(model);
-                // model.destroy(device);
             }
 
             if let Some(material) = self.material.take() {
                 resource_loader.unload_material(material);
             }
 
-            //This should be cleaned up by the ResourceLoader later
-            self.inst_buffer = None;
+            if let Some(inst_buffer) = self.inst_buffer.take() {
+                resource_loader.unload_dynamic_buffer(inst_buffer);
+            }
 
             self.is_loaded = false;
         }
     }
 
     fn create_frame_render_info(&self, frame_info: &mut SingleFrameRenderInfo, viewmodel: &glm::Mat4, previous_viewmodel: Option<&glm::Mat4>) -> Result<()> {
-        if let (Some(material), Some(model), Some(inst_buffer)) = (self.material, self.model, self.inst_buffer) {
-            model.create_frame_render_info_instanced(frame_info, material, false, true, viewmodel, previous_viewmodel, inst_buffer)?;
+        if let (Some(material), Some(model), Some(inst_buffer)) = (self.material, self.model, self.inst_buffer.as_ref()) {
+            model.create_frame_render_info_instanced(frame_info, material, false, self.is_opaque, viewmodel, previous_viewmodel, inst_buffer.current(), Some(&self.instance_transforms[..]))?;
         }
 
         Ok(())
@@ -1,5 +1,7 @@
 pub mod marbles_component;
+pub mod marble_body_component;
 pub mod rotate_over_time_component;
 
 pub use rotate_over_time_component::{RotateOverTimeComponent};
 pub use marbles_component::{RenderMarbleComponent};
+pub use marble_body_component::{MarbleBodyComponent};
@@ -3,6 +3,7 @@ use anyhow::{Result};
 
 use engine::{
     frame_info::{FrameInfo},
+    input::{InputState},
     game::{
         components::{GameComponent},
         can_be_enabled::{CanBeEnabled},
@@ -14,13 +15,20 @@ use engine::{
 pub struct RotateOverTimeComponent {
     enabled: bool,
     pub time: f32,
-    pub angle: f32
+    pub angle: f32,
+    //The angle before the last fixed update, so the rendered rotation can be interpolated between fixed updates
+    previous_angle: f32
 }
 
 impl RotateOverTimeComponent {
     pub fn new() -> Self {
         Self::default()
     }
+
+    fn orientation(angle: f32) -> glm::Quat {
+        let rotate_matrix = glm::rotate(&glm::identity(), angle, &*DEFAULT_UP);
+        glm::to_quat(&rotate_matrix)
+    }
 }
 
 impl Default for RotateOverTimeComponent {
@@ -28,7 +36,8 @@ impl Default for RotateOverTimeComponent {
         Self {
             enabled: true,
             time: 0.0,
-            angle: 0.0
+            angle: 0.0,
+            previous_angle: 0.0
         }
     }
 }
@@ -43,12 +52,21 @@ impl CanBeEnabled for RotateOverTimeComponent {
 }
 
 impl GameComponent for RotateOverTimeComponent {
-    fn tick(&mut self, frame_info: &FrameInfo, transform: &mut Transform) -> Result<()> {
-        self.time += frame_info.current_frame_delta_time.as_secs_f32();
+    fn fixed_tick(&mut self, frame_info: &FrameInfo, _input: &InputState, transform: &mut Transform) -> Result<()> {
+        self.previous_angle = self.angle;
+        self.time += frame_info.fixed_delta_time.as_secs_f32();
         self.angle = self.time * glm::radians(&glm::vec1(30.0))[0]; // Rotate 30 degrees per second
 
-        let rotate_matrix = glm::rotate(&glm::identity(), self.angle, &*DEFAULT_UP);
-        transform.orient = glm::to_quat(&rotate_matrix);
+        //The physics step after this sees the exact fixed update rotation rather than the last frame's interpolated one
+        transform.orient = Self::orientation(self.angle);
+
+        Ok(())
+    }
+
+    fn tick(&mut self, frame_info: &FrameInfo, _input: &InputState, transform: &mut Transform) -> Result<()> {
+        let angle = glm::lerp_scalar(self.previous_angle, self.angle, frame_info.fixed_update_alpha);
+
+        transform.orient = Self::orientation(angle);
 
         // transform.pos = glm::vec3(f64::clamp(f64::sin((self.time * 4.0) as f64), 0.0f64, 1.0f64), 0.0, 0.0);
         // transform.pos = glm::vec3(0.0, f64::clamp(f64::sin((self.time * 4.0) as f64), 0.0f64, 1.0f64), 0.0);
@@ -3,11 +3,18 @@ mod scenes;
 mod shader_input;
 
 use anyhow::{Result};
-use winit::dpi::{LogicalSize};
+use winit::{
+    dpi::{LogicalSize, PhysicalSize},
+    event::{VirtualKeyCode}
+};
 
 use engine::{
     builder::{HasHeapBuilder},
-    app::{App}
+    app::{App},
+    bootstrap::{DlssQualityMode, TaaSettings},
+    postprocessing::{MotionBlurSettings, BloomSettings, ToneMappingSettings},
+    resources::{ScreenshotSettings},
+    game::camera_controllers::{OrbitCameraController}
 };
 
 use scenes::{marbles};
@@ -15,6 +22,8 @@ use scenes::{marbles};
 #[macro_use] extern crate log;
 
 const VALIDATION_ENABLED: bool = cfg!(debug_assertions);
+//Frames to render before the headless readback, so that TAA and auto exposure have settled
+const HEADLESS_WARMUP_FRAMES: u32 = 60;
 
 fn main() -> Result<()> {
     pretty_env_logger::init();
@@ -24,13 +33,45 @@ fn main() -> Result<()> {
         .initial_title("Rust Engine Sample App")
         .default_size(LogicalSize::new(1920, 1080))
         .add_default_bootstrap_loaders()
-        .add_dlss();
+        .add_dlss(DlssQualityMode::Quality)
+        .add_taa(TaaSettings::default())
+        .add_postprocess_effect(MotionBlurSettings::default())
+        .add_postprocess_effect(BloomSettings::default())
+        .add_postprocess_effect(ToneMappingSettings::default())
+        .screenshot_settings(ScreenshotSettings {
+            key: Some(VirtualKeyCode::F12),
+            hdr_key: Some(VirtualKeyCode::F10),
+            ..Default::default()
+        });
 
     if VALIDATION_ENABLED {
         builder = builder.add_validation();
     }
 
+    let headless = std::env::args().any(|arg| arg == "--headless");
+    if headless {
+        builder = builder.headless(PhysicalSize::new(1920, 1080));
+    } else {
+        builder = builder.show_stats_overlay(true);
+    }
+
     let mut app = builder.build()?;
+    OrbitCameraController::bind_default_actions(&mut app.input.actions);
     marbles::create_scene(&mut app.scene)?;
+
+    if headless {
+        app.render_frames(HEADLESS_WARMUP_FRAMES)?;
+        app.request_readback()?;
+        app.render_frames(1)?;
+        app.last_readback().unwrap().save_png("headless.png")?;
+        info!("Saved headless frame to headless.png");
+        for line in app.frame_stats().overlay_lines() {
+            info!("{}", line);
+        }
+        app.close();
+
+        return Ok(());
+    }
+
     app.run()
 }
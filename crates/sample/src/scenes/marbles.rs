@@ -1,27 +1,45 @@
+use std::{
+    cell::{RefCell},
+    rc::{Rc}
+};
 use anyhow::{Result};
 use nalgebra_glm as glm;
 use engine::{
     game::{
         scene::{Scene},
-        lights::{DirectionalLight},
+        camera_controllers::{OrbitCameraController},
+        lights::{DirectionalLight, ShadowSettings},
         game_object::{GameObject},
-        components::{RenderModelComponent}
+        components::{RenderModelComponent, ColliderComponent, RigidBodyComponent, AnimatorComponent}
     },
-    shader_input::{standard}
+    physics::{Collider, RigidBody},
+    shader_input::{standard, standard_skinned}
+};
+
+use crate::{
+    components::{RenderMarbleComponent, MarbleBodyComponent, RotateOverTimeComponent},
+    shader_input::marble::{MARBLE_INSTANCES}
 };
 
-use crate::components::{RenderMarbleComponent, RotateOverTimeComponent};
+//Matches resources/models/marbles/marble.obj
+const MARBLE_RADIUS: f32 = 0.2;
+//Marbles start this far above where they settled in the baked simulation, and fall into the bowl from there
+const MARBLE_DROP_HEIGHT: f64 = 1.5;
+const TENTACLE_PATH: &str = "resources/models/tentacle/tentacle.gltf";
 
 pub fn create_scene(scene: &mut Box<Scene>) -> Result<()> {
     // scene.render_camera.transform.pos = glm::vec3(5.0, 5.0, 3.0);
-    scene.render_camera.transform.pos = glm::vec3(2.2, 2.2, 2.0);
     // scene.render_camera.transform.pos = glm::vec3(1.0, 1.0, 0.75);
     // scene.render_camera.look_at(*crate::game::transform::ORIGIN);
-    scene.render_camera.look_at(glm::vec3(0.0, 0.0, -0.9));
+    scene.camera_controller = Some(Box::new(OrbitCameraController::from_position(glm::vec3(2.2, 2.2, 2.0), glm::vec3(0.0, 0.0, -0.9))));
     scene.ambient_light = glm::vec3(4.0, 4.0, 4.0);
     scene.directional_light = Some(DirectionalLight {
         direction: glm::vec3(-1.0, 0.0, -0.3),
         color: glm::vec3(10.0, 10.0, 10.0),
+        shadows: Some(ShadowSettings {
+            max_distance: 10.0,
+            ..Default::default()
+        })
     });
 
     let mut game_object = Box::new(GameObject::new());
@@ -31,9 +49,42 @@ pub fn create_scene(scene: &mut Box<Scene>) -> Result<()> {
     // game_object.add_component(Box::new(RenderModelComponent::<simple::Vertex>::new("resources/models/coords/coords.obj")?))?;
     // game_object.add_component(Box::new(RenderModelComponent::<simple::Vertex>::new("resources/models/sphere/sphere.obj")?))?;
     // game_object.add_component(Box::new(RenderModelComponent::<standard::Vertex>::new("resources/models/marbles/flat_plane.obj")?))?;
-    game_object.add_component(Box::new(RenderModelComponent::<standard::Vertex>::new("resources/models/marbles/bowl.obj")?))?;
-    game_object.add_component(Box::new(RenderMarbleComponent::new("resources/models/marbles/marble.obj")?))?;
+    game_object.add_component(Box::new(RenderModelComponent::<standard::Vertex>::new_with_textures(
+        "resources/models/marbles/bowl.obj",
+        Some("resources/models/marbles/bowl_DefaultMaterial_BaseColor.png"),
+        Some("resources/models/marbles/bowl_DefaultMaterial_Normal.png"),
+        Some("resources/models/marbles/bowl_DefaultMaterial_OcclusionRoughnessMetallic.png")
+    )?))?;
+    //Kinematic so the marbles are carried around as it turns
+    game_object.add_component(Box::new(ColliderComponent::new(Collider::triangle_mesh())))?;
+    game_object.add_component(Box::new(RigidBodyComponent::new(RigidBody::kinematic())))?;
     scene.add_game_object(game_object)?;
 
+    //Skinned and animated, standing beside the bowl
+    let mut tentacle = Box::new(GameObject::new());
+    tentacle.transform.pos = glm::vec3(2.4, -0.6, -2.0);
+    tentacle.add_component(Box::new(RenderModelComponent::<standard_skinned::Vertex>::new(TENTACLE_PATH)?.with_shader_name("standard_skinned")))?;
+    tentacle.add_component(Box::new(AnimatorComponent::from_gltf(TENTACLE_PATH).with_autoplay("Sway")?))?;
+    scene.add_game_object(tentacle)?;
+
+    //Every marble is drawn by this one component, with the transforms the marbles' bodies write each frame
+    let marble_instances = Rc::new(RefCell::new(Vec::with_capacity(MARBLE_INSTANCES.len())));
+    let mut marbles = Box::new(GameObject::new());
+    marbles.add_component(Box::new(RenderMarbleComponent::new("resources/models/marbles/marble.obj", marble_instances.clone())?))?;
+    scene.add_game_object(marbles)?;
+
+    for instance in MARBLE_INSTANCES.iter() {
+        let baked_transform = instance.transform();
+
+        let mut marble = Box::new(GameObject::new());
+        marble.transform.pos = glm::convert::<glm::Vec3, glm::DVec3>(glm::column(&baked_transform, 3).xyz()) + glm::vec3(0.0, 0.0, MARBLE_DROP_HEIGHT);
+        marble.transform.orient = glm::to_quat(&baked_transform);
+
+        marble.add_component(Box::new(ColliderComponent::new(Collider::sphere(MARBLE_RADIUS))))?;
+        marble.add_component(Box::new(RigidBodyComponent::new(RigidBody::dynamic(0.02))))?;
+        marble.add_component(Box::new(MarbleBodyComponent::new(marble_instances.clone(), instance.color())))?;
+        scene.add_game_object(marble)?;
+    }
+
     Ok(())
 }
@@ -25,12 +25,21 @@ impl MarbleInstance {
 
         let transform = Transform {
             pos: glm::convert::<glm::Vec3, glm::DVec3>(pos),
-            orient: orient
+            orient: orient,
+            ..Default::default()
         };
         let inst_matrix = glm::convert::<glm::DMat4, glm::Mat4>(transform.as_matrix().unwrap());
 
         Self::new(color, inst_matrix)
     }
+
+    pub fn color(&self) -> glm::Vec4 {
+        self.color
+    }
+
+    pub fn transform(&self) -> glm::Mat4 {
+        self.transform
+    }
 }
 
 impl CanBeInstVertexBufferType for MarbleInstance { }